@@ -43,15 +43,30 @@ class Sharing implements PublicSharing {
     if(agent.did != this.#agent.did)
       throw "Can't set Agent Expression for foreign DID - only for self"
 
-    if(!agent.directMessageLanguage)
-      agent.directMessageLanguage = undefined
+    // The agent_store zome checks the signature against its own serialization
+    // of the profile, so we sign exactly the fields it stores, with nulls for unset ones.
+    const profile = {
+      did: agent.did,
+      perspective: {
+        links: agent.perspective!.links.map(link => ({
+          author: link.author,
+          timestamp: link.timestamp,
+          data: {
+            source: link.data.source,
+            predicate: link.data.predicate ?? null,
+            target: link.data.target,
+          },
+          proof: {
+            signature: link.proof.signature,
+            key: link.proof.key,
+          },
+        }))
+      },
+      directMessageLanguage: agent.directMessageLanguage || null,
+    }
 
-    agent.perspective!.links.forEach(link => {
-      delete link.proof.valid
-      delete link.proof.invalid
-    })
-
-    const expression = this.#agent.createSignedExpression(agent);
+    await this.authorizeAgentKey();
+    const expression = this.#agent.createSignedExpression(profile);
     await this.#DNA.call(
       DNA_NICK,
       "agent_store",
@@ -61,4 +76,23 @@ class Sharing implements PublicSharing {
 
     return agent.did
   }
+
+  // Profiles can only be linked to our DID by agent keys
+  // it signed an entanglement proof for
+  async authorizeAgentKey() {
+    const agentKey = await this.#DNA.call(
+      DNA_NICK,
+      "agent_store",
+      "get_agent_key",
+      null
+    );
+    //@ts-ignore
+    const proof = this.#agent.createEntanglementProof("holochain", agentKey);
+    await this.#DNA.call(
+      DNA_NICK,
+      "agent_store",
+      "add_entanglement_proof",
+      proof
+    );
+  }
 }
\ No newline at end of file
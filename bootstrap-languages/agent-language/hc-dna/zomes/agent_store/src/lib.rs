@@ -1,9 +1,15 @@
-use agent_store_integrity::{AgentExpression, Did, EntryTypes, LinkTypes};
+use agent_store_integrity::{
+    profile_link_tag, AgentExpression, Did, EntanglementProof, EntryTypes, LinkTypes,
+};
 use hdk::prelude::*;
+use holo_hash::AgentPubKeyB64;
 
 mod utils;
 
-use utils::{err, get_latest_link};
+use utils::{
+    err, get_agent_key_links, get_latest_link, get_own_agent_expression,
+    get_own_entanglement_proof, get_profile_links,
+};
 
 #[hdk_extern]
 fn init(_: ()) -> ExternResult<InitCallbackResult> {
@@ -15,23 +21,90 @@ pub fn create_agent_expression(agent_expression: AgentExpression) -> ExternResul
     let did = EntryTypes::Did(Did(agent_expression.author.clone()));
     let did_hash = hash_entry(&did)?;
 
+    let agent = agent_info()?.agent_initial_pubkey;
+    let proof_hash = get_own_entanglement_proof(&agent_expression.author, &agent)?.ok_or(err(&format!(
+        "Add an entanglement proof for this agent key before publishing profiles for DID {}",
+        agent_expression.author
+    )))?;
+    let previous = get_own_agent_expression(&agent_expression.author)?;
+
     create_entry(&did)?;
 
     let agent_expression = EntryTypes::AgentExpression(agent_expression);
     let agent_expression_hash = hash_entry(&agent_expression)?;
-    create_entry(&agent_expression)?;
+    // Validation only accepts updates that are newer than the profile they update
+    match previous {
+        Some(previous) => update_entry(previous, &agent_expression)?,
+        None => create_entry(&agent_expression)?,
+    };
 
     //Link profile entry to did
     create_link(
         did_hash,
         agent_expression_hash,
         LinkTypes::ProfileLink,
-        LinkTag::new("profile"),
+        profile_link_tag(&proof_hash),
+    )?;
+
+    Ok(())
+}
+
+/// Our agent key in the form entanglement proofs for it have to sign.
+#[hdk_extern]
+pub fn get_agent_key(_: ()) -> ExternResult<AgentPubKeyB64> {
+    Ok(agent_info()?.agent_initial_pubkey.into())
+}
+
+/// Authorizes our agent key to publish profiles for the DID of the proof.
+#[hdk_extern]
+pub fn add_entanglement_proof(proof: EntanglementProof) -> ExternResult<()> {
+    let did = EntryTypes::Did(Did(proof.did.clone()));
+    let did_hash = hash_entry(&did)?;
+
+    let proof = EntryTypes::EntanglementProof(proof);
+    let proof_hash = hash_entry(&proof)?;
+    let agent = agent_info()?.agent_initial_pubkey;
+    if get_agent_key_links(did_hash.clone())?
+        .iter()
+        .any(|link| link.author == agent && link.target == AnyLinkableHash::from(proof_hash.clone()))
+    {
+        return Ok(());
+    }
+
+    create_entry(&did)?;
+    create_entry(&proof)?;
+    create_link(
+        did_hash,
+        proof_hash,
+        LinkTypes::AgentKeyLink,
+        LinkTag::new("agent_key"),
     )?;
 
     Ok(())
 }
 
+#[hdk_extern]
+pub fn get_entanglement_proofs(did: Did) -> ExternResult<Vec<EntanglementProof>> {
+    let mut proofs = Vec::new();
+    for link in get_agent_key_links(hash_entry(did)?)? {
+        let target = link
+            .target
+            .into_entry_hash()
+            .ok_or(err("Agent key link does not point to an entry"))?;
+        if let Some(record) = get(target, GetOptions::default())? {
+            let proof: EntanglementProof = record
+                .entry()
+                .to_app_option()
+                .map_err(|sb_err| err(&format!("{}", sb_err)))?
+                .ok_or(err("Could not deserialize entanglement proof"))?;
+            if !proofs.contains(&proof) {
+                proofs.push(proof);
+            }
+        }
+    }
+    Ok(proofs)
+}
+
 #[hdk_extern]
 pub fn get_agent_expression(did: Did) -> ExternResult<Option<AgentExpression>> {
     let expression_links = get_latest_link(
@@ -41,60 +114,46 @@ pub fn get_agent_expression(did: Did) -> ExternResult<Option<AgentExpression>> {
     .map_err(|error| err(format!("{}", error).as_ref()))?;
 
     match expression_links {
-        Some(link) => {
-            match get(
-                link.target
-                    .into_entry_hash()
-                    .expect("could not get action hash"),
-                GetOptions::default(),
-            )
-            .map_err(|error| err(format!("{}", error).as_ref()))?
-            {
-                Some(elem) => {
-                    let exp_data: AgentExpression = elem
-                        .entry()
-                        .to_app_option()
-                        .map_err(|sb_err| err(&format!("{}", sb_err)))?
-                        .ok_or(err(
-                            "Could not deserialize link expression data into Profile type",
-                        ))?;
-                    Ok(Some(exp_data))
-                }
-                None => Ok(None),
-            }
-        }
+        Some(link) => get_linked_expression(link),
         None => Ok(None),
     }
 }
 
-//Validation logic
-
-//Validate did entry
-//Validate did syntax
-//Validate integrity of DID
-
-//Validate did document entry
-//TODO: resolve did subject and validate that did documents are the same.
-//Validate that signed_agent inside did document is the same agent who is trying to post this did document. This is the validation stage that allows for the "claiming/pairing" of a did on this DHT.
-//Note that this signed_agent validation doesnt give us anything that isnt already handled by holochain validation logic. It is however useful if we can do did resolving. So we can keep it here ready for the future.
-//In the case that we can resolve did's since we can trust a given did subject document pair we can deduce the the agent making the post is the same agent who authored the first claim of this DID on some other system.
-
-//Validate create profile entry
-//Validate length/size of entry?
-//Perhaps validate that agent does not have more than N profiles already post'd as to reduce possibility of someone spamming network?
-
-//Validate update profile entry
-//Validate length/size of entry
-//Validate that agent creating update is the same agent who made the first profile entry
-//Actually possible here that we could allow multiple agents to update profile entry if the did document had multiple signed_agent fields where each signed_agent was allowed editable agent
-//Editing from multiple agents would require that profile has links to did document so that we can check this signed agents field
-
-//Validate links
-
-//did subject -> did document:
-//Validate that author of subject and document are the same. Since creating a did document entry requires the validation of signed_agent field we can be sure that author of did document is the rightful owner of this did.
-//Validate that subject inside did document is the same as the did subject as source for this link.
+/// All profiles published for a DID, oldest first.
+/// Validation only lets agent keys the DID authorized link them.
+#[hdk_extern]
+pub fn get_agent_expression_history(did: Did) -> ExternResult<Vec<AgentExpression>> {
+    let mut links = get_profile_links(hash_entry(did)?, Some(LinkTag::new("profile")))?;
+    links.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    let mut history = Vec::new();
+    for link in links {
+        if let Some(expression) = get_linked_expression(link)? {
+            history.push(expression);
+        }
+    }
+    Ok(history)
+}
 
-//did subject -> profile
-//Validate that there is a link between did subject -> did document. This gives us the verification that creator of did subject is same agent as creator of did document.
-//Validate that agent posting profile is the same agent who created the did subject.
+fn get_linked_expression(link: Link) -> ExternResult<Option<AgentExpression>> {
+    match get(
+        link.target
+            .into_entry_hash()
+            .expect("could not get action hash"),
+        GetOptions::default(),
+    )
+    .map_err(|error| err(format!("{}", error).as_ref()))?
+    {
+        Some(elem) => {
+            let exp_data: AgentExpression = elem
+                .entry()
+                .to_app_option()
+                .map_err(|sb_err| err(&format!("{}", sb_err)))?
+                .ok_or(err(
+                    "Could not deserialize link expression data into Profile type",
+                ))?;
+            Ok(Some(exp_data))
+        }
+        None => Ok(None),
+    }
+}
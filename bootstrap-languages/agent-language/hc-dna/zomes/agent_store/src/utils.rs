@@ -1,11 +1,12 @@
-use agent_store_integrity::LinkTypes;
+use agent_store_integrity::{AgentExpression, EntanglementProof, LinkTypes, UnitEntryTypes};
 use hdk::prelude::*;
+use holo_hash::AgentPubKeyB64;
 
 pub(crate) fn err(reason: &str) -> WasmError {
     wasm_error!(WasmErrorInner::Host(String::from(reason)))
 }
 
-pub(crate) fn get_latest_link(base: EntryHash, tag: Option<LinkTag>) -> ExternResult<Option<Link>> {
+pub(crate) fn get_profile_links(base: EntryHash, tag: Option<LinkTag>) -> ExternResult<Vec<Link>> {
     let input = GetLinksInputBuilder::try_new(
         base,
         LinkTypes::ProfileLink,
@@ -15,21 +16,75 @@ pub(crate) fn get_latest_link(base: EntryHash, tag: Option<LinkTag>) -> ExternRe
     .get_options(GetStrategy::Network)
     .build();
 
-    let profile_info = get_links(input)?;
+    get_links(input)
+}
+
+pub(crate) fn get_agent_key_links(base: EntryHash) -> ExternResult<Vec<Link>> {
+    let input = GetLinksInputBuilder::try_new(
+        base,
+        LinkTypes::AgentKeyLink,
+    )
+    .unwrap()
+    .get_options(GetStrategy::Network)
+    .build();
+
+    get_links(input)
+}
+
+pub(crate) fn get_latest_link(base: EntryHash, tag: Option<LinkTag>) -> ExternResult<Option<Link>> {
+    let profile_info = get_profile_links(base, tag)?;
 
     // Find the latest
-    let latest_info =
-        profile_info
-            .into_iter()
-            .fold(None, |latest: Option<Link>, link| match latest {
-                Some(latest) => {
-                    if link.timestamp > latest.timestamp {
-                        Some(link)
-                    } else {
-                        Some(latest)
-                    }
+    let latest_info = profile_info
+        .into_iter()
+        .fold(None, |latest: Option<Link>, link| match latest {
+            Some(latest) => {
+                if link.timestamp > latest.timestamp {
+                    Some(link)
+                } else {
+                    Some(latest)
                 }
-                None => Some(link),
-            });
+            }
+            None => Some(link),
+        });
     return Ok(latest_info);
 }
+
+/// The entanglement proof on our own chain that authorizes our agent key for the DID.
+pub(crate) fn get_own_entanglement_proof(did: &str, agent: &AgentPubKey) -> ExternResult<Option<EntryHash>> {
+    let device_key = AgentPubKeyB64::from(agent.clone()).to_string();
+    for record in query_own_entries(UnitEntryTypes::EntanglementProof)? {
+        let proof: Option<EntanglementProof> = record
+            .entry()
+            .to_app_option()
+            .map_err(|sb_err| err(&format!("{}", sb_err)))?;
+        if let Some(proof) = proof {
+            if proof.did == did && proof.device_key == device_key {
+                return Ok(record.action().entry_hash().cloned());
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// The last profile we published for the DID, which new ones update.
+pub(crate) fn get_own_agent_expression(did: &str) -> ExternResult<Option<ActionHash>> {
+    let mut latest = None;
+    for record in query_own_entries(UnitEntryTypes::AgentExpression)? {
+        let expression: Option<AgentExpression> = record
+            .entry()
+            .to_app_option()
+            .map_err(|sb_err| err(&format!("{}", sb_err)))?;
+        if expression.map(|expression| expression.author == did).unwrap_or(false) {
+            latest = Some(record.action_address().clone());
+        }
+    }
+    Ok(latest)
+}
+
+fn query_own_entries(entry_type: UnitEntryTypes) -> ExternResult<Vec<Record>> {
+    let mut filter = QueryFilter::new();
+    filter.entry_type = Some(vec![entry_type.try_into()?]);
+    filter.include_entries = true;
+    query(filter)
+}
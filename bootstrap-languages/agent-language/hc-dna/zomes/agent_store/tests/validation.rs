@@ -0,0 +1,308 @@
+//! Sweettest coverage for the agent_store validation rules.
+//! Needs the packed DNA, run `./build.sh` in hc-dna first.
+
+use agent_store_integrity::{
+    AgentExpression, AgentExpressionData, Did, EntanglementProof, ExpressionProof, Link,
+    LinkExpression, Perspective,
+};
+use chrono::{SecondsFormat, Utc};
+use did_key_signatures::did_key;
+use ed25519_dalek::{Signer, SigningKey};
+use holo_hash::AgentPubKeyB64;
+use holochain::conductor::api::error::ConductorApiResult;
+use holochain::prelude::AgentPubKey;
+use holochain::sweettest::*;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+struct DidKey(SigningKey);
+
+impl DidKey {
+    fn new(seed: u8) -> Self {
+        DidKey(SigningKey::from_bytes(&[seed; 32]))
+    }
+
+    fn did(&self) -> String {
+        did_key(&self.0.verifying_key())
+    }
+
+    fn profile(&self, direct_message_language: &str) -> AgentExpressionData {
+        AgentExpressionData {
+            did: self.did(),
+            perspective: Some(Perspective {
+                links: vec![LinkExpression {
+                    author: self.did(),
+                    timestamp: "2024-01-01T11:00:00.000Z".to_string(),
+                    data: Link {
+                        source: "ad4m://self".to_string(),
+                        target: "literal://string:profile".to_string(),
+                        predicate: None,
+                    },
+                    proof: ExpressionProof {
+                        signature: "00".to_string(),
+                        key: "key".to_string(),
+                    },
+                }],
+            }),
+            direct_message_language: Some(direct_message_language.to_string()),
+        }
+    }
+
+    /// What the executor's EntanglementProofController creates for a Holochain agent key
+    fn entanglement_proof(&self, agent: &AgentPubKey) -> EntanglementProof {
+        let device_key = AgentPubKeyB64::from(agent.clone()).to_string();
+        EntanglementProof {
+            did: self.did(),
+            did_signing_key_id: format!("{}#primary", self.did()),
+            device_key_type: "holochain".to_string(),
+            device_key_signed_by_did: hex::encode(
+                self.0
+                    .sign(&Sha256::digest(device_key.as_bytes()))
+                    .to_bytes(),
+            ),
+            device_key,
+            did_signed_by_device_key: None,
+        }
+    }
+
+    /// Signs like the executor's createSignedExpression
+    fn sign(&self, data: AgentExpressionData) -> AgentExpression {
+        let timestamp = Utc::now();
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_vec(&serde_json::to_value(&data).unwrap()).unwrap());
+        hasher.update(timestamp.to_rfc3339_opts(SecondsFormat::Millis, true));
+        AgentExpression {
+            author: self.did(),
+            timestamp,
+            data,
+            proof: ExpressionProof {
+                signature: hex::encode(self.0.sign(&hasher.finalize()).to_bytes()),
+                key: format!("{}#primary", self.did()),
+            },
+        }
+    }
+}
+
+async fn setup() -> (SweetConductorBatch, SweetCell, SweetCell) {
+    let dna_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../workdir/agent-store.dna");
+    let dna = SweetDnaFile::from_bundle(&dna_path).await.unwrap();
+
+    let mut conductors = SweetConductorBatch::from_standard_config(2).await;
+    let apps = conductors.setup_app("agent-store", &[dna]).await.unwrap();
+    conductors.exchange_peer_info().await;
+    let ((alice,), (bob,)) = apps.into_tuples();
+    (conductors, alice, bob)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_profiles_not_signed_by_their_did() {
+    let (conductors, alice, _bob) = setup().await;
+    let zome = alice.zome("agent_store");
+    let key = DidKey::new(1);
+    let _: () = conductors[0]
+        .call(
+            &zome,
+            "add_entanglement_proof",
+            key.entanglement_proof(alice.agent_pubkey()),
+        )
+        .await;
+
+    let valid = key.sign(key.profile("language://dm"));
+    let _: () = conductors[0]
+        .call(&zome, "create_agent_expression", valid.clone())
+        .await;
+
+    let mut unsigned = valid.clone();
+    unsigned.proof.signature = "sig".to_string();
+    let result: ConductorApiResult<()> = conductors[0]
+        .call_fallible(&zome, "create_agent_expression", unsigned)
+        .await;
+    assert!(result.is_err());
+
+    let mut tampered = valid.clone();
+    tampered.data.direct_message_language = Some("language://evil".to_string());
+    let result: ConductorApiResult<()> = conductors[0]
+        .call_fallible(&zome, "create_agent_expression", tampered)
+        .await;
+    assert!(result.is_err());
+
+    let other = DidKey::new(2);
+    let foreign = other.sign(key.profile("language://dm"));
+    let result: ConductorApiResult<()> = conductors[0]
+        .call_fallible(&zome, "create_agent_expression", foreign)
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rejects_oversized_profiles() {
+    let (conductors, alice, _bob) = setup().await;
+    let zome = alice.zome("agent_store");
+    let key = DidKey::new(1);
+    let _: () = conductors[0]
+        .call(
+            &zome,
+            "add_entanglement_proof",
+            key.entanglement_proof(alice.agent_pubkey()),
+        )
+        .await;
+
+    let mut profile = key.profile("language://dm");
+    let link = profile.perspective.as_ref().unwrap().links[0].clone();
+    profile.perspective = Some(Perspective {
+        links: vec![link; 2000],
+    });
+    let result: ConductorApiResult<()> = conductors[0]
+        .call_fallible(&zome, "create_agent_expression", key.sign(profile))
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn only_entangled_agents_can_link_profiles() {
+    let (conductors, alice, bob) = setup().await;
+    let alice_zome = alice.zome("agent_store");
+    let bob_zome = bob.zome("agent_store");
+    let key = DidKey::new(1);
+
+    let first = key.sign(key.profile("language://first"));
+    let result: ConductorApiResult<()> = conductors[0]
+        .call_fallible(&alice_zome, "create_agent_expression", first.clone())
+        .await;
+    assert!(result.is_err());
+
+    let _: () = conductors[0]
+        .call(
+            &alice_zome,
+            "add_entanglement_proof",
+            key.entanglement_proof(alice.agent_pubkey()),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(&alice_zome, "create_agent_expression", first.clone())
+        .await;
+    await_consistency(60, [&alice, &bob]).await.unwrap();
+
+    // Bob replays a genuinely signed profile of Alice's DID
+    let result: ConductorApiResult<()> = conductors[1]
+        .call_fallible(&bob_zome, "create_agent_expression", first)
+        .await;
+    assert!(result.is_err());
+
+    let second = key.sign(key.profile("language://second"));
+    let _: () = conductors[0]
+        .call(&alice_zome, "create_agent_expression", second)
+        .await;
+    await_consistency(60, [&alice, &bob]).await.unwrap();
+
+    // Profiles signed before the latest one can't be published again
+    let result: ConductorApiResult<()> = conductors[0]
+        .call_fallible(&alice_zome, "create_agent_expression", first)
+        .await;
+    assert!(result.is_err());
+
+    let profile: Option<AgentExpression> = conductors[1]
+        .call(&bob_zome, "get_agent_expression", Did(key.did()))
+        .await;
+    assert_eq!(
+        profile.unwrap().data.direct_message_language,
+        Some("language://second".to_string())
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn entangled_agent_keys_share_a_did() {
+    let (conductors, laptop, desktop) = setup().await;
+    let laptop_zome = laptop.zome("agent_store");
+    let desktop_zome = desktop.zome("agent_store");
+    let key = DidKey::new(1);
+
+    let _: () = conductors[0]
+        .call(
+            &laptop_zome,
+            "add_entanglement_proof",
+            key.entanglement_proof(laptop.agent_pubkey()),
+        )
+        .await;
+    let _: () = conductors[0]
+        .call(
+            &laptop_zome,
+            "create_agent_expression",
+            key.sign(key.profile("language://laptop")),
+        )
+        .await;
+    await_consistency(60, [&laptop, &desktop]).await.unwrap();
+
+    let result: ConductorApiResult<()> = conductors[1]
+        .call_fallible(
+            &desktop_zome,
+            "create_agent_expression",
+            key.sign(key.profile("language://desktop")),
+        )
+        .await;
+    assert!(result.is_err());
+
+    // Proofs need to be signed by the DID and can only be published by the key they authorize
+    let forged = DidKey::new(2).entanglement_proof(desktop.agent_pubkey());
+    let result: ConductorApiResult<()> = conductors[1]
+        .call_fallible(
+            &desktop_zome,
+            "add_entanglement_proof",
+            EntanglementProof {
+                did: key.did(),
+                ..forged
+            },
+        )
+        .await;
+    assert!(result.is_err());
+    let result: ConductorApiResult<()> = conductors[1]
+        .call_fallible(
+            &desktop_zome,
+            "add_entanglement_proof",
+            key.entanglement_proof(laptop.agent_pubkey()),
+        )
+        .await;
+    assert!(result.is_err());
+
+    let agent_key: AgentPubKeyB64 = conductors[1].call(&desktop_zome, "get_agent_key", ()).await;
+    assert_eq!(AgentPubKey::from(agent_key), desktop.agent_pubkey().clone());
+    let _: () = conductors[1]
+        .call(
+            &desktop_zome,
+            "add_entanglement_proof",
+            key.entanglement_proof(desktop.agent_pubkey()),
+        )
+        .await;
+    let _: () = conductors[1]
+        .call(
+            &desktop_zome,
+            "create_agent_expression",
+            key.sign(key.profile("language://desktop")),
+        )
+        .await;
+    await_consistency(60, [&laptop, &desktop]).await.unwrap();
+
+    let proofs: Vec<EntanglementProof> = conductors[0]
+        .call(&laptop_zome, "get_entanglement_proofs", Did(key.did()))
+        .await;
+    assert_eq!(proofs.len(), 2);
+    assert!(proofs.contains(&key.entanglement_proof(laptop.agent_pubkey())));
+    assert!(proofs.contains(&key.entanglement_proof(desktop.agent_pubkey())));
+
+    let profile: Option<AgentExpression> = conductors[0]
+        .call(&laptop_zome, "get_agent_expression", Did(key.did()))
+        .await;
+    assert_eq!(
+        profile.unwrap().data.direct_message_language,
+        Some("language://desktop".to_string())
+    );
+
+    let history: Vec<AgentExpression> = conductors[0]
+        .call(&laptop_zome, "get_agent_expression_history", Did(key.did()))
+        .await;
+    let languages: Vec<_> = history
+        .into_iter()
+        .map(|expression| expression.data.direct_message_language.unwrap())
+        .collect();
+    assert_eq!(languages, vec!["language://laptop", "language://desktop"]);
+}
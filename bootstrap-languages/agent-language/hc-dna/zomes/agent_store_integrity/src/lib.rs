@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use hdi::prelude::*;
 
+pub mod validation;
+
 #[derive(Serialize, Deserialize, Clone, SerializedBytes, Debug)]
 pub struct Link {
     pub source: String,
@@ -16,10 +18,12 @@ pub struct ExpressionProof {
 
 #[derive(Serialize, Deserialize, Clone, SerializedBytes, Debug)]
 pub struct LinkExpression {
-    author: String,
-    timestamp: DateTime<Utc>,
-    data: Link,
-    proof: ExpressionProof,
+    pub author: String,
+    // Kept as the string that was signed, so the AgentExpression signature
+    // can be checked against exactly what the executor hashed
+    pub timestamp: String,
+    pub data: Link,
+    pub proof: ExpressionProof,
 }
 
 #[derive(Serialize, Deserialize, Clone, SerializedBytes, Debug)]
@@ -34,11 +38,31 @@ pub enum EntryTypes {
     Did(Did),
     #[entry_type(visibility = "public")]
     AgentExpression(AgentExpression),
+    #[entry_type(visibility = "public")]
+    EntanglementProof(EntanglementProof),
 }
 
 #[hdk_link_types]
 pub enum LinkTypes {
     ProfileLink,
+    AgentKeyLink,
+}
+
+/// Profile links are tagged with this prefix followed by the hash of the entanglement proof
+/// that authorizes the link's author to publish profiles for the DID.
+pub const PROFILE_LINK_TAG: &[u8] = b"profile";
+
+pub fn profile_link_tag(entanglement_proof: &EntryHash) -> LinkTag {
+    let mut tag = PROFILE_LINK_TAG.to_vec();
+    tag.extend_from_slice(entanglement_proof.get_raw_39());
+    LinkTag::new(tag)
+}
+
+/// The entanglement proof referenced by a profile link tag.
+pub fn profile_link_proof(tag: &LinkTag) -> Option<EntryHash> {
+    tag.0
+        .strip_prefix(PROFILE_LINK_TAG)
+        .and_then(|hash| EntryHash::try_from_raw_39(hash.to_vec()).ok())
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, SerializedBytes)]
@@ -58,9 +82,16 @@ app_entry!(AgentExpression);
 
 #[derive(Serialize, Deserialize, Clone, SerializedBytes, Debug)]
 pub struct AgentExpressionData {
-    did: String,
-    perspective: Option<Perspective>,
+    pub did: String,
+    pub perspective: Option<Perspective>,
     #[serde(rename(serialize = "directMessageLanguage"))]
     #[serde(rename(deserialize = "directMessageLanguage"))]
-    direct_message_language: Option<String>,
+    pub direct_message_language: Option<String>,
+}
+
+pub use did_key_signatures::EntanglementProof;
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    validation::validate(op)
 }
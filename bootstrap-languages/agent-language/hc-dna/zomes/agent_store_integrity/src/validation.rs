@@ -0,0 +1,457 @@
+use did_key_signatures::{check_entanglement_proof, did_key_public_key, verify_expression_signature};
+use hdi::prelude::*;
+use holo_hash::AgentPubKeyB64;
+
+use crate::{profile_link_proof, AgentExpression, Did, EntanglementProof, EntryTypes, LinkTypes};
+
+pub use did_key_signatures::{HOLOCHAIN_DEVICE_KEY_TYPE, MAX_DID_LENGTH};
+
+/// Upper bound for the serialized size of an agent profile.
+pub const MAX_AGENT_EXPRESSION_BYTES: usize = 256 * 1024;
+/// Upper bound for the serialized size of an entanglement proof.
+pub const MAX_ENTANGLEMENT_PROOF_BYTES: usize = 4 * 1024;
+
+// A DID is claimed by signing an entanglement proof for an agent key with it.
+// Profile links reference such a proof for their author in the tag, so only agent keys
+// the DID authorized can link profiles to it, no matter who published one first.
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(OpEntry::CreateEntry { app_entry, action })
+        | FlatOp::StoreRecord(OpRecord::CreateEntry { app_entry, action }) => {
+            validate_entry(app_entry, &action.author)
+        }
+        FlatOp::StoreEntry(OpEntry::UpdateEntry { app_entry, action, .. })
+        | FlatOp::StoreRecord(OpRecord::UpdateEntry { app_entry, action, .. }) => {
+            validate_entry_update(app_entry, &action)
+        }
+        FlatOp::RegisterCreateLink {
+            link_type: LinkTypes::ProfileLink,
+            base_address,
+            target_address,
+            tag,
+            action,
+            ..
+        } => validate_profile_link(&base_address, &target_address, &tag, &action.author),
+        FlatOp::RegisterCreateLink {
+            link_type: LinkTypes::AgentKeyLink,
+            base_address,
+            target_address,
+            action,
+            ..
+        } => validate_agent_key_link(&base_address, &target_address, &action.author),
+        FlatOp::RegisterDeleteLink {
+            original_action,
+            action,
+            ..
+        } => {
+            if original_action.author == action.author {
+                Ok(ValidateCallbackResult::Valid)
+            } else {
+                Ok(ValidateCallbackResult::Invalid(
+                    "Only the author of a link may delete it".to_string(),
+                ))
+            }
+        }
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+fn validate_entry(app_entry: EntryTypes, author: &AgentPubKey) -> ExternResult<ValidateCallbackResult> {
+    match app_entry {
+        EntryTypes::Did(did) => Ok(match did_key_public_key(&did.0) {
+            Ok(_) => ValidateCallbackResult::Valid,
+            Err(error) => ValidateCallbackResult::Invalid(format!("Invalid DID {}: {}", did.0, error)),
+        }),
+        EntryTypes::AgentExpression(expression) => {
+            let size = SerializedBytes::try_from(expression.clone())
+                .map_err(|err| wasm_error!(WasmErrorInner::Serialize(err)))?
+                .bytes()
+                .len();
+            if size > MAX_AGENT_EXPRESSION_BYTES {
+                return Ok(ValidateCallbackResult::Invalid(format!(
+                    "Agent expression of {} bytes exceeds the limit of {} bytes",
+                    size, MAX_AGENT_EXPRESSION_BYTES
+                )));
+            }
+            Ok(check_agent_expression(&expression))
+        }
+        EntryTypes::EntanglementProof(proof) => {
+            let size = SerializedBytes::try_from(proof.clone())
+                .map_err(|err| wasm_error!(WasmErrorInner::Serialize(err)))?
+                .bytes()
+                .len();
+            if size > MAX_ENTANGLEMENT_PROOF_BYTES {
+                return Ok(ValidateCallbackResult::Invalid(format!(
+                    "Entanglement proof of {} bytes exceeds the limit of {} bytes",
+                    size, MAX_ENTANGLEMENT_PROOF_BYTES
+                )));
+            }
+            Ok(check_proof_for_author(&proof, author))
+        }
+    }
+}
+
+/// Agents publish new profiles as updates of their previous one, which has to be
+/// for the same DID and signed earlier, so old profiles can't be replayed over newer ones.
+fn validate_entry_update(app_entry: EntryTypes, action: &Update) -> ExternResult<ValidateCallbackResult> {
+    if let EntryTypes::AgentExpression(expression) = &app_entry {
+        let original = must_get_valid_record(action.original_action_address.clone())?;
+        if original.action().author() != &action.author {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Agents can only update their own profiles".to_string(),
+            ));
+        }
+        let previous = match original
+            .entry()
+            .to_app_option::<AgentExpression>()
+            .map_err(|err| wasm_error!(WasmErrorInner::Serialize(err)))?
+        {
+            Some(previous) => previous,
+            None => {
+                return Ok(ValidateCallbackResult::Invalid(
+                    "Agent expressions can only update agent expressions".to_string(),
+                ))
+            }
+        };
+        let result = check_profile_update(&previous, expression);
+        if result != ValidateCallbackResult::Valid {
+            return Ok(result);
+        }
+    }
+    validate_entry(app_entry, &action.author)
+}
+
+fn check_profile_update(previous: &AgentExpression, expression: &AgentExpression) -> ValidateCallbackResult {
+    if previous.author != expression.author {
+        return ValidateCallbackResult::Invalid(format!(
+            "Profile of {} can't update a profile of {}",
+            expression.author, previous.author
+        ));
+    }
+    if expression.timestamp <= previous.timestamp {
+        return ValidateCallbackResult::Invalid(format!(
+            "Profile signed at {} is not newer than the profile it updates, signed at {}",
+            expression.timestamp, previous.timestamp
+        ));
+    }
+    ValidateCallbackResult::Valid
+}
+
+/// Agent key links point from a DID to an entanglement proof
+/// and can only be made by the agent the proof authorizes.
+fn validate_agent_key_link(
+    base_address: &AnyLinkableHash,
+    target_address: &AnyLinkableHash,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let proof = match target_address
+        .clone()
+        .into_entry_hash()
+        .map(|target| must_get_entry(target).map(|entry| EntanglementProof::try_from(entry.content)))
+        .transpose()?
+    {
+        Some(Ok(proof)) => proof,
+        _ => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Agent key links must point to an entanglement proof entry".to_string(),
+            ))
+        }
+    };
+
+    let did_address = AnyLinkableHash::from(hash_entry(Did(proof.did.clone()))?);
+    if &did_address != base_address {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Entanglement proof for {} can only be linked from its own DID",
+            proof.did
+        )));
+    }
+
+    Ok(check_proof_for_author(&proof, author))
+}
+
+/// Devices publish their own entanglement proofs: the proof must be for the author's
+/// agent key and the key must be signed by the DID.
+fn check_proof_for_author(proof: &EntanglementProof, author: &AgentPubKey) -> ValidateCallbackResult {
+    match check_entanglement_proof(proof, &AgentPubKeyB64::from(author.clone()).to_string()) {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(error) => ValidateCallbackResult::Invalid(error),
+    }
+}
+
+/// Profile links must point from a DID to a profile signed by that DID
+/// and reference an entanglement proof by which the DID authorized their author.
+fn validate_profile_link(
+    base_address: &AnyLinkableHash,
+    target_address: &AnyLinkableHash,
+    tag: &LinkTag,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let proof_hash = match profile_link_proof(tag) {
+        Some(proof_hash) => proof_hash,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Profile links must be tagged 'profile' followed by an entanglement proof hash"
+                    .to_string(),
+            ))
+        }
+    };
+
+    let target = match target_address.clone().into_entry_hash() {
+        Some(target) => target,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Profile links must point to an agent expression entry".to_string(),
+            ))
+        }
+    };
+    let expression = match AgentExpression::try_from(must_get_entry(target)?.content) {
+        Ok(expression) => expression,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Profile links must point to an agent expression entry".to_string(),
+            ))
+        }
+    };
+
+    let did_address = AnyLinkableHash::from(hash_entry(Did(expression.author.clone()))?);
+    if &did_address != base_address {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Profile of {} can only be linked from its own DID",
+            expression.author
+        )));
+    }
+
+    let proof = match EntanglementProof::try_from(must_get_entry(proof_hash)?.content) {
+        Ok(proof) => proof,
+        Err(_) => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "Profile link tags must reference an entanglement proof entry".to_string(),
+            ))
+        }
+    };
+    let result = check_profile_authorization(&proof, &expression, author);
+    if result != ValidateCallbackResult::Valid {
+        return Ok(result);
+    }
+
+    Ok(check_agent_expression(&expression))
+}
+
+fn check_profile_authorization(
+    proof: &EntanglementProof,
+    expression: &AgentExpression,
+    author: &AgentPubKey,
+) -> ValidateCallbackResult {
+    if proof.did != expression.author {
+        return ValidateCallbackResult::Invalid(format!(
+            "Entanglement proof for {} does not authorize profiles of {}",
+            proof.did, expression.author
+        ));
+    }
+    check_proof_for_author(proof, author)
+}
+
+fn check_agent_expression(expression: &AgentExpression) -> ValidateCallbackResult {
+    if expression.author.len() > MAX_DID_LENGTH {
+        return ValidateCallbackResult::Invalid(format!(
+            "DID exceeds the limit of {} characters",
+            MAX_DID_LENGTH
+        ));
+    }
+    if expression.data.did != expression.author {
+        return ValidateCallbackResult::Invalid(format!(
+            "Agent expression for {} was authored by {}",
+            expression.data.did, expression.author
+        ));
+    }
+    match verify_agent_expression_signature(expression) {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(error) => ValidateCallbackResult::Invalid(format!(
+            "Invalid signature on agent expression by {}: {}",
+            expression.author, error
+        )),
+    }
+}
+
+fn verify_agent_expression_signature(expression: &AgentExpression) -> Result<(), String> {
+    verify_expression_signature(
+        &expression.author,
+        &expression.data,
+        &expression.timestamp,
+        &expression.proof.signature,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        profile_link_tag, AgentExpressionData, ExpressionProof, Link, LinkExpression, Perspective,
+    };
+    use chrono::{DateTime, Duration, Utc};
+    use did_key_signatures::did_key;
+    use ed25519_dalek::{Signer, SigningKey};
+    use sha2::{Digest, Sha256};
+
+    fn did_for(key: &SigningKey) -> String {
+        did_key(&key.verifying_key())
+    }
+
+    fn signed_expression(key: &SigningKey) -> AgentExpression {
+        let did = did_for(key);
+        let timestamp: DateTime<Utc> = "2024-01-01T12:00:00.120Z".parse().unwrap();
+        let data = AgentExpressionData {
+            did: did.clone(),
+            perspective: Some(Perspective {
+                links: vec![LinkExpression {
+                    author: did.clone(),
+                    timestamp: "2024-01-01T11:00:00.000Z".to_string(),
+                    data: Link {
+                        source: "ad4m://self".to_string(),
+                        target: "literal://string:Alice".to_string(),
+                        predicate: None,
+                    },
+                    proof: ExpressionProof {
+                        signature: "00".to_string(),
+                        key: "key".to_string(),
+                    },
+                }],
+            }),
+            direct_message_language: None,
+        };
+        // What the executor signs: JSON with sorted keys, nulls for unset fields,
+        // and timestamps exactly as they were given
+        let signed_json = format!(
+            r#"{{"did":"{did}","directMessageLanguage":null,"perspective":{{"links":[{{"author":"{did}","data":{{"predicate":null,"source":"ad4m://self","target":"literal://string:Alice"}},"proof":{{"key":"key","signature":"00"}},"timestamp":"2024-01-01T11:00:00.000Z"}}]}}}}"#
+        );
+        let mut hasher = Sha256::new();
+        hasher.update(signed_json);
+        hasher.update("2024-01-01T12:00:00.120Z");
+        let signature = key.sign(&hasher.finalize());
+
+        AgentExpression {
+            author: did.clone(),
+            timestamp,
+            data,
+            proof: ExpressionProof {
+                signature: hex::encode(signature.to_bytes()),
+                key: format!("{}#primary", did),
+            },
+        }
+    }
+
+    #[test]
+    fn accepts_profiles_signed_by_their_did() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        assert_eq!(
+            check_agent_expression(&signed_expression(&key)),
+            ValidateCallbackResult::Valid
+        );
+    }
+
+    #[test]
+    fn rejects_forged_or_tampered_profiles() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+
+        let mut impostor = signed_expression(&key);
+        impostor.author = did_for(&other);
+        impostor.data.did = did_for(&other);
+        assert!(verify_agent_expression_signature(&impostor).is_err());
+
+        let mut foreign_did = signed_expression(&key);
+        foreign_did.data.did = did_for(&other);
+        assert_ne!(check_agent_expression(&foreign_did), ValidateCallbackResult::Valid);
+
+        let mut tampered = signed_expression(&key);
+        tampered.data.direct_message_language = Some("language://other".to_string());
+        assert!(verify_agent_expression_signature(&tampered).is_err());
+    }
+
+    fn entanglement_proof(key: &SigningKey, agent: &AgentPubKey) -> EntanglementProof {
+        let device_key = AgentPubKeyB64::from(agent.clone()).to_string();
+        EntanglementProof {
+            did: did_for(key),
+            did_signing_key_id: format!("{}#primary", did_for(key)),
+            device_key_type: HOLOCHAIN_DEVICE_KEY_TYPE.to_string(),
+            device_key_signed_by_did: hex::encode(
+                key.sign(&Sha256::digest(device_key.as_bytes())).to_bytes(),
+            ),
+            device_key,
+            did_signed_by_device_key: None,
+        }
+    }
+
+    #[test]
+    fn accepts_entanglement_proofs_for_the_publishing_agent() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let agent = AgentPubKey::from_raw_32(vec![1u8; 32]);
+        let proof = entanglement_proof(&key, &agent);
+        assert_eq!(check_proof_for_author(&proof, &agent), ValidateCallbackResult::Valid);
+
+        let other_agent = AgentPubKey::from_raw_32(vec![2u8; 32]);
+        assert_ne!(check_proof_for_author(&proof, &other_agent), ValidateCallbackResult::Valid);
+
+        let other_key = SigningKey::from_bytes(&[8u8; 32]);
+        let mut forged = entanglement_proof(&other_key, &agent);
+        forged.did = did_for(&key);
+        assert_ne!(check_proof_for_author(&forged, &agent), ValidateCallbackResult::Valid);
+
+        let mut wrong_type = entanglement_proof(&key, &agent);
+        wrong_type.device_key_type = "ethereum".to_string();
+        assert_ne!(check_proof_for_author(&wrong_type, &agent), ValidateCallbackResult::Valid);
+    }
+
+    #[test]
+    fn profile_links_need_a_proof_for_their_author_and_did() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let agent = AgentPubKey::from_raw_32(vec![1u8; 32]);
+        let expression = signed_expression(&key);
+        let proof = entanglement_proof(&key, &agent);
+        assert_eq!(
+            check_profile_authorization(&proof, &expression, &agent),
+            ValidateCallbackResult::Valid
+        );
+
+        let other_agent = AgentPubKey::from_raw_32(vec![2u8; 32]);
+        assert_ne!(
+            check_profile_authorization(&proof, &expression, &other_agent),
+            ValidateCallbackResult::Valid
+        );
+
+        let other_key = SigningKey::from_bytes(&[8u8; 32]);
+        let foreign_proof = entanglement_proof(&other_key, &agent);
+        assert_ne!(
+            check_profile_authorization(&foreign_proof, &expression, &agent),
+            ValidateCallbackResult::Valid
+        );
+    }
+
+    #[test]
+    fn profile_link_tags_reference_the_proof() {
+        let proof_hash = EntryHash::from_raw_32(vec![3u8; 32]);
+        assert_eq!(
+            profile_link_proof(&profile_link_tag(&proof_hash)),
+            Some(proof_hash)
+        );
+        assert_eq!(profile_link_proof(&LinkTag::new("profile")), None);
+        assert_eq!(profile_link_proof(&LinkTag::new("agent_key")), None);
+    }
+
+    #[test]
+    fn profile_updates_only_move_forward() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let previous = signed_expression(&key);
+
+        let mut newer = previous.clone();
+        newer.timestamp = previous.timestamp + Duration::seconds(1);
+        assert_eq!(check_profile_update(&previous, &newer), ValidateCallbackResult::Valid);
+
+        assert_ne!(check_profile_update(&previous, &previous), ValidateCallbackResult::Valid);
+        assert_ne!(check_profile_update(&newer, &previous), ValidateCallbackResult::Valid);
+
+        let mut other_did = newer.clone();
+        other_did.author = did_for(&SigningKey::from_bytes(&[8u8; 32]));
+        assert_ne!(check_profile_update(&previous, &other_did), ValidateCallbackResult::Valid);
+    }
+}
@@ -1,7 +1,7 @@
 import { Scenario, runScenario, addAllAgentsToAllConductors, cleanAllConductors } from '@holochain/tryorama'
 import path from 'path'
 import test from "tape-promise/tape";
-import { createConductors } from "./utils";
+import { createAgent, createConductors } from "./utils";
 
 //@ts-ignore
 test("Create update agent expression", async (t) => {
@@ -13,48 +13,51 @@ test("Create update agent expression", async (t) => {
 
     await scenario.shareAllAgents();
      
-    const call1 = await alice.cells[0].callZome({
+    const agent = createAgent();
+    const profile = (directMessageLanguage: string) => ({
+      did: agent.did,
+      perspective: {
+        links: [
+          {
+            author: agent.did,
+            timestamp: new Date().toISOString(),
+            data: {
+              source: "language://src",
+              predicate: "language://pred",
+              target: "language://target"
+            },
+            proof: {
+              signature: "sig",
+              key: "key",
+            },
+          }
+        ]
+      },
+      directMessageLanguage
+    });
+
+    const aliceKey = await alice.cells[0].callZome({
+      zome_name: "agent_store", 
+      fn_name: "get_agent_key", 
+      payload: null
+    });
+    await alice.cells[0].callZome({
+      zome_name: "agent_store", 
+      fn_name: "add_entanglement_proof", 
+      //@ts-ignore
+      payload: agent.createEntanglementProof(aliceKey)
+    });
+
+    await alice.cells[0].callZome({
       zome_name: "agent_store", 
       fn_name: "create_agent_expression",  
-      payload: {
-        author: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC",
-        timestamp: new Date().toISOString(),
-        data: {
-          did: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC",
-          perspective: {
-            links: [
-              {
-                author: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC",
-                timestamp: new Date().toISOString(),    
-                data: {
-                  source: "language://src",
-                  target: "language://target",
-                  predicate: "language://pred"
-                },
-                proof: {
-                  signature: "sig",
-                  key: "key",
-                  valid: true,
-                  invalid: false,
-                },
-              }
-            ]
-          },
-          directMessageLanguage: "language://hashyHash"
-        },
-        proof: {
-          signature: "sig",
-          key: "key",
-          valid: true,
-          invalid: false,
-        },
-      }
+      payload: agent.createSignedExpression(profile("language://hashyHash"))
     });
     
     let getResp = await alice.cells[0].callZome({
       zome_name: "agent_store", 
       fn_name: "get_agent_expression", 
-      payload: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC"
+      payload: agent.did
     });
     t.ok(getResp);
     //@ts-ignore
@@ -63,50 +66,38 @@ test("Create update agent expression", async (t) => {
     await alice.cells[0].callZome({
       zome_name: "agent_store", 
       fn_name: "create_agent_expression",  
-      payload:{
-        author: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC",
-        timestamp: new Date().toISOString(),
-        data: {
-          did: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC",
-          perspective: {
-            links: [
-              {
-                author: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC",
-                timestamp: new Date().toISOString(),    
-                data: {
-                  source: "language://src",
-                  target: "language://target",
-                  predicate: "language://pred"
-                },
-                proof: {
-                  signature: "sig",
-                  key: "key",
-                  valid: true,
-                  invalid: false,
-                },
-              }
-            ]
-          },
-          directMessageLanguage: "language://hashyHash2"
-        },
-        proof: {
-          signature: "sig",
-          key: "key",
-          valid: true,
-          invalid: false,
-        },
-      }
+      payload: agent.createSignedExpression(profile("language://hashyHash2"))
     })
 
+    let forgedError
+    try {
+      await bob.cells[0].callZome({
+        zome_name: "agent_store", 
+        fn_name: "create_agent_expression",  
+        payload: { ...agent.createSignedExpression(profile("language://hashyHash2")), author: createAgent().did }
+      })
+    } catch (e) {
+      forgedError = e
+    }
+    t.ok(forgedError, "profiles not signed by their DID are rejected");
+
     let getResp2 = await alice.cells[0].callZome({
       zome_name: "agent_store", 
       fn_name: "get_agent_expression", 
-      payload: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC"
+      payload: agent.did
     });
     t.ok(getResp2);
     //@ts-ignore
     t.deepEqual(getResp2.data.directMessageLanguage, "language://hashyHash2");
 
+    let history = await alice.cells[0].callZome({
+      zome_name: "agent_store", 
+      fn_name: "get_agent_expression_history", 
+      payload: agent.did
+    });
+    //@ts-ignore
+    t.deepEqual(history.map(expression => expression.data.directMessageLanguage), ["language://hashyHash", "language://hashyHash2"]);
+
 
     //====================
     await new Promise(r => setTimeout(r, 5000))
@@ -115,7 +106,7 @@ test("Create update agent expression", async (t) => {
     let bobResult = await bob.cells[0].callZome({
       zome_name: "agent_store", 
       fn_name: "get_agent_expression", 
-      payload: "did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC"
+      payload: agent.did
     });
     t.ok(bobResult);
     //@ts-ignore
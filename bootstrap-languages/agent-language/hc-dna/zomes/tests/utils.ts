@@ -3,6 +3,8 @@ import { dnas } from './common';
 import { createConductor } from "@holochain/tryorama";
 import { resolve } from "path";
 
+export { createAgent } from "../../../../shared/test-agent/index";
+
 export function sleep(ms: number) {
     return new Promise(resolve => setTimeout(resolve, ms));
 }
@@ -43,4 +45,4 @@ export async function createConductors(num: number): Promise<{agent_happ: AgentA
         }
     }
     return out
-}
\ No newline at end of file
+}
@@ -13,9 +13,6 @@ function constructorIcon(): string {
   return ConstructorIcon as unknown as string;
 }
 
-function interactions(expression: Address): Interaction[] {
-  return [];
-}
 
 //!@ad4m-template-variable
 export const name = "agent-expression-store";
@@ -27,11 +24,30 @@ export default async function create(context: LanguageContext): Promise<Language
     [{ file: DNA, nick: DNA_NICK, zomeCalls:
       [
         ["agent_store", "create_agent_expression"],
-        ["agent_store", "get_agent_expression"]
+        ["agent_store", "get_agent_expression"],
+        ["agent_store", "get_agent_expression_history"],
+        ["agent_store", "get_agent_key"],
+        ["agent_store", "add_entanglement_proof"],
+        ["agent_store", "get_entanglement_proofs"]
       ] 
     }], 
   );
 
+  // Lets clients audit profile changes of a DID through expressionInteract
+  function interactions(expression: Address): Interaction[] {
+    return [{
+      label: "Profile history",
+      name: "history",
+      parameters: [],
+      execute: async () => JSON.stringify(await Holochain.call(
+        DNA_NICK,
+        "agent_store",
+        "get_agent_expression_history",
+        expression
+      ))
+    }];
+  }
+
   const expressionAdapter = new ExpressionAdapter(context);
   const expressionUI = new UI();
 
@@ -1,22 +1,42 @@
-import { DirectMessageAdapter, HolochainLanguageDelegate, LanguageContext, MessageCallback, Perspective, PerspectiveExpression } from "https://esm.sh/@perspect3vism/ad4m@0.5.0";
+import { DirectMessageAdapter, Expression, HolochainLanguageDelegate, LanguageContext, MessageCallback, Perspective, PerspectiveExpression } from "https://esm.sh/@perspect3vism/ad4m@0.5.0";
 import { DNA, DNA_NICK } from "./build/dna.js";
 
+// Newer than the ad4m version imported above
+interface DirectMessage {
+  message: PerspectiveExpression
+  replyTo?: string
+}
+
+interface DirectMessageOptions {
+  replyTo?: string
+}
+
 //!@ad4m-template-variable
 const recipient_did = "<not templated yet>"
 
 //@ts-ignore
 export const sleep = ms => new Promise(r => setTimeout(r, ms))
 
+// What gets encrypted: either a message, optionally replying to another one,
+// or a signed receipt for messages the recipient sent earlier.
+interface Envelope {
+  message?: PerspectiveExpression
+  replyTo?: string
+  receipt?: Expression
+}
+
 export default class DMAdapter implements DirectMessageAdapter {
   #context: LanguageContext
   #holochain: HolochainLanguageDelegate;
   #messageCallbacks: MessageCallback[];
+  #receiptCallbacks: ((receipt: Expression) => void)[];
 
 
   constructor(context: LanguageContext) {
     this.#context = context
     this.#holochain = context.Holochain as HolochainLanguageDelegate;
     this.#messageCallbacks = []
+    this.#receiptCallbacks = []
   }
 
   async init() {
@@ -35,23 +55,32 @@ export default class DMAdapter implements DirectMessageAdapter {
             ["direct-message", "get_status"],
             ["direct-message", "fetch_inbox"],
             ["direct-message", "inbox"],
+            ["direct-message", "remove_from_inbox"],
           ] 
         }
       ], async (signal) => {
         console.debug("DM Language got HC signal:", signal)
-        //@ts-ignore
-        let payload = signal.payload
+        let envelope: Envelope
+        let parsed
         try {
           //@ts-ignore
           let string = signal.payload.toString()
           let cropped = string.substring(string.indexOf("{"))
-          let parsed = JSON.parse(cropped)
-          payload = parsed
+          parsed = JSON.parse(cropped)
+          envelope = that.decrypt(parsed)
         } catch(e) {
           console.error(e)
+          return
         }
-        for (const cb of that.#messageCallbacks) {
-          await cb(payload)
+        if(envelope.receipt) {
+          await that.receiptReceived(envelope.receipt)
+          // The zome stored it like any other message, it's handled now
+          await that.removeFromInbox([parsed.proof.signature])
+        } else {
+          for (const cb of that.#messageCallbacks) {
+            //@ts-ignore
+            await cb({ message: envelope.message, replyTo: envelope.replyTo })
+          }
         }
       });
   }
@@ -71,26 +100,64 @@ export default class DMAdapter implements DirectMessageAdapter {
     return status
   }
 
-  async sendP2P(message: Perspective): Promise<PerspectiveExpression|void> {
+  // Envelopes get encrypted to the recipient's DID key before they leave this agent,
+  // so neither the signal nor the public inbox entry reveal their content.
+  // The outer expression signs the ciphertext, the inner one the plaintext message.
+  encrypt(envelope: Envelope): object {
+    //@ts-ignore
+    const encrypted = this.#context.agent.encryptFor(recipient_did, JSON.stringify(envelope))
+    return this.#context.agent.createSignedExpression(encrypted)
+  }
+
+  decrypt(encryptedMessage: any): Envelope {
+    // Agents running versions from before encryption send plain PerspectiveExpressions
+    if(!encryptedMessage.data?.ciphertext) return { message: encryptedMessage }
+    //@ts-ignore
+    const decrypted = JSON.parse(this.#context.agent.decrypt(encryptedMessage.data))
+    // Sent before envelopes existed: a bare PerspectiveExpression
+    if(!decrypted.message && !decrypted.receipt) return { message: decrypted }
+    return decrypted
+  }
+
+  // Throws if the message couldn't be sent, so the caller can fall back to the inbox
+  async sendP2P(message: Perspective, options?: DirectMessageOptions): Promise<PerspectiveExpression|void> {
+    const messageExpression = this.#context.agent.createSignedExpression(message)
+    const envelope = { message: messageExpression, replyTo: options?.replyTo }
     try {
-      const messageExpression = this.#context.agent.createSignedExpression(message)
-      await this.#holochain.call(DNA_NICK, "direct-message", "send_p2p", messageExpression)
-      return messageExpression
+      await this.#holochain.call(DNA_NICK, "direct-message", "send_p2p", this.encrypt(envelope))
     } catch(e) {
       console.error("Direct Message Language: Error sending p2p to", recipient_did)
+      throw e
     }
+    return messageExpression
   }
 
-  async sendInbox(message: Perspective): Promise<PerspectiveExpression|void> {
+  async sendInbox(message: Perspective, options?: DirectMessageOptions): Promise<PerspectiveExpression|void> {
     try {
       const messageExpression = this.#context.agent.createSignedExpression(message)
-      await this.#holochain.call(DNA_NICK, "direct-message", "send_inbox", messageExpression)
+      const envelope = { message: messageExpression, replyTo: options?.replyTo }
+      await this.#holochain.call(DNA_NICK, "direct-message", "send_inbox", this.encrypt(envelope))
       return messageExpression
     } catch(e) {
       console.error("Direct Message Language: Error sending to inbox of", recipient_did)
     }
   }
 
+  async sendReceipt(receipt: Expression): Promise<void> {
+    const encrypted = this.encrypt({ receipt })
+    if(await this.status()) {
+      await this.#holochain.call(DNA_NICK, "direct-message", "send_p2p", encrypted)
+    } else {
+      await this.#holochain.call(DNA_NICK, "direct-message", "send_inbox", encrypted)
+    }
+  }
+
+  async receiptReceived(receipt: Expression) {
+    for (const cb of this.#receiptCallbacks) {
+      await cb(receipt)
+    }
+  }
+
   onlyRecipient() {
     console.log(recipient_did, this.#context.agent.did);
     if(recipient_did !== this.#context.agent.did) throw new Error("Only recipient can call this function!")
@@ -102,12 +169,41 @@ export default class DMAdapter implements DirectMessageAdapter {
     await this.#holochain.call(DNA_NICK, "direct-message", "set_status", statusExpression)
   }
 
-  async inbox(filter?: string): Promise<PerspectiveExpression[]> {
+  async removeFromInbox(signatures: string[]) {
+    if(signatures.length == 0) return
+    try {
+      await this.#holochain.call(DNA_NICK, "direct-message", "remove_from_inbox", signatures)
+    } catch(e) {
+      console.error("Direct Message Language: Couldn't remove handled receipts from inbox", e)
+    }
+  }
+
+  // Receipts that arrived through the DHT inbox get handed to the receipt callbacks
+  // and removed from the inbox, so they only get handled once.
+  //@ts-ignore
+  async inbox(filter?: string): Promise<DirectMessage[]> {
     this.onlyRecipient()
     //@ts-ignore
     await this.#holochain.call(DNA_NICK, "direct-message", "fetch_inbox", null)
     //@ts-ignore
-    return await this.#holochain.call(DNA_NICK, "direct-message", "inbox", filter)
+    const encryptedMessages = await this.#holochain.call(DNA_NICK, "direct-message", "inbox", filter)
+    const messages = []
+    const handledReceipts = []
+    for (const encryptedMessage of encryptedMessages) {
+      try {
+        const envelope = this.decrypt(encryptedMessage)
+        if(envelope.receipt) {
+          await this.receiptReceived(envelope.receipt)
+          handledReceipts.push(encryptedMessage.proof.signature)
+        } else {
+          messages.push({ message: envelope.message, replyTo: envelope.replyTo })
+        }
+      } catch(e) {
+        console.error("Direct Message Language: Couldn't decrypt message from", encryptedMessage.author, e)
+      }
+    }
+    await this.removeFromInbox(handledReceipts)
+    return messages
   }
 
   addMessageCallback(callback: MessageCallback) {
@@ -115,4 +211,9 @@ export default class DMAdapter implements DirectMessageAdapter {
     this.onlyRecipient()
     this.#messageCallbacks.push(callback)
   }
+
+  addReceiptCallback(callback: (receipt: Expression) => void) {
+    this.onlyRecipient()
+    this.#receiptCallbacks.push(callback)
+  }
 }
\ No newline at end of file
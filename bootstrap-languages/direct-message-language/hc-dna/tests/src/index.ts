@@ -2,6 +2,7 @@ import { Scenario, runScenario, Dna } from '@holochain/tryorama'
 import path from 'path'
 import test from "tape-promise/tape";
 import { resolve } from "path";
+import { createAgent } from "../../../../shared/test-agent/index";
 
 const dnas: Dna[] = [{ source: {path: path.join("../workdir/direct-message-language.dna") } }];
 
@@ -152,17 +153,14 @@ test("send direct message", async (t) => {
     // P2P Message:
     // ------------
 
-    const message1 = {
-      author: "did:test:test",
-      timestamp: new Date().toISOString(),
-      data: {
-        links: [],
-      },
-      proof: {
-        signature: "asdfasdfasdf",
-        key: "did:test:test#primary"
-      }
-    }
+    // Messages are encrypted to the recipient's DID by the executor,
+    // the zome only ever sees ciphertext
+    const sender = createAgent()
+    const message1 = sender.createSignedExpression({
+      ephemeralPublicKey: "8f40c5adb68f25624ae5b214ea767a6ec94d829d3d7b5e1ad1ba6f3e2138285f",
+      nonce: "69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37",
+      ciphertext: "Cy0Px6tfsfNDXfHXk2WKMGUyd5fC7ZFKNbAl8xJoYj6JKrLd",
+    })
 
     await bob.cells[0].callZome({
       zome_name: ZOME, 
@@ -186,8 +184,39 @@ test("send direct message", async (t) => {
     // Inbox Message:
     // --------------
 
-    const message2 = JSON.parse(JSON.stringify(message1))
-    message2.data.links.push(link)
+    const message2 = sender.createSignedExpression({
+      ephemeralPublicKey: "8f40c5adb68f25624ae5b214ea767a6ec94d829d3d7b5e1ad1ba6f3e2138285f",
+      nonce: "d1ba6f3e2138285f69696ee955b62b73cd62bda875fc73d6",
+      ciphertext: "x3T1bWZFlGsrDlnmNIGFAQAF3jJ8n2uoj8LyfXFbwqEwTNWP2bJ8vQ",
+    })
+
+    // Messages need a valid signature by their author
+    const forged = JSON.parse(JSON.stringify(message2))
+    forged.author = createAgent().did
+    let forgedError
+    try {
+      await bob.cells[0].callZome({
+        zome_name: ZOME, 
+        fn_name: "send_inbox", 
+        payload: forged
+      })
+    } catch(e) {
+      forgedError = e
+    }
+    t.ok(forgedError, "send_inbox rejects messages not signed by their author")
+
+    // Plaintext messages would be readable by anyone on the DHT and get rejected
+    let plaintextError
+    try {
+      await bob.cells[0].callZome({
+        zome_name: ZOME, 
+        fn_name: "send_inbox", 
+        payload: status
+      })
+    } catch(e) {
+      plaintextError = e
+    }
+    t.ok(plaintextError, "send_inbox rejects unencrypted messages")
 
     console.log("send_inbox:", await bob.cells[0].callZome({
       zome_name: ZOME, 
@@ -230,10 +259,12 @@ test("send direct message", async (t) => {
     inbox = await alice.cells[0].callZome({
       zome_name: ZOME, 
       fn_name: "inbox", 
-      payload: "did:test:test"
+      payload: sender.did
     })
     //@ts-ignore
     t.equal(inbox.length, 2)
+    //@ts-ignore
+    t.deepEqual(inbox.map(message => message.data.ciphertext).sort(), [message1.data.ciphertext, message2.data.ciphertext].sort())
 
     inbox = await alice.cells[0].callZome({
       zome_name: ZOME, 
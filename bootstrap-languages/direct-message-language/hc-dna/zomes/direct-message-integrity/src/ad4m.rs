@@ -34,3 +34,32 @@ pub struct PerspectiveExpression {
     pub data: Perspective,
     pub proof: ExpressionProof,
 }
+
+/// Message content encrypted to the recipient's DID key by the ad4m-executor.
+/// Fields are encoded the way the executor's agent.encryptFor() returns them.
+#[derive(Clone, Debug, Serialize, Deserialize, SerializedBytes)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedData {
+    pub ephemeral_public_key: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Content of a direct message: encrypted to the recipient, or the plain
+/// PerspectiveExpression that agents running versions from before encryption send.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageData {
+    Encrypted(EncryptedData),
+    Plaintext(Perspective),
+}
+
+/// Expression whose data is normally a PerspectiveExpression encrypted to the recipient.
+/// Only author and timestamp stay readable, so the inbox can still be filtered by author.
+#[derive(Clone, Debug, Serialize, Deserialize, SerializedBytes)]
+pub struct MessageExpression {
+    pub author: String,
+    pub timestamp: DateTime<Utc>,
+    pub data: MessageData,
+    pub proof: ExpressionProof,
+}
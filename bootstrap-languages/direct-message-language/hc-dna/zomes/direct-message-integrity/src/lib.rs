@@ -1,6 +1,7 @@
 use hdi::prelude::*;
 
 pub mod ad4m;
+pub mod validation;
 
 use ad4m::*;
 
@@ -16,23 +17,23 @@ impl Into<PerspectiveExpression> for StatusUpdate {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, SerializedBytes)]
-pub struct StoredMessage(pub PerspectiveExpression);
+pub struct StoredMessage(pub MessageExpression);
 
 app_entry!(StoredMessage);
 
-impl Into<PerspectiveExpression> for StoredMessage {
-    fn into(self) -> PerspectiveExpression {
+impl Into<MessageExpression> for StoredMessage {
+    fn into(self) -> MessageExpression {
         self.0
     }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, SerializedBytes)]
-pub struct PublicMessage(pub PerspectiveExpression);
+pub struct PublicMessage(pub MessageExpression);
 
 app_entry!(PublicMessage);
 
-impl Into<PerspectiveExpression> for PublicMessage {
-    fn into(self) -> PerspectiveExpression {
+impl Into<MessageExpression> for PublicMessage {
+    fn into(self) -> MessageExpression {
         self.0
     }
 }
@@ -75,3 +76,8 @@ pub enum EntryTypes {
 pub enum LinkTypes {
     Message,
 }
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    validation::validate(op)
+}
@@ -0,0 +1,207 @@
+use did_key_signatures::verify_expression_signature;
+use hdi::prelude::*;
+
+use crate::ad4m::{MessageData, MessageExpression};
+use crate::{EntryTypes, LinkTypes, Recipient};
+
+/// Upper bound for the serialized size of any entry of this DNA.
+pub const MAX_ENTRY_BYTES: usize = 512 * 1024;
+
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(OpEntry::CreateEntry { app_entry, .. })
+        | FlatOp::StoreEntry(OpEntry::UpdateEntry { app_entry, .. })
+        | FlatOp::StoreRecord(OpRecord::CreateEntry { app_entry, .. })
+        | FlatOp::StoreRecord(OpRecord::UpdateEntry { app_entry, .. }) => validate_entry(app_entry),
+        FlatOp::RegisterDelete(OpDelete { action })
+        | FlatOp::StoreRecord(OpRecord::DeleteEntry { action, .. }) => {
+            validate_entry_delete(&action)
+        }
+        FlatOp::RegisterDeleteLink {
+            link_type: LinkTypes::Message,
+            base_address,
+            action,
+            ..
+        } => validate_inbox_link_delete(&base_address, action.author),
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+fn validate_entry(app_entry: EntryTypes) -> ExternResult<ValidateCallbackResult> {
+    let size = match &app_entry {
+        EntryTypes::StatusUpdate(status) => serialized_size(status)?,
+        EntryTypes::StoredMessage(message) => serialized_size(message)?,
+        EntryTypes::PublicMessage(message) => serialized_size(message)?,
+        EntryTypes::Recipient(_) => return Ok(ValidateCallbackResult::Valid),
+    };
+    if size > MAX_ENTRY_BYTES {
+        return Ok(ValidateCallbackResult::Invalid(format!(
+            "Entry of {} bytes exceeds the limit of {} bytes",
+            size, MAX_ENTRY_BYTES
+        )));
+    }
+
+    match app_entry {
+        EntryTypes::StoredMessage(message) => Ok(check_message_signature(&message.0)),
+        // Only messages received from agents running older versions may be plaintext,
+        // anything put on the DHT would be readable by everyone.
+        EntryTypes::PublicMessage(message) => match message.0.data {
+            MessageData::Plaintext(_) => Ok(ValidateCallbackResult::Invalid(
+                "Messages in the public inbox have to be encrypted".to_string(),
+            )),
+            MessageData::Encrypted(_) => Ok(check_message_signature(&message.0)),
+        },
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+fn serialized_size<T>(entry: &T) -> ExternResult<usize>
+where
+    T: Clone,
+    SerializedBytes: TryFrom<T, Error = SerializedBytesError>,
+{
+    Ok(SerializedBytes::try_from(entry.clone())
+        .map_err(|err| wasm_error!(WasmErrorInner::Serialize(err)))?
+        .bytes()
+        .len())
+}
+
+/// Messages and status updates can only be deleted by the agent that created them.
+/// Recipients take messages out of their inbox by deleting the inbox link instead.
+fn validate_entry_delete(action: &Delete) -> ExternResult<ValidateCallbackResult> {
+    let original = must_get_action(action.deletes_address.clone())?;
+    Ok(check_delete_author(
+        original.action().author(),
+        &action.author,
+    ))
+}
+
+fn check_delete_author(
+    original_author: &AgentPubKey,
+    author: &AgentPubKey,
+) -> ValidateCallbackResult {
+    if original_author == author {
+        ValidateCallbackResult::Valid
+    } else {
+        ValidateCallbackResult::Invalid("Only the author of an entry may delete it".to_string())
+    }
+}
+
+/// Inbox links hang off the recipient's `Recipient` entry,
+/// so only the agent whose entry hashes to the link base may remove them.
+fn validate_inbox_link_delete(
+    base_address: &AnyLinkableHash,
+    author: AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let recipient_address = AnyLinkableHash::from(hash_entry(Recipient(author))?);
+    if &recipient_address == base_address {
+        Ok(ValidateCallbackResult::Valid)
+    } else {
+        Ok(ValidateCallbackResult::Invalid(
+            "Only the recipient may delete inbox links".to_string(),
+        ))
+    }
+}
+
+fn check_message_signature(message: &MessageExpression) -> ValidateCallbackResult {
+    match verify_message_signature(message) {
+        Ok(()) => ValidateCallbackResult::Valid,
+        Err(error) => ValidateCallbackResult::Invalid(format!(
+            "Invalid signature on message by {}: {}",
+            message.author, error
+        )),
+    }
+}
+
+fn verify_message_signature(message: &MessageExpression) -> Result<(), String> {
+    verify_expression_signature(
+        &message.author,
+        &message.data,
+        &message.timestamp,
+        &message.proof.signature,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ad4m::{EncryptedData, ExpressionProof};
+    use chrono::{DateTime, Utc};
+    use did_key_signatures::did_key;
+    use ed25519_dalek::{Signer, SigningKey};
+    use sha2::{Digest, Sha256};
+
+    fn did_for(key: &SigningKey) -> String {
+        did_key(&key.verifying_key())
+    }
+
+    fn signed_message(key: &SigningKey) -> MessageExpression {
+        let timestamp: DateTime<Utc> = "2024-01-01T12:00:00.123Z".parse().unwrap();
+        let data = EncryptedData {
+            ephemeral_public_key: "00".repeat(32),
+            nonce: "11".repeat(24),
+            ciphertext: "c2VjcmV0".to_string(),
+        };
+        let mut hasher = Sha256::new();
+        hasher.update(
+            // What the executor signs: JSON with sorted keys
+            r#"{"ciphertext":"c2VjcmV0","ephemeralPublicKey":""#.to_string()
+                + &data.ephemeral_public_key
+                + r#"","nonce":""#
+                + &data.nonce
+                + r#""}"#,
+        );
+        hasher.update("2024-01-01T12:00:00.123Z");
+        let signature = key.sign(&hasher.finalize());
+
+        MessageExpression {
+            author: did_for(key),
+            timestamp,
+            data: MessageData::Encrypted(data),
+            proof: ExpressionProof {
+                signature: hex::encode(signature.to_bytes()),
+                key: format!("{}#primary", did_for(key)),
+            },
+        }
+    }
+
+    #[test]
+    fn only_authors_can_delete_entries() {
+        let author = AgentPubKey::from_raw_32(vec![1u8; 32]);
+        let recipient = AgentPubKey::from_raw_32(vec![2u8; 32]);
+        assert_eq!(
+            check_delete_author(&author, &author),
+            ValidateCallbackResult::Valid
+        );
+        assert_ne!(
+            check_delete_author(&author, &recipient),
+            ValidateCallbackResult::Valid
+        );
+    }
+
+    #[test]
+    fn accepts_messages_signed_by_author() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        assert_eq!(verify_message_signature(&signed_message(&key)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_forged_or_tampered_messages() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+
+        let mut impostor = signed_message(&key);
+        impostor.author = did_for(&other);
+        assert!(verify_message_signature(&impostor).is_err());
+
+        let mut tampered = signed_message(&key);
+        if let MessageData::Encrypted(data) = &mut tampered.data {
+            data.ciphertext = "b3RoZXI=".to_string();
+        }
+        assert!(verify_message_signature(&tampered).is_err());
+
+        let mut unsigned = signed_message(&key);
+        unsigned.author = "did:test:test".to_string();
+        assert!(verify_message_signature(&unsigned).is_err());
+    }
+}
@@ -119,7 +119,7 @@ pub fn get_status(_: ()) -> ExternResult<Option<PerspectiveExpression>> {
 #[hdk_extern]
 fn recv_remote_signal(signal: SerializedBytes) -> ExternResult<()> {
     debug!("RECEIVEING MESSAGE...");
-    match PerspectiveExpression::try_from(signal) {
+    match MessageExpression::try_from(signal) {
         Ok(message) => {
             let json = serde_json::to_string(&message).unwrap();
             emit_signal(
@@ -131,7 +131,7 @@ fn recv_remote_signal(signal: SerializedBytes) -> ExternResult<()> {
         }
         Err(error) => {
             let error_message = format!(
-                "Received signal that does not parse to PerspectiveExpression: {}",
+                "Received signal that does not parse to MessageExpression: {}",
                 error
             );
             debug!("Error in recv_remote_sigal: {}", error_message);
@@ -141,7 +141,7 @@ fn recv_remote_signal(signal: SerializedBytes) -> ExternResult<()> {
 }
 
 #[hdk_extern]
-fn inbox(did_filter: Option<String>) -> ExternResult<Vec<PerspectiveExpression>> {
+fn inbox(did_filter: Option<String>) -> ExternResult<Vec<MessageExpression>> {
     //debug!("INBOX({:?})", did_filter);
     let mut filter = QueryFilter::new();
     filter.entry_type = Some(vec![EntryType::App(AppEntryDef::new(
@@ -154,7 +154,7 @@ fn inbox(did_filter: Option<String>) -> ExternResult<Vec<PerspectiveExpression>>
         .into_iter()
         .map(|val| {
             val.entry()
-                .to_app_option::<PerspectiveExpression>()
+                .to_app_option::<MessageExpression>()
                 .map_err(|err| wasm_error!(WasmErrorInner::Host(err.to_string())))?
                 .ok_or(wasm_error!(WasmErrorInner::Host(
                     "Expected entry to contain data".to_string()
@@ -176,8 +176,33 @@ fn inbox(did_filter: Option<String>) -> ExternResult<Vec<PerspectiveExpression>>
         .collect::<Result<Vec<_>, _>>()?)
 }
 
+/// Deletes the stored messages with the given signatures,
+/// e.g. receipts that have been handled already.
 #[hdk_extern]
-pub fn send_p2p(message: PerspectiveExpression) -> ExternResult<()> {
+fn remove_from_inbox(signatures: Vec<String>) -> ExternResult<()> {
+    let mut filter = QueryFilter::new();
+    filter.entry_type = Some(vec![EntryType::App(AppEntryDef::new(
+        1.into(),
+        0.into(),
+        EntryVisibility::Private,
+    ))]);
+    filter.include_entries = true;
+    for record in query(filter)? {
+        let message = record
+            .entry()
+            .to_app_option::<MessageExpression>()
+            .map_err(|err| wasm_error!(WasmErrorInner::Host(err.to_string())))?;
+        if let Some(message) = message {
+            if signatures.contains(&message.proof.signature) {
+                delete_entry(record.action_address().clone())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[hdk_extern]
+pub fn send_p2p(message: MessageExpression) -> ExternResult<()> {
     //debug!("SENDING MESSAGE...");
     send_remote_signal(
         SerializedBytes::try_from(message)
@@ -187,7 +212,7 @@ pub fn send_p2p(message: PerspectiveExpression) -> ExternResult<()> {
 }
 
 #[hdk_extern]
-pub fn send_inbox(message: PerspectiveExpression) -> ExternResult<()> {
+pub fn send_inbox(message: MessageExpression) -> ExternResult<()> {
     //debug!("SEND INBOX");
     let entry = PublicMessage(message);
     let entry_hash = hash_entry(&entry)?;
@@ -227,12 +252,11 @@ pub fn fetch_inbox(_: ()) -> ExternResult<()> {
                 GetOptions::network(),
             )? {
                 //debug!("fetch_inbox link got");
-                let header_address = message_entry.action_address().clone();
                 let public_message = PublicMessage::try_from(message_entry)?;
-                let message: PerspectiveExpression = public_message.into();
+                let message: MessageExpression = public_message.into();
                 create_entry(EntryTypes::StoredMessage(StoredMessage(message)))?;
+                // The public entry belongs to its sender, removing the link takes it out of the inbox
                 delete_link(link.create_link_hash)?;
-                delete_entry(header_address)?;
             } else {
                 error!("Message linked in inbox not retrievable")
             }
@@ -244,18 +268,3 @@ pub fn fetch_inbox(_: ()) -> ExternResult<()> {
         )))
     }
 }
-
-/*
-#[hdk_extern]
-fn validate_delete_link(
-    validate_delete_link: ValidateDeleteLinkData,
-) -> ExternResult<ValidateLinkCallbackResult> {
-    let delete_link = validate_delete_link.delete_link;
-    let recipient = recipient()?;
-    if delete_link.author == recipient {
-        Ok(ValidateLinkCallbackResult::Valid)
-    } else {
-        Ok(ValidateLinkCallbackResult::Invalid("Only recipient is allowed to delete inbox links".to_string()),)
-    }
-}
- */
@@ -2,11 +2,12 @@
 extern crate lazy_static;
 
 use hdk::prelude::*;
+use holo_hash::AgentPubKeyB64;
 use inputs::PullArguments;
 use lazy_static::lazy_static;
 
 use perspective_diff_sync_integrity::{
-    HashBroadcast, OnlineAgent, OnlineAgentAndAction, Perspective, PerspectiveDiff,
+    EntanglementProof, HashBroadcast, OnlineAgent, OnlineAgentAndAction, Perspective, PerspectiveDiff,
     PerspectiveExpression, PullResult,
 };
 
@@ -134,6 +135,18 @@ pub fn create_did_pub_key_link(did: String) -> ExternResult<()> {
     Ok(())
 }
 
+#[hdk_extern]
+pub fn get_agent_key(_: ()) -> ExternResult<AgentPubKeyB64> {
+    Ok(agent_info()?.agent_initial_pubkey.into())
+}
+
+#[hdk_extern]
+pub fn add_entanglement_proof(proof: EntanglementProof) -> ExternResult<()> {
+    telepresence::status::add_entanglement_proof(proof)
+        .map_err(|error| utils::err(&format!("{}", error)))?;
+    Ok(())
+}
+
 #[hdk_extern]
 pub fn get_online_agents(_: ()) -> ExternResult<Vec<OnlineAgent>> {
     let res = telepresence::status::get_online_agents()
@@ -9,7 +9,7 @@ use crate::link_adapter::revisions::{current_revision, update_current_revision};
 use crate::link_adapter::snapshots::generate_snapshot;
 use crate::retriever::holochain::{get_active_agent_anchor, get_active_agents};
 use crate::retriever::PerspectiveDiffRetreiver;
-use crate::telepresence::status::get_my_did;
+use crate::telepresence::status::{get_my_did, get_my_did_proof};
 use crate::utils::get_now;
 use crate::{Hash, ENABLE_SIGNALS, SNAPSHOT_INTERVAL};
 
@@ -47,6 +47,7 @@ pub fn commit<Retriever: PerspectiveDiffRetreiver>(
         diff: diff_entry_create.clone(),
         parents: current_revision.map(|val| vec![val.hash]),
         diffs_since_snapshot: entries_since_snapshot,
+        did_proof: get_my_did_proof()?,
     };
     let diff_entry_reference = Retriever::create_entry(EntryTypes::PerspectiveDiffEntryReference(
         diff_entry_ref_entry.clone(),
@@ -34,6 +34,7 @@ fn merge<Retriever: PerspectiveDiffRetreiver>(
         diffs_since_snapshot: latest_diff.diffs_since_snapshot
             + current_diff.diffs_since_snapshot
             + 1,
+        did_proof: None,
     };
     let merge_entry_reference_hash = Retriever::create_entry(
         EntryTypes::PerspectiveDiffEntryReference(merge_entry_reference.clone()),
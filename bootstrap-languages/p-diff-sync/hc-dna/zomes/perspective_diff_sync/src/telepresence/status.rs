@@ -1,7 +1,8 @@
 use hdk::prelude::*;
 
 use perspective_diff_sync_integrity::{
-    Anchor, EntryTypes, LinkTypes, OnlineAgent, OnlineAgentAndAction, PerspectiveExpression,
+    Anchor, EntanglementProof, EntryTypes, LinkTypes, OnlineAgent, OnlineAgentAndAction,
+    PerspectiveExpression,
 };
 
 use crate::errors::{SocialContextError, SocialContextResult};
@@ -88,6 +89,30 @@ pub fn create_did_pub_key_link(did: String) -> SocialContextResult<()> {
     Ok(())
 }
 
+/// Stores the proof that our agent key acts for our DID,
+/// to be attached to our commits.
+pub fn add_entanglement_proof(proof: EntanglementProof) -> SocialContextResult<()> {
+    create_entry(&EntryTypes::PrivateEntanglementProof(proof))?;
+    Ok(())
+}
+
+pub fn get_my_did_proof() -> SocialContextResult<Option<EntanglementProof>> {
+    let records = query(
+        QueryFilter::new()
+            .entry_type(EntryType::App(AppEntryDef {
+                entry_index: 8.into(),
+                zome_index: 0.into(),
+                visibility: EntryVisibility::Private,
+            }))
+            .include_entries(true)
+            .descending(),
+    )?;
+    match records.first() {
+        Some(record) => Ok(record.entry().to_app_option::<EntanglementProof>()?),
+        None => Ok(None),
+    }
+}
+
 pub fn get_my_did() -> SocialContextResult<Option<String>> {
     let input = GetLinksInputBuilder::try_new(
         agent_info()?.agent_latest_pubkey,
@@ -51,6 +51,7 @@ impl PerspectiveDiffEntryReference {
             diff: diff,
             parents: parents,
             diffs_since_snapshot: 0,
+            did_proof: None,
         }
     }
 }
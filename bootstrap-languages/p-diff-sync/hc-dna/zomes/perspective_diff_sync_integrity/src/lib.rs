@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use hdi::prelude::*;
 
 pub mod impls;
+pub mod validation;
 
 #[derive(
     Serialize, Deserialize, Clone, SerializedBytes, Debug, PartialEq, Eq, Hash, Ord, PartialOrd,
@@ -57,6 +58,8 @@ impl PerspectiveDiff {
 
 app_entry!(PerspectiveDiff);
 
+pub use did_key_signatures::EntanglementProof;
+
 #[derive(Clone, Debug, Serialize, Deserialize, SerializedBytes)]
 pub struct Snapshot {
     pub diff_chunks: Vec<HoloHash<holo_hash::hash_type::Action>>,
@@ -70,6 +73,10 @@ pub struct PerspectiveDiffEntryReference {
     pub diff: HoloHash<holo_hash::hash_type::Action>,
     pub parents: Option<Vec<HoloHash<holo_hash::hash_type::Action>>>,
     pub diffs_since_snapshot: usize,
+    /// Proves which DID the committing agent acts for,
+    /// needed to commit to neighbourhoods with a membership policy
+    #[serde(default)]
+    pub did_proof: Option<EntanglementProof>,
 }
 
 app_entry!(PerspectiveDiffEntryReference);
@@ -156,6 +163,8 @@ pub enum EntryTypes {
     Anchor(Anchor),
     #[entry_type(visibility = "private")]
     PrivateOnlineStatus(PerspectiveExpression),
+    #[entry_type(visibility = "private")]
+    PrivateEntanglementProof(EntanglementProof),
 }
 
 #[hdk_link_types]
@@ -167,3 +176,8 @@ pub enum LinkTypes {
     Index,
     DidLink,
 }
+
+#[hdk_extern]
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    validation::validate(op)
+}
@@ -0,0 +1,269 @@
+use did_key_signatures::{check_entanglement_proof, verify_expression_signature};
+use hdi::prelude::*;
+use holo_hash::AgentPubKeyB64;
+pub use neighbourhood_membership::MembershipPolicy;
+
+use crate::{
+    EntanglementProof, EntryTypes, LinkExpression, LinkTypes, PerspectiveDiff,
+    PerspectiveDiffEntryReference,
+};
+
+pub use did_key_signatures::HOLOCHAIN_DEVICE_KEY_TYPE;
+
+#[derive(Serialize, Deserialize, Debug, SerializedBytes)]
+struct Properties {
+    #[serde(default)]
+    membership: Option<MembershipPolicy>,
+}
+
+/// Returns the membership policy this DNA was templated with,
+/// or None for open neighbourhoods.
+pub fn membership_policy() -> ExternResult<Option<MembershipPolicy>> {
+    let properties = Properties::try_from(dna_info()?.modifiers.properties)
+        .map_err(|err| wasm_error!(WasmErrorInner::Guest(err.to_string())))?;
+    Ok(properties.membership)
+}
+
+pub fn validate(op: Op) -> ExternResult<ValidateCallbackResult> {
+    match op.flattened::<EntryTypes, LinkTypes>()? {
+        FlatOp::StoreEntry(OpEntry::CreateEntry { app_entry, action })
+        | FlatOp::StoreRecord(OpRecord::CreateEntry { app_entry, action }) => match app_entry {
+            EntryTypes::PerspectiveDiffEntryReference(reference) => {
+                validate_diff_reference(&reference, &action.author)
+            }
+            _ => Ok(ValidateCallbackResult::Valid),
+        },
+        _ => Ok(ValidateCallbackResult::Valid),
+    }
+}
+
+/// Rejects references to diffs the membership policy does not permit
+/// the committing agent, or the authors of the added links, to make.
+fn validate_diff_reference(
+    reference: &PerspectiveDiffEntryReference,
+    author: &AgentPubKey,
+) -> ExternResult<ValidateCallbackResult> {
+    let policy = match membership_policy()? {
+        Some(policy) => policy,
+        None => return Ok(ValidateCallbackResult::Valid),
+    };
+
+    let record = must_get_valid_record(reference.diff.clone())?;
+    let diff = match record
+        .entry()
+        .to_app_option::<PerspectiveDiff>()
+        .map_err(|err| wasm_error!(WasmErrorInner::Serialize(err)))?
+    {
+        Some(diff) => diff,
+        None => {
+            return Ok(ValidateCallbackResult::Invalid(
+                "PerspectiveDiffEntryReference does not point to a PerspectiveDiff".to_string(),
+            ))
+        }
+    };
+
+    // Merge entries only tie together diffs that were validated on their own
+    if diff.total_diff_number() == 0 {
+        return Ok(ValidateCallbackResult::Valid);
+    }
+
+    let committer = match &reference.did_proof {
+        Some(proof) => match check_did_proof(proof, author) {
+            Ok(()) => &proof.did,
+            Err(error) => return Ok(ValidateCallbackResult::Invalid(error)),
+        },
+        None => return Ok(ValidateCallbackResult::Invalid(
+            "Diffs in neighbourhoods with a membership policy need a proof of the committer's DID"
+                .to_string(),
+        )),
+    };
+
+    Ok(check_diff(&policy, committer, &diff))
+}
+
+/// The committing agent key has to be authorized by the DID it claims to act for.
+fn check_did_proof(proof: &EntanglementProof, author: &AgentPubKey) -> Result<(), String> {
+    check_entanglement_proof(proof, &AgentPubKeyB64::from(author.clone()).to_string())
+}
+
+fn check_diff(
+    policy: &MembershipPolicy,
+    committer: &str,
+    diff: &PerspectiveDiff,
+) -> ValidateCallbackResult {
+    if !policy.can_write(committer) {
+        return ValidateCallbackResult::Invalid(format!(
+            "{} is not permitted to write to this neighbourhood",
+            committer
+        ));
+    }
+
+    for link in &diff.additions {
+        if !policy.can_write(&link.author) {
+            return ValidateCallbackResult::Invalid(format!(
+                "Links by {} can't be added, they are not permitted to write to this neighbourhood",
+                link.author
+            ));
+        }
+        if let Err(error) = verify_link_signature(link) {
+            return ValidateCallbackResult::Invalid(format!(
+                "Invalid signature on link by {}: {}",
+                link.author, error
+            ));
+        }
+    }
+
+    // Removals carry the removed links as they were authored, possibly by agents
+    // that are no longer permitted to write, so only the committer gets checked for them.
+    ValidateCallbackResult::Valid
+}
+
+fn verify_link_signature(link: &LinkExpression) -> Result<(), String> {
+    verify_expression_signature(
+        &link.author,
+        &link.data,
+        &link.timestamp,
+        &link.proof.signature,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExpressionProof, Triple};
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use did_key_signatures::did_key;
+    use ed25519_dalek::{Signer, SigningKey};
+    use sha2::{Digest, Sha256};
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn did(seed: u8) -> String {
+        did_key(&key(seed).verifying_key())
+    }
+
+    const AUTHOR: u8 = 1;
+    const MEMBER: u8 = 2;
+    const READER: u8 = 3;
+    const STRANGER: u8 = 4;
+
+    fn link_by(seed: u8) -> LinkExpression {
+        let data = Triple {
+            source: Some("ad4m://self".to_string()),
+            target: Some("literal://string:hello".to_string()),
+            predicate: None,
+        };
+        let timestamp: DateTime<Utc> = "2024-01-01T12:00:00.000Z".parse().unwrap();
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_vec(&serde_json::to_value(&data).unwrap()).unwrap());
+        hasher.update(timestamp.to_rfc3339_opts(SecondsFormat::Millis, true));
+        LinkExpression {
+            author: did(seed),
+            data,
+            timestamp,
+            proof: ExpressionProof {
+                signature: hex::encode(key(seed).sign(&hasher.finalize()).to_bytes()),
+                key: format!("{}#primary", did(seed)),
+            },
+        }
+    }
+
+    fn policy() -> MembershipPolicy {
+        MembershipPolicy {
+            author: did(AUTHOR),
+            members_only: true,
+            members: vec![did(MEMBER), did(READER)],
+            read_only: vec![did(READER)],
+        }
+    }
+
+    fn did_proof(seed: u8, agent: &AgentPubKey) -> EntanglementProof {
+        let device_key = AgentPubKeyB64::from(agent.clone()).to_string();
+        EntanglementProof {
+            did: did(seed),
+            did_signing_key_id: format!("{}#primary", did(seed)),
+            device_key_type: HOLOCHAIN_DEVICE_KEY_TYPE.to_string(),
+            device_key_signed_by_did: hex::encode(
+                key(seed)
+                    .sign(&Sha256::digest(device_key.as_bytes()))
+                    .to_bytes(),
+            ),
+            device_key,
+            did_signed_by_device_key: None,
+        }
+    }
+
+    #[test]
+    fn accepts_did_proofs_for_the_committing_agent() {
+        let agent = AgentPubKey::from_raw_32(vec![1u8; 32]);
+        assert_eq!(check_did_proof(&did_proof(MEMBER, &agent), &agent), Ok(()));
+
+        let other_agent = AgentPubKey::from_raw_32(vec![2u8; 32]);
+        assert!(check_did_proof(&did_proof(MEMBER, &agent), &other_agent).is_err());
+
+        let mut forged = did_proof(STRANGER, &agent);
+        forged.did = did(MEMBER);
+        assert!(check_did_proof(&forged, &agent).is_err());
+    }
+
+    #[test]
+    fn only_permitted_committers_can_write() {
+        let diff = PerspectiveDiff {
+            additions: vec![link_by(AUTHOR)],
+            removals: vec![],
+        };
+        assert_eq!(
+            check_diff(&policy(), &did(MEMBER), &diff),
+            ValidateCallbackResult::Valid
+        );
+        assert_ne!(
+            check_diff(&policy(), &did(READER), &diff),
+            ValidateCallbackResult::Valid
+        );
+        assert_ne!(
+            check_diff(&policy(), &did(STRANGER), &diff),
+            ValidateCallbackResult::Valid
+        );
+    }
+
+    #[test]
+    fn rejects_added_links_from_non_writers_or_with_invalid_signatures() {
+        let by_reader = PerspectiveDiff {
+            additions: vec![link_by(MEMBER), link_by(READER)],
+            removals: vec![],
+        };
+        assert_ne!(
+            check_diff(&policy(), &did(MEMBER), &by_reader),
+            ValidateCallbackResult::Valid
+        );
+
+        let mut forged = link_by(STRANGER);
+        forged.author = did(MEMBER);
+        let forged = PerspectiveDiff {
+            additions: vec![forged],
+            removals: vec![],
+        };
+        assert_ne!(
+            check_diff(&policy(), &did(MEMBER), &forged),
+            ValidateCallbackResult::Valid
+        );
+    }
+
+    #[test]
+    fn members_can_remove_links_by_anyone() {
+        let diff = PerspectiveDiff {
+            additions: vec![],
+            removals: vec![link_by(READER), link_by(STRANGER)],
+        };
+        assert_eq!(
+            check_diff(&policy(), &did(MEMBER), &diff),
+            ValidateCallbackResult::Valid
+        );
+        assert_ne!(
+            check_diff(&policy(), &did(READER), &diff),
+            ValidateCallbackResult::Valid
+        );
+    }
+}
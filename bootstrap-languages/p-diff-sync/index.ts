@@ -29,6 +29,8 @@ export default async function create(context: LanguageContext): Promise<Language
         [ZOME_NAME, "get_others"],
         [ZOME_NAME, "add_active_agent_link"],
         [ZOME_NAME, "create_did_pub_key_link"],
+        [ZOME_NAME, "get_agent_key"],
+        [ZOME_NAME, "add_entanglement_proof"],
       ]
     }],
     async (signal) => { 
@@ -46,6 +48,12 @@ export default async function create(context: LanguageContext): Promise<Language
   //Setup the link between did and agent pub key
   await Holochain.call(DNA_NICK, ZOME_NAME, "create_did_pub_key_link", agent.did);
 
+  //Prove that our agent key acts for our DID, so neighbourhoods with a membership policy accept our commits
+  const agentKey = await Holochain.call(DNA_NICK, ZOME_NAME, "get_agent_key", null);
+  //@ts-ignore
+  const proof = agent.createEntanglementProof("holochain", agentKey);
+  await Holochain.call(DNA_NICK, ZOME_NAME, "add_entanglement_proof", proof);
+
   //@ts-ignore
   return {
     name,
@@ -0,0 +1,97 @@
+//! Entanglement proofs by which a DID authorizes a device key to act for it.
+
+#[cfg(feature = "hdi")]
+use hdi::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::verify_string_signature;
+
+/// Device key type of entanglement proofs for Holochain agent keys
+pub const HOLOCHAIN_DEVICE_KEY_TYPE: &str = "holochain";
+
+/// Authorizes a Holochain agent key to act for a DID,
+/// as created by the executor's EntanglementProofController.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "hdi", derive(SerializedBytes))]
+#[serde(rename_all = "camelCase")]
+pub struct EntanglementProof {
+    pub did: String,
+    pub did_signing_key_id: String,
+    pub device_key_type: String,
+    /// Base64 encoded Holochain agent key
+    pub device_key: String,
+    pub device_key_signed_by_did: String,
+    pub did_signed_by_device_key: Option<String>,
+}
+
+#[cfg(feature = "hdi")]
+app_entry!(EntanglementProof);
+
+/// Checks that the proof is for the given base64 encoded Holochain agent key
+/// and that the key is signed by the proof's DID.
+pub fn check_entanglement_proof(proof: &EntanglementProof, device_key: &str) -> Result<(), String> {
+    if proof.device_key_type != HOLOCHAIN_DEVICE_KEY_TYPE {
+        return Err(format!(
+            "Entanglement proofs must be for '{}' device keys, not '{}'",
+            HOLOCHAIN_DEVICE_KEY_TYPE, proof.device_key_type
+        ));
+    }
+    if proof.device_key != device_key {
+        return Err(format!(
+            "Entanglement proof is for agent key {}, not {}",
+            proof.device_key, device_key
+        ));
+    }
+    verify_string_signature(
+        &proof.did,
+        &proof.device_key,
+        &proof.device_key_signed_by_did,
+    )
+    .map_err(|error| {
+        format!(
+            "Invalid signature on entanglement proof by {}: {}",
+            proof.did, error
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::did_key;
+    use ed25519_dalek::{Signer, SigningKey};
+    use sha2::{Digest, Sha256};
+
+    const DEVICE_KEY: &str = "uhCAkmrkoAHPVf_eufG7eC5fm6QKrW5pPMoktvG5LOC0SnJ4vV1Uv";
+
+    fn proof(key: &SigningKey, device_key: &str) -> EntanglementProof {
+        EntanglementProof {
+            did: did_key(&key.verifying_key()),
+            did_signing_key_id: format!("{}#primary", did_key(&key.verifying_key())),
+            device_key_type: HOLOCHAIN_DEVICE_KEY_TYPE.to_string(),
+            device_key: device_key.to_string(),
+            device_key_signed_by_did: hex::encode(
+                key.sign(&Sha256::digest(device_key.as_bytes())).to_bytes(),
+            ),
+            did_signed_by_device_key: None,
+        }
+    }
+
+    #[test]
+    fn accepts_proofs_for_the_device_key_signed_by_their_did() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        assert_eq!(
+            check_entanglement_proof(&proof(&key, DEVICE_KEY), DEVICE_KEY),
+            Ok(())
+        );
+        assert!(check_entanglement_proof(&proof(&key, DEVICE_KEY), "uhCAkother").is_err());
+
+        let mut forged = proof(&SigningKey::from_bytes(&[8u8; 32]), DEVICE_KEY);
+        forged.did = did_key(&key.verifying_key());
+        assert!(check_entanglement_proof(&forged, DEVICE_KEY).is_err());
+
+        let mut wrong_type = proof(&key, DEVICE_KEY);
+        wrong_type.device_key_type = "ethereum".to_string();
+        assert!(check_entanglement_proof(&wrong_type, DEVICE_KEY).is_err());
+    }
+}
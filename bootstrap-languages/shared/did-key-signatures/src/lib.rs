@@ -0,0 +1,167 @@
+//! Verification of signatures made by the ad4m-executor with an agent's did:key,
+//! shared by the integrity zomes of the bootstrap languages.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+mod entanglement_proof;
+
+pub use entanglement_proof::{
+    check_entanglement_proof, EntanglementProof, HOLOCHAIN_DEVICE_KEY_TYPE,
+};
+
+/// Upper bound for the length of a DID.
+pub const MAX_DID_LENGTH: usize = 256;
+
+/// Multicodec prefix of Ed25519 public keys in did:key identifiers
+pub const ED25519_MULTICODEC: [u8; 2] = [0xed, 0x01];
+
+/// Checks an ExpressionProof the same way the ad4m-executor does:
+/// an Ed25519 signature by the author's did:key over
+/// sha256(JSON of the data with sorted keys ++ RFC 3339 timestamp).
+pub fn verify_expression_signature<T: Serialize>(
+    author: &str,
+    data: &T,
+    timestamp: &DateTime<Utc>,
+    signature: &str,
+) -> Result<(), String> {
+    let public_key = did_key_public_key(author)?;
+    let signature = decode_signature(signature)?;
+
+    // serde_json::Value keeps object keys sorted, like the executor's signing code does
+    let data = serde_json::to_value(data).map_err(|err| err.to_string())?;
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&data).map_err(|err| err.to_string())?);
+    hasher.update(
+        timestamp
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .as_bytes(),
+    );
+
+    public_key
+        .verify(&hasher.finalize(), &signature)
+        .map_err(|_| "signature does not match".to_string())
+}
+
+/// Checks a signature made with the executor's signString: Ed25519 over sha256(data)
+pub fn verify_string_signature(did: &str, data: &str, signature: &str) -> Result<(), String> {
+    let public_key = did_key_public_key(did)?;
+    let signature = decode_signature(signature)?;
+
+    public_key
+        .verify(&Sha256::digest(data.as_bytes()), &signature)
+        .map_err(|_| "signature does not match".to_string())
+}
+
+pub fn did_key_public_key(did: &str) -> Result<VerifyingKey, String> {
+    if did.len() > MAX_DID_LENGTH {
+        return Err(format!("longer than {} characters", MAX_DID_LENGTH));
+    }
+    let encoded = did
+        .strip_prefix("did:key:z")
+        .ok_or("not a base58 did:key")?;
+    let bytes = bs58::decode(encoded)
+        .into_vec()
+        .map_err(|err| err.to_string())?;
+    let key: [u8; 32] = bytes
+        .strip_prefix(&ED25519_MULTICODEC[..])
+        .and_then(|key| key.try_into().ok())
+        .ok_or("not an Ed25519 did:key")?;
+    VerifyingKey::from_bytes(&key).map_err(|err| err.to_string())
+}
+
+/// The did:key identifier of an Ed25519 public key
+pub fn did_key(public_key: &VerifyingKey) -> String {
+    let mut bytes = ED25519_MULTICODEC.to_vec();
+    bytes.extend_from_slice(public_key.as_bytes());
+    format!("did:key:z{}", bs58::encode(bytes).into_string())
+}
+
+fn decode_signature(signature: &str) -> Result<Signature, String> {
+    hex::decode(signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| "signature is not a hex encoded Ed25519 signature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    #[derive(Serialize)]
+    struct Link {
+        source: String,
+        target: String,
+        predicate: Option<String>,
+    }
+
+    fn link() -> Link {
+        Link {
+            source: "ad4m://self".to_string(),
+            target: "literal://string:hello".to_string(),
+            predicate: None,
+        }
+    }
+
+    #[test]
+    fn accepts_only_ed25519_did_keys() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        assert_eq!(
+            did_key_public_key(&did_key(&key.verifying_key())),
+            Ok(key.verifying_key())
+        );
+        assert!(
+            did_key_public_key("did:key:zQ3shc5AcaZyRo6qP3wuXvYT8xtiyFFL25RjMEuT81WMHEibC")
+                .is_err()
+        );
+        assert!(did_key_public_key("did:test:test").is_err());
+        assert!(did_key_public_key(&format!("did:key:z{}", "1".repeat(MAX_DID_LENGTH))).is_err());
+    }
+
+    #[test]
+    fn verifies_expressions_like_the_executor_signs_them() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let did = did_key(&key.verifying_key());
+        let timestamp: DateTime<Utc> = "2024-01-01T12:00:00.120Z".parse().unwrap();
+
+        // What the executor signs: JSON with sorted keys and the timestamp with milliseconds
+        let mut hasher = Sha256::new();
+        hasher.update(
+            r#"{"predicate":null,"source":"ad4m://self","target":"literal://string:hello"}"#,
+        );
+        hasher.update("2024-01-01T12:00:00.120Z");
+        let signature = hex::encode(key.sign(&hasher.finalize()).to_bytes());
+
+        assert_eq!(
+            verify_expression_signature(&did, &link(), &timestamp, &signature),
+            Ok(())
+        );
+
+        let other = did_key(&SigningKey::from_bytes(&[8u8; 32]).verifying_key());
+        assert!(verify_expression_signature(&other, &link(), &timestamp, &signature).is_err());
+
+        let mut tampered = link();
+        tampered.target = "literal://string:bye".to_string();
+        assert!(verify_expression_signature(&did, &tampered, &timestamp, &signature).is_err());
+
+        let later = timestamp + chrono::Duration::seconds(1);
+        assert!(verify_expression_signature(&did, &link(), &later, &signature).is_err());
+    }
+
+    #[test]
+    fn verifies_signed_strings() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let did = did_key(&key.verifying_key());
+        let signature = hex::encode(key.sign(&Sha256::digest("device key")).to_bytes());
+
+        assert_eq!(
+            verify_string_signature(&did, "device key", &signature),
+            Ok(())
+        );
+        assert!(verify_string_signature(&did, "other key", &signature).is_err());
+        assert!(verify_string_signature(&did, "device key", "not hex").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Membership and write permissions of a neighbourhood.
+//!
+//! The policy is declared in the neighbourhood's meta perspective:
+//!
+//! * `ad4m://membership` -> `ad4m://members_only` restricts writing to the author and members
+//! * `ad4m://has_member` -> DID adds a member
+//! * `ad4m://has_read_only_member` -> DID adds a member that can read but not write
+//!
+//! The ad4m-executor templates the link language with the same policy (as `membership`)
+//! so that p-diff-sync validation rejects diffs from agents that are not permitted to write.
+
+use serde::{Deserialize, Serialize};
+
+pub const MEMBERSHIP_PREDICATE: &str = "ad4m://membership";
+pub const MEMBERS_ONLY: &str = "ad4m://members_only";
+pub const HAS_MEMBER_PREDICATE: &str = "ad4m://has_member";
+pub const HAS_READ_ONLY_MEMBER_PREDICATE: &str = "ad4m://has_read_only_member";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MembershipPolicy {
+    pub author: String,
+    #[serde(default)]
+    pub members_only: bool,
+    #[serde(default)]
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub read_only: Vec<String>,
+}
+
+impl MembershipPolicy {
+    /// Reads the policy of a neighbourhood by `author`
+    /// from the (predicate, target) pairs of its meta links.
+    pub fn from_meta<'a>(
+        author: &str,
+        links: impl IntoIterator<Item = (Option<&'a str>, &'a str)>,
+    ) -> MembershipPolicy {
+        let mut policy = MembershipPolicy {
+            author: author.to_string(),
+            ..Default::default()
+        };
+
+        for (predicate, target) in links {
+            match predicate {
+                Some(MEMBERSHIP_PREDICATE) => policy.members_only = target == MEMBERS_ONLY,
+                Some(HAS_MEMBER_PREDICATE) => policy.members.push(target.to_string()),
+                Some(HAS_READ_ONLY_MEMBER_PREDICATE) => policy.read_only.push(target.to_string()),
+                _ => {}
+            }
+        }
+
+        policy
+    }
+
+    /// Whether everyone may write, i.e. the link language doesn't need to enforce anything.
+    pub fn is_open(&self) -> bool {
+        !self.members_only && self.read_only.is_empty()
+    }
+
+    pub fn can_write(&self, did: &str) -> bool {
+        if did == self.author {
+            return true;
+        }
+        if self.read_only.iter().any(|member| member == did) {
+            return false;
+        }
+        !self.members_only || self.members.iter().any(|member| member == did)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbourhoods_without_policy_are_open() {
+        let policy = MembershipPolicy::from_meta("did:key:author", vec![]);
+        assert!(policy.is_open());
+        assert!(policy.can_write("did:key:anyone"));
+    }
+
+    #[test]
+    fn only_writing_members_can_write() {
+        let policy = MembershipPolicy::from_meta(
+            "did:key:author",
+            vec![
+                (Some(MEMBERSHIP_PREDICATE), MEMBERS_ONLY),
+                (Some(HAS_MEMBER_PREDICATE), "did:key:member"),
+                (Some(HAS_MEMBER_PREDICATE), "did:key:reader"),
+                (Some(HAS_READ_ONLY_MEMBER_PREDICATE), "did:key:reader"),
+                (None, "did:key:stranger"),
+            ],
+        );
+        assert!(!policy.is_open());
+        assert!(policy.can_write("did:key:author"));
+        assert!(policy.can_write("did:key:member"));
+        assert!(!policy.can_write("did:key:reader"));
+        assert!(!policy.can_write("did:key:stranger"));
+
+        let open = MembershipPolicy {
+            members_only: false,
+            ..policy
+        };
+        assert!(!open.is_open());
+        assert!(open.can_write("did:key:stranger"));
+        assert!(!open.can_write("did:key:reader"));
+    }
+}
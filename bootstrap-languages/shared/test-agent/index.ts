@@ -0,0 +1,56 @@
+import crypto from "crypto";
+
+const BASE58_ALPHABET = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz"
+
+function base58(bytes: Buffer): string {
+    let value = BigInt("0x" + (bytes.toString("hex") || "0"))
+    let encoded = ""
+    while (value > 0n) {
+        encoded = BASE58_ALPHABET[Number(value % 58n)] + encoded
+        value = value / 58n
+    }
+    for (const byte of bytes) {
+        if (byte !== 0) break
+        encoded = "1" + encoded
+    }
+    return encoded
+}
+
+function sortKeys(value: any): any {
+    if (Array.isArray(value)) return value.map(sortKeys)
+    if (value && typeof value === "object") {
+        return Object.fromEntries(Object.keys(value).sort().map(key => [key, sortKeys(value[key])]))
+    }
+    return value
+}
+
+// Stand-in for the ad4m agent: an Ed25519 did:key signing expressions
+// the way the executor does (sha256 over JSON with sorted keys and the timestamp)
+export function createAgent() {
+    const { publicKey, privateKey } = crypto.generateKeyPairSync("ed25519")
+    const rawPublicKey = Buffer.from(publicKey.export({ format: "jwk" }).x!, "base64url")
+    const did = "did:key:z" + base58(Buffer.concat([Buffer.from([0xed, 0x01]), rawPublicKey]))
+    const createSignedExpression = (data: object) => {
+        const timestamp = new Date().toISOString()
+        const hash = crypto.createHash("sha256").update(JSON.stringify(sortKeys(data))).update(timestamp).digest()
+        return {
+            author: did,
+            timestamp,
+            data,
+            proof: {
+                signature: crypto.sign(null, hash, privateKey).toString("hex"),
+                key: `${did}#primary`
+            }
+        }
+    }
+    // Like the executor's EntanglementProofController, signString signs sha256(data)
+    const createEntanglementProof = (deviceKey: string) => ({
+        did,
+        didSigningKeyId: `${did}#primary`,
+        deviceKeyType: "holochain",
+        deviceKey,
+        deviceKeySignedByDid: crypto.sign(null, crypto.createHash("sha256").update(deviceKey).digest(), privateKey).toString("hex"),
+        didSignedByDeviceKey: null
+    })
+    return { did, createSignedExpression, createEntanglementProof }
+}
@@ -118,7 +118,19 @@ enum Domain {
         #[arg(short, long, action)]
         connect_holochain: Option<bool>,
         #[arg(long, action)]
-        admin_credential: Option<String>
+        admin_credential: Option<String>,
+        /// Seconds a fetched expression is served from the cache before it gets fetched again
+        #[arg(long, action)]
+        expression_cache_ttl: Option<u64>,
+        /// Maximum size of the expression cache in bytes
+        #[arg(long, action)]
+        expression_cache_max_size: Option<u64>,
+        /// Seconds a Prolog query may run before it gets aborted
+        #[arg(long, action)]
+        prolog_query_timeout: Option<u64>,
+        /// Maximum number of inferences a Prolog query may take
+        #[arg(long, action)]
+        prolog_inference_limit: Option<u64>
     },
     RunLocalHcServices {}
 }
@@ -162,7 +174,11 @@ async fn main() -> Result<()> {
         hc_proxy_url,
         hc_bootstrap_url,
         connect_holochain,
-        admin_credential
+        admin_credential,
+        expression_cache_ttl,
+        expression_cache_max_size,
+        prolog_query_timeout,
+        prolog_inference_limit
     } = args.domain
     {
         let _ = tokio::spawn(async move {
@@ -182,7 +198,11 @@ async fn main() -> Result<()> {
                 hc_bootstrap_url,
                 connect_holochain,
                 admin_credential,
-                auto_permit_cap_requests: Some(true)
+                auto_permit_cap_requests: Some(true),
+                expression_cache_ttl_seconds: expression_cache_ttl,
+                expression_cache_max_size,
+                prolog_query_timeout_seconds: prolog_query_timeout,
+                prolog_inference_limit
             }).await;
         }).await;
         
@@ -55,6 +55,10 @@ pub enum AgentFunctions {
         request_id: String,
         rand: String,
     },
+    /// Exchange a refresh token for a new pair of access and refresh tokens
+    RefreshJwt {
+        refresh_token: String,
+    },
     /// Stay connected and print any agent status changed events
     Watch {},
 }
@@ -198,6 +202,10 @@ pub async fn run(ad4m_client: Ad4mClient, command: AgentFunctions) -> Result<()>
                 .await?;
             println!("JWT: {:#?}", result);
         }
+        AgentFunctions::RefreshJwt { refresh_token } => {
+            let result = ad4m_client.agent.refresh_jwt(refresh_token).await?;
+            println!("JWT: {:#?}", result);
+        }
         AgentFunctions::Watch {} => {
             ad4m_client.agent.watch().await?;
         }
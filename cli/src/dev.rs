@@ -49,6 +49,10 @@ pub async fn run(command: DevFunctions) -> Result<()> {
                     hc_proxy_url: None,
                     hc_bootstrap_url: None,
                     auto_permit_cap_requests: Some(true),
+                    expression_cache_ttl_seconds: None,
+                    expression_cache_max_size: None,
+                    prolog_query_timeout_seconds: None,
+                    prolog_inference_limit: None,
                 })
                 .await
                 .join()
@@ -78,7 +82,7 @@ pub async fn run(command: DevFunctions) -> Result<()> {
                     serde_json::Value::from_str(&data).expect("could not cast input data to serde_json::Value"), 
                 ).await;
                 println!("Expression create: {:?}", expression);
-                let expression = client.expressions.expression(expression.unwrap()).await;
+                let expression = client.expressions.expression(expression.unwrap(), false).await;
                 println!("Expression get: {:?}", expression);
             }).await;
             green_ln!("Test future finished with: {:?}", test_res);
@@ -180,6 +184,10 @@ pub async fn run(command: DevFunctions) -> Result<()> {
                     hc_proxy_url: None,
                     hc_bootstrap_url: None,
                     auto_permit_cap_requests: Some(true),
+                    expression_cache_ttl_seconds: None,
+                    expression_cache_max_size: None,
+                    prolog_query_timeout_seconds: None,
+                    prolog_inference_limit: None,
                 })
                 .await
                 .join()
@@ -11,6 +11,9 @@ pub enum ExpressionFunctions {
     },
     Get {
         url: String,
+        /// Fetch from the language instead of the executor's expression cache
+        #[arg(short, long, action)]
+        refresh: bool,
     },
     GetRaw {
         url: String,
@@ -32,8 +35,8 @@ pub async fn run(ad4m_client: Ad4mClient, command: ExpressionFunctions) -> Resul
                 .await?;
             println!("Expression created with url: {}", expression_url);
         }
-        ExpressionFunctions::Get { url } => {
-            let maybe_content: Option<expression::ExpressionExpression> = ad4m_client.expressions.expression(url.clone()).await?;
+        ExpressionFunctions::Get { url, refresh } => {
+            let maybe_content: Option<expression::ExpressionExpression> = ad4m_client.expressions.expression(url.clone(), refresh).await?;
             match maybe_content {
                 Some(content) => {
                     println!("author: {}", content.author);
@@ -50,7 +53,7 @@ pub async fn run(ad4m_client: Ad4mClient, command: ExpressionFunctions) -> Resul
         }
 
         ExpressionFunctions::GetRaw { url } => {
-            let maybe_content: Option<expression::ExpressionExpression> = ad4m_client.expressions.expression(url.clone()).await?;
+            let maybe_content: Option<expression::ExpressionExpression> = ad4m_client.expressions.expression(url.clone(), false).await?;
             match maybe_content {
                 Some(content) => {
                     if let Ok(Value::String(content)) = serde_json::from_str::<Value>(&content.data) {
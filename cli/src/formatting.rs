@@ -1,7 +1,10 @@
 use anyhow::{bail, Result};
 use serde_json::Value;
 
-use crate::types::{Agent, LinkExpression, PerspectiveExpression, SentPerspectiveMessage};
+use crate::types::{
+    Agent, LinkExpression, PerspectiveExpression, ReceivedPerspectiveMessage,
+    SentPerspectiveMessage,
+};
 
 pub fn print_prolog_results(results: Value) -> Result<()> {
     match results {
@@ -143,9 +146,26 @@ pub fn print_message_perspective(perspective: PerspectiveExpression) {
     }
 }
 
+pub fn print_received_message_perspective(received: ReceivedPerspectiveMessage) {
+    println!("\x1b[36mId: {}", received.id);
+    println!("\x1b[36mStatus: {}", received.status);
+    if let Some(reply_to) = received.reply_to {
+        println!("\x1b[36mReply to: {}", reply_to);
+    }
+    print_message_perspective(received.message);
+}
+
 pub fn print_sent_message_perspective(sent: SentPerspectiveMessage) {
+    println!("\x1b[36mId: {}", sent.id);
     println!("\x1b[36mTo: {}", sent.recipient);
     println!("\x1b[36mTimestamp: {}", sent.message.timestamp);
+    println!(
+        "\x1b[36mStatus: {} (since {})",
+        sent.status, sent.status_updated_at
+    );
+    if let Some(reply_to) = sent.reply_to {
+        println!("\x1b[36mReply to: {}", reply_to);
+    }
     for link in sent.message.data.links {
         print_link(link);
     }
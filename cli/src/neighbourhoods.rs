@@ -11,6 +11,13 @@ pub enum NeighbourhoodFunctions {
     Join {
         url: String,
     },
+    /// Stop syncing a Neighbourhood. Rejoin an archived one with `join` and its URL
+    Leave {
+        perspective_id: String,
+        /// Keep the perspective as a read-only local copy instead of removing it
+        #[arg(short, long)]
+        keep_local: bool,
+    },
 }
 
 pub async fn run(ad4m_client: Ad4mClient, command: NeighbourhoodFunctions) -> Result<()> {
@@ -29,6 +36,20 @@ pub async fn run(ad4m_client: Ad4mClient, command: NeighbourhoodFunctions) -> Re
             let neighbourhood = ad4m_client.neighbourhoods.join(url).await?;
             println!("Neighbourhood joined!\n{:#?}", neighbourhood);
         }
+        NeighbourhoodFunctions::Leave {
+            perspective_id,
+            keep_local,
+        } => {
+            ad4m_client
+                .neighbourhoods
+                .leave(perspective_id, keep_local)
+                .await?;
+            if keep_local {
+                println!("Neighbourhood left, perspective archived");
+            } else {
+                println!("Neighbourhood left, perspective removed");
+            }
+        }
     };
     Ok(())
 }
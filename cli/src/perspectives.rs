@@ -1,7 +1,11 @@
 use crate::{formatting::*, repl::repl_loop, util::maybe_parse_datetime};
-use ad4m_client::Ad4mClient;
+use ad4m_client::{
+    codegen::{generate_subject_classes, subject_classes_from_perspective},
+    Ad4mClient,
+};
 use anyhow::{anyhow, Context, Result};
 use clap::{Args, Subcommand};
+use futures::{StreamExt, TryStreamExt};
 
 #[derive(Args, Debug)]
 pub struct QueryLinksArgs {
@@ -28,6 +32,14 @@ pub struct QueryLinksArgs {
     /// Get only the first n links
     #[arg(short, long)]
     limit: Option<f64>,
+
+    /// Fetch links in pages of this size and print them as they arrive
+    #[arg(long)]
+    page_size: Option<f64>,
+
+    /// Query the perspective as it was at this date (format: %Y-%m-%dT%H:%M:%S%.fZ)
+    #[arg(long)]
+    at: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -38,6 +50,38 @@ pub enum PerspectiveFunctions {
     /// Remove perspective with given uuid
     Remove { id: String },
 
+    /// Export perspective with given uuid as archive, including all signed links and cached expressions
+    Export {
+        id: String,
+        /// Write the archive to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Import a perspective from an archive file created with `export`
+    Import { file: String },
+
+    /// Export links of perspective with given uuid as RDF (turtle, ntriples or jsonld)
+    ExportRdf {
+        id: String,
+        #[arg(short, long, default_value = "turtle")]
+        format: String,
+        /// Write the RDF document to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Import links from an RDF file (turtle, ntriples or jsonld) into perspective with given uuid
+    ImportRdf {
+        id: String,
+        file: String,
+        #[arg(short, long, default_value = "turtle")]
+        format: String,
+        /// Status for imported links that don't carry one (shared or local)
+        #[arg(short, long)]
+        status: Option<String>,
+    },
+
     /// Add link to perspective with given uuid
     AddLink {
         id: String,
@@ -51,7 +95,21 @@ pub enum PerspectiveFunctions {
     QueryLinks(QueryLinksArgs),
 
     /// Retrieve snapshot of perspective with given uuid
-    Snapshot { id: String },
+    Snapshot {
+        id: String,
+        /// Render the perspective as it was at this date (format: %Y-%m-%dT%H:%M:%S%.fZ)
+        #[arg(long)]
+        at: Option<String>,
+    },
+
+    /// Full-text search over literal link targets of perspective with given uuid
+    Search {
+        id: String,
+        text: String,
+        /// Only search links with this predicate
+        #[arg(short, long)]
+        predicate: Option<String>,
+    },
 
     /// Run Prolog / SDNA query on perspective with given uuid
     Infer { id: String, query: String },
@@ -68,6 +126,14 @@ pub enum PerspectiveFunctions {
     /// Get all defined Subject classes
     SubjectClasses { id: String },
 
+    /// Generate typed Rust structs for all Subject classes of perspective with given uuid
+    SubjectGenerate {
+        id: String,
+        /// Write the generated code to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
     /// Construct a new Subject instance of given class over given base
     SubjectConstruct {
         id: String,
@@ -142,6 +208,45 @@ pub async fn run(ad4m_client: Ad4mClient, command: Option<PerspectiveFunctions>)
         PerspectiveFunctions::Remove { id } => {
             ad4m_client.perspectives.remove(id).await?;
         }
+        PerspectiveFunctions::Export { id, output } => {
+            let archive = ad4m_client.perspectives.export(id).await?;
+            match output {
+                Some(file) => {
+                    std::fs::write(&file, archive)
+                        .with_context(|| anyhow!("Could not write archive to {}", file))?;
+                    println!("Perspective exported to {}", file);
+                }
+                None => println!("{}", archive),
+            }
+        }
+        PerspectiveFunctions::Import { file } => {
+            let archive = std::fs::read_to_string(&file)
+                .with_context(|| anyhow!("Could not read archive file {}", file))?;
+            let new_perspective_id = ad4m_client.perspectives.import(archive).await?;
+            println!("{}", new_perspective_id);
+        }
+        PerspectiveFunctions::ExportRdf { id, format, output } => {
+            let rdf = ad4m_client.perspectives.export_rdf(id, format).await?;
+            match output {
+                Some(file) => std::fs::write(&file, rdf)
+                    .with_context(|| anyhow!("Could not write RDF to file {}", file))?,
+                None => println!("{}", rdf),
+            }
+        }
+        PerspectiveFunctions::ImportRdf {
+            id,
+            file,
+            format,
+            status,
+        } => {
+            let data = std::fs::read_to_string(&file)
+                .with_context(|| anyhow!("Could not read RDF file {}", file))?;
+            let added = ad4m_client
+                .perspectives
+                .import_rdf(id, data, format, status)
+                .await?;
+            println!("Imported {} links", added.len());
+        }
         PerspectiveFunctions::AddLink {
             id,
             source,
@@ -157,6 +262,25 @@ pub async fn run(ad4m_client: Ad4mClient, command: Option<PerspectiveFunctions>)
         PerspectiveFunctions::QueryLinks(args) => {
             let from_date = maybe_parse_datetime(args.from_date)?;
             let until_date = maybe_parse_datetime(args.until_date)?;
+            let at = maybe_parse_datetime(args.at)?;
+            if let Some(page_size) = args.page_size {
+                let links = ad4m_client.perspectives.query_links_stream(
+                    args.id,
+                    args.source,
+                    args.target,
+                    args.predicate,
+                    from_date,
+                    until_date,
+                    page_size,
+                    at,
+                );
+                let limit = args.limit.map(|limit| limit.max(0.0) as usize).unwrap_or(usize::MAX);
+                let mut links = Box::pin(links.take(limit));
+                while let Some(link) = links.try_next().await? {
+                    print_link(link);
+                }
+                return Ok(());
+            }
             let result = ad4m_client
                 .perspectives
                 .query_links(
@@ -167,12 +291,23 @@ pub async fn run(ad4m_client: Ad4mClient, command: Option<PerspectiveFunctions>)
                     from_date,
                     until_date,
                     args.limit,
+                    at,
                 )
                 .await?;
             for link in result {
                 print_link(link.into());
             }
         }
+        PerspectiveFunctions::Search {
+            id,
+            text,
+            predicate,
+        } => {
+            let result = ad4m_client.perspectives.search(id, text, predicate).await?;
+            for link in result {
+                print_link(link.into());
+            }
+        }
         PerspectiveFunctions::Infer { id, query } => {
             let results = ad4m_client.perspectives.infer(id, query).await?;
             print_prolog_results(results)?;
@@ -188,8 +323,9 @@ pub async fn run(ad4m_client: Ad4mClient, command: Option<PerspectiveFunctions>)
                 )
                 .await?;
         }
-        PerspectiveFunctions::Snapshot { id } => {
-            let result = ad4m_client.perspectives.snapshot(id).await?;
+        PerspectiveFunctions::Snapshot { id, at } => {
+            let at = maybe_parse_datetime(at)?;
+            let result = ad4m_client.perspectives.snapshot(id, at).await?;
             println!("{:#?}", result);
         }
         PerspectiveFunctions::Repl { id } => {
@@ -208,6 +344,19 @@ pub async fn run(ad4m_client: Ad4mClient, command: Option<PerspectiveFunctions>)
             let classes = perspective.subject_classes().await?;
             println!("{}", classes.join("\n"));
         }
+        PerspectiveFunctions::SubjectGenerate { id, output } => {
+            let perspective = ad4m_client.perspectives.get(id).await?;
+            let classes = subject_classes_from_perspective(&perspective).await?;
+            let code = generate_subject_classes(&classes);
+            match output {
+                Some(file) => {
+                    std::fs::write(&file, code)
+                        .with_context(|| anyhow!("Could not write generated code to {}", file))?;
+                    println!("Generated {} subject classes into {}", classes.len(), file);
+                }
+                None => println!("{}", code),
+            }
+        }
         PerspectiveFunctions::SubjectConstruct { id, class, base } => {
             let perspective = ad4m_client.perspectives.get(id).await?;
             perspective.create_subject(&class, &base).await?;
@@ -1,4 +1,6 @@
-use crate::formatting::{print_message_perspective, print_sent_message_perspective};
+use crate::formatting::{
+    print_message_perspective, print_received_message_perspective, print_sent_message_perspective,
+};
 use ad4m_client::Ad4mClient;
 use anyhow::Result;
 use clap::Subcommand;
@@ -8,6 +10,8 @@ use crate::util::string_2_perspective_snapshot;
 #[derive(Debug, Subcommand)]
 pub enum RuntimeFunctions {
     Info,
+    /// Show how the expression cache is doing (needs admin capability)
+    ExpressionCacheStats,
     Quit,
     AddTrustedAgents {
         agents: Vec<String>,
@@ -49,10 +53,21 @@ pub enum RuntimeFunctions {
     FriendSendMessage {
         agent: String,
         message: String,
+        /// Id of the message this one replies to
+        #[arg(long)]
+        reply_to: Option<String>,
     },
     MessageInbox {
         filter: Option<String>,
     },
+    /// Inbox with message ids, delivery status and threading
+    ReceivedMessages {
+        filter: Option<String>,
+    },
+    /// Mark received messages as read and send read receipts to their authors
+    MarkMessagesRead {
+        ids: Vec<String>,
+    },
     MessageOutbox {
         filter: Option<String>,
     },
@@ -64,6 +79,10 @@ pub async fn run(ad4m_client: Ad4mClient, command: RuntimeFunctions) -> Result<(
             let info = ad4m_client.runtime.info().await?;
             println!("{:#?}", info);
         }
+        RuntimeFunctions::ExpressionCacheStats => {
+            let stats = ad4m_client.runtime.expression_cache_stats().await?;
+            println!("{:#?}", stats);
+        }
         RuntimeFunctions::Quit => {
             ad4m_client.runtime.quit().await?;
             println!("Executor shut down!");
@@ -180,11 +199,15 @@ pub async fn run(ad4m_client: Ad4mClient, command: RuntimeFunctions) -> Result<(
             let status = ad4m_client.runtime.friend_status(agent).await?;
             println!("{:?}", status.runtime_friend_status);
         }
-        RuntimeFunctions::FriendSendMessage { agent, message } => {
+        RuntimeFunctions::FriendSendMessage {
+            agent,
+            message,
+            reply_to,
+        } => {
             let message = string_2_perspective_snapshot(&ad4m_client, message).await?;
             ad4m_client
                 .runtime
-                .friend_send_message(agent, message.into())
+                .friend_send_message(agent, message.into(), reply_to)
                 .await?;
             println!("Message sent!");
         }
@@ -195,6 +218,17 @@ pub async fn run(ad4m_client: Ad4mClient, command: RuntimeFunctions) -> Result<(
                 println!();
             }
         }
+        RuntimeFunctions::ReceivedMessages { filter } => {
+            let messages = ad4m_client.runtime.received_messages(filter).await?;
+            for message in messages {
+                print_received_message_perspective(message);
+                println!();
+            }
+        }
+        RuntimeFunctions::MarkMessagesRead { ids } => {
+            ad4m_client.runtime.mark_messages_read(ids).await?;
+            println!("Messages marked as read!");
+        }
         RuntimeFunctions::MessageOutbox { filter } => {
             let messages = ad4m_client.runtime.message_outbox(filter).await?;
             for message in messages {
@@ -50,6 +50,7 @@ pub async fn get_cap_token(executor_url: String) -> Result<String> {
     let cap_token;
 
     let cap_token_file = data_path()?.join("cap_token");
+    let refresh_token_file = data_path()?.join("refresh_token");
     if cap_token_file.exists() {
         cap_token = std::fs::read_to_string(&cap_token_file)
             .with_context(|| format!("Could not read file `{}`", cap_token_file.display()))?;
@@ -58,6 +59,15 @@ pub async fn get_cap_token(executor_url: String) -> Result<String> {
         }
     }
 
+    if refresh_token_file.exists() {
+        let refresh_token = std::fs::read_to_string(&refresh_token_file)
+            .with_context(|| format!("Could not read file `{}`", refresh_token_file.display()))?;
+        if let Ok(tokens) = agent::refresh_jwt(executor_url.clone(), refresh_token).await {
+            write_tokens(&tokens.access_token, &tokens.refresh_token)?;
+            return Ok(tokens.access_token);
+        }
+    }
+
     println!("No cap token found in file or token not valid. Requesting one...");
 
     let app_name = "AD4M cli".to_string();
@@ -88,14 +98,22 @@ pub async fn get_cap_token(executor_url: String) -> Result<String> {
 
     let mut rl = Editor::<()>::new()?;
     let rand = rl.readline("Enter the 6-digit 2FA number from AD4M UI: ")?;
-    let jwt = agent::retrieve_capability(executor_url, request_id, rand)
+    let tokens = agent::generate_auth_tokens(executor_url, request_id, rand)
         .await
         .with_context(|| "Error generating capability token!".to_string())?;
 
-    let cap_token = jwt.clone();
-    std::fs::write(&cap_token_file, jwt)
-        .with_context(|| format!("Could not write file `{}`", cap_token_file.display()))?;
+    write_tokens(&tokens.access_token, &tokens.refresh_token)?;
     println!("Wrote cap token to file.");
 
-    Ok(cap_token)
+    Ok(tokens.access_token)
+}
+
+fn write_tokens(cap_token: &str, refresh_token: &str) -> Result<()> {
+    let cap_token_file = data_path()?.join("cap_token");
+    std::fs::write(&cap_token_file, cap_token)
+        .with_context(|| format!("Could not write file `{}`", cap_token_file.display()))?;
+    let refresh_token_file = data_path()?.join("refresh_token");
+    std::fs::write(&refresh_token_file, refresh_token)
+        .with_context(|| format!("Could not write file `{}`", refresh_token_file.display()))?;
+    Ok(())
 }
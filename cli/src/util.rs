@@ -100,7 +100,7 @@ pub async fn string_2_perspective_snapshot(
 
     let snapshot = ad4m_client
         .perspectives
-        .snapshot(temp_perspective.clone())
+        .snapshot(temp_perspective.clone(), None)
         .await?;
     println!("Created snapshot of temporary perspective");
 
@@ -2,9 +2,11 @@ import {
   ApolloClient,
   InMemoryCache,
   NormalizedCacheObject,
+  from,
 } from "@apollo/client/core";
 import { createClient, Client as WSClient } from "graphql-ws";
 import { GraphQLWsLink } from "@apollo/client/link/subscriptions";
+import { onError } from "@apollo/client/link/error";
 import { Ad4mClient, CapabilityInput } from "@coasys/ad4m";
 import { checkPort, connectWebSocket } from "./utils";
 import autoBind from "auto-bind";
@@ -19,6 +21,7 @@ export type Ad4mConnectOptions = {
   dataPath?: string;
   port?: number;
   token?: string;
+  refreshToken?: string;
   url?: string;
 };
 
@@ -29,7 +32,7 @@ export type Event =
   | "connectionstatechange"
   | "configstatechange";
 
-export type ConfigStates = "port" | "url" | "token";
+export type ConfigStates = "port" | "url" | "token" | "refreshToken";
 export type ConnectionStates =
   | "connecting"
   | "connected"
@@ -49,6 +52,9 @@ export default class Ad4mConnect {
   requestId?: string;
   url: string;
   token: string;
+  refreshToken: string;
+  refreshTimeout?: ReturnType<typeof setTimeout>;
+  refreshing?: Promise<boolean>;
   port = 12000;
   capabilities: CapabilityInput[] = [];
   appName: string;
@@ -72,6 +78,7 @@ export default class Ad4mConnect {
     capabilities,
     port,
     token,
+    refreshToken,
     url,
   }: Ad4mConnectOptions) {
     autoBind(this);
@@ -85,7 +92,9 @@ export default class Ad4mConnect {
     this.port = port || this.port;
     this.url = url || `ws://localhost:${this.port}/graphql`;
     this.token = token || this.token;
+    this.refreshToken = refreshToken || this.refreshToken;
     this.buildClient();
+    this.scheduleRefresh();
   }
 
   private notifyConfigChange(val: ConfigStates, data: string | number) {
@@ -129,6 +138,12 @@ export default class Ad4mConnect {
     this.notifyConfigChange("token", token);
   }
 
+  setRefreshToken(refreshToken: string) {
+    if (this.refreshToken === refreshToken) return;
+    this.refreshToken = refreshToken;
+    this.notifyConfigChange("refreshToken", refreshToken);
+  }
+
   on(event: Event, cb: Function) {
     this.listeners[event].push(cb);
   }
@@ -252,8 +267,15 @@ export default class Ad4mConnect {
       },
     });
 
+    // The executor rejects expired access tokens, get a new one and reconnect with it
+    const authErrorLink = onError(({ graphQLErrors }) => {
+      if (graphQLErrors?.some((e) => isExpiredTokenError(e.message))) {
+        this.refreshAuth().then((refreshed) => refreshed && this.checkAuth());
+      }
+    });
+
     this.apolloClient = new ApolloClient({
-      link: new GraphQLWsLink(this.wsClient),
+      link: from([authErrorLink, new GraphQLWsLink(this.wsClient)]),
       cache: new InMemoryCache({ resultCaching: false, addTypename: false }),
       defaultOptions: {
         watchQuery: {
@@ -273,7 +295,7 @@ export default class Ad4mConnect {
     return this.ad4mClient;
   }
 
-  async checkAuth(): Promise<boolean> {
+  async checkAuth(allowRefresh = true): Promise<boolean> {
     try {
       const isLocked = await this.ad4mClient.agent.isLocked();
 
@@ -293,6 +315,8 @@ export default class Ad4mConnect {
         // TODO: isLocked throws an error, should just return a boolean. Temp fix
         this.notifyAuthChange("locked");
         return true;
+      } else if (allowRefresh && (await this.refreshAuth())) {
+        return this.checkAuth(false);
       } else {
         this.notifyAuthChange("unauthenticated");
         return false;
@@ -300,9 +324,52 @@ export default class Ad4mConnect {
     }
   }
 
+  /**
+   * Exchanges the refresh token for new tokens.
+   * Refresh tokens are single-use, so concurrent callers share one request.
+   * With reconnect, the connection gets rebuilt to use the new access token right away,
+   * otherwise it is used from the next reconnect on.
+   */
+  refreshAuth(reconnect = true): Promise<boolean> {
+    if (!this.refreshToken) return Promise.resolve(false);
+    if (!this.refreshing) {
+      this.refreshing = this.ad4mClient.agent
+        .refreshJwt(this.refreshToken)
+        .then((tokens) => {
+          this.setTokens(tokens.accessToken, tokens.refreshToken);
+          if (reconnect) this.buildClient();
+          return true;
+        })
+        .catch(() => {
+          this.setRefreshToken(null);
+          return false;
+        })
+        .finally(() => {
+          this.refreshing = undefined;
+        });
+    }
+    return this.refreshing;
+  }
+
+  private setTokens(token: string, refreshToken: string) {
+    this.setToken(token);
+    this.setRefreshToken(refreshToken);
+    this.scheduleRefresh();
+  }
+
+  // Refreshes the access token a minute before it expires
+  private scheduleRefresh() {
+    clearTimeout(this.refreshTimeout);
+    const expiresAt = tokenExpiry(this.token);
+    if (!this.refreshToken || !expiresAt) return;
+    const delay = Math.max(expiresAt - Date.now() - 60 * 1000, 0);
+    this.refreshTimeout = setTimeout(() => this.refreshAuth(false), delay);
+  }
+
   async requestCapability(invalidateToken = false): Promise<string> {
     if (invalidateToken) {
       this.setToken(null);
+      this.setRefreshToken(null);
     }
 
     this.requestId = await this.ad4mClient?.agent.requestCapability({
@@ -318,10 +385,25 @@ export default class Ad4mConnect {
   }
 
   async verifyCode(code: string): Promise<string> {
-    const jwt = await this.ad4mClient?.agent.generateJwt(this.requestId!, code);
-    this.setToken(jwt);
+    const tokens = await this.ad4mClient?.agent.generateAuthTokens(this.requestId!, code);
+    this.setTokens(tokens.accessToken, tokens.refreshToken);
     await this.buildClient();
     await this.checkAuth();
     return this.token;
   }
 }
+
+function isExpiredTokenError(message: string): boolean {
+  return message.includes("ExpiredSignature");
+}
+
+// Expiry of a JWT in milliseconds, read from its payload
+function tokenExpiry(token?: string): number | undefined {
+  try {
+    const payload = token.split(".")[1].replace(/-/g, "+").replace(/_/g, "/");
+    const { exp } = JSON.parse(atob(payload));
+    return exp ? exp * 1000 : undefined;
+  } catch {
+    return undefined;
+  }
+}
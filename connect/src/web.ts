@@ -453,6 +453,9 @@ export class Ad4mConnectElement extends LitElement {
   @property({ type: String })
   token = getForVersion("ad4mToken") || "";
 
+  @property({ type: String })
+  refreshToken = getForVersion("ad4mrefreshToken") || "";
+
   // TODO: localstorage doesnt work here
   @property({ type: String, reflect: true })
   port = parseInt(getForVersion("ad4mport")) || 12000;
@@ -486,6 +489,7 @@ export class Ad4mConnectElement extends LitElement {
         : JSON.parse(this.capabilities),
       port: this.port || parseInt(getForVersion("ad4mport")) || 12000,
       token: this.token || getForVersion("ad4mtoken"),
+      refreshToken: this.refreshToken || getForVersion("ad4mrefreshToken"),
       url: this.url || getForVersion("ad4murl"),
     });
 
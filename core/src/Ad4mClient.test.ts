@@ -28,6 +28,7 @@ import { AuthInfoInput, EntanglementProofInput, CapabilityInput, ResourceInput }
 import { LanguageMetaInput } from "./language/LanguageMeta";
 import { InteractionCall } from "./language/Language";
 import { PerspectiveState } from "./perspectives/PerspectiveHandle";
+import { PrologTermKind, prologSolutionBindings } from "./perspectives/PrologQueryResult";
 
 jest.setTimeout(15000)
 
@@ -283,6 +284,29 @@ describe('Ad4mClient', () => {
             expect(jwt).toBe("test-jwt")
         })
 
+        it('agentGenerateAuthTokens() smoke tests', async () => {
+            const tokens = await ad4mClient.agent.generateAuthTokens("test-request-id", "123")
+            expect(tokens.accessToken).toBe("test-jwt")
+            expect(tokens.refreshToken).toBe("test-refresh-jwt")
+        })
+
+        it('agentRefreshJwt() smoke tests', async () => {
+            const tokens = await ad4mClient.agent.refreshJwt("test-refresh-jwt")
+            expect(tokens.accessToken).toBe("test-jwt-2")
+            expect(tokens.refreshToken).toBe("test-refresh-jwt-2")
+        })
+
+        it('agentRotateAppTokens() smoke tests', async () => {
+            const tokens = await ad4mClient.agent.rotateAppTokens("test-request-id")
+            expect(tokens.accessToken).toBe("test-jwt-3")
+        })
+
+        it('agentGetAppTokens() smoke tests', async () => {
+            const tokens = await ad4mClient.agent.getAppTokens("test-request-id")
+            expect(tokens.length).toBe(1)
+            expect(tokens[0].kind).toBe("access")
+        })
+
         it('agentRevokeToken() smoke tests', async () => {
             const newApps = await ad4mClient.agent.revokeToken('test-request-id')
             expect(newApps.length).toBe(1)
@@ -574,6 +598,12 @@ describe('Ad4mClient', () => {
             expect(ps.links[0].data.target).toBe('neighbourhood://Qm12345')
         })
 
+        it('snapshotByUUID() with date smoke test', async () => {
+            const ps = await ad4mClient.perspective.snapshotByUUID('00004', new Date())
+            expect(ps.links.length).toBe(1)
+            expect(ps.links[0].data.target).toBe('neighbourhood://Qm12345')
+        })
+
         it('publishSnapshotByUUID() smoke test', async () => {
             const snapshotUrl = await ad4mClient.perspective.publishSnapshotByUUID('00004')
             expect(snapshotUrl).toBe('perspective://Qm12345')
@@ -587,6 +617,26 @@ describe('Ad4mClient', () => {
             expect(links[0].data.target).toBe('neighbourhood://Qm12345')
         })
 
+        it('queryLinks() with date smoke test', async () => {
+            const links = await ad4mClient.perspective.queryLinks('000001', {source: 'root'}, new Date())
+            expect(links.length).toBe(1)
+            expect(links[0].data.source).toBe('root')
+        })
+
+        it('queryLinksConnection() smoke test', async () => {
+            const page = await ad4mClient.perspective.queryLinksConnection('000001', {source: 'root', limit: 1})
+            expect(page.edges.length).toBe(1)
+            expect(page.edges[0].node.data.source).toBe('root')
+            expect(page.pageInfo.endCursor).toBe(page.edges[0].cursor)
+            expect(page.pageInfo.hasNextPage).toBe(false)
+        })
+
+        it('search() smoke test', async () => {
+            const links = await ad4mClient.perspective.search('000001', 'meeting notes', 'flux://title')
+            expect(links.length).toBe(1)
+            expect(links[0].data.source).toBe('root')
+        })
+
         it('queryProlog() smoke test', async () => {
             let result = await ad4mClient.perspective.queryProlog('000001', "link(X, 2).")
             expect(result.length).toBe(1)
@@ -598,6 +648,28 @@ describe('Ad4mClient', () => {
             expect(result[0].X).toBe(1)
         })
 
+        it('queryProlog() with limits smoke test', async () => {
+            const result = await ad4mClient.perspective.queryProlog('000001', "link(X, 2).", { timeoutMs: 1000, inferenceLimit: 100000 })
+            expect(result.length).toBe(1)
+            expect(result[0].X).toBe(1)
+        })
+
+        it('queryPrologResult() smoke test', async () => {
+            const result = await ad4mClient.perspective.queryPrologResult('000001', "X = [1].")
+            expect(result.success).toBe(true)
+            const bindings = prologSolutionBindings(result.solutions[0])
+            expect(bindings.X).toEqual({ kind: PrologTermKind.List, elements: [{ kind: PrologTermKind.Integer, value: '1' }] })
+
+            const proxy = await ad4mClient.perspective.byUUID('000001')
+            const proxyResult = await proxy.inferResult("X = [1].")
+            expect(proxyResult.solutions.length).toBe(1)
+        })
+
+        it('cancelPrologQuery() smoke test', async () => {
+            const cancelled = await ad4mClient.perspective.cancelPrologQuery('000001')
+            expect(cancelled).toBe(true)
+        })
+
         it('add() smoke test', async () => {
             const p = await ad4mClient.perspective.add('p-name')
             expect(p.uuid).toBe('00006')
@@ -682,6 +754,44 @@ describe('Ad4mClient', () => {
             expect(mutations.removals[0].data.target).toBe('lang://Qm123')
         })
 
+        it('export() smoke test', async () => {
+            const archive = JSON.parse(await ad4mClient.perspective.export('00004'))
+            expect(archive.version).toBe(1)
+            expect(archive.handle.uuid).toBe('00004')
+        })
+
+        it('import() smoke test', async () => {
+            const archive = JSON.stringify({ version: 1, handle: { uuid: '00007', name: 'imported' }, links: [], expressions: [] })
+            const p = await ad4mClient.perspective.import(archive)
+            expect(p.uuid).toBe('00007')
+            expect(p.name).toBe('imported')
+        })
+
+        it('exportRdf() smoke test', async () => {
+            const rdf = await ad4mClient.perspective.exportRdf('00004', 'ntriples')
+            expect(rdf).toContain('<root>')
+        })
+
+        it('importRdf() smoke test', async () => {
+            const diff = await ad4mClient.perspective.importRdf('00004', '<root> <p> <lang://Qm123> .', 'ntriples')
+            expect(diff.additions.length).toBe(1)
+            expect(diff.additions[0].author).toBe('did:ad4m:test')
+        })
+
+        it('undo() smoke test', async () => {
+            const diff = await ad4mClient.perspective.undo('00001')
+            expect(diff.additions.length).toBe(0)
+            expect(diff.removals.length).toBe(1)
+            expect(diff.removals[0].author).toBe('did:ad4m:test')
+        })
+
+        it('redo() smoke test', async () => {
+            const diff = await ad4mClient.perspective.redo('00001')
+            expect(diff.additions.length).toBe(1)
+            expect(diff.removals.length).toBe(0)
+            expect(diff.additions[0].author).toBe('did:ad4m:test')
+        })
+
         it('addLinkExpression() smoke test', async () => {
             const testLink = new LinkExpression()
             testLink.author = "did:ad4m:test"
@@ -892,6 +1002,7 @@ describe('Ad4mClient', () => {
             link.data = new Link({source: 'root', target: 'perspective://Qm34589a3ccc0'})
             link.proof = { signature: 'asdfasdf', key: 'asdfasdf' }
             await ad4mClient.runtime.friendSendMessage('did:ad4m:test', new Perspective([link]))
+            await ad4mClient.runtime.friendSendMessage('did:ad4m:test', new Perspective([link]), 'test-message-id')
         })
 
         it('messageInbox smoke test', async () => {
@@ -909,7 +1020,10 @@ describe('Ad4mClient', () => {
             const sentMessages = await ad4mClient.runtime.messageOutbox("did:ad4m:test")
             expect(sentMessages.length).toBe(1)
             const sentMessage = sentMessages[0]
+            expect(sentMessage.id).toBe("test-message-id")
             expect(sentMessage.recipient).toBe("did:test:recipient")
+            expect(sentMessage.status).toBe("READ")
+            expect(sentMessage.replyTo).toBeNull()
             const message = sentMessage.message
             expect(message.author).toBe("did:ad4m:test")
             const messagePersp = message.data
@@ -918,6 +1032,19 @@ describe('Ad4mClient', () => {
             expect(messagePersp.links[0].data.target).toBe('neighbourhood://Qm12345')
         })
 
+        it('receivedMessages smoke test', async () => {
+            const messages = await ad4mClient.runtime.receivedMessages()
+            expect(messages.length).toBe(1)
+            expect(messages[0].id).toBe("test-message-id")
+            expect(messages[0].replyTo).toBe("test-reply-to-id")
+            expect(messages[0].status).toBe("FETCHED")
+            expect(messages[0].message.author).toBe("did:ad4m:test")
+        })
+
+        it('markMessagesRead smoke test', async () => {
+            expect(await ad4mClient.runtime.markMessagesRead(["test-message-id"])).toBe(true)
+        })
+
         it('runtimeInfo smoke test', async () => {
             const runtimeInfo = await ad4mClient.runtime.info();
             expect(runtimeInfo.ad4mExecutorVersion).toBe("x.x.x");
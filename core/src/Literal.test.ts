@@ -28,4 +28,22 @@ describe("Literal", () => {
         expect(Literal.from(testString).toUrl()).toBe(testUrl)
         expect(Literal.fromUrl(testUrl).get()).toBe(testString)
     })
-})
\ No newline at end of file
+
+    it("can handle typed values", () => {
+        expect(Literal.from(true).toUrl()).toBe("literal://boolean:true")
+        expect(Literal.fromUrl("literal://boolean:false").get()).toBe(false)
+
+        expect(Literal.fromUrl("literal://integer:-42").get()).toBe(-42)
+        expect(Literal.from(BigInt("9007199254740993")).toUrl()).toBe("literal://integer:9007199254740993")
+        expect(Literal.fromUrl("literal://integer:9007199254740993").get()).toBe(BigInt("9007199254740993"))
+
+        const date = new Date("2023-11-05T13:30:00.123Z")
+        const dateUrl = "literal://datetime:2023-11-05T13%3A30%3A00.123Z"
+        expect(Literal.from(date).toUrl()).toBe(dateUrl)
+        expect(Literal.fromUrl(dateUrl).get()).toStrictEqual(date)
+
+        const bytes = new Uint8Array([0, 255, 1])
+        expect(Literal.from(bytes).toUrl()).toBe("literal://bytes:AP8B")
+        expect(Literal.fromUrl("literal://bytes:AP8B").get()).toStrictEqual(bytes)
+    })
+})
@@ -6,6 +6,16 @@ function encodeRFC3986URIComponent(str) {
         );
 }
 
+function bytesToBase64(bytes: Uint8Array): string {
+    let binary = ""
+    bytes.forEach(b => binary += String.fromCharCode(b))
+    return btoa(binary)
+}
+
+function base64ToBytes(base64: string): Uint8Array {
+    return Uint8Array.from(atob(base64), c => c.charCodeAt(0))
+}
+
 export class Literal {
     #literal?: any
     #url?: string
@@ -25,9 +35,9 @@ export class Literal {
     }
 
     toUrl(): string {
-        if(this.#url && !this.#literal)
+        if(this.#url && this.#literal === undefined)
             return this.#url
-        if(!this.#url && !this.#literal)
+        if(!this.#url && this.#literal === undefined)
             throw new Error("Can't turn empty Literal into URL")
 
         let encoded
@@ -38,8 +48,20 @@ export class Literal {
             case 'number':
                 encoded = `number:${encodeRFC3986URIComponent(this.#literal)}`
                 break;
+            case 'bigint':
+                encoded = `integer:${this.#literal.toString()}`
+                break;
+            case 'boolean':
+                encoded = `boolean:${this.#literal}`
+                break;
             case 'object':
-                encoded = `json:${encodeRFC3986URIComponent(JSON.stringify(this.#literal))}`
+                if(this.#literal instanceof Date) {
+                    encoded = `datetime:${encodeRFC3986URIComponent(this.#literal.toISOString())}`
+                } else if(this.#literal instanceof Uint8Array) {
+                    encoded = `bytes:${encodeRFC3986URIComponent(bytesToBase64(this.#literal))}`
+                } else {
+                    encoded = `json:${encodeRFC3986URIComponent(JSON.stringify(this.#literal))}`
+                }
                 break;
         }
 
@@ -47,7 +69,7 @@ export class Literal {
     }
 
     get(): any {
-        if(this.#literal)
+        if(this.#literal !== undefined)
             return this.#literal
             
         if(!this.#url)
@@ -69,6 +91,24 @@ export class Literal {
             return parseFloat(numberString)
         }
 
+        if(body.startsWith("integer:")) {
+            const integerString = decodeURIComponent(body.substring(8))
+            const integer = parseInt(integerString)
+            return Number.isSafeInteger(integer) ? integer : BigInt(integerString)
+        }
+
+        if(body.startsWith("boolean:")) {
+            return body.substring(8) === "true"
+        }
+
+        if(body.startsWith("datetime:")) {
+            return new Date(decodeURIComponent(body.substring(9)))
+        }
+
+        if(body.startsWith("bytes:")) {
+            return base64ToBytes(decodeURIComponent(body.substring(6)))
+        }
+
         if(body.startsWith("json:")) {
             const json = body.substring(5)
             return JSON.parse(decodeURIComponent(json))
@@ -78,4 +118,4 @@ export class Literal {
     }
 
 
-}
\ No newline at end of file
+}
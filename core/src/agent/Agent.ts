@@ -200,6 +200,48 @@ export class AuthInfo {
   }
 }
 
+@ObjectType()
+export class AuthTokens {
+  @Field()
+  accessToken: string;
+
+  @Field()
+  refreshToken: string;
+
+  /** When the access token expires and has to be exchanged using the refresh token */
+  @Field()
+  expiresAt: Date;
+
+  constructor(accessToken: string, refreshToken: string, expiresAt: Date) {
+    this.accessToken = accessToken;
+    this.refreshToken = refreshToken;
+    this.expiresAt = expiresAt;
+  }
+}
+
+@ObjectType()
+export class AppToken {
+  @Field()
+  id: string;
+
+  /** "access" or "refresh" */
+  @Field()
+  kind: string;
+
+  @Field()
+  issuedAt: Date;
+
+  @Field()
+  expiresAt: Date;
+
+  constructor(id: string, kind: string, issuedAt: Date, expiresAt: Date) {
+    this.id = id;
+    this.kind = kind;
+    this.issuedAt = issuedAt;
+    this.expiresAt = expiresAt;
+  }
+}
+
 @ObjectType()
 export class Apps {
   @Field()
@@ -4,10 +4,12 @@ import unwrapApolloResult from "../unwrapApolloResult";
 import {
   Agent,
   Apps,
+  AppToken,
   AuthInfo,
   AuthInfoInput,
   EntanglementProof,
   EntanglementProofInput,
+  AuthTokens,
 } from "./Agent";
 import { AgentStatus } from "./AgentStatus";
 import { LinkMutations } from "../links/Links";
@@ -47,6 +49,12 @@ const Apps_FIELDS = `
     }
 `;
 
+const AUTH_TOKENS_FIELDS = `
+    accessToken
+    refreshToken
+    expiresAt
+`;
+
 const AGENT_STATUS_FIELDS = `
     isInitialized
     isUnlocked
@@ -461,6 +469,79 @@ export class AgentClient {
     return agentGenerateJwt;
   }
 
+  /**
+   * Exchanges a permitted capability request for a short-lived access token
+   * and a refresh token that can be used with refreshJwt() to get new ones.
+   */
+  async generateAuthTokens(requestId: string, rand: string): Promise<AuthTokens> {
+    const { agentGenerateAuthTokens } = unwrapApolloResult(
+      await this.#apolloClient.mutate({
+        mutation: gql`
+          mutation agentGenerateAuthTokens($requestId: String!, $rand: String!) {
+            agentGenerateAuthTokens(requestId: $requestId, rand: $rand) {
+              ${AUTH_TOKENS_FIELDS}
+            }
+          }
+        `,
+        variables: { requestId, rand },
+      })
+    );
+    return agentGenerateAuthTokens;
+  }
+
+  /** Every refresh token can only be used once, use the returned one for the next refresh */
+  async refreshJwt(refreshToken: string): Promise<AuthTokens> {
+    const { agentRefreshJwt } = unwrapApolloResult(
+      await this.#apolloClient.mutate({
+        mutation: gql`
+          mutation agentRefreshJwt($refreshToken: String!) {
+            agentRefreshJwt(refreshToken: $refreshToken) {
+              ${AUTH_TOKENS_FIELDS}
+            }
+          }
+        `,
+        variables: { refreshToken },
+      })
+    );
+    return agentRefreshJwt;
+  }
+
+  /** Revokes all tokens of the given app and issues a new pair */
+  async rotateAppTokens(requestId: string): Promise<AuthTokens> {
+    const { agentRotateAppTokens } = unwrapApolloResult(
+      await this.#apolloClient.mutate({
+        mutation: gql`
+          mutation agentRotateAppTokens($requestId: String!) {
+            agentRotateAppTokens(requestId: $requestId) {
+              ${AUTH_TOKENS_FIELDS}
+            }
+          }
+        `,
+        variables: { requestId },
+      })
+    );
+    return agentRotateAppTokens;
+  }
+
+  async getAppTokens(requestId: string): Promise<AppToken[]> {
+    const { agentGetAppTokens } = unwrapApolloResult(
+      await this.#apolloClient.query({
+        query: gql`
+          query agentGetAppTokens($requestId: String!) {
+            agentGetAppTokens(requestId: $requestId) {
+              id
+              kind
+              issuedAt
+              expiresAt
+            }
+          }
+        `,
+        variables: { requestId },
+      })
+    );
+    return agentGetAppTokens;
+  }
+
   async getApps(): Promise<Apps[]> {
     const { agentGetApps } = unwrapApolloResult(
       await this.#apolloClient.mutate({
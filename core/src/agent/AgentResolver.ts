@@ -11,6 +11,8 @@ import {
   Agent,
   AgentSignature,
   Apps,
+  AppToken,
+  AuthTokens,
   AuthInfoInput,
   EntanglementProof,
   EntanglementProofInput,
@@ -207,6 +209,31 @@ export default class AgentResolver {
     return "test-jwt";
   }
 
+  @Mutation((returns) => AuthTokens)
+  agentGenerateAuthTokens(
+    @Arg("requestId") requestId: string,
+    @Arg("rand") rand: string
+  ): AuthTokens {
+    return new AuthTokens("test-jwt", "test-refresh-jwt", new Date());
+  }
+
+  @Mutation((returns) => AuthTokens)
+  agentRefreshJwt(@Arg("refreshToken") refreshToken: string): AuthTokens {
+    return new AuthTokens("test-jwt-2", "test-refresh-jwt-2", new Date());
+  }
+
+  @Mutation((returns) => AuthTokens)
+  agentRotateAppTokens(@Arg("requestId") requestId: string): AuthTokens {
+    return new AuthTokens("test-jwt-3", "test-refresh-jwt-3", new Date());
+  }
+
+  @Query((returns) => [AppToken])
+  agentGetAppTokens(@Arg("requestId") requestId: string): AppToken[] {
+    return [
+      new AppToken("test-token-id", "access", new Date(), new Date()),
+    ];
+  }
+
   @Query((returns) => Boolean)
   agentIsLocked(): Boolean {
     return false;
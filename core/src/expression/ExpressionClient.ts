@@ -10,10 +10,14 @@ export class ExpressionClient {
         this.#apolloClient = client
     }
 
-    async get(url: string): Promise<ExpressionRendered> {
+    /**
+     * Expressions get served from the executor's cache until they expire.
+     * Set refresh to bypass the cache and fetch the expression from its language.
+     */
+    async get(url: string, refresh: boolean = false): Promise<ExpressionRendered> {
         const { expression } = unwrapApolloResult(await this.#apolloClient.query({
-            query: gql`query expression($url: String!) {
-                expression(url: $url) {
+            query: gql`query expression($url: String!, $refresh: Boolean) {
+                expression(url: $url, refresh: $refresh) {
                     author
                     timestamp
                     data
@@ -26,7 +30,7 @@ export class ExpressionClient {
                     }
                 }
             }`,
-            variables: { url }
+            variables: { url, refresh }
         }))
         return expression
     }
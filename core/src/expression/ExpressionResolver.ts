@@ -14,7 +14,7 @@ const testExpression = e
 @Resolver()
 export default class ExpressionResolver {
     @Query(returns => ExpressionRendered, {nullable: true})
-    expression(@Arg('url') url: string): ExpressionRendered {
+    expression(@Arg('url') url: string, @Arg('refresh', { nullable: true }) refresh?: boolean): ExpressionRendered {
         if(url === 'neighbourhood://Qm123') {
             return testExpression
         } else {
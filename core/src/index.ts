@@ -3,6 +3,7 @@ export * from "./Address";
 export * from "./agent/Agent";
 export * from "./agent/AgentStatus";
 export * from "./Exception";
+export * from "./runtime/MessageStatus";
 export * from "./expression/Expression";
 export * from "./expression/ExpressionRef";
 export * from "./language/Icon";
@@ -18,6 +19,7 @@ export * from "./perspectives/PerspectiveHandle";
 export * from "./perspectives/PerspectiveProxy";
 export * from "./perspectives/PerspectiveDiff";
 export * from "./perspectives/LinkQuery";
+export * from "./perspectives/PrologQueryResult";
 export * from "./SmartLiteral";
 export * from "./subject/SDNADecorators";
 export * from "./subject/Subject";
@@ -189,18 +189,35 @@ export interface LinkSyncAdapter {
     addSyncStateChangeCallback(callback: SyncStateChangeObserver);
 }
 
-export type MessageCallback = (message: PerspectiveExpression) => void;
+/** A received message and the id (signature) of the message it replies to */
+export interface DirectMessage {
+    message: PerspectiveExpression;
+    replyTo?: string;
+}
+
+/** Signed by the reader, data is `{ status: "FETCHED" | "READ", messageIds: string[] }` */
+export type MessageReceipt = Expression;
+
+export interface DirectMessageOptions {
+    replyTo?: string;
+}
+
+export type MessageCallback = (message: DirectMessage) => void;
+export type ReceiptCallback = (receipt: MessageReceipt) => void;
 export type StatusCallback = (caller: DID) => Perspective;
 export interface DirectMessageAdapter {
     recipient(): DID;
 
     status(): Promise<PerspectiveExpression | void>;
-    sendP2P(message: Perspective): Promise<PerspectiveExpression|void>;
-    sendInbox(message: Perspective): Promise<PerspectiveExpression|void>;
+    sendP2P(message: Perspective, options?: DirectMessageOptions): Promise<PerspectiveExpression|void>;
+    sendInbox(message: Perspective, options?: DirectMessageOptions): Promise<PerspectiveExpression|void>;
+    /** Sends a receipt for messages the recipient of this language sent to us */
+    sendReceipt?(receipt: MessageReceipt): Promise<void>;
 
     setStatus(status: PerspectiveExpression);
-    inbox(filter?: string): Promise<PerspectiveExpression[]>
+    inbox(filter?: string): Promise<DirectMessage[]>
     addMessageCallback(callback: MessageCallback);
+    addReceiptCallback?(callback: ReceiptCallback);
 }
 
 @ObjectType()
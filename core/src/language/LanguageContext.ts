@@ -1,9 +1,22 @@
 import type { AppSignalCb } from '@holochain/client'
 import { Expression } from "../expression/Expression";
+import type { EntanglementProof } from "../agent/Agent";
 
 export interface AgentService {
     readonly did: string
     createSignedExpression(data: any): Expression
+    /** Encrypts data so that only the agent with the given DID can read it */
+    encryptFor(did: string, data: string): EncryptedData
+    /** Decrypts data that was encrypted for this agent. Only the agent's own direct message language may call this. */
+    decrypt(encrypted: EncryptedData): string
+    /** Signs a device key with this agent's DID, authorizing it to act for the agent */
+    createEntanglementProof(deviceKeyType: string, deviceKey: string): EntanglementProof
+}
+
+export interface EncryptedData {
+    ephemeralPublicKey: string
+    nonce: string
+    ciphertext: string
 }
 
 export interface SignaturesService {
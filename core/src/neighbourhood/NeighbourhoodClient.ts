@@ -67,6 +67,16 @@ export class NeighbourhoodClient {
         return neighbourhoodJoinFromUrl
     }
 
+    async leave(perspectiveUUID: string, keepLocal: boolean = false): Promise<boolean> {
+        const { neighbourhoodLeave } = unwrapApolloResult(await this.#apolloClient.mutate({
+            mutation: gql`mutation neighbourhoodLeave($uuid: String!, $keepLocal: Boolean!) {
+                neighbourhoodLeave(uuid: $uuid, keepLocal: $keepLocal)
+            }`,
+            variables: { uuid: perspectiveUUID, keepLocal }
+        }))
+        return neighbourhoodLeave
+    }
+
     async otherAgents(perspectiveUUID: string): Promise<DID[]> {
         const { neighbourhoodOtherAgents } = unwrapApolloResult(await this.#apolloClient.query({
             query: gql`query neighbourhoodOtherAgents($perspectiveUUID: String!) {
@@ -45,6 +45,11 @@ export default class NeighbourhoodResolver {
         return perspective
     }
 
+    @Mutation(returns => Boolean)
+    neighbourhoodLeave(@Arg('uuid') uuid: string, @Arg('keepLocal') keepLocal: boolean): boolean {
+        return true
+    }
+
     @Query(returns => [String])
     neighbourhoodOtherAgents(@Arg('perspectiveUUID') perspectiveUUID: string): DID[] {
         return ['did:test:other']
@@ -1,5 +1,5 @@
 import { Field, InputType, ObjectType } from "type-graphql";
-import { Link } from "../links/Links"
+import { Link, LinkExpression } from "../links/Links"
 
 @ObjectType()
 @InputType()
@@ -22,6 +22,10 @@ export class LinkQuery {
     @Field({nullable: true})
     limit?: number;
 
+    // Cursor of the edge to start after, when paging through a LinkConnection
+    @Field({nullable: true})
+    after?: string;
+
     constructor(obj: object) {
         if(obj) {
             // @ts-ignore
@@ -45,6 +49,11 @@ export class LinkQuery {
                 // @ts-ignore
                 this.limit = obj.limit;
             }
+            // @ts-ignore
+            if (obj.after) {
+                // @ts-ignore
+                this.after = obj.after;
+            }
         }
     }
 
@@ -63,4 +72,31 @@ export class LinkQuery {
 
         return true
     }
-}
\ No newline at end of file
+}
+
+@ObjectType()
+export class LinkEdge {
+    @Field()
+    cursor: string;
+
+    @Field()
+    node: LinkExpression;
+}
+
+@ObjectType()
+export class PageInfo {
+    @Field({nullable: true})
+    endCursor?: string;
+
+    @Field()
+    hasNextPage: boolean;
+}
+
+@ObjectType()
+export class LinkConnection {
+    @Field(type => [LinkEdge])
+    edges: LinkEdge[];
+
+    @Field()
+    pageInfo: PageInfo;
+}
@@ -5,9 +5,10 @@ import { Link, LinkExpressionInput, LinkExpression, LinkInput, LinkMutations, Li
 import { NeighbourhoodClient } from "../neighbourhood/NeighbourhoodClient";
 import { NeighbourhoodProxy } from "../neighbourhood/NeighbourhoodProxy";
 import unwrapApolloResult from "../unwrapApolloResult";
-import { LinkQuery } from "./LinkQuery";
+import { LinkConnection, LinkQuery } from "./LinkQuery";
 import { Perspective } from "./Perspective";
 import { PerspectiveHandle, PerspectiveState } from "./PerspectiveHandle";
+import { PrologQueryResult } from "./PrologQueryResult";
 import { LinkStatus, PerspectiveProxy } from './PerspectiveProxy';
 
 const LINK_EXPRESSION_FIELDS = `
@@ -100,14 +101,14 @@ export class PerspectiveClient {
         return new PerspectiveProxy(perspective, this)
     }
 
-    async snapshotByUUID(uuid: string): Promise<Perspective|null> {
+    async snapshotByUUID(uuid: string, at?: Date): Promise<Perspective|null> {
         const { perspectiveSnapshot } = unwrapApolloResult(await this.#apolloClient.query({
-            query: gql`query perspectiveSnapshot($uuid: String!) {
-                perspectiveSnapshot(uuid: $uuid) {
+            query: gql`query perspectiveSnapshot($uuid: String!, $at: DateTime) {
+                perspectiveSnapshot(uuid: $uuid, at: $at) {
                     links { ${LINK_EXPRESSION_FIELDS} }
                 }
             }`,
-            variables: { uuid }
+            variables: { uuid, at }
         }))
         return perspectiveSnapshot
     }
@@ -121,29 +122,106 @@ export class PerspectiveClient {
         return perspectivePublishSnapshot
     }
 
-    async queryLinks(uuid: string, query: LinkQuery): Promise<LinkExpression[]> {
+    async queryLinks(uuid: string, query: LinkQuery, at?: Date): Promise<LinkExpression[]> {
         const { perspectiveQueryLinks } = unwrapApolloResult(await this.#apolloClient.query({
-            query: gql`query perspectiveQueryLinks($uuid: String!, $query: LinkQuery!) {
-                perspectiveQueryLinks(query: $query, uuid: $uuid) {
+            query: gql`query perspectiveQueryLinks($uuid: String!, $query: LinkQuery!, $at: DateTime) {
+                perspectiveQueryLinks(query: $query, uuid: $uuid, at: $at) {
                     ${LINK_EXPRESSION_FIELDS}
                 }
             }`,
-            variables: { uuid, query }
+            variables: { uuid, query, at }
         }))
         return perspectiveQueryLinks
     }
 
-    async queryProlog(uuid: string, query: string): Promise<any> {
+    async queryLinksConnection(uuid: string, query: LinkQuery, at?: Date): Promise<LinkConnection> {
+        const { perspectiveQueryLinksConnection } = unwrapApolloResult(await this.#apolloClient.query({
+            query: gql`query perspectiveQueryLinksConnection($uuid: String!, $query: LinkQuery!, $at: DateTime) {
+                perspectiveQueryLinksConnection(query: $query, uuid: $uuid, at: $at) {
+                    edges {
+                        cursor
+                        node {
+                            ${LINK_EXPRESSION_FIELDS}
+                        }
+                    }
+                    pageInfo {
+                        endCursor
+                        hasNextPage
+                    }
+                }
+            }`,
+            variables: { uuid, query, at }
+        }))
+        return perspectiveQueryLinksConnection
+    }
+
+    async search(uuid: string, text: string, predicate?: string): Promise<LinkExpression[]> {
+        const { perspectiveSearch } = unwrapApolloResult(await this.#apolloClient.query({
+            query: gql`query perspectiveSearch($uuid: String!, $text: String!, $predicate: String) {
+                perspectiveSearch(uuid: $uuid, text: $text, predicate: $predicate) {
+                    ${LINK_EXPRESSION_FIELDS}
+                }
+            }`,
+            variables: { uuid, text, predicate }
+        }))
+        return perspectiveSearch
+    }
+
+    /**
+     * Runs a Prolog query on the perspective.
+     * Queries that take longer than `timeoutMs` or more than `inferenceLimit` inferences
+     * fail with an error whose `extensions.code` says which limit was hit.
+     * Without limits given, the executor's defaults apply.
+     */
+    async queryProlog(uuid: string, query: string, limits?: { timeoutMs?: number, inferenceLimit?: number }): Promise<any> {
         const { perspectiveQueryProlog } = unwrapApolloResult(await this.#apolloClient.query({
-            query: gql`query perspectiveQueryProlog($uuid: String!, $query: String!) {
-                perspectiveQueryProlog(uuid: $uuid, query: $query)
+            query: gql`query perspectiveQueryProlog($uuid: String!, $query: String!, $timeoutMs: Int, $inferenceLimit: Int) {
+                perspectiveQueryProlog(uuid: $uuid, query: $query, timeoutMs: $timeoutMs, inferenceLimit: $inferenceLimit)
             }`,
-            variables: { uuid, query }
+            variables: { uuid, query, timeoutMs: limits?.timeoutMs, inferenceLimit: limits?.inferenceLimit }
         }))
 
         return JSON.parse(perspectiveQueryProlog)
     }
 
+    /**
+     * Like queryProlog(), but with the solutions as typed terms,
+     * so strings, atoms, lists and structures stay distinguishable.
+     */
+    async queryPrologResult(uuid: string, query: string, limits?: { timeoutMs?: number, inferenceLimit?: number }): Promise<PrologQueryResult> {
+        const { perspectiveQueryPrologResult } = unwrapApolloResult(await this.#apolloClient.query({
+            query: gql`query perspectiveQueryPrologResult($uuid: String!, $query: String!, $timeoutMs: Int, $inferenceLimit: Int) {
+                perspectiveQueryPrologResult(uuid: $uuid, query: $query, timeoutMs: $timeoutMs, inferenceLimit: $inferenceLimit) {
+                    success
+                    solutions {
+                        bindings {
+                            variable
+                            term
+                        }
+                        terms {
+                            kind
+                            value
+                            args
+                        }
+                    }
+                }
+            }`,
+            variables: { uuid, query, timeoutMs: limits?.timeoutMs, inferenceLimit: limits?.inferenceLimit }
+        }))
+        return perspectiveQueryPrologResult
+    }
+
+    /** Cancels the Prolog query currently running on the perspective, returns false if there was none */
+    async cancelPrologQuery(uuid: string): Promise<boolean> {
+        const { perspectiveCancelPrologQuery } = unwrapApolloResult(await this.#apolloClient.mutate({
+            mutation: gql`mutation perspectiveCancelPrologQuery($uuid: String!) {
+                perspectiveCancelPrologQuery(uuid: $uuid)
+            }`,
+            variables: { uuid }
+        }))
+        return perspectiveCancelPrologQuery
+    }
+
     async add(name: string): Promise<PerspectiveProxy> {
         const { perspectiveAdd } = unwrapApolloResult(await this.#apolloClient.mutate({
             mutation: gql`mutation perspectiveAdd($name: String!) {
@@ -177,6 +255,57 @@ export class PerspectiveClient {
         }))
     }
 
+    /** Returns a JSON archive of the perspective that can be imported on another executor */
+    async export(uuid: string): Promise<string> {
+        const { perspectiveExport } = unwrapApolloResult(await this.#apolloClient.mutate({
+            mutation: gql`mutation perspectiveExport($uuid: String!) {
+                perspectiveExport(uuid: $uuid)
+            }`,
+            variables: { uuid }
+        }))
+        return perspectiveExport
+    }
+
+    /** Returns all links of the perspective as RDF, format is one of 'ntriples', 'turtle' or 'jsonld' */
+    async exportRdf(uuid: string, format: string): Promise<string> {
+        const { perspectiveExportRdf } = unwrapApolloResult(await this.#apolloClient.query({
+            query: gql`query perspectiveExportRdf($uuid: String!, $format: String!) {
+                perspectiveExportRdf(uuid: $uuid, format: $format)
+            }`,
+            variables: { uuid, format }
+        }))
+        return perspectiveExportRdf
+    }
+
+    async importRdf(uuid: string, data: string, format: string, status?: LinkStatus): Promise<LinkExpressionMutations> {
+        const { perspectiveImportRdf } = unwrapApolloResult(await this.#apolloClient.mutate({
+            mutation: gql`mutation perspectiveImportRdf($uuid: String!, $data: String!, $format: String!, $status: String) {
+                perspectiveImportRdf(uuid: $uuid, data: $data, format: $format, status: $status) {
+                    additions {
+                        ${LINK_EXPRESSION_FIELDS}
+                    }
+                    removals {
+                        ${LINK_EXPRESSION_FIELDS}
+                    }
+                }
+            }`,
+            variables: { uuid, data, format, status }
+        }))
+        return perspectiveImportRdf
+    }
+
+    async import(archive: string): Promise<PerspectiveProxy> {
+        const { perspectiveImport } = unwrapApolloResult(await this.#apolloClient.mutate({
+            mutation: gql`mutation perspectiveImport($archive: String!) {
+                perspectiveImport(archive: $archive) {
+                    ${PERSPECTIVE_HANDLE_FIELDS}
+                }
+            }`,
+            variables: { archive }
+        }))
+        return new PerspectiveProxy(perspectiveImport, this)
+    }
+
     async addLink(uuid: string, link: Link, status?: LinkStatus): Promise<LinkExpression> {
         const { perspectiveAddLink } = unwrapApolloResult(await this.#apolloClient.mutate({
             mutation: gql`mutation perspectiveAddLink($uuid: String!, $link: LinkInput!, $status: String){
@@ -230,6 +359,40 @@ export class PerspectiveClient {
         return perspectiveLinkMutations
     }
 
+    async undo(uuid: string): Promise<LinkExpressionMutations|null> {
+        const { perspectiveUndo } = unwrapApolloResult(await this.#apolloClient.mutate({
+            mutation: gql`mutation perspectiveUndo($uuid: String!){
+                perspectiveUndo(uuid: $uuid) {
+                    additions {
+                        ${LINK_EXPRESSION_FIELDS}
+                    }
+                    removals {
+                        ${LINK_EXPRESSION_FIELDS}
+                    }
+                }
+            }`,
+            variables: { uuid }
+        }))
+        return perspectiveUndo
+    }
+
+    async redo(uuid: string): Promise<LinkExpressionMutations|null> {
+        const { perspectiveRedo } = unwrapApolloResult(await this.#apolloClient.mutate({
+            mutation: gql`mutation perspectiveRedo($uuid: String!){
+                perspectiveRedo(uuid: $uuid) {
+                    additions {
+                        ${LINK_EXPRESSION_FIELDS}
+                    }
+                    removals {
+                        ${LINK_EXPRESSION_FIELDS}
+                    }
+                }
+            }`,
+            variables: { uuid }
+        }))
+        return perspectiveRedo
+    }
+
     async addLinkExpression(uuid: string, link: LinkExpressionInput, status?: LinkStatus): Promise<LinkExpression> {
         const { perspectiveAddLinkExpression } = unwrapApolloResult(await this.#apolloClient.mutate({
             mutation: gql`mutation perspectiveAddLinkExpression($uuid: String!, $link: LinkExpressionInput!, $status: String){
@@ -8,6 +8,8 @@ export enum PerspectiveState {
     LinkLanguageFailedToInstall = "LINK_LANGUAGE_FAILED_TO_INSTALL",
     LinkLanguageInstalledButNotSynced = "LINK_LANGUAGE_INSTALLED_BUT_NOT_SYNCED",
     Synced = "SYNCED",
+    NotPermitted = "NOT_PERMITTED",
+    Archived = "ARCHIVED",
 }
 // This type is used in the GraphQL interface to reference a mutable
 // prespective that is implemented locally by the Ad4m runtime.
@@ -1,6 +1,7 @@
 import { LinkCallback, PerspectiveClient, SyncStateChangeCallback } from "./PerspectiveClient";
 import { Link, LinkExpression, LinkExpressionInput, LinkExpressionMutations, LinkMutations } from "../links/Links";
 import { LinkQuery } from "./LinkQuery";
+import { PrologQueryResult } from "./PrologQueryResult";
 import { PerspectiveHandle, PerspectiveState } from './PerspectiveHandle'
 import { Perspective } from "./Perspective";
 import { Literal } from "../Literal";
@@ -122,11 +123,26 @@ export class PerspectiveProxy {
         return await this.#client.queryLinks(this.#handle.uuid, query)
     }
 
+    /** Like get(), but on the state of the perspective at the given point in time */
+    async getAt(query: LinkQuery, at: Date): Promise<LinkExpression[]> {
+        return await this.#client.queryLinks(this.#handle.uuid, query, at)
+    }
+
+    /** Returns links whose literal target contains all words of the given text, best matches first */
+    async search(text: string, predicate?: string): Promise<LinkExpression[]> {
+        return await this.#client.search(this.#handle.uuid, text, predicate)
+    }
+
     /** Runs a Prolog query on the perspective's Prolog engine */
     async infer(query: string): Promise<any> {
         return await this.#client.queryProlog(this.#handle.uuid, query)
     }
 
+    /** Like infer(), but with typed solutions instead of parsed JSON */
+    async inferResult(query: string): Promise<PrologQueryResult> {
+        return await this.#client.queryPrologResult(this.#handle.uuid, query)
+    }
+
     /** Adds a link to this perspective */
     async add(link: Link, status: LinkStatus = 'shared'): Promise<LinkExpression> {
         return await this.#client.addLink(this.#handle.uuid, link, status)
@@ -213,9 +229,10 @@ export class PerspectiveProxy {
 
     /** Create and return a snapshot of this perspective
      * A snapshot is a rendered Perspectie object that contains all the links of the perspective.
+     * If `at` is given, the snapshot shows the perspective as it was at that point in time.
      */
-    async snapshot(): Promise<Perspective> {
-        return this.#client.snapshotByUUID(this.#handle.uuid)
+    async snapshot(at?: Date): Promise<Perspective> {
+        return this.#client.snapshotByUUID(this.#handle.uuid, at)
     }
 
     /** Take and load all the links from the given snapshot */
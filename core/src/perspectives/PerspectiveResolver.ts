@@ -1,7 +1,8 @@
-import { Arg, Mutation, PubSub, Query, Resolver, Subscription } from "type-graphql";
+import { Arg, Int, Mutation, PubSub, Query, Resolver, Subscription } from "type-graphql";
 import { LinkExpression, LinkExpressionInput, LinkExpressionMutations, LinkExpressionUpdated, LinkInput, LinkMutations } from "../links/Links";
 import { Neighbourhood, NeighbourhoodExpression } from "../neighbourhood/Neighbourhood";
-import { LinkQuery } from "./LinkQuery";
+import { LinkConnection, LinkQuery } from "./LinkQuery";
+import { PrologQueryResult, PrologTermKind } from "./PrologQueryResult";
 import { Perspective } from "./Perspective";
 import { LinkStatus } from "./PerspectiveProxy";
 import { PerspectiveHandle, PerspectiveState } from "./PerspectiveHandle";
@@ -56,8 +57,13 @@ export default class PerspectiveResolver {
         return new PerspectiveHandle(uuid, 'test-perspective-1')
     }
 
+    @Query(returns => String)
+    perspectiveExportRdf(@Arg('uuid') uuid: string, @Arg('format') format: string): string {
+        return `<${testLink.data.source}> <ad4m://no_predicate> <${testLink.data.target}> .\n`
+    }
+
     @Query(returns => Perspective, {nullable: true})
-    perspectiveSnapshot(@Arg('uuid') uuid: string): Perspective|null {
+    perspectiveSnapshot(@Arg('uuid') uuid: string, @Arg('at', { nullable: true }) at?: Date): Perspective|null {
         return new Perspective([testLink])
     }
 
@@ -67,15 +73,47 @@ export default class PerspectiveResolver {
     }
 
     @Query(returns => [LinkExpression], {nullable: true})
-    perspectiveQueryLinks(@Arg('uuid') uuid: string, @Arg('query') query: LinkQuery): LinkExpression[] {
+    perspectiveQueryLinks(@Arg('uuid') uuid: string, @Arg('query') query: LinkQuery, @Arg('at', { nullable: true }) at?: Date): LinkExpression[] {
+        return [testLink]
+    }
+
+    @Query(returns => LinkConnection)
+    perspectiveQueryLinksConnection(@Arg('uuid') uuid: string, @Arg('query') query: LinkQuery, @Arg('at', { nullable: true }) at?: Date): LinkConnection {
+        return {
+            edges: [{ cursor: 'MXwyMDIzLTAxLTAxVDAwOjAwOjAwWg', node: testLink }],
+            pageInfo: { endCursor: 'MXwyMDIzLTAxLTAxVDAwOjAwOjAwWg', hasNextPage: false }
+        }
+    }
+
+    @Query(returns => [LinkExpression])
+    perspectiveSearch(@Arg('uuid') uuid: string, @Arg('text') text: string, @Arg('predicate', { nullable: true }) predicate?: string): LinkExpression[] {
         return [testLink]
     }
 
     @Query(returns => String)
-    perspectiveQueryProlog(@Arg('uuid') uuid: string, @Arg('query') query: String): string {
+    perspectiveQueryProlog(@Arg('uuid') uuid: string, @Arg('query') query: String, @Arg('timeoutMs', type => Int, { nullable: true }) timeoutMs?: number, @Arg('inferenceLimit', type => Int, { nullable: true }) inferenceLimit?: number): string {
         return `[{"X": 1}]`
     }
 
+    @Query(returns => PrologQueryResult)
+    perspectiveQueryPrologResult(@Arg('uuid') uuid: string, @Arg('query') query: String, @Arg('timeoutMs', type => Int, { nullable: true }) timeoutMs?: number, @Arg('inferenceLimit', type => Int, { nullable: true }) inferenceLimit?: number): PrologQueryResult {
+        return {
+            success: true,
+            solutions: [{
+                bindings: [{ variable: 'X', term: 0 }],
+                terms: [
+                    { kind: PrologTermKind.List, args: [1] },
+                    { kind: PrologTermKind.Integer, value: '1', args: [] }
+                ]
+            }]
+        }
+    }
+
+    @Mutation(returns => Boolean)
+    perspectiveCancelPrologQuery(@Arg('uuid') uuid: string): boolean {
+        return true
+    }
+
     @Mutation(returns => PerspectiveHandle)
     perspectiveAdd(@Arg('name') name: string, @PubSub() pubSub: any): PerspectiveHandle {
         const perspective = new PerspectiveHandle('00006', name);
@@ -97,6 +135,24 @@ export default class PerspectiveResolver {
         return true
     }
 
+    @Mutation(returns => String)
+    perspectiveExport(@Arg('uuid') uuid: string): string {
+        return JSON.stringify({ version: 1, handle: new PerspectiveHandle(uuid, 'test-perspective-1'), links: [], expressions: [] })
+    }
+
+    @Mutation(returns => LinkExpressionMutations)
+    perspectiveImportRdf(@Arg('uuid') uuid: string, @Arg('data') data: string, @Arg('format') format: string, @Arg('status', { nullable: true }) status: LinkStatus): LinkExpressionMutations {
+        return new LinkExpressionMutations([testLink], [])
+    }
+
+    @Mutation(returns => PerspectiveHandle)
+    perspectiveImport(@Arg('archive') archive: string, @PubSub() pubSub: any): PerspectiveHandle {
+        const { handle } = JSON.parse(archive)
+        const perspective = new PerspectiveHandle(handle.uuid, handle.name);
+        pubSub.publish(PERSPECTIVE_ADDED_TOPIC, { perspective })
+        return perspective
+    }
+
     @Mutation(returns => LinkExpression)
     perspectiveAddLink(@Arg('uuid') uuid: string, @Arg('link') link: LinkInput, @Arg('status', { nullable: true, defaultValue: 'shared'}) status: LinkStatus, @PubSub() pubSub: any): LinkExpression {
         const l = new LinkExpression()
@@ -156,6 +212,16 @@ export default class PerspectiveResolver {
         return new LinkExpressionMutations(perspectiveAddLinks, perspectiveRemoveLinks)
     }
 
+    @Mutation(returns => LinkExpressionMutations, { nullable: true })
+    perspectiveUndo(@Arg('uuid') uuid: string): LinkExpressionMutations|null {
+        return new LinkExpressionMutations([], [testLink])
+    }
+
+    @Mutation(returns => LinkExpressionMutations, { nullable: true })
+    perspectiveRedo(@Arg('uuid') uuid: string): LinkExpressionMutations|null {
+        return new LinkExpressionMutations([testLink], [])
+    }
+
     @Mutation(returns => LinkExpression)
     perspectiveAddLinkExpression(@Arg('uuid') uuid: string, @Arg('link') link: LinkExpressionInput, @Arg('status', { nullable: true}) status: LinkStatus, @PubSub() pubSub: any): LinkExpression {
         pubSub.publish(LINK_ADDED_TOPIC, { link })
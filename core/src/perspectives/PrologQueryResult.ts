@@ -0,0 +1,85 @@
+import { Field, Int, ObjectType } from "type-graphql";
+
+export enum PrologTermKind {
+    Integer = "INTEGER",
+    Float = "FLOAT",
+    Rational = "RATIONAL",
+    Atom = "ATOM",
+    String = "STRING",
+    List = "LIST",
+    Structure = "STRUCTURE",
+    Variable = "VARIABLE",
+}
+
+@ObjectType()
+export class PrologTerm {
+    @Field()
+    kind: PrologTermKind;
+
+    // Number as text, the atom's name, the string or the functor of a structure
+    @Field({nullable: true})
+    value?: string;
+
+    // Indices of list elements or structure arguments in the solution's terms
+    @Field(type => [Int])
+    args: number[];
+}
+
+@ObjectType()
+export class PrologBinding {
+    @Field()
+    variable: string;
+
+    // Index of the bound term in the solution's terms
+    @Field(type => Int)
+    term: number;
+}
+
+// GraphQL can't select trees of arbitrary depth, so the terms of a solution
+// come as a flat list. Use prologSolutionBindings() to get the trees.
+@ObjectType()
+export class PrologSolution {
+    @Field(type => [PrologBinding])
+    bindings: PrologBinding[];
+
+    @Field(type => [PrologTerm])
+    terms: PrologTerm[];
+}
+
+@ObjectType()
+export class PrologQueryResult {
+    @Field()
+    success: boolean;
+
+    @Field(type => [PrologSolution])
+    solutions: PrologSolution[];
+}
+
+export type PrologTermTree =
+    { kind: PrologTermKind.List, elements: PrologTermTree[] }
+    | { kind: PrologTermKind.Structure, functor: string, args: PrologTermTree[] }
+    | { kind: PrologTermKind.Variable }
+    | { kind: PrologTermKind, value: string };
+
+function termTree(terms: PrologTerm[], index: number): PrologTermTree {
+    const term = terms[index]
+    switch (term.kind) {
+        case PrologTermKind.List:
+            return { kind: term.kind, elements: term.args.map(arg => termTree(terms, arg)) }
+        case PrologTermKind.Structure:
+            return { kind: term.kind, functor: term.value, args: term.args.map(arg => termTree(terms, arg)) }
+        case PrologTermKind.Variable:
+            return { kind: term.kind }
+        default:
+            return { kind: term.kind, value: term.value }
+    }
+}
+
+/** Resolves the flat term list of a solution into one term tree per variable */
+export function prologSolutionBindings(solution: PrologSolution): { [variable: string]: PrologTermTree } {
+    const bindings = {}
+    for (const binding of solution.bindings) {
+        bindings[binding.variable] = termTree(solution.terms, binding.term)
+    }
+    return bindings
+}
@@ -0,0 +1,8 @@
+// Ordered: a message only ever moves forward in this list
+export enum MessageStatus {
+    Sent = "SENT",
+    DeliveredP2P = "DELIVERED_P2P",
+    QueuedInbox = "QUEUED_INBOX",
+    Fetched = "FETCHED",
+    Read = "READ",
+}
@@ -1,7 +1,7 @@
 import { ApolloClient, gql } from "@apollo/client/core"
 import { Perspective, PerspectiveExpression } from "../perspectives/Perspective"
 import unwrapApolloResult from "../unwrapApolloResult"
-import { RuntimeInfo, ExceptionInfo, SentMessage } from "./RuntimeResolver"
+import { RuntimeInfo, ExceptionInfo, SentMessage, ReceivedMessage, ExpressionCacheStats } from "./RuntimeResolver"
 
 const PERSPECTIVE_EXPRESSION_FIELDS = `
 author
@@ -17,21 +17,32 @@ data {
 proof { valid, invalid, signature, key }
 `
 
+const RECEIVED_MESSAGE_FIELDS = `
+id
+message { ${PERSPECTIVE_EXPRESSION_FIELDS} }
+replyTo
+status
+`
+
 export type MessageCallback = (message: PerspectiveExpression) => null
+export type ReceivedMessageCallback = (message: ReceivedMessage) => null
 export type ExceptionCallback = (info: ExceptionInfo) => null
 
 export class RuntimeClient {
     #apolloClient: ApolloClient<any>
     #messageReceivedCallbacks: MessageCallback[]
+    #receivedMessageCallbacks: ReceivedMessageCallback[]
     #exceptionOccurredCallbacks: ExceptionCallback[]
 
     constructor(client: ApolloClient<any>, subscribe: boolean = true) {
         this.#apolloClient = client
         this.#messageReceivedCallbacks = []
+        this.#receivedMessageCallbacks = []
         this.#exceptionOccurredCallbacks = []
 
         if(subscribe) {
             this.subscribeMessageReceived()
+            this.subscribeReceivedMessage()
             this.subscribeExceptionOccurred()
         }
     }
@@ -49,6 +60,23 @@ export class RuntimeClient {
         return runtimeInfo
     }
 
+    async expressionCacheStats(): Promise<ExpressionCacheStats> {
+        const { runtimeExpressionCacheStats } = unwrapApolloResult(await this.#apolloClient.query({
+            query: gql`query runtimeExpressionCacheStats {
+                runtimeExpressionCacheStats {
+                    entries
+                    totalSize
+                    maxSize
+                    ttlSeconds
+                    hits
+                    misses
+                    evictions
+                }
+            }`,
+        }));
+        return runtimeExpressionCacheStats
+    }
+
     async quit(): Promise<Boolean> {
         const result = unwrapApolloResult(await this.#apolloClient.mutate({
             mutation: gql`mutation runtimeQuit { runtimeQuit }`
@@ -203,12 +231,12 @@ export class RuntimeClient {
         return runtimeFriendStatus
     }
 
-    async friendSendMessage(did: string, message: Perspective): Promise<boolean> {
+    async friendSendMessage(did: string, message: Perspective, replyTo?: string): Promise<boolean> {
         const { runtimeFriendSendMessage } = unwrapApolloResult(await this.#apolloClient.mutate({
-            mutation: gql`mutation runtimeFriendSendMessage($did: String!, $message: PerspectiveInput!) {
-                runtimeFriendSendMessage(did: $did, message: $message)
+            mutation: gql`mutation runtimeFriendSendMessage($did: String!, $message: PerspectiveInput!, $replyTo: String) {
+                runtimeFriendSendMessage(did: $did, message: $message, replyTo: $replyTo)
             }`,
-            variables: { did,  message }
+            variables: { did,  message, replyTo }
         }))
         return runtimeFriendSendMessage
     }
@@ -227,10 +255,14 @@ export class RuntimeClient {
         const { runtimeMessageOutbox } = unwrapApolloResult(await this.#apolloClient.query({
             query: gql`query runtimeMessageOutbox($filter: String) {
                 runtimeMessageOutbox(filter: $filter) { 
+                    id,
                     recipient,
                     message {
                         ${PERSPECTIVE_EXPRESSION_FIELDS} 
                     }
+                    replyTo,
+                    status,
+                    statusUpdatedAt
                 }
             }`,
             variables: { filter }
@@ -238,6 +270,28 @@ export class RuntimeClient {
         return runtimeMessageOutbox
     }
 
+    /** Like messageInbox() but with each message's id, delivery status and the message it replies to */
+    async receivedMessages(filter?: string): Promise<ReceivedMessage[]> {
+        const { runtimeReceivedMessages } = unwrapApolloResult(await this.#apolloClient.query({
+            query: gql`query runtimeReceivedMessages($filter: String) {
+                runtimeReceivedMessages(filter: $filter) { ${RECEIVED_MESSAGE_FIELDS} }
+            }`,
+            variables: { filter }
+        }))
+        return runtimeReceivedMessages
+    }
+
+    /** Marks received messages as read and sends signed read receipts to their authors */
+    async markMessagesRead(ids: string[]): Promise<boolean> {
+        const { runtimeMarkMessagesRead } = unwrapApolloResult(await this.#apolloClient.mutate({
+            mutation: gql`mutation runtimeMarkMessagesRead($ids: [String!]!) {
+                runtimeMarkMessagesRead(ids: $ids)
+            }`,
+            variables: { ids }
+        }))
+        return runtimeMarkMessagesRead
+    }
+
     addMessageCallback(cb: MessageCallback) {
         this.#messageReceivedCallbacks.push(cb)
     }
@@ -257,6 +311,26 @@ export class RuntimeClient {
         })
     }
 
+    /** Like addMessageCallback() but with each message's id, delivery status and the message it replies to */
+    addReceivedMessageCallback(cb: ReceivedMessageCallback) {
+        this.#receivedMessageCallbacks.push(cb)
+    }
+
+    subscribeReceivedMessage() {
+        this.#apolloClient.subscribe({
+            query: gql` subscription {
+                runtimeReceivedMessage { ${RECEIVED_MESSAGE_FIELDS} }
+            }
+        `}).subscribe({
+            next: result => {
+                this.#receivedMessageCallbacks.forEach(cb => {
+                    cb(result.data.runtimeReceivedMessage)
+                })
+            },
+            error: (e) => console.error(e)
+        })
+    }
+
     addExceptionCallback(cb: ExceptionCallback) {
         this.#exceptionOccurredCallbacks.push(cb)
     }
@@ -3,7 +3,8 @@ import { Perspective, PerspectiveExpression, PerspectiveInput } from "../perspec
 import { ExpressionProof } from "../expression/Expression";
 import { LinkExpression } from "../links/Links";
 import { ExceptionType } from "../Exception";
-import { RUNTIME_MESSAGED_RECEIVED_TOPIC, EXCEPTION_OCCURRED_TOPIC } from '../PubSub';
+import { MessageStatus } from "./MessageStatus";
+import { RUNTIME_MESSAGED_RECEIVED_TOPIC, RUNTIME_RECEIVED_MESSAGE_TOPIC, EXCEPTION_OCCURRED_TOPIC } from '../PubSub';
 
 const testLink = new LinkExpression()
 testLink.author = "did:ad4m:test"
@@ -26,10 +27,31 @@ testPerspectiveExpression.data = new Perspective([testLink])
 
 @ObjectType()
 export class SentMessage {
+    // The signature of the message, which is also how the recipient refers to it
+    @Field()
+    id: string;
     @Field()
     recipient: string;
     @Field()
     message: PerspectiveExpression;
+    @Field({nullable: true})
+    replyTo?: string;
+    @Field()
+    status: MessageStatus;
+    @Field()
+    statusUpdatedAt: string;
+}
+
+@ObjectType()
+export class ReceivedMessage {
+    @Field()
+    id: string;
+    @Field()
+    message: PerspectiveExpression;
+    @Field({nullable: true})
+    replyTo?: string;
+    @Field()
+    status: MessageStatus;
 }
 
 @ObjectType()
@@ -42,6 +64,24 @@ export class RuntimeInfo {
     isUnlocked: Boolean;
 }
 
+@ObjectType()
+export class ExpressionCacheStats {
+    @Field(type => Int)
+    entries: number;
+    @Field(type => Int)
+    totalSize: number;
+    @Field(type => Int)
+    maxSize: number;
+    @Field(type => Int)
+    ttlSeconds: number;
+    @Field(type => Int)
+    hits: number;
+    @Field(type => Int)
+    misses: number;
+    @Field(type => Int)
+    evictions: number;
+}
+
 @ObjectType()
 export class ExceptionInfo {
     @Field()
@@ -72,6 +112,19 @@ export default class RuntimeResolver {
         return true
     }
 
+    @Query(returns => ExpressionCacheStats)
+    runtimeExpressionCacheStats(): ExpressionCacheStats {
+        return {
+            entries: 1,
+            totalSize: 256,
+            maxSize: 104857600,
+            ttlSeconds: 3600,
+            hits: 1,
+            misses: 1,
+            evictions: 0
+        } as ExpressionCacheStats
+    }
+
     @Query(returns => RuntimeInfo)
     runtimeInfo(): RuntimeInfo {
         return {
@@ -158,7 +211,8 @@ export default class RuntimeResolver {
     @Mutation()
     runtimeFriendSendMessage(
         @Arg("did", type => String) did: string, 
-        @Arg("message", type => PerspectiveInput) message: PerspectiveInput
+        @Arg("message", type => PerspectiveInput) message: PerspectiveInput,
+        @Arg("replyTo", type => String, {nullable: true}) replyTo?: string
     ): boolean {
         return true
     }
@@ -168,20 +222,46 @@ export default class RuntimeResolver {
         return [testPerspectiveExpression]
     }
 
+    @Query(returns => [ReceivedMessage])
+    runtimeReceivedMessages(@Arg("filter", type => String, {nullable: true }) filter?: string): ReceivedMessage[] {
+        return [{
+            id: "test-message-id",
+            message: testPerspectiveExpression,
+            replyTo: "test-reply-to-id",
+            status: MessageStatus.Fetched
+        } as ReceivedMessage]
+    }
+
     @Query(returns => [SentMessage])
     runtimeMessageOutbox(@Arg("filter", type => String, {nullable: true }) filter?: string): SentMessage[] {
         return [{
+            id: "test-message-id",
             recipient: "did:test:recipient", 
-            message: testPerspectiveExpression
+            message: testPerspectiveExpression,
+            status: MessageStatus.Read,
+            statusUpdatedAt: new Date(0).toISOString()
         } as SentMessage]
     }
 
+    @Mutation()
+    runtimeMarkMessagesRead(@Arg("ids", type => [String]) ids: string[]): boolean {
+        return true
+    }
  
     @Subscription({topics: RUNTIME_MESSAGED_RECEIVED_TOPIC, nullable: true})
     runtimeMessageReceived(): PerspectiveExpression {
         return testPerspectiveExpression
     }
 
+    @Subscription({topics: RUNTIME_RECEIVED_MESSAGE_TOPIC, nullable: true})
+    runtimeReceivedMessage(): ReceivedMessage {
+        return {
+            id: "test-message-id",
+            message: testPerspectiveExpression,
+            status: MessageStatus.DeliveredP2P
+        } as ReceivedMessage
+    }
+
     @Subscription({topics: EXCEPTION_OCCURRED_TOPIC, nullable: true})
     exceptionOccurred(): ExceptionInfo {
         return {
@@ -37,6 +37,12 @@ export interface Expression {
     proof: ExpressionProof;
 }
 
+export interface EncryptedData {
+    ephemeralPublicKey: string;
+    nonce: string;
+    ciphertext: string;
+}
+
 export interface ExpressionProof {
     signature: string;
     key: string;
@@ -50,6 +56,8 @@ declare global {
         createSignedExpression: (data: any) => Expression;
         sign: (payload: Uint8Array) => Uint8Array;
         signStringHex: (payload: string) => string;
+        encryptFor: (did: string, message: string) => EncryptedData;
+        decrypt: (encrypted: EncryptedData) => string;
     }
 
     const AGENT: RustAgent;
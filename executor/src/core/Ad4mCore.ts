@@ -13,11 +13,16 @@ import * as PubSubDefinitions from './graphQL-interface/SubscriptionDefinitions'
 import EntanglementProofController from './EntanglementProof'
 import fs from 'node:fs'
 import { AgentInfoResponse } from '@holochain/client'
-import RuntimeService from './RuntimeService'
+import RuntimeService, { messageId, normalizeDirectMessage } from './RuntimeService'
+import { MessageStatus } from '@coasys/ad4m'
+import type { ReceivedMessage } from '@coasys/ad4m/lib/src/runtime/RuntimeResolver'
 import { v4 as uuidv4 } from 'uuid';
 import { MainConfig } from './Config'
 import { getPubSub, sleep } from "./utils";
 
+/** How often our inbox gets fetched in the background */
+const INBOX_FETCH_INTERVAL = 60 * 1000
+
 export interface InitServicesParams {
     agentService: AgentService,
 }
@@ -210,6 +215,7 @@ export default class Ad4mCore {
     async initLanguages() {
         await this.#languageController!.loadLanguages()
         this.#resolveLanguagesReady()
+        this.#fetchInboxPeriodically()
     }
 
     async languageApplyTemplateAndPublish(sourceLanguageHash: string, templateData: object): Promise<LanguageRef> {
@@ -307,6 +313,73 @@ export default class Ad4mCore {
         const dmLang = this.#agentService.agent!.directMessageLanguage!
         return await this.#languageController!.languageByRef(new LanguageRef(dmLang))
     }
+
+    #fetchInboxPeriodically() {
+        setInterval(async () => {
+            if(!this.#agentService.agent?.directMessageLanguage) return
+            try {
+                await this.fetchInbox()
+            } catch(e) {
+                console.error("Ad4mCore: couldn't fetch inbox:", e)
+            }
+        }, INBOX_FETCH_INTERVAL)
+    }
+
+    /**
+     * Pulls new messages into our inbox and sends a FETCHED receipt for each one we didn't acknowledge yet,
+     * including the ones that arrived p2p and are stored in the inbox as well.
+     */
+    async fetchInbox() {
+        const dmLang = await this.myDirectMessageLanguage()
+        const inbox = (await dmLang.directMessageAdapter!.inbox()).map(normalizeDirectMessage)
+
+        const fetched: { id: string, author: string }[] = []
+        for (const { message, replyTo } of inbox) {
+            if(this.#runtimeService.addReceivedMessage(message, MessageStatus.Fetched, replyTo)) {
+                fetched.push({ id: messageId(message), author: message.author })
+            }
+        }
+        fetched.push(...this.#runtimeService.setReceivedMessagesStatus(inbox.map(({ message }) => messageId(message)), MessageStatus.Fetched))
+        await this.sendMessageReceipts(fetched, MessageStatus.Fetched)
+    }
+
+    /** Our inbox with delivery status and threading. Doesn't send any receipts. */
+    async receivedMessages(filter?: string): Promise<ReceivedMessage[]> {
+        const dmLang = await this.myDirectMessageLanguage()
+        const inbox = (await dmLang.directMessageAdapter!.inbox(filter)).map(normalizeDirectMessage)
+
+        const known = new Map(this.#runtimeService.getReceivedMessages().map(m => [m.id, m]))
+        return inbox.map(({ message, replyTo }) => {
+            const id = messageId(message)
+            return { id, message, replyTo, status: known.get(id)?.status ?? MessageStatus.Fetched }
+        })
+    }
+
+    /** Fetches the inbox first, so messages that haven't been acknowledged yet get their FETCHED receipt before the READ one */
+    async markMessagesRead(ids: string[]) {
+        await this.fetchInbox()
+        const read = this.#runtimeService.setReceivedMessagesStatus(ids, MessageStatus.Read)
+        await this.sendMessageReceipts(read, MessageStatus.Read)
+    }
+
+    /** Sends one signed receipt per author through their direct message language */
+    async sendMessageReceipts(messages: { id: string, author: string }[], status: MessageStatus) {
+        const idsByAuthor = new Map<string, string[]>()
+        for (const { id, author } of messages) {
+            idsByAuthor.set(author, [...(idsByAuthor.get(author) ?? []), id])
+        }
+
+        for (const [author, messageIds] of idsByAuthor) {
+            try {
+                const dmLang = await this.friendsDirectMessageLanguage(author)
+                if(!dmLang?.directMessageAdapter?.sendReceipt) continue
+                const receipt = this.#agentService.createSignedExpression({ status, messageIds })
+                await dmLang.directMessageAdapter.sendReceipt(receipt)
+            } catch(e) {
+                console.error(`Ad4mCore: couldn't send ${status} receipt to ${author}:`, e)
+            }
+        }
+    }
 }
 
 export function create(config: Config.CoreConfig): Ad4mCore {
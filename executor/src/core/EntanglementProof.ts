@@ -14,7 +14,7 @@ export default class EntanglementProofController {
     }
 
     signDeviceKey(deviceKey: string, deviceKeyType: string): EntanglementProof {
-        return new EntanglementProof(this.#agentService.did!, this.#agentService.signingKeyId!, deviceKeyType, deviceKey, this.#agentService.signString(deviceKey))
+        return this.#agentService.createEntanglementProof(deviceKeyType, deviceKey)
     }
 
     generateHolochainProof(holochainPubKey: string, signedDid: string): EntanglementProof {
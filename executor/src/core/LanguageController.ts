@@ -1,7 +1,8 @@
 import {
     Address, Expression, Language, LanguageContext, LinkSyncAdapter, InteractionCall, InteractionMeta,
     PublicSharing, ReadOnlyLanguage, LanguageMetaInternal, LanguageMetaInput, PerspectiveExpression,
-    parseExprUrl, Literal, TelepresenceAdapter, PerspectiveState
+    parseExprUrl, Literal, TelepresenceAdapter, PerspectiveState, DirectMessageAdapter, DirectMessage,
+    MessageReceipt, MessageStatus, EncryptedData
 } from '@coasys/ad4m';
 import { ExpressionRef, LanguageRef, LanguageExpression, LanguageLanguageInput, ExceptionType, PerspectiveDiff } from '@coasys/ad4m';
 import { ExceptionInfo } from '@coasys/ad4m/lib/src/runtime/RuntimeResolver';
@@ -13,7 +14,7 @@ import type AgentService from './agent/AgentService'
 import * as PubSubDefinitions from './graphQL-interface/SubscriptionDefinitions'
 import yaml from "js-yaml";
 import { v4 as uuidv4 } from 'uuid';
-import RuntimeService from './RuntimeService';
+import RuntimeService, { messageId, normalizeDirectMessage } from './RuntimeService';
 import { Ad4mDb } from './db';
 import stringify from 'json-stable-stringify'
 import { getPubSub, tagExpressionSignatureStatus } from './utils';
@@ -268,7 +269,8 @@ export default class LanguageController {
         const Holochain = this.#holochainService?.getDelegateForLanguage(hash)
         //@ts-ignore
         const ad4mSignal = this.#context.ad4mSignal.bind({language: hash, pubsub: this.#pubSub});
-        const language = await create({...this.#context, customSettings, storageDirectory, Holochain, ad4mSignal})
+        const agent = this.#languageAgentService(hash)
+        const language = await create({...this.#context, agent, customSettings, storageDirectory, Holochain, ad4mSignal})
 
         if(language.linksAdapter) {
             language.linksAdapter.addCallback((diff: PerspectiveDiff) => {
@@ -293,9 +295,7 @@ export default class LanguageController {
 
         //@ts-ignore
         if(language.directMessageAdapter && language.directMessageAdapter.recipient() == this.#context.agent.did) {
-            language.directMessageAdapter.addMessageCallback(async (message: PerspectiveExpression) => {
-                await this.#pubSub.publish(PubSubDefinitions.RUNTIME_MESSAGED_RECEIVED_TOPIC, message)
-            })
+            this.registerDirectMessageCallbacks(language.directMessageAdapter)
         }
 
         this.#languages.set(hash, language)
@@ -304,6 +304,26 @@ export default class LanguageController {
         return { hash, language }
     }
 
+    // Every language gets the agent service, but decrypting messages for this agent
+    // is reserved for the agent's own direct message language.
+    #languageAgentService(hash: string): AgentService {
+        const agentService = (this.#context as LanguageContext).agent as AgentService
+        return new Proxy(agentService, {
+            get: (target, property) => {
+                if(property === "decrypt") {
+                    return (encrypted: EncryptedData) => {
+                        if(target.agent?.directMessageLanguage !== hash) {
+                            throw new Error(`Language ${hash} is not allowed to decrypt messages for this agent`)
+                        }
+                        return target.decrypt(encrypted)
+                    }
+                }
+                const value = Reflect.get(target, property, target)
+                return typeof value === "function" ? value.bind(target) : value
+            }
+        })
+    }
+
     async reloadLanguage(hash: string): Promise<{
         language: Language,
         hash: string
@@ -318,8 +338,9 @@ export default class LanguageController {
         const Holochain = this.#holochainService?.getDelegateForLanguage(hash)
         //@ts-ignore
         const ad4mSignal = this.#context.ad4mSignal.bind({language: address, pubsub: this.#pubSub});
+        const agent = this.#languageAgentService(hash)
         //@ts-ignore
-        const language = await create!({...this.#context, storageDirectory, Holochain, ad4mSignal, customSettings})
+        const language = await create!({...this.#context, agent, storageDirectory, Holochain, ad4mSignal, customSettings})
 
         if(language.linksAdapter) {
             language.linksAdapter.addCallback((diff: PerspectiveDiff) => {
@@ -342,9 +363,7 @@ export default class LanguageController {
 
         //@ts-ignore
         if(language.directMessageAdapter && language.directMessageAdapter.recipient() == this.#context.agent.did) {
-            language.directMessageAdapter.addMessageCallback(async (message: PerspectiveExpression) => {
-                await this.#pubSub.publish(PubSubDefinitions.RUNTIME_MESSAGED_RECEIVED_TOPIC, message)
-            })
+            this.registerDirectMessageCallbacks(language.directMessageAdapter)
         }
 
         this.#languages.set(hash, language)
@@ -472,7 +491,10 @@ export default class LanguageController {
         }
     }
 
-    async languageRemove(hash: String): Promise<void> {
+    // Tears down a running language and removes it from memory, but keeps its files
+    // so it can be loaded again later, e.g. when rejoining a neighbourhood.
+    // Returns whether the language was loaded.
+    languageUnload(hash: String): boolean {
         //Teardown any intervals the language has running
         const language = this.#languages.get(hash as string);
         if (language?.teardown) {
@@ -482,6 +504,11 @@ export default class LanguageController {
         //Remove language from memory
         this.#languages.delete(hash as string);
         this.#languageConstructors.delete(hash as string);
+        return !!language;
+    }
+
+    async languageRemove(hash: String): Promise<void> {
+        this.languageUnload(hash);
         try {
             await this.#holochainService?.removeDnaForLang(hash as string);
         } catch(e) {
@@ -1036,7 +1063,28 @@ export default class LanguageController {
         return await interaction.execute(interactionCall.parameters)
     }
 
-    async getExpression(ref: ExpressionRef): Promise<Expression | null> {
+    #getCachedExpression(url: string, immutable: boolean): Expression | null {
+        try {
+            return LANGUAGE_CONTROLLER.expressionCacheGet(url, immutable)
+        } catch (e) {
+            console.error("LanguageController: Error reading expression cache for", url, ":", e)
+            return null
+        }
+    }
+
+    #cacheExpression(url: string, expression: Expression) {
+        try {
+            LANGUAGE_CONTROLLER.expressionCachePut(url, expression)
+        } catch (e) {
+            console.error("LanguageController: Error caching expression", url, ":", e)
+        }
+    }
+
+    /**
+     * Expressions are served from the executor's expression cache as long as they
+     * haven't expired. Mutable expressions expire after a short TTL. Pass refresh to always fetch from the language and update the cache.
+     */
+    async getExpression(ref: ExpressionRef, refresh: boolean = false): Promise<Expression | null> {
         if(this.#config.bootstrapFixtures?.perspectives && ref.language.address === "neighbourhood") {
             const fixturePerspective = this.#config.bootstrapFixtures.perspectives!.find(f=>f.address===ref.expression)
             if(fixturePerspective && fixturePerspective.expression) return fixturePerspective.expression
@@ -1064,20 +1112,13 @@ export default class LanguageController {
                     throw Error("Language does not have an expresionAdapter!")
                 };
 
-                const langIsImmutable = await this.isImmutableExpression(ref);
-                if (langIsImmutable) {
-                    console.log("Calling cache for expression...");
-                    const cachedExpression = await this.#db.getExpression(ref.expression);
-                    if (cachedExpression) {
-                        console.log("Cache hit...");
-                        expr = cachedExpression;
-                    } else {
-                        console.log("Cache miss...");
-                        expr = await lang.expressionAdapter.get(ref.expression);
-                        if (expr) { await this.#db.addExpression(ref.expression, expr) };
-                    };
-                } else {
+                const url = `${ref.language.address}://${ref.expression}`
+                if (!refresh) {
+                    expr = this.#getCachedExpression(url, await this.isImmutableExpression(ref))
+                }
+                if (!expr) {
                     expr = await lang.expressionAdapter.get(ref.expression);
+                    if (expr) { this.#cacheExpression(url, expr) };
                 }
             }
         } catch (e) {
@@ -1092,6 +1133,38 @@ export default class LanguageController {
         return expr
     }
 
+    registerDirectMessageCallbacks(adapter: DirectMessageAdapter) {
+        adapter.addMessageCallback(async (directMessage: DirectMessage) => {
+            const { message, replyTo } = normalizeDirectMessage(directMessage)
+            await this.tagPerspectiveExpressionSignatureStatus(message)
+            this.#runtimeService.addReceivedMessage(message, MessageStatus.DeliveredP2P, replyTo)
+            await this.#pubSub.publish(PubSubDefinitions.RUNTIME_MESSAGED_RECEIVED_TOPIC, message)
+            await this.#pubSub.publish(PubSubDefinitions.RUNTIME_RECEIVED_MESSAGE_TOPIC, {
+                id: messageId(message),
+                message,
+                replyTo,
+                status: MessageStatus.DeliveredP2P
+            })
+        })
+
+        if(adapter.addReceiptCallback) {
+            adapter.addReceiptCallback(async (receipt: MessageReceipt) => {
+                let verified = false
+                try {
+                    verified = SIGNATURE.verify(receipt)
+                } catch(e) {}
+                if(!verified) {
+                    console.warn("LanguageController: ignoring message receipt with invalid signature from", receipt.author)
+                    return
+                }
+                const { status, messageIds } = receipt.data
+                if(![MessageStatus.Fetched, MessageStatus.Read].includes(status)) return
+                // Only the recipient of a message can acknowledge it
+                this.#runtimeService.updateOutboxStatus(receipt.author, messageIds, status)
+            })
+        }
+    }
+
     async tagExpressionSignatureStatus(expression: Expression) {
         if(expression) {
             tagExpressionSignatureStatus(expression)
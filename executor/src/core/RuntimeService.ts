@@ -1,12 +1,26 @@
 import * as path from 'node:path';
 import * as fs from 'node:fs';
-import { PerspectiveExpression } from '@coasys/ad4m';
+import { DirectMessage, MessageStatus, PerspectiveExpression } from '@coasys/ad4m';
 import { MainConfig } from './Config';
 
 const TRUSTED_AGENTS_FILE = "trustedAgents.json"
 const KNOW_LINK_LANGUAGES_FILE = "knownLinkLanguages.json"
 const FRIENDS_FILE = "friends.json"
 const OUTBOX_FILE = "outbox.json"
+const RECEIVED_MESSAGES_FILE = "receivedMessages.json"
+
+const MESSAGE_STATUS_ORDER = [
+    MessageStatus.Sent,
+    MessageStatus.DeliveredP2P,
+    MessageStatus.QueuedInbox,
+    MessageStatus.Fetched,
+    MessageStatus.Read,
+]
+
+// Receipts can arrive out of order, e.g. READ before FETCHED
+function isStatusAdvance(current: MessageStatus, next: MessageStatus): boolean {
+    return MESSAGE_STATUS_ORDER.indexOf(next) > MESSAGE_STATUS_ORDER.indexOf(current)
+}
 
 function _add(items: string[], file: string): void {
     let all: string[];
@@ -43,6 +57,10 @@ function _delete(items: string[], file: string): void {
     }
 }
 
+function _setObjects(items: object[], file: string): void {
+    fs.writeFileSync(file, JSON.stringify(items))
+}
+
 function _get(file: string): string[] {
     let all: string[] = []
     if (fs.existsSync(file)) {
@@ -60,8 +78,32 @@ function _getObjects(file: string): object[] {
 }
 
 export interface Message {
+    id: string;
     recipient: string;
     message: PerspectiveExpression;
+    replyTo?: string;
+    status: MessageStatus;
+    statusUpdatedAt: string;
+}
+
+/** What we keep about received messages, their content stays with the direct message language */
+export interface ReceivedMessageInfo {
+    id: string;
+    author: string;
+    replyTo?: string;
+    status: MessageStatus;
+}
+
+export function messageId(message: PerspectiveExpression): string {
+    return message.proof.signature
+}
+
+// Direct message languages written before threading hand out bare PerspectiveExpressions
+export function normalizeDirectMessage(message: DirectMessage | PerspectiveExpression): DirectMessage {
+    if("message" in message && message.message) {
+        return message as DirectMessage
+    }
+    return { message: message as PerspectiveExpression }
 }
 
 export default class RuntimeService {
@@ -94,6 +136,10 @@ export default class RuntimeService {
         return path.join(this.#config.rootConfigPath, OUTBOX_FILE)
     }
 
+    receivedMessagesPath(): string {
+        return path.join(this.#config.rootConfigPath, RECEIVED_MESSAGES_FILE)
+    }
+
     addTrustedAgents(agents: string[]): void {
         _add(agents, this.trustedAgentsPath())
     }
@@ -132,12 +178,47 @@ export default class RuntimeService {
         return _get(this.friendsPath())
     }
 
-    addMessageOutbox(recipient: string, message: PerspectiveExpression) {
-        _addObject({recipient, message}, this.outboxPath())
+    addMessageOutbox(recipient: string, message: PerspectiveExpression, status: MessageStatus, replyTo?: string) {
+        const sent: Message = {
+            id: messageId(message),
+            recipient,
+            message,
+            replyTo,
+            status,
+            statusUpdatedAt: new Date().toISOString()
+        }
+        _addObject(sent, this.outboxPath())
+    }
+
+    #allMessagesOutbox(): Message[] {
+        // Outboxes written before delivery tracking only have recipient and message
+        return (_getObjects(this.outboxPath()) as Message[]).map(m => ({
+            ...m,
+            id: m.id ?? messageId(m.message),
+            status: m.status ?? MessageStatus.Sent,
+            statusUpdatedAt: m.statusUpdatedAt ?? m.message.timestamp,
+        }))
+    }
+
+    /** Applies a receipt from `recipient`, returns the messages whose status changed */
+    updateOutboxStatus(recipient: string, ids: string[], status: MessageStatus): Message[] {
+        const messages = this.#allMessagesOutbox()
+        const updated: Message[] = []
+        for (const m of messages) {
+            if(m.recipient === recipient && ids.includes(m.id) && isStatusAdvance(m.status, status)) {
+                m.status = status
+                m.statusUpdatedAt = new Date().toISOString()
+                updated.push(m)
+            }
+        }
+        if(updated.length > 0) {
+            _setObjects(messages, this.outboxPath())
+        }
+        return updated
     }
 
     getMessagesOutbox(filter?: string): Message[] {
-        let messages = _getObjects(this.outboxPath()) as Message[]
+        let messages = this.#allMessagesOutbox()
         // console.log("OUTBOX:", messages)
         if(filter) {
             messages = messages.filter(m => m.recipient === filter)
@@ -145,4 +226,28 @@ export default class RuntimeService {
         return messages
     }
 
+    getReceivedMessages(): ReceivedMessageInfo[] {
+        return _getObjects(this.receivedMessagesPath()) as ReceivedMessageInfo[]
+    }
+
+    /** Records a received message, returns false if we knew it already */
+    addReceivedMessage(message: PerspectiveExpression, status: MessageStatus, replyTo?: string): boolean {
+        const id = messageId(message)
+        if(this.getReceivedMessages().some(m => m.id === id)) return false
+        _addObject({id, author: message.author, replyTo, status}, this.receivedMessagesPath())
+        return true
+    }
+
+    /** Sets the status of received messages, returns the ones that changed */
+    setReceivedMessagesStatus(ids: string[], status: MessageStatus): ReceivedMessageInfo[] {
+        const messages = this.getReceivedMessages()
+        const updated = messages.filter(m => ids.includes(m.id) && isStatusAdvance(m.status, status))
+        for (const m of updated) {
+            m.status = status
+        }
+        if(updated.length > 0) {
+            _setObjects(messages, this.receivedMessagesPath())
+        }
+        return updated
+    }
 }
\ No newline at end of file
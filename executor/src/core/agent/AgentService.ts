@@ -6,7 +6,7 @@ import {
   PublicSharing,
   ReadOnlyLanguage,
 } from "@coasys/ad4m";
-import { Agent, ExpressionProof, AgentSignature } from "@coasys/ad4m";
+import { Agent, ExpressionProof, AgentSignature, EncryptedData, EntanglementProof } from "@coasys/ad4m";
 import * as PubSubDefinitions from "../graphQL-interface/SubscriptionDefinitions";
 import { resolver } from "@transmute/did-key.js";
 import { getPubSub, tagExpressionSignatureStatus } from "../utils";
@@ -88,6 +88,19 @@ export default class AgentService {
     return AGENT.signStringHex(data);
   }
 
+  createEntanglementProof(deviceKeyType: string, deviceKey: string): EntanglementProof {
+    return new EntanglementProof(this.did!, this.signingKeyId, deviceKeyType, deviceKey, this.signString(deviceKey))
+  }
+
+  encryptFor(did: string, data: string): EncryptedData {
+    return AGENT.encryptFor(did, data);
+  }
+
+  decrypt(encrypted: EncryptedData): string {
+    this.signingChecks()
+    return AGENT.decrypt(encrypted);
+  }
+
   async updateAgent(a: Agent) {
     this.#agent = a;
     await this.storeAgentProfile();
@@ -5,6 +5,7 @@ import * as PubSubDefinitions from './SubscriptionDefinitions'
 import { ad4mExecutorVersion } from '../Config';
 import { OuterConfig } from '../../main';
 import { getPubSub, tagExpressionSignatureStatus } from '../utils';
+import { MessageStatus } from '@coasys/ad4m';
 
 
 export function createResolvers(core: Ad4mCore, config: OuterConfig) {
@@ -49,7 +50,7 @@ export function createResolvers(core: Ad4mCore, config: OuterConfig) {
             expression: async (args, context) => {
                 const url = args.url.toString();
                 const ref = parseExprUrl(url)
-                const expression = await core.languageController.getExpression(ref);
+                const expression = await core.languageController.getExpression(ref, args.refresh ?? false);
                 if(expression) {
                     expression.ref = ref
                     expression.url = url
@@ -264,8 +265,13 @@ export function createResolvers(core: Ad4mCore, config: OuterConfig) {
             //@ts-ignore
             runtimeMessageInbox: async (args, context) => {
                 const { filter } = args
-                const dmLang = await core.myDirectMessageLanguage()
-                return await dmLang.directMessageAdapter!.inbox(filter)
+                const messages = await core.receivedMessages(filter)
+                return messages.map(m => m.message)
+            },
+            //@ts-ignore
+            runtimeReceivedMessages: async (args, context) => {
+                const { filter } = args
+                return await core.receivedMessages(filter)
             },
             //@ts-ignore
             runtimeMessageOutbox: (args, context) => {
@@ -544,30 +550,37 @@ export function createResolvers(core: Ad4mCore, config: OuterConfig) {
 
             //@ts-ignore
             runtimeFriendSendMessage: async (args, context) => {
-                const { did, message } = args
+                const { did, message, replyTo } = args
                 if(!core.runtimeService.friends().includes(did)) throw `${did} is not a friend`
                 const dmLang = await core.friendsDirectMessageLanguage(did)
                 if(!dmLang) return false
 
-                let wasSent = false
                 let messageExpression
+                let deliveryStatus
                 try {
                     const status = await dmLang.directMessageAdapter!.status()
-                    if(status) {
-                        messageExpression = await dmLang.directMessageAdapter!.sendP2P(message)
-                        wasSent = true
-                    } else {
-                        throw "Friends seems offline"
-                    }
+                    if(!status) throw "Friends seems offline"
+                    messageExpression = await dmLang.directMessageAdapter!.sendP2P(message, { replyTo })
+                    if(!messageExpression) throw "Direct message language didn't send the message p2p"
+                    // Signals are fire-and-forget, only the recipient's receipt tells us it arrived
+                    deliveryStatus = MessageStatus.Sent
                 } catch(e) {
-                    messageExpression = await dmLang.directMessageAdapter!.sendInbox(message)
-                    wasSent = true
+                    messageExpression = await dmLang.directMessageAdapter!.sendInbox(message, { replyTo })
+                    deliveryStatus = MessageStatus.QueuedInbox
                 }
 
-                if(wasSent && messageExpression) {
-                    core.runtimeService.addMessageOutbox(did, messageExpression)
+                const wasSent = !!messageExpression
+                if(wasSent) {
+                    core.runtimeService.addMessageOutbox(did, messageExpression, deliveryStatus, replyTo)
                 }
                 return wasSent
+            },
+
+            //@ts-ignore
+            runtimeMarkMessagesRead: async (args, context) => {
+                const { ids } = args
+                await core.markMessagesRead(ids)
+                return true
             }
 
         },
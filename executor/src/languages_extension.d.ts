@@ -21,6 +21,13 @@ export interface PerspectiveDiff {
     removals: LinkExpression[];
 }
 
+export interface Expression {
+    author: string;
+    timestamp: string;
+    data: any;
+    proof: ExpressionProof;
+}
+
 export interface PerspectiveExpression {
     author: string;
     timestamp: string;
@@ -33,13 +40,16 @@ export interface Perspective {
 }
 
 // PerspectiveState is an enum in Rust, which can be represented as a union type in TypeScript
-export type PerspectiveState = 'PRIVATE' | 'NEIGHBOURHOOD_JOIN_INITIATED' | 'LINK_LANGUAGE_FAILED_TO_INSTALL' | 'LINK_LANGUAGE_INSTALLED_BUT_NOT_SYNCED' | 'SYNCED';
+export type PerspectiveState = 'PRIVATE' | 'NEIGHBOURHOOD_JOIN_INITIATED' | 'LINK_LANGUAGE_FAILED_TO_INSTALL' | 'LINK_LANGUAGE_INSTALLED_BUT_NOT_SYNCED' | 'SYNCED' | 'NOT_PERMITTED' | 'ARCHIVED';
 
 declare global {
     interface RustLanguages {
         perspectiveDiffReceived: (diff: PerspectiveDiff, languageAddress: string) => void;
         syncStateChanged: (state: PerspectiveState, languageAddress: string) => void;
         telepresenceSignalReceived: (signal: PerspectiveExpression, languageAddress: string) => void;
+        expressionCacheGet: (url: string, immutable: boolean) => Expression | null;
+        expressionCachePut: (url: string, expression: Expression) => void;
+        expressionCacheInvalidate: (url: string) => void;
     }
 
     const LANGUAGES: RustLanguages;
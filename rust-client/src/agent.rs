@@ -10,6 +10,8 @@ use futures::StreamExt;
 use graphql_client::{GraphQLQuery, Response};
 use graphql_ws_client::graphql::StreamingOperation;
 
+type DateTime = chrono::DateTime<chrono::Utc>;
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "schema.gql",
@@ -80,6 +82,66 @@ pub async fn retrieve_capability(
     Ok(response_data.agent_generate_jwt)
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/agent.gql",
+    response_derives = "Debug"
+)]
+pub struct GenerateAuthTokens;
+
+/// Like [`retrieve_capability`], but returns a short-lived access token
+/// together with a refresh token for [`refresh_jwt`].
+pub async fn generate_auth_tokens(
+    executor_url: String,
+    request_id: String,
+    rand: String,
+) -> Result<generate_auth_tokens::GenerateAuthTokensAgentGenerateAuthTokens> {
+    let query =
+        GenerateAuthTokens::build_query(generate_auth_tokens::Variables { request_id, rand });
+    let response_body: Response<generate_auth_tokens::ResponseData> = reqwest::Client::new()
+        .post(executor_url)
+        .json(&query)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    let response_data = response_body
+        .data
+        .ok_or_else(|| anyhow!("No data in response! Errors: {:?}", response_body.errors))?;
+    Ok(response_data.agent_generate_auth_tokens)
+}
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/agent.gql",
+    response_derives = "Debug"
+)]
+pub struct RefreshJwt;
+
+/// Exchanges a refresh token for new tokens. Needs no cap token,
+/// since it is used when the access token has expired.
+pub async fn refresh_jwt(
+    executor_url: String,
+    refresh_token: String,
+) -> Result<refresh_jwt::RefreshJwtAgentRefreshJwt> {
+    let query = RefreshJwt::build_query(refresh_jwt::Variables { refresh_token });
+    let response_body: Response<refresh_jwt::ResponseData> = reqwest::Client::new()
+        .post(executor_url)
+        .json(&query)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    let response_data = response_body
+        .data
+        .ok_or_else(|| anyhow!("No data in response! Errors: {:?}", response_body.errors))?;
+    Ok(response_data.agent_refresh_jwt)
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "schema.gql",
@@ -141,6 +203,52 @@ pub async fn revoke_token(
     Ok(response_data.agent_revoke_token)
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/agent.gql",
+    response_derives = "Debug"
+)]
+pub struct RotateAppTokens;
+
+pub async fn rotate_app_tokens(
+    executor_url: String,
+    cap_token: String,
+    request_id: String,
+) -> Result<rotate_app_tokens::RotateAppTokensAgentRotateAppTokens> {
+    let response_data: rotate_app_tokens::ResponseData = query(
+        executor_url,
+        cap_token,
+        RotateAppTokens::build_query(rotate_app_tokens::Variables { request_id }),
+    )
+    .await
+    .with_context(|| "Failed to run agent->rotate_app_tokens query")?;
+    Ok(response_data.agent_rotate_app_tokens)
+}
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/agent.gql",
+    response_derives = "Debug"
+)]
+pub struct GetAppTokens;
+
+pub async fn get_app_tokens(
+    executor_url: String,
+    cap_token: String,
+    request_id: String,
+) -> Result<Vec<get_app_tokens::GetAppTokensAgentGetAppTokens>> {
+    let response_data: get_app_tokens::ResponseData = query(
+        executor_url,
+        cap_token,
+        GetAppTokens::build_query(get_app_tokens::Variables { request_id }),
+    )
+    .await
+    .with_context(|| "Failed to run agent->get_app_tokens query")?;
+    Ok(response_data.agent_get_app_tokens)
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "schema.gql",
@@ -451,6 +559,45 @@ impl AgentClient {
         retrieve_capability(self.info.executor_url.clone(), request_id, rand).await
     }
 
+    pub async fn generate_auth_tokens(
+        &self,
+        request_id: String,
+        rand: String,
+    ) -> Result<generate_auth_tokens::GenerateAuthTokensAgentGenerateAuthTokens> {
+        generate_auth_tokens(self.info.executor_url.clone(), request_id, rand).await
+    }
+
+    pub async fn refresh_jwt(
+        &self,
+        refresh_token: String,
+    ) -> Result<refresh_jwt::RefreshJwtAgentRefreshJwt> {
+        refresh_jwt(self.info.executor_url.clone(), refresh_token).await
+    }
+
+    pub async fn rotate_app_tokens(
+        &self,
+        request_id: String,
+    ) -> Result<rotate_app_tokens::RotateAppTokensAgentRotateAppTokens> {
+        rotate_app_tokens(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            request_id,
+        )
+        .await
+    }
+
+    pub async fn get_app_tokens(
+        &self,
+        request_id: String,
+    ) -> Result<Vec<get_app_tokens::GetAppTokensAgentGetAppTokens>> {
+        get_app_tokens(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            request_id,
+        )
+        .await
+    }
+
     pub async fn me(&self) -> Result<me::MeAgent> {
         me(self.info.executor_url.clone(), self.info.cap_token.clone()).await
     }
@@ -0,0 +1,729 @@
+//! Generates typed Rust wrappers for SDNA subject classes.
+//!
+//! Class definitions can be read from a running perspective
+//! ([`subject_classes_from_perspective`]) or from SDNA source
+//! ([`subject_classes_from_sdna`]), e.g. in a build script:
+//!
+//! ```ignore
+//! // build.rs
+//! let code = ad4m_client::codegen::generate_from_sdna_file("sdna/todo.pl").unwrap();
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! std::fs::write(format!("{}/subject_classes.rs", out_dir), code).unwrap();
+//!
+//! // lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/subject_classes.rs"));
+//! ```
+//!
+//! Every generated struct wraps a [`SubjectProxy`](crate::subject_proxy::SubjectProxy),
+//! so all reads and writes go through the class' Prolog getters and SDNA actions.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::perspective_proxy::PerspectiveProxy;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubjectClassDefinition {
+    pub name: String,
+    pub properties: Vec<PropertyDefinition>,
+    pub collections: Vec<CollectionDefinition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyDefinition {
+    pub name: String,
+    /// Language the property value should be resolved with, e.g. "literal"
+    pub resolve_language: Option<String>,
+    pub writable: bool,
+}
+
+/// A collection with the names its actions are registered under
+/// (which can be the plural of the collection name)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollectionDefinition {
+    pub name: String,
+    pub adder: Option<String>,
+    pub remover: Option<String>,
+    pub setter: Option<String>,
+}
+
+/// The facts of one class, as found in SDNA or Prolog results
+#[derive(Default)]
+struct ClassFacts {
+    properties: Vec<String>,
+    resolve_languages: BTreeMap<String, String>,
+    property_setters: Vec<String>,
+    collections: Vec<String>,
+    collection_adders: Vec<String>,
+    collection_removers: Vec<String>,
+    collection_setters: Vec<String>,
+}
+
+impl ClassFacts {
+    fn into_definition(self, name: String) -> SubjectClassDefinition {
+        let find_action = |actions: &Vec<String>, collection: &String| {
+            actions
+                .iter()
+                .find(|a| *a == collection || **a == singular_to_plural(collection))
+                .cloned()
+        };
+
+        SubjectClassDefinition {
+            properties: self
+                .properties
+                .iter()
+                .map(|p| PropertyDefinition {
+                    name: p.clone(),
+                    resolve_language: self.resolve_languages.get(p).cloned(),
+                    writable: self.property_setters.contains(p),
+                })
+                .collect(),
+            collections: self
+                .collections
+                .iter()
+                .map(|c| CollectionDefinition {
+                    name: c.clone(),
+                    adder: find_action(&self.collection_adders, c),
+                    remover: find_action(&self.collection_removers, c),
+                    setter: find_action(&self.collection_setters, c),
+                })
+                .collect(),
+            name,
+        }
+    }
+}
+
+/// Reads all subject classes defined in the given perspective's SDNA
+pub async fn subject_classes_from_perspective(
+    perspective: &PerspectiveProxy,
+) -> Result<Vec<SubjectClassDefinition>> {
+    let mut classes = Vec::new();
+    for class in perspective.subject_classes().await? {
+        let query = |predicate: &str| format!(r#"subject_class("{}", C), {}"#, class, predicate);
+        let facts = ClassFacts {
+            properties: infer_strings(perspective, query("property(C, X)"), "X").await?,
+            resolve_languages: infer_pairs(
+                perspective,
+                query("property_resolve_language(C, X, Y)"),
+            )
+            .await?
+            .into_iter()
+            .collect(),
+            property_setters: infer_strings(perspective, query("property_setter(C, X, _)"), "X")
+                .await?,
+            collections: infer_strings(perspective, query("collection(C, X)"), "X").await?,
+            collection_adders: infer_strings(perspective, query("collection_adder(C, X, _)"), "X")
+                .await?,
+            collection_removers: infer_strings(
+                perspective,
+                query("collection_remover(C, X, _)"),
+                "X",
+            )
+            .await?,
+            collection_setters: infer_strings(
+                perspective,
+                query("collection_setter(C, X, _)"),
+                "X",
+            )
+            .await?,
+        };
+        classes.push(facts.into_definition(class));
+    }
+    Ok(classes)
+}
+
+async fn infer_strings(
+    perspective: &PerspectiveProxy,
+    query: String,
+    variable: &str,
+) -> Result<Vec<String>> {
+    let mut values = Vec::new();
+    if let Value::Array(results) = perspective.infer(query).await? {
+        for result in results {
+            if let Some(Value::String(value)) = result.get(variable) {
+                if !values.contains(value) {
+                    values.push(value.clone());
+                }
+            }
+        }
+    }
+    Ok(values)
+}
+
+async fn infer_pairs(
+    perspective: &PerspectiveProxy,
+    query: String,
+) -> Result<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    if let Value::Array(results) = perspective.infer(query).await? {
+        for result in results {
+            if let (Some(Value::String(x)), Some(Value::String(y))) =
+                (result.get("X"), result.get("Y"))
+            {
+                pairs.push((x.clone(), y.clone()));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Reads all subject classes defined in the given SDNA source.
+/// Only the facts (and clause heads) relevant for code generation are looked at,
+/// so rule bodies don't need to be understood.
+pub fn subject_classes_from_sdna(sdna: &str) -> Result<Vec<SubjectClassDefinition>> {
+    // Class atom (like `c`) -> class name, in order of definition
+    let mut class_names: Vec<(String, String)> = Vec::new();
+    let mut facts: BTreeMap<String, ClassFacts> = BTreeMap::new();
+
+    for clause in split_clauses(sdna)? {
+        let head = clause_head(&clause);
+        let (functor, args) = match parse_head(head) {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+        if args.len() < 2 {
+            continue;
+        }
+        let class = args[0].clone();
+        let name = match term_value(&args[1]) {
+            Some(name) => name,
+            // Rules like `property_setter(c, Property, Actions) :- ...` don't name anything
+            None => continue,
+        };
+        let entry = facts.entry(class.clone()).or_default();
+        let add = |list: &mut Vec<String>, name: String| {
+            if !list.contains(&name) {
+                list.push(name);
+            }
+        };
+
+        match (functor.as_str(), args.len()) {
+            ("subject_class", 2) => {
+                // subject_class(Name, Class) has its arguments the other way around
+                if let Some(class_name) = term_value(&args[0]) {
+                    class_names.push((args[1].clone(), class_name));
+                }
+            }
+            ("property", 2) => add(&mut entry.properties, name),
+            ("property_resolve_language", 3) => {
+                if let Some(language) = term_value(&args[2]) {
+                    entry.resolve_languages.insert(name, language);
+                }
+            }
+            ("property_setter", 3) => add(&mut entry.property_setters, name),
+            ("collection", 2) => add(&mut entry.collections, name),
+            ("collection_adder", 3) => add(&mut entry.collection_adders, name),
+            ("collection_remover", 3) => add(&mut entry.collection_removers, name),
+            ("collection_setter", 3) => add(&mut entry.collection_setters, name),
+            _ => {}
+        }
+    }
+
+    Ok(class_names
+        .into_iter()
+        .map(|(class, name)| {
+            facts
+                .remove(&class)
+                .unwrap_or_default()
+                .into_definition(name)
+        })
+        .collect())
+}
+
+/// Reads the SDNA file at `path` and generates code for all subject classes in it
+pub fn generate_from_sdna_file(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let sdna = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read SDNA file {}", path.display()))?;
+    Ok(generate_subject_classes(&subject_classes_from_sdna(&sdna)?))
+}
+
+/// Splits Prolog source into clauses, dropping comments
+fn split_clauses(source: &str) -> Result<Vec<String>> {
+    let mut clauses = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(q) => {
+                current.push(c);
+                if c == '\\' {
+                    if let Some(escaped) = chars.next() {
+                        current.push(escaped);
+                    }
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None => match c {
+                '"' | '\'' => {
+                    quote = Some(c);
+                    current.push(c);
+                }
+                '%' => while chars.next_if(|c| *c != '\n').is_some() {},
+                '.' if chars.peek().map_or(true, |next| next.is_whitespace()) => {
+                    let clause = current.trim().to_string();
+                    if !clause.is_empty() {
+                        clauses.push(clause);
+                    }
+                    current.clear();
+                }
+                _ => current.push(c),
+            },
+        }
+    }
+
+    if quote.is_some() {
+        return Err(anyhow::anyhow!("Unterminated quote in SDNA"));
+    }
+    Ok(clauses)
+}
+
+/// Returns the part of the clause before a top-level `:-`
+fn clause_head(clause: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (i, c) in clause.char_indices() {
+        match quote {
+            Some(q) if c == q && previous != '\\' => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '-' && previous == ':' => return &clause[..i - 1],
+            None => {}
+        }
+        previous = c;
+    }
+    clause
+}
+
+/// Splits `functor(arg1, arg2, ...)` into the functor and its top-level arguments
+fn parse_head(head: &str) -> Option<(String, Vec<String>)> {
+    let head = head.trim();
+    let open = head.find('(')?;
+    if !head.ends_with(')') {
+        return None;
+    }
+    let functor = head[..open].trim().to_string();
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut depth = 0;
+    for c in head[open + 1..head.len() - 1].chars() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+                current.push(c);
+            }
+            None => match c {
+                '"' | '\'' => {
+                    quote = Some(c);
+                    current.push(c);
+                }
+                '(' | '[' => {
+                    depth += 1;
+                    current.push(c);
+                }
+                ')' | ']' => {
+                    depth -= 1;
+                    current.push(c);
+                }
+                ',' if depth == 0 => {
+                    args.push(current.trim().to_string());
+                    current.clear();
+                }
+                _ => current.push(c),
+            },
+        }
+    }
+    args.push(current.trim().to_string());
+    Some((functor, args))
+}
+
+/// The value of a quoted string or atom argument, None for variables
+fn term_value(term: &str) -> Option<String> {
+    let first = term.chars().next()?;
+    if (first == '"' || first == '\'') && term.len() >= 2 && term.ends_with(first) {
+        Some(term[1..term.len() - 1].to_string())
+    } else if first.is_lowercase() {
+        Some(term.to_string())
+    } else {
+        None
+    }
+}
+
+/// Same as `singularToPlural()` in @coasys/ad4m, which names collection actions
+fn singular_to_plural(singular: &str) -> String {
+    match singular.strip_suffix('y') {
+        Some(stem) => format!("{}ies", stem),
+        None => format!("{}s", singular),
+    }
+}
+
+fn plural_to_singular(plural: &str) -> String {
+    if let Some(stem) = plural.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if let Some(stem) = plural.strip_suffix('s') {
+        stem.to_string()
+    } else {
+        plural.to_string()
+    }
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "abstract", "become", "box", "do", "final", "macro", "override", "priv",
+    "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// Methods every generated struct has, which properties must not shadow
+const RESERVED_METHODS: &[&str] = &["create", "get", "base", "subject"];
+
+/// "likedMessages" -> "liked_messages"
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for c in name.chars() {
+        if c.is_uppercase() {
+            if !snake.is_empty() && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else if c.is_alphanumeric() {
+            snake.push(c);
+        } else if !snake.ends_with('_') {
+            snake.push('_');
+        }
+    }
+    let snake = snake.trim_matches('_').to_string();
+    if snake.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", snake)
+    } else {
+        snake
+    }
+}
+
+/// "todo item" -> "TodoItem"
+fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    let mut upper_next = true;
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            if upper_next {
+                camel.extend(c.to_uppercase());
+            } else {
+                camel.push(c);
+            }
+            upper_next = false;
+        } else {
+            upper_next = true;
+        }
+    }
+    if camel.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", camel)
+    } else {
+        camel
+    }
+}
+
+fn method_name(name: &str) -> String {
+    let name = snake_case(name);
+    if RUST_KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else if RESERVED_METHODS.contains(&name.as_str()) {
+        format!("{}_property", name)
+    } else {
+        name
+    }
+}
+
+/// Generates one struct per subject class, with typed getters, setters and collection methods.
+/// The code refers to everything by full path, so it can be `include!`d anywhere.
+pub fn generate_subject_classes(classes: &[SubjectClassDefinition]) -> String {
+    let mut code = String::new();
+    code.push_str("// Generated by ad4m_client::codegen from subject class SDNA. Do not edit.\n");
+    for class in classes {
+        generate_class(&mut code, class).expect("Writing to a String can't fail");
+    }
+    code
+}
+
+fn generate_class(code: &mut String, class: &SubjectClassDefinition) -> std::fmt::Result {
+    let struct_name = camel_case(&class.name);
+    let class_name = &class.name;
+    write!(
+        code,
+        r#"
+/// Instance of subject class {class_name:?}
+pub struct {struct_name}<'a> {{
+    subject: ad4m_client::subject_proxy::SubjectProxy<'a>,
+}}
+
+impl<'a> {struct_name}<'a> {{
+    pub const CLASS_NAME: &str = {class_name:?};
+
+    /// Runs the class constructor on `base` and returns the new instance
+    pub async fn create(
+        perspective: &'a ad4m_client::perspective_proxy::PerspectiveProxy,
+        base: &str,
+    ) -> ad4m_client::anyhow::Result<Self> {{
+        let class = Self::CLASS_NAME.to_string();
+        let base = base.to_string();
+        perspective.create_subject(&class, &base).await?;
+        Self::get(perspective, &base).await
+    }}
+
+    /// Fails if `base` is not an instance of this class
+    pub async fn get(
+        perspective: &'a ad4m_client::perspective_proxy::PerspectiveProxy,
+        base: &str,
+    ) -> ad4m_client::anyhow::Result<Self> {{
+        let class = Self::CLASS_NAME.to_string();
+        let base = base.to_string();
+        Ok(Self {{
+            subject: perspective.get_subject(&class, &base).await?,
+        }})
+    }}
+
+    pub fn base(&self) -> &String {{
+        self.subject.base()
+    }}
+
+    pub fn subject(&self) -> &ad4m_client::subject_proxy::SubjectProxy<'a> {{
+        &self.subject
+    }}
+"#
+    )?;
+
+    for property in &class.properties {
+        generate_property(code, property)?;
+    }
+    for collection in &class.collections {
+        generate_collection(code, collection)?;
+    }
+
+    writeln!(code, "}}")
+}
+
+fn generate_property(code: &mut String, property: &PropertyDefinition) -> std::fmt::Result {
+    let getter = method_name(&property.name);
+    let setter = format!("set_{}", snake_case(&property.name));
+    let name = &property.name;
+    match property.resolve_language.as_deref() {
+        Some("literal") => {
+            write!(
+                code,
+                r#"
+    pub async fn {getter}(
+        &self,
+    ) -> ad4m_client::anyhow::Result<Option<ad4m_client::literal::LiteralValue>> {{
+        let property = {name:?}.to_string();
+        match self.subject.get_property(&property).await? {{
+            Some(url) => Ok(Some(ad4m_client::literal::Literal::from_url(url)?.get()?)),
+            None => Ok(None),
+        }}
+    }}
+"#
+            )?;
+            if property.writable {
+                write!(
+                    code,
+                    r#"
+    pub async fn {setter}(
+        &self,
+        value: ad4m_client::literal::LiteralValue,
+    ) -> ad4m_client::anyhow::Result<()> {{
+        let url = ad4m_client::literal::Literal::from_value(value).to_url()?;
+        self.subject.set_property(&{name:?}.to_string(), &url).await
+    }}
+"#
+                )?;
+            }
+        }
+        language => {
+            let doc = match language {
+                Some(language) => {
+                    format!(
+                        "    /// Address of an expression in language {:?}\n",
+                        language
+                    )
+                }
+                None => String::new(),
+            };
+            write!(
+                code,
+                r#"
+{doc}    pub async fn {getter}(&self) -> ad4m_client::anyhow::Result<Option<String>> {{
+        self.subject.get_property(&{name:?}.to_string()).await
+    }}
+"#
+            )?;
+            if property.writable {
+                write!(
+                    code,
+                    r#"
+    pub async fn {setter}(&self, value: &str) -> ad4m_client::anyhow::Result<()> {{
+        let property = {name:?}.to_string();
+        let value = value.to_string();
+        self.subject.set_property(&property, &value).await
+    }}
+"#
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn generate_collection(code: &mut String, collection: &CollectionDefinition) -> std::fmt::Result {
+    let getter = method_name(&collection.name);
+    let singular = snake_case(&plural_to_singular(&collection.name));
+    let name = &collection.name;
+    write!(
+        code,
+        r#"
+    pub async fn {getter}(&self) -> ad4m_client::anyhow::Result<Vec<String>> {{
+        self.subject.get_collection(&{name:?}.to_string()).await
+    }}
+"#
+    )?;
+    if let Some(adder) = &collection.adder {
+        write!(
+            code,
+            r#"
+    pub async fn add_{singular}(&self, element: &str) -> ad4m_client::anyhow::Result<()> {{
+        let collection = {adder:?}.to_string();
+        let element = element.to_string();
+        self.subject.add_collection(&collection, &element).await
+    }}
+"#
+        )?;
+    }
+    if let Some(remover) = &collection.remover {
+        write!(
+            code,
+            r#"
+    pub async fn remove_{singular}(&self, element: &str) -> ad4m_client::anyhow::Result<()> {{
+        let collection = {remover:?}.to_string();
+        let element = element.to_string();
+        self.subject.remove_collection(&collection, &element).await
+    }}
+"#
+        )?;
+    }
+    if let Some(setter) = &collection.setter {
+        let setter_name = snake_case(&collection.name);
+        write!(
+            code,
+            r#"
+    pub async fn set_{setter_name}(&self, elements: &[String]) -> ad4m_client::anyhow::Result<()> {{
+        let collection = {setter:?}.to_string();
+        self.subject.set_collection(&collection, elements).await
+    }}
+"#
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SDNA: &str = r#"
+subject_class("Todo", c).
+constructor(c, '[{action: "addLink", source: "this", predicate: "todo://state", target: "todo://ready"}]').
+instance(c, Base) :- triple(Base, "todo://state", _).
+
+% Properties
+property(c, "state").
+property_getter(c, Base, "state", Value) :- triple(Base, "todo://state", Value).
+property_setter(c, "state", '[{action: "setSingleTarget", source: "this", predicate: "todo://state", target: "value"}]').
+
+property(c, "title").
+property_resolve_language(c, "title", "literal").
+property_getter(c, Base, "title", Value) :- triple(Base, "todo://has_title", Value).
+property_setter(c, "title", '[{action: "setSingleTarget", source: "this", predicate: "todo://has_title", target: "value"}]').
+
+property(c, "isLiked").
+property_getter(c, Base, "isLiked", Value) :- triple(Base, "flux://has_reaction", "flux://thumbsup"), Value = true.
+
+collection(c, "comments").
+collection_getter(c, Base, "comments", List) :- findall(C, triple(Base, "todo://comment", C), List).
+collection_adder(c, "commentss", '[{action: "addLink", source: "this", predicate: "todo://comment", target: "value"}]').
+collection_remover(c, "commentss", '[{action: "removeLink", source: "this", predicate: "todo://comment", target: "value"}]').
+
+subject_class("Message", m).
+property(m, "body").
+property_setter(m, Property, Actions) :- Property = "body", Actions = [].
+"#;
+
+    #[test]
+    fn can_read_subject_classes_from_sdna() {
+        let classes = subject_classes_from_sdna(SDNA).unwrap();
+        assert_eq!(classes.len(), 2);
+
+        let todo = &classes[0];
+        assert_eq!(todo.name, "Todo");
+        assert_eq!(
+            todo.properties,
+            vec![
+                PropertyDefinition {
+                    name: "state".into(),
+                    resolve_language: None,
+                    writable: true,
+                },
+                PropertyDefinition {
+                    name: "title".into(),
+                    resolve_language: Some("literal".into()),
+                    writable: true,
+                },
+                PropertyDefinition {
+                    name: "isLiked".into(),
+                    resolve_language: None,
+                    writable: false,
+                },
+            ]
+        );
+        assert_eq!(
+            todo.collections,
+            vec![CollectionDefinition {
+                name: "comments".into(),
+                adder: Some("commentss".into()),
+                remover: Some("commentss".into()),
+                setter: None,
+            }]
+        );
+
+        let message = &classes[1];
+        assert_eq!(message.name, "Message");
+        assert_eq!(message.properties.len(), 1);
+        assert!(!message.properties[0].writable);
+    }
+
+    /// tests/codegen.rs compiles the fixture, so this also makes sure the generated code builds
+    #[test]
+    fn generates_typed_accessors() {
+        let code = generate_subject_classes(&subject_classes_from_sdna(SDNA).unwrap());
+        assert_eq!(code, include_str!("../tests/fixtures/subject_classes.rs"));
+    }
+
+    #[test]
+    fn names_are_valid_rust_identifiers() {
+        assert_eq!(camel_case("todo item"), "TodoItem");
+        assert_eq!(method_name("likedMessages"), "liked_messages");
+        assert_eq!(method_name("type"), "r#type");
+        assert_eq!(method_name("base"), "base_property");
+        assert_eq!(plural_to_singular("entries"), "entry");
+        assert_eq!(singular_to_plural("entry"), "entries");
+    }
+}
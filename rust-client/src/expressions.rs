@@ -46,11 +46,15 @@ pub async fn expression(
     executor_url: String,
     cap_token: String,
     url: String,
+    refresh: bool,
 ) -> Result<Option<expression::ExpressionExpression>> {
     let response_data: expression::ResponseData = query(
         executor_url,
         cap_token,
-        Expression::build_query(expression::Variables { url }),
+        Expression::build_query(expression::Variables {
+            url,
+            refresh: Some(refresh),
+        }),
     )
     .await
     .with_context(|| "Failed to run expressions->get query")?;
@@ -83,11 +87,13 @@ impl ExpressionsClient {
     pub async fn expression(
         &self,
         url: String,
+        refresh: bool,
     ) -> Result<Option<expression::ExpressionExpression>> {
         expression(
             self.info.executor_url.clone(),
             self.info.cap_token.clone(),
             url,
+            refresh,
         )
         .await
     }
@@ -7,7 +7,7 @@ use neighbourhoods::NeighbourhoodsClient;
 use perspectives::PerspectivesClient;
 use runtime::RuntimeClient;
 
-extern crate anyhow;
+pub extern crate anyhow;
 extern crate async_tungstenite;
 extern crate chrono;
 extern crate clap;
@@ -21,13 +21,16 @@ extern crate rustyline;
 extern crate tokio;
 
 pub mod agent;
+pub mod codegen;
 pub mod expressions;
 pub mod languages;
 pub mod literal;
 pub mod neighbourhoods;
 pub mod perspective_proxy;
 pub mod perspectives;
+pub mod prolog;
 pub mod runtime;
+pub mod sdna_action;
 pub mod subject_proxy;
 pub mod types;
 mod util;
@@ -1,9 +1,21 @@
 use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, FixedOffset, SecondsFormat};
 
+/// Decoded value of a `literal://` URL.
+///
+/// New literal types may be added in minor releases, so matches on this
+/// enum need a wildcard arm.
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum LiteralValue {
     String(String),
+    /// A float, written as `literal://number:` like JS numbers
     Number(f64),
+    Integer(i64),
+    Boolean(bool),
+    DateTime(DateTime<FixedOffset>),
+    Bytes(Vec<u8>),
     Json(serde_json::Value),
 }
 
@@ -11,12 +23,73 @@ impl ToString for LiteralValue {
     fn to_string(&self) -> String {
         match self {
             LiteralValue::String(string) => string.clone(),
-            LiteralValue::Number(number) => number.to_string(),
+            LiteralValue::Number(number) => format_number(*number),
+            LiteralValue::Integer(integer) => integer.to_string(),
+            LiteralValue::Boolean(boolean) => boolean.to_string(),
+            LiteralValue::DateTime(datetime) => format_datetime(datetime),
+            LiteralValue::Bytes(bytes) => BASE64.encode(bytes),
             LiteralValue::Json(json) => json.to_string(),
         }
     }
 }
 
+/// Writes numbers so that JS' `parseFloat()` reads them back
+fn format_number(number: f64) -> String {
+    if number.is_nan() {
+        "NaN".to_string()
+    } else if number.is_infinite() {
+        if number > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        number.to_string()
+    }
+}
+
+fn format_datetime(datetime: &DateTime<FixedOffset>) -> String {
+    datetime.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Encodes the given value as literal URL.
+/// This is the one encoder both ad4m-client and the executor use.
+pub fn literal_value_to_url(value: &LiteralValue) -> String {
+    match value {
+        LiteralValue::String(string) => format!("literal://string:{}", urlencoding::encode(string)),
+        LiteralValue::Json(json) => format!("literal://json:{}", urlencoding::encode(&json.to_string())),
+        LiteralValue::Number(_) => format!("literal://number:{}", value.to_string()),
+        LiteralValue::Integer(_) => format!("literal://integer:{}", value.to_string()),
+        LiteralValue::Boolean(_) => format!("literal://boolean:{}", value.to_string()),
+        LiteralValue::DateTime(_) => format!("literal://datetime:{}", urlencoding::encode(&value.to_string())),
+        LiteralValue::Bytes(_) => format!("literal://bytes:{}", urlencoding::encode(&value.to_string())),
+    }
+}
+
+/// Parses a literal URL.
+/// This is the one parser both ad4m-client and the executor use.
+pub fn parse_literal_url(url: &str) -> Result<LiteralValue> {
+    let body = url
+        .strip_prefix("literal://")
+        .ok_or(anyhow!("Not a literal URL"))?;
+    let (kind, encoded) = body
+        .split_once(':')
+        .ok_or(anyhow!("Literal URL without type: {}", url))?;
+
+    match kind {
+        "string" => Ok(LiteralValue::String(urlencoding::decode(encoded)?.into())),
+        "number" => Ok(LiteralValue::Number(urlencoding::decode(encoded)?.parse::<f64>()?)),
+        "integer" => Ok(LiteralValue::Integer(urlencoding::decode(encoded)?.parse::<i64>()?)),
+        "boolean" => match encoded {
+            "true" => Ok(LiteralValue::Boolean(true)),
+            "false" => Ok(LiteralValue::Boolean(false)),
+            _ => Err(anyhow!("Invalid boolean literal: {}", encoded)),
+        },
+        "datetime" => Ok(LiteralValue::DateTime(DateTime::parse_from_rfc3339(
+            &urlencoding::decode(encoded)?,
+        )?)),
+        "bytes" => Ok(LiteralValue::Bytes(BASE64.decode(urlencoding::decode(encoded)?.as_bytes())?)),
+        "json" => Ok(LiteralValue::Json(serde_json::from_str(&urlencoding::decode(encoded)?)?)),
+        _ => Err(anyhow!("Unknown literal type: {}", kind)),
+    }
+}
+
 pub struct Literal {
     value: Option<LiteralValue>,
     url: Option<String>,
@@ -35,22 +108,36 @@ impl Literal {
     }
 
     pub fn from_string(string: String) -> Self {
-        Self {
-            value: Some(LiteralValue::String(string)),
-            url: None,
-        }
+        Self::from_value(LiteralValue::String(string))
     }
 
     pub fn from_number(number: f64) -> Self {
-        Self {
-            value: Some(LiteralValue::Number(number)),
-            url: None,
-        }
+        Self::from_value(LiteralValue::Number(number))
+    }
+
+    pub fn from_integer(integer: i64) -> Self {
+        Self::from_value(LiteralValue::Integer(integer))
+    }
+
+    pub fn from_bool(boolean: bool) -> Self {
+        Self::from_value(LiteralValue::Boolean(boolean))
+    }
+
+    pub fn from_datetime(datetime: DateTime<FixedOffset>) -> Self {
+        Self::from_value(LiteralValue::DateTime(datetime))
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self::from_value(LiteralValue::Bytes(bytes))
     }
 
     pub fn from_json(json: serde_json::Value) -> Self {
+        Self::from_value(LiteralValue::Json(json))
+    }
+
+    pub fn from_value(value: LiteralValue) -> Self {
         Self {
-            value: Some(LiteralValue::Json(json)),
+            value: Some(value),
             url: None,
         }
     }
@@ -59,17 +146,7 @@ impl Literal {
         if let Some(url) = &self.url {
             Ok(url.clone())
         } else if let Some(value) = &self.value {
-            match value {
-                LiteralValue::String(string) => {
-                    let encoded = urlencoding::encode(string);
-                    Ok(format!("literal://string:{}", encoded))
-                }
-                LiteralValue::Number(number) => Ok(format!("literal://number:{}", number)),
-                LiteralValue::Json(json) => {
-                    let encoded = urlencoding::encode(&json.to_string()).to_string();
-                    Ok(format!("literal://json:{}", encoded))
-                }
-            }
+            Ok(literal_value_to_url(value))
         } else {
             Err(anyhow!("No value or URL"))
         }
@@ -77,27 +154,7 @@ impl Literal {
 
     pub fn parse_url(&self) -> Result<LiteralValue> {
         if let Some(url) = &self.url {
-            if url.starts_with("literal://") {
-                let literal = url.replace("literal://", "");
-                if literal.starts_with("string:") {
-                    let string = literal.replace("string:", "");
-                    let decoded = urlencoding::decode(&string)?;
-                    Ok(LiteralValue::String(decoded.into()))
-                } else if literal.starts_with("number:") {
-                    let number = literal.replace("number:", "");
-                    let parsed = number.parse::<f64>()?;
-                    Ok(LiteralValue::Number(parsed))
-                } else if literal.starts_with("json:") {
-                    let json = literal.replace("json:", "");
-                    let decoded = urlencoding::decode(&json)?;
-                    let parsed = serde_json::from_str::<serde_json::Value>(&decoded)?;
-                    Ok(LiteralValue::Json(parsed))
-                } else {
-                    Err(anyhow!("Unknown literal type"))
-                }
-            } else {
-                Err(anyhow!("Not a literal URL"))
-            }
+            parse_literal_url(url)
         } else {
             Err(anyhow!("No URL"))
         }
@@ -217,4 +274,93 @@ mod test {
             super::LiteralValue::String(test_string.into())
         );
     }
+
+    #[test]
+    fn can_handle_typed_values() {
+        let datetime = chrono::DateTime::parse_from_rfc3339("2023-11-05T14:30:00.123+01:00").unwrap();
+        let cases = vec![
+            (super::LiteralValue::Integer(-42), "literal://integer:-42"),
+            (super::LiteralValue::Boolean(true), "literal://boolean:true"),
+            (
+                super::LiteralValue::DateTime(datetime),
+                "literal://datetime:2023-11-05T14%3A30%3A00.123%2B01%3A00",
+            ),
+            (super::LiteralValue::Bytes(vec![0, 255, 1]), "literal://bytes:AP8B"),
+            (super::LiteralValue::Number(f64::INFINITY), "literal://number:Infinity"),
+        ];
+
+        for (value, url) in cases {
+            assert_eq!(super::Literal::from_value(value.clone()).to_url().unwrap(), url);
+            assert_eq!(super::Literal::from_url(url.into()).unwrap().get().unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn keeps_reading_untyped_numbers_and_json_booleans() {
+        assert_eq!(
+            super::parse_literal_url("literal://number:42").unwrap(),
+            super::LiteralValue::Number(42.0)
+        );
+        assert_eq!(
+            super::parse_literal_url("literal://json:true").unwrap(),
+            super::LiteralValue::Json(json!(true))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_literals() {
+        assert!(super::parse_literal_url("literal://boolean:yes").is_err());
+        assert!(super::parse_literal_url("literal://integer:4.2").is_err());
+        assert!(super::parse_literal_url("literal://datetime:yesterday").is_err());
+        assert!(super::parse_literal_url("literal://unknown:1").is_err());
+        assert!(super::parse_literal_url("literal://string").is_err());
+        assert!(super::parse_literal_url("ad4m://self").is_err());
+    }
+
+    mod round_trip {
+        use super::super::{parse_literal_url, literal_value_to_url, LiteralValue};
+        use chrono::{DateTime, FixedOffset, Utc};
+        use proptest::prelude::*;
+        use serde_json::json;
+
+        fn datetime() -> impl Strategy<Value = DateTime<FixedOffset>> {
+            // Years 1700 to 9900, with any offset RFC3339 can express
+            (-8_000_000_000i64..250_000_000_000i64, 0u32..1_000_000_000, -86_399i32..86_400).prop_map(
+                |(secs, nanos, offset)| {
+                    DateTime::<Utc>::from_timestamp(secs, nanos)
+                        .unwrap()
+                        .with_timezone(&FixedOffset::east_opt(offset - offset % 60).unwrap())
+                },
+            )
+        }
+
+        fn literal_value() -> impl Strategy<Value = LiteralValue> {
+            prop_oneof![
+                any::<String>().prop_map(LiteralValue::String),
+                any::<f64>()
+                    .prop_filter("NaN never equals itself", |n| !n.is_nan())
+                    .prop_map(LiteralValue::Number),
+                any::<i64>().prop_map(LiteralValue::Integer),
+                any::<bool>().prop_map(LiteralValue::Boolean),
+                datetime().prop_map(LiteralValue::DateTime),
+                any::<Vec<u8>>().prop_map(LiteralValue::Bytes),
+                (any::<String>(), any::<i32>(), any::<bool>())
+                    .prop_map(|(s, i, b)| LiteralValue::Json(json!({ "s": s, "list": [i, b] }))),
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn literal_urls_round_trip(value in literal_value()) {
+                let url = literal_value_to_url(&value);
+                prop_assert!(url.starts_with("literal://"));
+                prop_assert_eq!(parse_literal_url(&url).unwrap(), value);
+            }
+
+            #[test]
+            fn parsing_never_panics(url in "literal://[a-z]{0,8}:\\PC*") {
+                let _ = parse_literal_url(&url);
+            }
+        }
+    }
 }
@@ -57,6 +57,30 @@ pub async fn join(
     Ok(response_data.neighbourhood_join_from_url)
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/neighbourhoods.gql",
+    response_derives = "Debug"
+)]
+pub struct Leave;
+
+pub async fn leave(
+    executor_url: String,
+    cap_token: String,
+    uuid: String,
+    keep_local: bool,
+) -> Result<bool> {
+    let response_data: leave::ResponseData = query(
+        executor_url,
+        cap_token,
+        Leave::build_query(leave::Variables { uuid, keep_local }),
+    )
+    .await
+    .with_context(|| "Failed to run neighbourhoods->leave query")?;
+    Ok(response_data.neighbourhood_leave)
+}
+
 pub struct NeighbourhoodsClient {
     info: Arc<ClientInfo>,
 }
@@ -93,4 +117,14 @@ impl NeighbourhoodsClient {
         )
         .await
     }
+
+    pub async fn leave(&self, uuid: String, keep_local: bool) -> Result<bool> {
+        leave(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            uuid,
+            keep_local,
+        )
+        .await
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::HashSet;
 
 use crate::{
     literal::{Literal, LiteralValue},
@@ -6,12 +6,13 @@ use crate::{
         add_link::AddLinkPerspectiveAddLink, query_links::QueryLinksPerspectiveQueryLinks,
         PerspectivesClient,
     },
+    prolog::PrologResult,
+    sdna_action::{parse_action, Command, Parameter},
     subject_proxy::SubjectProxy,
-    types::LinkExpression,
+    types::{Link, LinkExpression, Perspective},
 };
 use anyhow::{anyhow, Result};
 use chrono::naive::NaiveDateTime;
-use regex::Regex;
 use serde_json::Value;
 type DateTime = NaiveDateTime;
 
@@ -58,16 +59,53 @@ impl PerspectiveProxy {
                 from_date,
                 until_date,
                 limit,
+                None,
+            )
+            .await
+    }
+
+    /// Like `get()`, but on the state of the perspective at the given point in time
+    pub async fn get_at(
+        &self,
+        source: Option<String>,
+        target: Option<String>,
+        predicate: Option<String>,
+        limit: Option<f64>,
+        at: DateTime,
+    ) -> Result<Vec<QueryLinksPerspectiveQueryLinks>> {
+        self.client
+            .query_links(
+                self.perspective_uuid.clone(),
+                source,
+                target,
+                predicate,
+                None,
+                None,
+                limit,
+                Some(at),
             )
             .await
     }
 
+    /// Returns all links of the perspective, optionally as they were at the given point in time
+    pub async fn snapshot(&self, at: Option<DateTime>) -> Result<Perspective> {
+        self.client
+            .snapshot(self.perspective_uuid.clone(), at)
+            .await
+    }
+
     pub async fn infer(&self, prolog_query: String) -> Result<Value> {
         self.client
             .infer(self.perspective_uuid.clone(), prolog_query)
             .await
     }
 
+    pub async fn infer_result(&self, prolog_query: String) -> Result<PrologResult> {
+        self.client
+            .infer_result(self.perspective_uuid.clone(), prolog_query)
+            .await
+    }
+
     pub async fn add_dna(&self, name: String, dna: String, dna_type: String) -> Result<()> {
         let mut predicate = "ad4m://has_custom_dna";
 
@@ -142,6 +180,7 @@ impl PerspectiveProxy {
                 None,
                 None,
                 None,
+                None,
             )
             .await?;
         if links.is_empty() {
@@ -169,6 +208,7 @@ impl PerspectiveProxy {
                 None,
                 None,
                 None,
+                None,
             )
             .await?
             .into_iter()
@@ -309,112 +349,135 @@ impl PerspectiveProxy {
         Ok(classes)
     }
 
+    /// Runs the given SDNA action on `base`, substituting `params` for their names.
+    /// All link changes of the action get applied together in one link mutation.
     pub async fn execute_action(
         &self,
         action: &String,
         base: &String,
-        params: Option<BTreeMap<&str, &String>>,
+        params: Option<Vec<Parameter>>,
     ) -> Result<()> {
-        let commands = parse_action(action)?;
+        let params = params.unwrap_or_default();
+        let commands = parse_action(action)?
+            .into_iter()
+            .map(|command| command.substitute(base, &params))
+            .collect::<Vec<Command>>();
+
+        let mut additions = Vec::new();
+        let mut removals = Vec::new();
+        let mut status: Option<String> = None;
+
         for command in commands {
-            let mut command = command.replace("this", base);
-            if let Some(ref params) = params {
-                for (key, value) in params.iter() {
-                    command = command.replace(key, value);
-                }
-            }
+            let adds_links = command.action != "removeLink";
             match command.action.as_str() {
                 "addLink" => {
-                    //println!("addLink: {:?}", command);
-                    self.add_link(command.source, command.target, command.predicate, command.status)
-                        .await?;
+                    additions.push(Link {
+                        source: command.source,
+                        predicate: command.predicate,
+                        target: command
+                            .target
+                            .ok_or(anyhow!("No target in addLink action"))?,
+                    });
                 }
                 "removeLink" => {
-                    unimplemented!();
-                    //let links = self.get(Some(source), Some(target), predicate, None, None, None).await?;
-                    //elf.remove_link(source, target.into(), predicate.into()).await?;
+                    removals.extend(
+                        self.get(
+                            Some(command.source),
+                            command.target,
+                            command.predicate,
+                            None,
+                            None,
+                            None,
+                        )
+                        .await?
+                        .into_iter()
+                        .map(LinkExpression::from),
+                    );
                 }
                 "setSingleTarget" => {
-                    self.set_single_target(
-                        command.source,
-                        command
-                            .predicate
-                            .ok_or(anyhow::anyhow!("No predicate in set_single_target action"))?,
-                        command.target,
-                    )
-                    .await?;
+                    let predicate = command
+                        .predicate
+                        .ok_or(anyhow!("No predicate in setSingleTarget action"))?;
+                    removals.extend(
+                        self.get(
+                            Some(command.source.clone()),
+                            None,
+                            Some(predicate.clone()),
+                            None,
+                            None,
+                            None,
+                        )
+                        .await?
+                        .into_iter()
+                        .map(LinkExpression::from),
+                    );
+                    additions.push(Link {
+                        source: command.source,
+                        predicate: Some(predicate),
+                        target: command
+                            .target
+                            .ok_or(anyhow!("No target in setSingleTarget action"))?,
+                    });
+                }
+                "collectionSetter" => {
+                    let predicate = command
+                        .predicate
+                        .ok_or(anyhow!("No predicate in collectionSetter action"))?;
+                    removals.extend(
+                        self.get(
+                            Some(command.source.clone()),
+                            None,
+                            Some(predicate.clone()),
+                            None,
+                            None,
+                            None,
+                        )
+                        .await?
+                        .into_iter()
+                        .map(LinkExpression::from),
+                    );
+                    // The collection gets replaced with all given values
+                    additions.extend(params.iter().map(|param| Link {
+                        source: command.source.clone(),
+                        predicate: Some(predicate.clone()),
+                        target: param.value.clone(),
+                    }));
                 }
                 _ => {
-                    return Err(anyhow::anyhow!("Unknown action: {}", command.action));
+                    return Err(anyhow!("Unknown action: {}", command.action));
+                }
+            }
+
+            if adds_links && command.status.is_some() {
+                if status.is_some() && status != command.status {
+                    return Err(anyhow!(
+                        "Can't apply action with mixed link statuses atomically: {}",
+                        action
+                    ));
                 }
+                status = command.status;
             }
         }
-        Ok(())
-    }
-}
 
-#[derive(Debug)]
-struct Command {
-    pub action: String,
-    pub source: String,
-    pub predicate: Option<String>,
-    pub target: String,
-    pub status: Option<String>
-}
+        // Different commands can match the same link
+        let mut seen = HashSet::new();
+        removals.retain(|link: &LinkExpression| {
+            seen.insert((
+                link.author.clone(),
+                link.timestamp.clone(),
+                link.data.source.clone(),
+                link.data.predicate.clone(),
+                link.data.target.clone(),
+            ))
+        });
 
-impl Command {
-    pub fn replace(&self, pattern: &str, replacement: &str) -> Command {
-        Command {
-            action: self.action.replace(pattern, replacement),
-            source: self.source.replace(pattern, replacement),
-            predicate: self
-                .predicate
-                .as_ref()
-                .map(|f| f.replace(pattern, replacement)),
-            target: self.target.replace(pattern, replacement),
-            status: self
-                .status
-                .as_ref()
-                .map(|f| f.replace(pattern, replacement)),
+        if additions.is_empty() && removals.is_empty() {
+            return Ok(());
         }
-    }
-}
 
-fn parse_action(action: &String) -> Result<Vec<Command>> {
-    let action_regex = Regex::new(r"\[(?P<command>\{.*})*\]")?;
-
-    // This parses strings like:
-    // {action: "<action>", source: "<source>", predicate: "<predicate>", target: "<target>", status: "<status>"}
-    let command_regex = Regex::new(
-        r#"\{(action:\s*"(?P<action>[\S--,]+)",?\s*)|(source:\s*"(?P<source>[\S--,]+)",?\s*)|(predicate:\s*"(?P<predicate>[\S--,]+)",?\s*)|(target:\s*"(?P<target>[\S--,]+)",?\s*)|(status:\s*"(?P<status>[\S--,]+)",?\s*)\}"#,
-    )?;
-
-    let mut commands = Vec::new();
-    for capture in action_regex.captures_iter(action) {
-        let mut action = None;
-        let mut source = None;
-        let mut predicate = None;
-        let mut target = None;
-        let mut status = None;
-        command_regex
-            .captures_iter(capture.name("command").unwrap().as_str())
-            .for_each(|capture| {
-                action = action.or(capture.name("action").map(|e| e.as_str()));
-                source = source.or(capture.name("source").map(|e| e.as_str()));
-                predicate = predicate.or(capture.name("predicate").map(|e| e.as_str()));
-                target = target.or(capture.name("target").map(|e| e.as_str()));
-                status = status.or(capture.name("status").map(|e| e.as_str()));
-            });
-
-        commands.push(Command {
-            action: action.ok_or(anyhow!("Comman without action"))?.into(),
-            source: source.ok_or(anyhow!("Comman without source"))?.into(),
-            predicate: predicate.map(|e| e.into()),
-            target: target.ok_or(anyhow!("Comman without target"))?.into(),
-            status: status.map(|e| e.into()),
-        });
-        //println!("{:?}", commands);
+        self.client
+            .apply_link_mutations(self.perspective_uuid.clone(), additions, removals, status)
+            .await?;
+        Ok(())
     }
-
-    Ok(commands)
 }
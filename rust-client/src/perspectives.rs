@@ -1,12 +1,13 @@
 use std::sync::Arc;
 
 use crate::perspective_proxy::PerspectiveProxy;
-use crate::types::{LinkExpression, Perspective};
+use crate::prolog::PrologResult;
+use crate::types::{Link, LinkExpression, Perspective};
 use crate::util::{create_websocket_client, query, query_raw};
 use crate::ClientInfo;
 use anyhow::{anyhow, Context, Result};
 use chrono::naive::NaiveDateTime;
-use futures::StreamExt;
+use futures::{stream, Stream, StreamExt, TryStreamExt};
 use graphql_client::{GraphQLQuery, Response};
 use graphql_ws_client::graphql::StreamingOperation;
 use serde_json::Value;
@@ -74,6 +75,99 @@ pub async fn remove(executor_url: String, cap_token: String, uuid: String) -> Re
     }
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/perspectives.gql",
+    response_derives = "Debug"
+)]
+pub struct Export;
+
+pub async fn export(executor_url: String, cap_token: String, uuid: String) -> Result<String> {
+    let response_data: export::ResponseData = query(
+        executor_url,
+        cap_token,
+        Export::build_query(export::Variables { uuid }),
+    )
+    .await
+    .with_context(|| "Failed to run perspectives->export query")?;
+    Ok(response_data.perspective_export)
+}
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/perspectives.gql",
+    response_derives = "Debug"
+)]
+pub struct ExportRdf;
+
+pub async fn export_rdf(
+    executor_url: String,
+    cap_token: String,
+    uuid: String,
+    format: String,
+) -> Result<String> {
+    let response_data: export_rdf::ResponseData = query(
+        executor_url,
+        cap_token,
+        ExportRdf::build_query(export_rdf::Variables { uuid, format }),
+    )
+    .await
+    .with_context(|| "Failed to run perspectives->exportRdf query")?;
+    Ok(response_data.perspective_export_rdf)
+}
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/perspectives.gql",
+    response_derives = "Debug"
+)]
+pub struct ImportRdf;
+
+pub async fn import_rdf(
+    executor_url: String,
+    cap_token: String,
+    uuid: String,
+    data: String,
+    format: String,
+    status: Option<String>,
+) -> Result<Vec<import_rdf::ImportRdfPerspectiveImportRdfAdditions>> {
+    let response_data: import_rdf::ResponseData = query(
+        executor_url,
+        cap_token,
+        ImportRdf::build_query(import_rdf::Variables {
+            uuid,
+            data,
+            format,
+            status,
+        }),
+    )
+    .await
+    .with_context(|| "Failed to run perspectives->importRdf query")?;
+    Ok(response_data.perspective_import_rdf.additions)
+}
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/perspectives.gql",
+    response_derives = "Debug"
+)]
+pub struct Import;
+
+pub async fn import(executor_url: String, cap_token: String, archive: String) -> Result<String> {
+    let response_data: import::ResponseData = query(
+        executor_url,
+        cap_token,
+        Import::build_query(import::Variables { archive }),
+    )
+    .await
+    .with_context(|| "Failed to run perspectives->import query")?;
+    Ok(response_data.perspective_import.uuid)
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "schema.gql",
@@ -157,6 +251,66 @@ pub async fn remove_link(
     }
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/perspectives.gql",
+    response_derives = "Debug"
+)]
+pub struct ApplyLinkMutations;
+
+/// Adds and removes the given links in one go
+pub async fn apply_link_mutations(
+    executor_url: String,
+    cap_token: String,
+    uuid: String,
+    additions: Vec<Link>,
+    removals: Vec<LinkExpression>,
+    status: Option<String>,
+) -> Result<apply_link_mutations::ApplyLinkMutationsPerspectiveLinkMutations> {
+    let response_data: apply_link_mutations::ResponseData = query(
+        executor_url,
+        cap_token,
+        ApplyLinkMutations::build_query(apply_link_mutations::Variables {
+            uuid,
+            mutations: apply_link_mutations::LinkMutations {
+                additions: additions
+                    .into_iter()
+                    .map(|link| apply_link_mutations::LinkInput {
+                        source: link.source,
+                        target: link.target,
+                        predicate: link.predicate,
+                    })
+                    .collect(),
+                removals: removals
+                    .into_iter()
+                    .map(|link| apply_link_mutations::LinkExpressionInput {
+                        author: link.author,
+                        timestamp: link.timestamp,
+                        data: apply_link_mutations::LinkInput {
+                            source: link.data.source,
+                            target: link.data.target,
+                            predicate: link.data.predicate,
+                        },
+                        proof: apply_link_mutations::ExpressionProofInput {
+                            signature: link.proof.signature,
+                            key: link.proof.key,
+                            invalid: link.proof.invalid,
+                            valid: link.proof.valid,
+                        },
+                        status: link.status,
+                    })
+                    .collect(),
+            },
+            status,
+        }),
+    )
+    .await
+    .with_context(|| "Failed to run perspectives->linkMutations query")?;
+
+    Ok(response_data.perspective_link_mutations)
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "schema.gql",
@@ -175,6 +329,7 @@ pub async fn query_links(
     from_date: Option<DateTime>,
     until_date: Option<DateTime>,
     limit: Option<f64>,
+    at: Option<DateTime>,
 ) -> Result<Vec<query_links::QueryLinksPerspectiveQueryLinks>> {
     let response_data: query_links::ResponseData = query(
         executor_url,
@@ -188,7 +343,9 @@ pub async fn query_links(
                 from_date: from_date,
                 until_date: until_date,
                 limit,
+                after: None,
             },
+            at,
         }),
     )
     .await
@@ -197,6 +354,81 @@ pub async fn query_links(
     Ok(response_data.perspective_query_links.unwrap_or_default())
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/perspectives.gql",
+    response_derives = "Debug"
+)]
+pub struct QueryLinksConnection;
+
+/// Fetches one page of at most `page_size` links, starting after the given cursor.
+pub async fn query_links_page(
+    executor_url: String,
+    cap_token: String,
+    uuid: String,
+    source: Option<String>,
+    target: Option<String>,
+    predicate: Option<String>,
+    from_date: Option<DateTime>,
+    until_date: Option<DateTime>,
+    page_size: f64,
+    after: Option<String>,
+    at: Option<DateTime>,
+) -> Result<query_links_connection::QueryLinksConnectionPerspectiveQueryLinksConnection> {
+    let response_data: query_links_connection::ResponseData = query(
+        executor_url,
+        cap_token,
+        QueryLinksConnection::build_query(query_links_connection::Variables {
+            uuid,
+            query: query_links_connection::LinkQuery {
+                source,
+                target,
+                predicate,
+                from_date,
+                until_date,
+                limit: Some(page_size),
+                after,
+            },
+            at,
+        }),
+    )
+    .await
+    .with_context(|| "Failed to run perspectives->queryLinksConnection query")?;
+
+    Ok(response_data.perspective_query_links_connection)
+}
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/perspectives.gql",
+    response_derives = "Debug"
+)]
+pub struct Search;
+
+pub async fn search(
+    executor_url: String,
+    cap_token: String,
+    uuid: String,
+    text: String,
+    predicate: Option<String>,
+) -> Result<Vec<search::SearchPerspectiveSearch>> {
+    let response_data: search::ResponseData = query(
+        executor_url,
+        cap_token,
+        Search::build_query(search::Variables {
+            uuid,
+            text,
+            predicate,
+        }),
+    )
+    .await
+    .with_context(|| "Failed to run perspectives->search query")?;
+
+    Ok(response_data.perspective_search)
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "schema.gql",
@@ -250,6 +482,34 @@ pub async fn infer(
     }
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/perspectives.gql",
+    response_derives = "Debug"
+)]
+pub struct InferResult;
+
+pub async fn infer_result(
+    executor_url: String,
+    cap_token: String,
+    uuid: String,
+    prolog_query: String,
+) -> Result<PrologResult> {
+    let response_data: infer_result::ResponseData = query(
+        executor_url,
+        cap_token,
+        InferResult::build_query(infer_result::Variables {
+            uuid,
+            query: prolog_query,
+        }),
+    )
+    .await
+    .with_context(|| "Failed to run perspectives->inferResult query")?;
+
+    PrologResult::try_from(response_data.perspective_query_prolog_result)
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "schema.gql",
@@ -311,11 +571,12 @@ pub async fn snapshot(
     executor_url: String,
     cap_token: String,
     uuid: String,
+    at: Option<DateTime>,
 ) -> Result<Perspective> {
     let response: snapshot::ResponseData = query(
         executor_url,
         cap_token,
-        Snapshot::build_query(snapshot::Variables { uuid }),
+        Snapshot::build_query(snapshot::Variables { uuid, at }),
     )
     .await
     .with_context(|| "Failed to run perspectives->snapshot query")?;
@@ -357,6 +618,52 @@ impl PerspectivesClient {
         .await
     }
 
+    pub async fn export(&self, uuid: String) -> Result<String> {
+        export(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            uuid,
+        )
+        .await
+    }
+
+    pub async fn export_rdf(&self, uuid: String, format: String) -> Result<String> {
+        export_rdf(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            uuid,
+            format,
+        )
+        .await
+    }
+
+    pub async fn import_rdf(
+        &self,
+        uuid: String,
+        data: String,
+        format: String,
+        status: Option<String>,
+    ) -> Result<Vec<import_rdf::ImportRdfPerspectiveImportRdfAdditions>> {
+        import_rdf(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            uuid,
+            data,
+            format,
+            status,
+        )
+        .await
+    }
+
+    pub async fn import(&self, archive: String) -> Result<String> {
+        import(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            archive,
+        )
+        .await
+    }
+
     pub async fn add_link(
         &self,
         uid: String,
@@ -387,6 +694,24 @@ impl PerspectivesClient {
         .await
     }
 
+    pub async fn apply_link_mutations(
+        &self,
+        uuid: String,
+        additions: Vec<Link>,
+        removals: Vec<LinkExpression>,
+        status: Option<String>,
+    ) -> Result<apply_link_mutations::ApplyLinkMutationsPerspectiveLinkMutations> {
+        apply_link_mutations(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            uuid,
+            additions,
+            removals,
+            status,
+        )
+        .await
+    }
+
     pub async fn query_links(
         &self,
         uuid: String,
@@ -396,6 +721,7 @@ impl PerspectivesClient {
         from_date: Option<DateTime>,
         until_date: Option<DateTime>,
         limit: Option<f64>,
+        at: Option<DateTime>,
     ) -> Result<Vec<query_links::QueryLinksPerspectiveQueryLinks>> {
         query_links(
             self.info.executor_url.clone(),
@@ -407,6 +733,81 @@ impl PerspectivesClient {
             from_date,
             until_date,
             limit,
+            at,
+        )
+        .await
+    }
+
+    /// Streams all matching links, fetching them page by page as the stream gets polled.
+    pub fn query_links_stream(
+        &self,
+        uuid: String,
+        source: Option<String>,
+        target: Option<String>,
+        predicate: Option<String>,
+        from_date: Option<DateTime>,
+        until_date: Option<DateTime>,
+        page_size: f64,
+        at: Option<DateTime>,
+    ) -> impl Stream<Item = Result<LinkExpression>> {
+        let executor_url = self.info.executor_url.clone();
+        let cap_token = self.info.cap_token.clone();
+
+        // The state is the cursor to continue after, or None once the last page was fetched
+        stream::try_unfold(Some(None), move |after: Option<Option<String>>| {
+            let executor_url = executor_url.clone();
+            let cap_token = cap_token.clone();
+            let uuid = uuid.clone();
+            let source = source.clone();
+            let target = target.clone();
+            let predicate = predicate.clone();
+            async move {
+                let after = match after {
+                    Some(after) => after,
+                    None => return Ok(None),
+                };
+                let page = query_links_page(
+                    executor_url,
+                    cap_token,
+                    uuid,
+                    source,
+                    target,
+                    predicate,
+                    from_date,
+                    until_date,
+                    page_size,
+                    after,
+                    at,
+                )
+                .await?;
+
+                let next = match (page.page_info.has_next_page, page.page_info.end_cursor) {
+                    (true, Some(end_cursor)) => Some(Some(end_cursor)),
+                    _ => None,
+                };
+                let links: Vec<Result<LinkExpression>> = page
+                    .edges
+                    .into_iter()
+                    .map(|edge| Ok(edge.node.into()))
+                    .collect();
+                Ok(Some((stream::iter(links), next)))
+            }
+        })
+        .try_flatten()
+    }
+
+    pub async fn search(
+        &self,
+        uuid: String,
+        text: String,
+        predicate: Option<String>,
+    ) -> Result<Vec<search::SearchPerspectiveSearch>> {
+        search(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            uuid,
+            text,
+            predicate,
         )
         .await
     }
@@ -421,6 +822,17 @@ impl PerspectivesClient {
         .await
     }
 
+    /// Like infer(), but with typed terms instead of JSON.
+    pub async fn infer_result(&self, uuid: String, prolog_query: String) -> Result<PrologResult> {
+        infer_result(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            uuid,
+            prolog_query,
+        )
+        .await
+    }
+
     pub async fn watch(
         &self,
         id: String,
@@ -435,11 +847,12 @@ impl PerspectivesClient {
         .await
     }
 
-    pub async fn snapshot(&self, uuid: String) -> Result<Perspective> {
+    pub async fn snapshot(&self, uuid: String, at: Option<DateTime>) -> Result<Perspective> {
         snapshot(
             self.info.executor_url.clone(),
             self.info.cap_token.clone(),
             uuid,
+            at,
         )
         .await
     }
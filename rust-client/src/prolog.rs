@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::perspectives::infer_result::{
+    InferResultPerspectiveQueryPrologResult, InferResultPerspectiveQueryPrologResultSolutions,
+    InferResultPerspectiveQueryPrologResultSolutionsTerms,
+};
+
+/// A Prolog term as returned by the executor, with strings and atoms kept apart.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrologTerm {
+    /// Arbitrary precision, so kept as text
+    Integer(String),
+    Float(f64),
+    Rational(String),
+    Atom(String),
+    String(String),
+    List(Vec<PrologTerm>),
+    Structure(String, Vec<PrologTerm>),
+    Variable,
+}
+
+/// Typed result of a Prolog query.
+/// A query without variables that succeeds has a single solution without bindings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrologResult {
+    pub success: bool,
+    pub solutions: Vec<BTreeMap<String, PrologTerm>>,
+}
+
+impl TryFrom<InferResultPerspectiveQueryPrologResult> for PrologResult {
+    type Error = anyhow::Error;
+
+    fn try_from(result: InferResultPerspectiveQueryPrologResult) -> Result<Self> {
+        Ok(PrologResult {
+            success: result.success,
+            solutions: result
+                .solutions
+                .into_iter()
+                .map(solution_bindings)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Builds the term trees from the flat term list the executor sends.
+fn solution_bindings(
+    solution: InferResultPerspectiveQueryPrologResultSolutions,
+) -> Result<BTreeMap<String, PrologTerm>> {
+    solution
+        .bindings
+        .iter()
+        .map(|binding| {
+            Ok((
+                binding.variable.clone(),
+                term_tree(&solution.terms, binding.term, 0)?,
+            ))
+        })
+        .collect()
+}
+
+fn term_tree(
+    terms: &[InferResultPerspectiveQueryPrologResultSolutionsTerms],
+    index: i64,
+    depth: usize,
+) -> Result<PrologTerm> {
+    // Arguments always come after their term, so a longer path means a cycle
+    if depth > terms.len() {
+        return Err(anyhow!("Cyclic Prolog term"));
+    }
+    let term = usize::try_from(index)
+        .ok()
+        .and_then(|index| terms.get(index))
+        .ok_or_else(|| anyhow!("Prolog term index {} out of range", index))?;
+    let value = || {
+        term.value
+            .clone()
+            .ok_or_else(|| anyhow!("Prolog {} term without value", term.kind))
+    };
+    let args = || {
+        term.args
+            .iter()
+            .map(|arg| term_tree(terms, *arg, depth + 1))
+            .collect::<Result<Vec<_>>>()
+    };
+
+    Ok(match term.kind.as_str() {
+        "INTEGER" => PrologTerm::Integer(value()?),
+        "FLOAT" => PrologTerm::Float(value()?.parse()?),
+        "RATIONAL" => PrologTerm::Rational(value()?),
+        "ATOM" => PrologTerm::Atom(value()?),
+        "STRING" => PrologTerm::String(value()?),
+        "LIST" => PrologTerm::List(args()?),
+        "STRUCTURE" => PrologTerm::Structure(value()?, args()?),
+        "VARIABLE" => PrologTerm::Variable,
+        kind => return Err(anyhow!("Unknown Prolog term kind: {}", kind)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perspectives::infer_result::InferResultPerspectiveQueryPrologResultSolutionsBindings;
+
+    fn term(kind: &str, value: Option<&str>, args: Vec<i64>) -> InferResultPerspectiveQueryPrologResultSolutionsTerms {
+        InferResultPerspectiveQueryPrologResultSolutionsTerms {
+            kind: kind.to_string(),
+            value: value.map(str::to_string),
+            args,
+        }
+    }
+
+    #[test]
+    fn builds_term_trees() {
+        let result = InferResultPerspectiveQueryPrologResult {
+            success: true,
+            solutions: vec![InferResultPerspectiveQueryPrologResultSolutions {
+                bindings: vec![InferResultPerspectiveQueryPrologResultSolutionsBindings {
+                    variable: "X".to_string(),
+                    term: 0,
+                }],
+                terms: vec![
+                    term("STRUCTURE", Some("pair"), vec![1, 2]),
+                    term("ATOM", Some("a"), vec![]),
+                    term("LIST", None, vec![3]),
+                    term("STRING", Some("a"), vec![]),
+                ],
+            }],
+        };
+
+        let result = PrologResult::try_from(result).unwrap();
+        assert_eq!(
+            result.solutions[0]["X"],
+            PrologTerm::Structure(
+                "pair".to_string(),
+                vec![
+                    PrologTerm::Atom("a".to_string()),
+                    PrologTerm::List(vec![PrologTerm::String("a".to_string())]),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn rejects_broken_term_lists() {
+        let solution = |terms| InferResultPerspectiveQueryPrologResult {
+            success: true,
+            solutions: vec![InferResultPerspectiveQueryPrologResultSolutions {
+                bindings: vec![InferResultPerspectiveQueryPrologResultSolutionsBindings {
+                    variable: "X".to_string(),
+                    term: 0,
+                }],
+                terms,
+            }],
+        };
+
+        assert!(PrologResult::try_from(solution(vec![])).is_err());
+        assert!(PrologResult::try_from(solution(vec![term("LIST", None, vec![0])])).is_err());
+        assert!(PrologResult::try_from(solution(vec![term("BLOB", None, vec![])])).is_err());
+    }
+}
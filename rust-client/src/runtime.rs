@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
-    types::{PerspectiveExpression, SentPerspectiveMessage},
+    types::{PerspectiveExpression, ReceivedPerspectiveMessage, SentPerspectiveMessage},
     util::query,
     ClientInfo,
 };
@@ -27,6 +27,28 @@ pub async fn info(executor_url: String, cap_token: String) -> Result<info::InfoR
     Ok(response_data.runtime_info)
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/runtime.gql",
+    response_derives = "Debug"
+)]
+pub struct ExpressionCacheStats;
+
+pub async fn expression_cache_stats(
+    executor_url: String,
+    cap_token: String,
+) -> Result<expression_cache_stats::ExpressionCacheStatsRuntimeExpressionCacheStats> {
+    let response_data: expression_cache_stats::ResponseData = query(
+        executor_url,
+        cap_token,
+        ExpressionCacheStats::build_query(expression_cache_stats::Variables {}),
+    )
+    .await
+    .with_context(|| "Failed to run runtime->expression_cache_stats query")?;
+    Ok(response_data.runtime_expression_cache_stats)
+}
+
 #[derive(GraphQLQuery)]
 #[graphql(
     schema_path = "schema.gql",
@@ -368,11 +390,16 @@ pub async fn friend_send_message(
     cap_token: String,
     did: String,
     message: friend_send_message::PerspectiveInput,
+    reply_to: Option<String>,
 ) -> Result<friend_send_message::ResponseData> {
     query(
         executor_url,
         cap_token,
-        FriendSendMessage::build_query(friend_send_message::Variables { did, message }),
+        FriendSendMessage::build_query(friend_send_message::Variables {
+            did,
+            message,
+            reply_to,
+        }),
     )
     .await
     .with_context(|| "Failed to run runtime->friend-send-message query")
@@ -434,6 +461,57 @@ pub async fn message_outbox(
         .collect())
 }
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/runtime.gql",
+    response_derives = "Debug"
+)]
+pub struct ReceivedMessages;
+
+pub async fn received_messages(
+    executor_url: String,
+    cap_token: String,
+    filter: Option<String>,
+) -> Result<Vec<ReceivedPerspectiveMessage>> {
+    let response: received_messages::ResponseData = query(
+        executor_url,
+        cap_token,
+        ReceivedMessages::build_query(received_messages::Variables { filter }),
+    )
+    .await
+    .with_context(|| "Failed to run runtime->received-messages query")?;
+
+    Ok(response
+        .runtime_received_messages
+        .into_iter()
+        .map(|d| d.into())
+        .collect())
+}
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schema.gql",
+    query_path = "src/runtime.gql",
+    response_derives = "Debug"
+)]
+pub struct MarkMessagesRead;
+
+pub async fn mark_messages_read(
+    executor_url: String,
+    cap_token: String,
+    ids: Vec<String>,
+) -> Result<bool> {
+    let response: mark_messages_read::ResponseData = query(
+        executor_url,
+        cap_token,
+        MarkMessagesRead::build_query(mark_messages_read::Variables { ids }),
+    )
+    .await
+    .with_context(|| "Failed to run runtime->mark-messages-read mutation")?;
+    Ok(response.runtime_mark_messages_read)
+}
+
 pub struct RuntimeClient {
     info: Arc<ClientInfo>,
 }
@@ -447,6 +525,12 @@ impl RuntimeClient {
         info(self.info.executor_url.clone(), self.info.cap_token.clone()).await
     }
 
+    pub async fn expression_cache_stats(
+        &self,
+    ) -> Result<expression_cache_stats::ExpressionCacheStatsRuntimeExpressionCacheStats> {
+        expression_cache_stats(self.info.executor_url.clone(), self.info.cap_token.clone()).await
+    }
+
     pub async fn quit(&self) -> Result<quit::ResponseData> {
         quit(self.info.executor_url.clone(), self.info.cap_token.clone()).await
     }
@@ -592,12 +676,14 @@ impl RuntimeClient {
         &self,
         did: String,
         message: friend_send_message::PerspectiveInput,
+        reply_to: Option<String>,
     ) -> Result<friend_send_message::ResponseData> {
         friend_send_message(
             self.info.executor_url.clone(),
             self.info.cap_token.clone(),
             did,
             message,
+            reply_to,
         )
         .await
     }
@@ -625,4 +711,25 @@ impl RuntimeClient {
         )
         .await
     }
+
+    pub async fn received_messages(
+        &self,
+        filter: Option<String>,
+    ) -> Result<Vec<ReceivedPerspectiveMessage>> {
+        received_messages(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            filter,
+        )
+        .await
+    }
+
+    pub async fn mark_messages_read(&self, ids: Vec<String>) -> Result<bool> {
+        mark_messages_read(
+            self.info.executor_url.clone(),
+            self.info.cap_token.clone(),
+            ids,
+        )
+        .await
+    }
 }
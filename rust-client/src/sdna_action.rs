@@ -0,0 +1,318 @@
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Number, Value};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// A named value that gets substituted into an SDNA action,
+/// like the new value of a property setter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    pub name: String,
+    pub value: String,
+}
+
+impl Parameter {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// One command of an SDNA action, like
+/// `{action: "addLink", source: "this", predicate: "todo://state", target: "todo://ready"}`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Command {
+    pub action: String,
+    pub source: String,
+    pub predicate: Option<String>,
+    pub target: Option<String>,
+    pub status: Option<String>,
+}
+
+impl Command {
+    fn from_object(object: Map<String, Value>) -> Result<Command> {
+        let string_field = |name: &str| -> Result<Option<String>> {
+            match object.get(name) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::String(s)) => Ok(Some(s.clone())),
+                Some(other) => Err(anyhow!(
+                    "Expected string for \"{}\" in action command, got: {}",
+                    name,
+                    other
+                )),
+            }
+        };
+
+        let local = match object.get("local") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(local)) => *local,
+            Some(other) => return Err(anyhow!("Expected boolean for \"local\", got: {}", other)),
+        };
+
+        let status = match string_field("status")? {
+            Some(status) => Some(status),
+            None if local => Some("local".to_string()),
+            None => None,
+        };
+
+        Ok(Command {
+            action: string_field("action")?.ok_or(anyhow!("Command without action"))?,
+            source: string_field("source")?.ok_or(anyhow!("Command without source"))?,
+            predicate: string_field("predicate")?,
+            target: string_field("target")?,
+            status,
+        })
+    }
+
+    /// Replaces `this` with the base expression and parameter names with their values.
+    /// Only whole fields get replaced, so URLs that happen to contain
+    /// a parameter name stay untouched.
+    pub fn substitute(self, base: &str, params: &[Parameter]) -> Command {
+        let replace = |field: String| -> String {
+            if field == "this" {
+                return base.to_string();
+            }
+            params
+                .iter()
+                .find(|p| p.name == field)
+                .map(|p| p.value.clone())
+                .unwrap_or(field)
+        };
+
+        Command {
+            action: self.action,
+            source: replace(self.source),
+            predicate: self.predicate.map(replace),
+            target: self.target.map(replace),
+            status: self.status,
+        }
+    }
+}
+
+/// Parses SDNA action strings as returned by Prolog, i.e. a list of
+/// JavaScript-like objects with unquoted keys and single or double quoted strings.
+pub(crate) fn parse_action(action: &str) -> Result<Vec<Command>> {
+    let mut parser = Parser {
+        chars: action.chars().peekable(),
+    };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if let Some(c) = parser.chars.next() {
+        return Err(anyhow!("Unexpected '{}' after end of action", c));
+    }
+
+    match value {
+        Value::Array(commands) => commands
+            .into_iter()
+            .map(|command| match command {
+                Value::Object(object) => Command::from_object(object),
+                other => Err(anyhow!("Expected action command object, got: {}", other)),
+            })
+            .collect(),
+        other => Err(anyhow!("Expected list of action commands, got: {}", other)),
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(anyhow!("Expected '{}' but found '{}'", expected, c)),
+            None => Err(anyhow!("Expected '{}' but action ended", expected)),
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some('[') => self.parse_array(),
+            Some('{') => self.parse_object(),
+            Some('"') | Some('\'') => Ok(Value::String(self.parse_string()?)),
+            Some(c) if c.is_ascii_digit() || c == '-' => self.parse_number(),
+            Some(_) => match self.parse_identifier()?.as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                "null" | "undefined" => Ok(Value::Null),
+                other => Err(anyhow!("Unexpected identifier: {}", other)),
+            },
+            None => Err(anyhow!("Unexpected end of action")),
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Value> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&']').is_some() {
+                break;
+            }
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&',').is_none() {
+                self.expect(']')?;
+                break;
+            }
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn parse_object(&mut self) -> Result<Value> {
+        self.expect('{')?;
+        let mut object = Map::new();
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&'}').is_some() {
+                break;
+            }
+            let key = match self.chars.peek().copied() {
+                Some('"') | Some('\'') => self.parse_string()?,
+                _ => self.parse_identifier()?,
+            };
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            object.insert(key, value);
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&',').is_none() {
+                self.expect('}')?;
+                break;
+            }
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        let quote = self
+            .chars
+            .next()
+            .ok_or(anyhow!("Expected string but action ended"))?;
+        let mut string = String::new();
+        loop {
+            match self.chars.next() {
+                Some('\\') => match self.chars.next() {
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some(c) => string.push(c),
+                    None => return Err(anyhow!("Unterminated escape sequence in string")),
+                },
+                Some(c) if c == quote => return Ok(string),
+                Some(c) => string.push(c),
+                None => return Err(anyhow!("Unterminated string: {}{}", quote, string)),
+            }
+        }
+    }
+
+    fn parse_identifier(&mut self) -> Result<String> {
+        let mut identifier = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+        {
+            identifier.push(c);
+        }
+        if identifier.is_empty() {
+            match self.chars.peek() {
+                Some(c) => Err(anyhow!("Unexpected character: '{}'", c)),
+                None => Err(anyhow!("Unexpected end of action")),
+            }
+        } else {
+            Ok(identifier)
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value> {
+        let mut number = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_ascii_digit() || matches!(*c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            number.push(c);
+        }
+        let parsed: f64 = number
+            .parse()
+            .map_err(|_| anyhow!("Invalid number: {}", number))?;
+        Number::from_f64(parsed)
+            .map(Value::Number)
+            .ok_or(anyhow!("Invalid number: {}", number))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn can_parse_actions() {
+        let commands = parse_action(
+            r#"[{action: "addLink", source: "this", predicate: "todo://state", target: "todo://ready"}, {action: 'removeLink', "source": "this", target: "value", local: true}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                Command {
+                    action: "addLink".into(),
+                    source: "this".into(),
+                    predicate: Some("todo://state".into()),
+                    target: Some("todo://ready".into()),
+                    status: None,
+                },
+                Command {
+                    action: "removeLink".into(),
+                    source: "this".into(),
+                    predicate: None,
+                    target: Some("value".into()),
+                    status: Some("local".into()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_commas_and_escaped_quotes_in_strings() {
+        let commands = parse_action(
+            r#"[{action: "addLink", source: "this", predicate: "todo://title", target: "literal://string:a,\"b\""}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            commands[0].target,
+            Some(r#"literal://string:a,"b""#.to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_actions() {
+        assert!(parse_action(r#"[{action: "addLink", source: "this""#).is_err());
+        assert!(parse_action(r#"{action: "addLink", source: "this"}"#).is_err());
+        assert!(parse_action(r#"[{source: "this", target: "value"}]"#).is_err());
+        assert!(parse_action(r#"[{action: "addLink", source: "this"}] trailing"#).is_err());
+    }
+
+    #[test]
+    fn substitutes_whole_fields_only() {
+        let command = Command {
+            action: "addLink".into(),
+            source: "this".into(),
+            predicate: Some("todo://value".into()),
+            target: Some("value".into()),
+            status: None,
+        }
+        .substitute("expr://base", &[Parameter::new("value", "literal://string:hi")]);
+
+        assert_eq!(command.source, "expr://base");
+        assert_eq!(command.predicate, Some("todo://value".into()));
+        assert_eq!(command.target, Some("literal://string:hi".into()));
+    }
+}
@@ -1,9 +1,9 @@
 use anyhow::{anyhow, Result};
-use maplit::btreemap;
 use serde_json::Value;
 use std::collections::BTreeMap;
 
 use crate::perspective_proxy::PerspectiveProxy;
+use crate::sdna_action::Parameter;
 
 fn prolog_list_to_array(list: &Value) -> Vec<String> {
     if let Some(Value::String(head)) = list.get("head") {
@@ -17,6 +17,14 @@ fn prolog_list_to_array(list: &Value) -> Vec<String> {
     }
 }
 
+/// Returns the action of the first solution of a setter, adder or remover query
+fn first_action(result: &Value) -> Option<&String> {
+    match result.as_array()?.first()?.get("Action") {
+        Some(Value::String(action)) => Some(action),
+        _ => None,
+    }
+}
+
 pub struct SubjectProxy<'a> {
     perspective: &'a PerspectiveProxy,
     subject_class: String,
@@ -56,22 +64,37 @@ impl<'a> SubjectProxy<'a> {
         .or_else(|_| Ok(Vec::new()))
     }
 
+    pub fn base(&self) -> &String {
+        &self.base
+    }
+
+    /// Returns the value of the given property, or None if the getter didn't resolve
+    pub async fn get_property(&self, property: &String) -> Result<Option<String>> {
+        let query = format!(
+            r#"subject_class("{}", C), property_getter(C, "{}", "{}", Value)"#,
+            self.subject_class, self.base, property
+        );
+        let result = self.perspective.infer(query).await?;
+
+        Ok(
+            match result.as_array().and_then(|results| results.first()) {
+                Some(result) => match result.get("Value") {
+                    Some(Value::String(value)) => Some(value.clone()),
+                    Some(Value::Number(value)) => Some(value.to_string()),
+                    Some(Value::Bool(value)) => Some(value.to_string()),
+                    _ => None,
+                },
+                None => None,
+            },
+        )
+    }
+
     pub async fn get_property_values(&self) -> Result<BTreeMap<String, String>> {
         let mut values = BTreeMap::new();
         let properties = self.property_names().await?;
         for p in properties {
-            let query = format!(
-                r#"subject_class("{}", C), property_getter(C, "{}", "{}", Value)"#,
-                self.subject_class, self.base, p
-            );
-            let result = self.perspective.infer(query).await?;
-
-            if let Some(result_array) = result.as_array() {
-                match result_array[0].get("Value") {
-                    Some(Value::String(value)) => values.insert(p.clone(), value.clone()),
-                    Some(Value::Number(value)) => values.insert(p.clone(), value.to_string()),
-                    _ => None,
-                };
+            if let Some(value) = self.get_property(&p).await? {
+                values.insert(p, value);
             }
         }
         Ok(values)
@@ -86,41 +109,47 @@ impl<'a> SubjectProxy<'a> {
         .or_else(|_| Ok(Vec::new()))
     }
 
+    /// Returns all elements of the given collection
+    pub async fn get_collection(&self, collection: &String) -> Result<Vec<String>> {
+        let query = format!(
+            r#"subject_class("{}", C), collection_getter(C, "{}", "{}", Value)"#,
+            self.subject_class, self.base, collection
+        );
+        let result = self.perspective.infer(query).await?;
+
+        let mut collection_values = Vec::new();
+        if let Some(result_array) = result.as_array() {
+            for p in result_array {
+                let value = p.get("Value");
+                match value {
+                    Some(Value::String(value)) => {
+                        collection_values.push(value.clone());
+                    }
+                    Some(Value::Object(_)) => {
+                        collection_values.extend(prolog_list_to_array(value.as_ref().unwrap()));
+                    }
+                    Some(Value::Array(value)) => {
+                        collection_values.extend(
+                            value
+                                .iter()
+                                .filter_map(|v| v.as_str())
+                                .map(|v| v.to_string())
+                                .collect::<Vec<String>>(),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(collection_values)
+    }
+
     pub async fn get_collection_values(&self) -> Result<BTreeMap<String, Vec<String>>> {
         let mut values = BTreeMap::new();
         let collections = self.collection_names().await?;
         for c in collections {
-            let query = format!(
-                r#"subject_class("{}", C), collection_getter(C, "{}", "{}", Value)"#,
-                self.subject_class, self.base, c
-            );
-            let result = self.perspective.infer(query).await?;
-
-            if let Some(result_array) = result.as_array() {
-                let mut collection_values = Vec::new();
-                for p in result_array {
-                    println!("{:?}", p.get("Value"));
-                    let value = p.get("Value");
-                    match value {
-                        Some(Value::String(value)) => {
-                            collection_values.push(value.clone());
-                        }
-                        Some(Value::Object(_)) => {
-                            collection_values.extend(prolog_list_to_array(value.as_ref().unwrap()));
-                        }
-                        Some(Value::Array(value)) => {
-                            collection_values.extend(
-                                value
-                                    .iter()
-                                    .map(|v| v.as_str().unwrap().to_string())
-                                    .collect::<Vec<String>>(),
-                            );
-                        }
-                        _ => {}
-                    }
-                }
-                values.insert(c, collection_values);
-            }
+            let collection_values = self.get_collection(&c).await?;
+            values.insert(c, collection_values);
         }
         Ok(values)
     }
@@ -131,19 +160,20 @@ impl<'a> SubjectProxy<'a> {
             self.subject_class, property,
         );
         let result = self.perspective.infer(query).await?;
-        if let Some(result_array) = result.as_array() {
-            if let Some(Value::String(action)) = result_array[0].get("Action") {
-                self.perspective
-                    .execute_action(action, &self.base, Some(btreemap! {"value" => value}))
-                    .await?;
-            }
-        } else {
-            return Err(anyhow!(
+        let action = first_action(&result).ok_or_else(|| {
+            anyhow!(
                 r#"No property_setter found for property "{}" on class "{}""#,
                 property,
                 self.subject_class
-            ));
-        }
+            )
+        })?;
+        self.perspective
+            .execute_action(
+                action,
+                &self.base,
+                Some(vec![Parameter::new("value", value.clone())]),
+            )
+            .await?;
         Ok(())
     }
 
@@ -153,19 +183,220 @@ impl<'a> SubjectProxy<'a> {
             self.subject_class, collection,
         );
         let result = self.perspective.infer(query).await?;
-        if let Some(result_array) = result.as_array() {
-            if let Some(Value::String(action)) = result_array[0].get("Action") {
-                self.perspective
-                    .execute_action(action, &self.base, Some(btreemap! {"value" => new_element}))
-                    .await?;
-            }
-        } else {
-            return Err(anyhow!(
+        let action = first_action(&result).ok_or_else(|| {
+            anyhow!(
                 r#"No collection_adder found for collection "{}" on class "{}""#,
                 collection,
                 self.subject_class
-            ));
-        }
+            )
+        })?;
+        self.perspective
+            .execute_action(
+                action,
+                &self.base,
+                Some(vec![Parameter::new("value", new_element.clone())]),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_collection(&self, collection: &String, element: &String) -> Result<()> {
+        let query = format!(
+            r#"subject_class("{}", C), collection_remover(C, "{}", Action)"#,
+            self.subject_class, collection,
+        );
+        let result = self.perspective.infer(query).await?;
+        let action = first_action(&result).ok_or_else(|| {
+            anyhow!(
+                r#"No collection_remover found for collection "{}" on class "{}""#,
+                collection,
+                self.subject_class
+            )
+        })?;
+        self.perspective
+            .execute_action(
+                action,
+                &self.base,
+                Some(vec![Parameter::new("value", element.clone())]),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_collection(&self, collection: &String, elements: &[String]) -> Result<()> {
+        let query = format!(
+            r#"subject_class("{}", C), collection_setter(C, "{}", Action)"#,
+            self.subject_class, collection,
+        );
+        let result = self.perspective.infer(query).await?;
+        let action = first_action(&result).ok_or_else(|| {
+            anyhow!(
+                r#"No collection_setter found for collection "{}" on class "{}""#,
+                collection,
+                self.subject_class
+            )
+        })?;
+        let params = elements
+            .iter()
+            .map(|element| Parameter::new("value", element.clone()))
+            .collect();
+        self.perspective
+            .execute_action(action, &self.base, Some(params))
+            .await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::perspectives::PerspectivesClient;
+    use crate::ClientInfo;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const COLLECTION_SETTER: &str = r#"[{action: "collectionSetter", source: "this", predicate: "todo://comment", target: "value"}]"#;
+
+    /// Answers GraphQL requests like an executor with a single comment link on
+    /// `expr://todo` would, and records every request it gets.
+    async fn mock_executor() -> (String, Arc<Mutex<Vec<Value>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/graphql", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buffer = Vec::new();
+                let body = loop {
+                    let mut chunk = [0; 4096];
+                    let read = socket.read(&mut chunk).await.unwrap();
+                    buffer.extend_from_slice(&chunk[..read]);
+                    let request = String::from_utf8_lossy(&buffer).to_string();
+                    if let Some(header_end) = request.find("\r\n\r\n") {
+                        let content_length = request[..header_end]
+                            .lines()
+                            .find_map(|line| {
+                                let (name, value) = line.split_once(':')?;
+                                name.eq_ignore_ascii_case("content-length")
+                                    .then(|| value.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if buffer.len() >= header_end + 4 + content_length {
+                            break buffer[header_end + 4..header_end + 4 + content_length].to_vec();
+                        }
+                    }
+                };
+
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let query = request["variables"]["query"].as_str().unwrap_or_default();
+                let data = match request["operationName"].as_str().unwrap() {
+                    "Infer" if query.contains("collection_setter") => json!({
+                        "perspectiveQueryProlog": json!([{ "Action": COLLECTION_SETTER }]).to_string()
+                    }),
+                    "Infer" => json!({ "perspectiveQueryProlog": "[]" }),
+                    "QueryLinks" => json!({
+                        "perspectiveQueryLinks": [{
+                            "author": "did:test",
+                            "timestamp": "2024-01-01T00:00:00.000Z",
+                            "data": {
+                                "source": "expr://todo",
+                                "predicate": "todo://comment",
+                                "target": "literal://string:old"
+                            },
+                            "proof": { "valid": true, "invalid": false, "signature": "sig", "key": "key" },
+                            "status": "shared"
+                        }]
+                    }),
+                    "ApplyLinkMutations" => json!({
+                        "perspectiveLinkMutations": { "additions": [], "removals": [] }
+                    }),
+                    other => panic!("Unexpected operation: {}", other),
+                };
+                recorded.lock().unwrap().push(request);
+
+                let response = json!({ "data": data }).to_string();
+                socket
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                            response.len(),
+                            response
+                        )
+                        .as_bytes(),
+                    )
+                    .await
+                    .unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn perspective(executor_url: String) -> PerspectiveProxy {
+        PerspectiveProxy::new(
+            PerspectivesClient::new(Arc::new(ClientInfo {
+                executor_url,
+                cap_token: String::new(),
+            })),
+            "uuid".to_string(),
+        )
+    }
+
+    #[tokio::test]
+    async fn set_collection_replaces_the_collection_in_one_mutation() {
+        let (url, requests) = mock_executor().await;
+        let perspective = perspective(url);
+        let todo = SubjectProxy::new(&perspective, "Todo".to_string(), "expr://todo".to_string());
+
+        todo.set_collection(
+            &"comments".to_string(),
+            &[
+                "literal://string:a".to_string(),
+                "literal://string:b".to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let requests = requests.lock().unwrap();
+        let mutations: Vec<&Value> = requests
+            .iter()
+            .filter(|request| request["operationName"] == "ApplyLinkMutations")
+            .collect();
+        assert_eq!(mutations.len(), 1);
+        let mutations = &mutations[0]["variables"]["mutations"];
+        assert_eq!(
+            mutations["additions"],
+            json!([
+                { "source": "expr://todo", "predicate": "todo://comment", "target": "literal://string:a" },
+                { "source": "expr://todo", "predicate": "todo://comment", "target": "literal://string:b" }
+            ])
+        );
+        let removals = mutations["removals"].as_array().unwrap();
+        assert_eq!(removals.len(), 1);
+        assert_eq!(removals[0]["data"]["target"], "literal://string:old");
+    }
+
+    #[tokio::test]
+    async fn missing_collection_remover_is_an_error() {
+        let (url, requests) = mock_executor().await;
+        let perspective = perspective(url);
+        let todo = SubjectProxy::new(&perspective, "Todo".to_string(), "expr://todo".to_string());
+
+        let result = todo
+            .remove_collection(&"comments".to_string(), &"literal://string:a".to_string())
+            .await;
+
+        assert!(result.is_err());
+        assert!(requests
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|request| request["operationName"] == "Infer"));
+    }
+}
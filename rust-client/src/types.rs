@@ -1,6 +1,8 @@
 use crate::agent::by_did::{ByDidAgentByDid, ByDidAgentByDidPerspectiveLinks};
 use crate::agent::me::{MeAgent, MeAgentPerspectiveLinks};
 use crate::perspectives::query_links::QueryLinksPerspectiveQueryLinks;
+use crate::perspectives::query_links_connection::QueryLinksConnectionPerspectiveQueryLinksConnectionEdgesNode;
+use crate::perspectives::search::SearchPerspectiveSearch;
 use crate::perspectives::subscription_link_added::SubscriptionLinkAddedPerspectiveLinkAdded;
 
 #[derive(Debug)]
@@ -89,6 +91,48 @@ impl From<QueryLinksPerspectiveQueryLinks> for LinkExpression {
     }
 }
 
+impl From<QueryLinksConnectionPerspectiveQueryLinksConnectionEdgesNode> for LinkExpression {
+    fn from(link: QueryLinksConnectionPerspectiveQueryLinksConnectionEdgesNode) -> Self {
+        Self {
+            author: link.author,
+            timestamp: link.timestamp,
+            data: Link {
+                predicate: link.data.predicate,
+                source: link.data.source,
+                target: link.data.target,
+            },
+            proof: ExpressionProof {
+                invalid: link.proof.invalid,
+                key: link.proof.key,
+                signature: link.proof.signature,
+                valid: link.proof.valid,
+            },
+            status: link.status,
+        }
+    }
+}
+
+impl From<SearchPerspectiveSearch> for LinkExpression {
+    fn from(link: SearchPerspectiveSearch) -> Self {
+        Self {
+            author: link.author,
+            timestamp: link.timestamp,
+            data: Link {
+                predicate: link.data.predicate,
+                source: link.data.source,
+                target: link.data.target,
+            },
+            proof: ExpressionProof {
+                invalid: link.proof.invalid,
+                key: link.proof.key,
+                signature: link.proof.signature,
+                valid: link.proof.valid,
+            },
+            status: link.status,
+        }
+    }
+}
+
 impl From<SubscriptionLinkAddedPerspectiveLinkAdded> for LinkExpression {
     fn from(link: SubscriptionLinkAddedPerspectiveLinkAdded) -> Self {
         Self {
@@ -382,14 +426,22 @@ impl From<MessageOutboxRuntimeMessageOutboxMessageDataLinks> for LinkExpression
     }
 }
 pub struct SentPerspectiveMessage {
+    pub id: String,
     pub recipient: String,
     pub message: PerspectiveExpression,
+    pub reply_to: Option<String>,
+    pub status: String,
+    pub status_updated_at: String,
 }
 
 impl From<MessageOutboxRuntimeMessageOutbox> for SentPerspectiveMessage {
     fn from(message: MessageOutboxRuntimeMessageOutbox) -> Self {
         Self {
+            id: message.id,
             recipient: message.recipient,
+            reply_to: message.reply_to,
+            status: message.status,
+            status_updated_at: message.status_updated_at,
             message: PerspectiveExpression {
                 author: message.message.author,
                 timestamp: message.message.timestamp,
@@ -413,6 +465,67 @@ impl From<MessageOutboxRuntimeMessageOutbox> for SentPerspectiveMessage {
     }
 }
 
+use crate::runtime::received_messages::{
+    ReceivedMessagesRuntimeReceivedMessages, ReceivedMessagesRuntimeReceivedMessagesMessageDataLinks,
+};
+
+impl From<ReceivedMessagesRuntimeReceivedMessagesMessageDataLinks> for LinkExpression {
+    fn from(link: ReceivedMessagesRuntimeReceivedMessagesMessageDataLinks) -> Self {
+        Self {
+            author: link.author,
+            timestamp: link.timestamp,
+            data: Link {
+                predicate: link.data.predicate,
+                source: link.data.source,
+                target: link.data.target,
+            },
+            proof: ExpressionProof {
+                invalid: None,
+                key: link.proof.key,
+                signature: link.proof.signature,
+                valid: None,
+            },
+            status: link.status,
+        }
+    }
+}
+
+pub struct ReceivedPerspectiveMessage {
+    pub id: String,
+    pub message: PerspectiveExpression,
+    pub reply_to: Option<String>,
+    pub status: String,
+}
+
+impl From<ReceivedMessagesRuntimeReceivedMessages> for ReceivedPerspectiveMessage {
+    fn from(received: ReceivedMessagesRuntimeReceivedMessages) -> Self {
+        Self {
+            id: received.id,
+            reply_to: received.reply_to,
+            status: received.status,
+            message: PerspectiveExpression {
+                author: received.message.author,
+                timestamp: received.message.timestamp,
+                data: Perspective {
+                    links: received
+                        .message
+                        .data
+                        .links
+                        .into_iter()
+                        .map(LinkExpression::from)
+                        .collect(),
+                },
+                proof: ExpressionProof {
+                    invalid: received.message.proof.invalid,
+                    key: received.message.proof.key,
+                    signature: received.message.proof.signature,
+                    valid: received.message.proof.valid,
+                },
+            },
+        }
+    }
+}
+
 pub struct Agent {
     pub did: String,
     pub direct_message_language: Option<String>,
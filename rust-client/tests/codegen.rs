@@ -0,0 +1,11 @@
+//! Compiles the code `codegen` generates for the SDNA in its unit tests,
+//! which make sure the fixture stays up to date.
+#![allow(dead_code)]
+
+include!("fixtures/subject_classes.rs");
+
+#[test]
+fn generated_subject_classes_compile() {
+    assert_eq!(Todo::CLASS_NAME, "Todo");
+    assert_eq!(Message::CLASS_NAME, "Message");
+}
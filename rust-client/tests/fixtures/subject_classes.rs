@@ -0,0 +1,133 @@
+// Generated by ad4m_client::codegen from subject class SDNA. Do not edit.
+
+/// Instance of subject class "Todo"
+pub struct Todo<'a> {
+    subject: ad4m_client::subject_proxy::SubjectProxy<'a>,
+}
+
+impl<'a> Todo<'a> {
+    pub const CLASS_NAME: &str = "Todo";
+
+    /// Runs the class constructor on `base` and returns the new instance
+    pub async fn create(
+        perspective: &'a ad4m_client::perspective_proxy::PerspectiveProxy,
+        base: &str,
+    ) -> ad4m_client::anyhow::Result<Self> {
+        let class = Self::CLASS_NAME.to_string();
+        let base = base.to_string();
+        perspective.create_subject(&class, &base).await?;
+        Self::get(perspective, &base).await
+    }
+
+    /// Fails if `base` is not an instance of this class
+    pub async fn get(
+        perspective: &'a ad4m_client::perspective_proxy::PerspectiveProxy,
+        base: &str,
+    ) -> ad4m_client::anyhow::Result<Self> {
+        let class = Self::CLASS_NAME.to_string();
+        let base = base.to_string();
+        Ok(Self {
+            subject: perspective.get_subject(&class, &base).await?,
+        })
+    }
+
+    pub fn base(&self) -> &String {
+        self.subject.base()
+    }
+
+    pub fn subject(&self) -> &ad4m_client::subject_proxy::SubjectProxy<'a> {
+        &self.subject
+    }
+
+    pub async fn state(&self) -> ad4m_client::anyhow::Result<Option<String>> {
+        self.subject.get_property(&"state".to_string()).await
+    }
+
+    pub async fn set_state(&self, value: &str) -> ad4m_client::anyhow::Result<()> {
+        let property = "state".to_string();
+        let value = value.to_string();
+        self.subject.set_property(&property, &value).await
+    }
+
+    pub async fn title(
+        &self,
+    ) -> ad4m_client::anyhow::Result<Option<ad4m_client::literal::LiteralValue>> {
+        let property = "title".to_string();
+        match self.subject.get_property(&property).await? {
+            Some(url) => Ok(Some(ad4m_client::literal::Literal::from_url(url)?.get()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn set_title(
+        &self,
+        value: ad4m_client::literal::LiteralValue,
+    ) -> ad4m_client::anyhow::Result<()> {
+        let url = ad4m_client::literal::Literal::from_value(value).to_url()?;
+        self.subject.set_property(&"title".to_string(), &url).await
+    }
+
+    pub async fn is_liked(&self) -> ad4m_client::anyhow::Result<Option<String>> {
+        self.subject.get_property(&"isLiked".to_string()).await
+    }
+
+    pub async fn comments(&self) -> ad4m_client::anyhow::Result<Vec<String>> {
+        self.subject.get_collection(&"comments".to_string()).await
+    }
+
+    pub async fn add_comment(&self, element: &str) -> ad4m_client::anyhow::Result<()> {
+        let collection = "commentss".to_string();
+        let element = element.to_string();
+        self.subject.add_collection(&collection, &element).await
+    }
+
+    pub async fn remove_comment(&self, element: &str) -> ad4m_client::anyhow::Result<()> {
+        let collection = "commentss".to_string();
+        let element = element.to_string();
+        self.subject.remove_collection(&collection, &element).await
+    }
+}
+
+/// Instance of subject class "Message"
+pub struct Message<'a> {
+    subject: ad4m_client::subject_proxy::SubjectProxy<'a>,
+}
+
+impl<'a> Message<'a> {
+    pub const CLASS_NAME: &str = "Message";
+
+    /// Runs the class constructor on `base` and returns the new instance
+    pub async fn create(
+        perspective: &'a ad4m_client::perspective_proxy::PerspectiveProxy,
+        base: &str,
+    ) -> ad4m_client::anyhow::Result<Self> {
+        let class = Self::CLASS_NAME.to_string();
+        let base = base.to_string();
+        perspective.create_subject(&class, &base).await?;
+        Self::get(perspective, &base).await
+    }
+
+    /// Fails if `base` is not an instance of this class
+    pub async fn get(
+        perspective: &'a ad4m_client::perspective_proxy::PerspectiveProxy,
+        base: &str,
+    ) -> ad4m_client::anyhow::Result<Self> {
+        let class = Self::CLASS_NAME.to_string();
+        let base = base.to_string();
+        Ok(Self {
+            subject: perspective.get_subject(&class, &base).await?,
+        })
+    }
+
+    pub fn base(&self) -> &String {
+        self.subject.base()
+    }
+
+    pub fn subject(&self) -> &ad4m_client::subject_proxy::SubjectProxy<'a> {
+        &self.subject
+    }
+
+    pub async fn body(&self) -> ad4m_client::anyhow::Result<Option<String>> {
+        self.subject.get_property(&"body".to_string()).await
+    }
+}
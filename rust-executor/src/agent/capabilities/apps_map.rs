@@ -1,4 +1,4 @@
-use super::types::AuthInfoExtended;
+use super::types::{AuthInfoExtended, Claims};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::{self, File};
@@ -11,6 +11,8 @@ pub struct App {
     auth_info_extended: AuthInfoExtended,
     revoked: bool,
     token: String,
+    #[serde(default)]
+    tokens: Vec<AppToken>,
 }
 
 impl App {
@@ -19,6 +21,39 @@ impl App {
             auth_info_extended,
             revoked,
             token,
+            tokens: Vec::new(),
+        }
+    }
+
+    pub fn auth_info_extended(&self) -> &AuthInfoExtended {
+        &self.auth_info_extended
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenKind {
+    Access,
+    Refresh,
+}
+
+/// Bookkeeping for a token issued to an app, identified by the nonce of its claims.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AppToken {
+    pub nonce: String,
+    pub kind: TokenKind,
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+impl AppToken {
+    pub fn new(kind: TokenKind, claims: &Claims) -> Self {
+        AppToken {
+            nonce: claims.nonce().clone(),
+            kind,
+            issued_at: claims.issued_at(),
+            expires_at: claims.expires_at(),
         }
     }
 }
@@ -86,6 +121,118 @@ pub fn revoke_app(request_key: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Records newly issued tokens for the app and makes `access_token` its current token.
+/// Expired tokens get dropped from the list on the way.
+pub fn add_app_tokens(
+    request_key: &str,
+    access_token: String,
+    tokens: Vec<AppToken>,
+) -> Result<(), String> {
+    let mut apps = APPS.lock().map_err(|e| e.to_string())?;
+    let app = apps
+        .get_mut(request_key)
+        .ok_or(format!("App with request_key '{}' not found.", request_key))?;
+    let now = unix_now();
+    app.tokens.retain(|token| token.expires_at >= now);
+    app.tokens.extend(tokens);
+    app.token = access_token;
+    persist_apps_to_file(&apps).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Drops all tokens issued to the app so far, without revoking the app itself
+pub fn revoke_app_tokens(request_key: &str) -> Result<(), String> {
+    let mut apps = APPS.lock().map_err(|e| e.to_string())?;
+    if let Some(app) = apps.get_mut(request_key) {
+        app.tokens.clear();
+        persist_apps_to_file(&apps).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Consumes the refresh token with the given nonce.
+/// A refresh token that isn't known (anymore) was either used before or rotated away,
+/// so all tokens of the app get dropped in that case.
+pub fn use_refresh_token(request_key: &str, nonce: &str) -> Result<(), String> {
+    let mut apps = APPS.lock().map_err(|e| e.to_string())?;
+    let app = apps
+        .get_mut(request_key)
+        .ok_or("Unauthorized access".to_string())?;
+    if app.revoked {
+        return Err("Unauthorized access".to_string());
+    }
+    let position = app
+        .tokens
+        .iter()
+        .position(|token| token.nonce == nonce && token.kind == TokenKind::Refresh);
+    match position {
+        Some(position) => {
+            app.tokens.remove(position);
+            persist_apps_to_file(&apps).map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        None => {
+            app.tokens.clear();
+            persist_apps_to_file(&apps).map_err(|e| e.to_string())?;
+            Err("Unauthorized access".to_string())
+        }
+    }
+}
+
+/// Checks that the app is still permitted and the token with the given nonce
+/// was issued to it and hasn't been dropped since.
+pub fn check_app_token(request_key: &str, nonce: &str, kind: TokenKind) -> Result<(), String> {
+    let apps = APPS.lock().map_err(|e| e.to_string())?;
+    let app = apps
+        .get(request_key)
+        .ok_or("Unauthorized access".to_string())?;
+    if app.revoked {
+        return Err("Unauthorized access".to_string());
+    }
+    if app
+        .tokens
+        .iter()
+        .any(|token| token.nonce == nonce && token.kind == kind)
+    {
+        Ok(())
+    } else {
+        Err("Unauthorized access".to_string())
+    }
+}
+
+/// Checks a token issued before tokens were tracked per app.
+/// Those are only valid as long as they are the current token of a permitted app.
+pub fn check_legacy_app_token(token: &str) -> Result<(), String> {
+    let apps = APPS.lock().map_err(|e| e.to_string())?;
+    match apps.values().find(|app| app.token == token) {
+        Some(app) if !app.revoked => Ok(()),
+        _ => Err("Unauthorized access".to_string()),
+    }
+}
+
+pub fn get_app_tokens(
+    request_key: &str,
+) -> Result<Vec<crate::graphql::graphql_types::AppTokenInfo>, String> {
+    let apps = APPS.lock().map_err(|e| e.to_string())?;
+    let app = apps
+        .get(request_key)
+        .ok_or(format!("App with request_key '{}' not found.", request_key))?;
+    let now = unix_now();
+    Ok(app
+        .tokens
+        .iter()
+        .filter(|token| token.expires_at >= now)
+        .map(|token| crate::graphql::graphql_types::AppTokenInfo::from(token.clone()))
+        .collect())
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
 pub fn remove_app(request_key: &str) -> Result<(), String> {
     let mut apps = APPS.lock().map_err(|e| e.to_string())?;
     if apps.remove(request_key).is_some() {
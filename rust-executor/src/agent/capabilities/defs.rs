@@ -190,6 +190,16 @@ pub fn perspective_delete_capability(pointers: Vec<String>) -> Capability {
     }
 }
 
+pub fn perspective_subscribe_capability(pointers: Vec<String>) -> Capability {
+    Capability {
+        with: Resource {
+            domain: PERSPECTIVE.to_string(),
+            pointers,
+        },
+        can: vec![SUBSCRIBE.to_string()],
+    }
+}
+
 lazy_static! {
     pub static ref PERSPECTIVE_SUBSCRIBE_CAPABILITY: Capability = Capability {
         with: Resource {
@@ -13,6 +13,7 @@ use crate::graphql::graphql_types::*;
 use crate::pubsub::{get_global_pubsub, APPS_CHANGED, EXCEPTION_OCCURRED_TOPIC};
 
 pub const DEFAULT_TOKEN_VALID_PERIOD: u64 = 180 * 24 * 60 * 60; // 180 days in seconds
+pub const ACCESS_TOKEN_VALID_PERIOD: u64 = 15 * 60; // 15 minutes in seconds
 
 pub fn check_capability(
     capabilities: &Result<Vec<Capability>, String>,
@@ -56,16 +57,35 @@ pub fn check_capability(
     Ok(())
 }
 
-pub fn check_token_revoked(
-    token: &String,
+/// Like check_capability(), but passes if the expected actions are granted
+/// for any pointer of the domain. Meant for listings and subscriptions
+/// that get filtered per pointer with check_capability() afterwards.
+pub fn check_capability_for_any_pointer(
+    capabilities: &Result<Vec<Capability>, String>,
+    expected: &Capability,
 ) -> Result<(), String> {
-    if let Some(app) = apps_map::get_apps().iter().find(|app| app.token == *token) {
-        if app.revoked.unwrap_or(false) {
-            return Err("Unauthorized access".to_string());
-        }
-    };
+    let granted = capabilities.clone()?;
+    let matches_any = granted.iter().any(|cap| {
+        let mut expected_for_cap = expected.clone();
+        expected_for_cap.with.pointers = cap.with.pointers.clone();
+        check_capability(&Ok(vec![cap.clone()]), &expected_for_cap).is_ok()
+    });
+
+    if matches_any {
+        Ok(())
+    } else {
+        Err(format!(
+            "Capability is not matched, you have capabilities: {:?}, expected: {:?}",
+            granted, expected
+        ))
+    }
+}
 
-    Ok(())
+pub fn check_token_revoked(claims: &Claims) -> Result<(), String> {
+    let request_id = claims
+        .subject()
+        .ok_or("Unauthorized access".to_string())?;
+    apps_map::check_app_token(request_id, claims.nonce(), apps_map::TokenKind::Access)
 }
 
 pub fn capabilities_from_token(
@@ -89,9 +109,25 @@ pub fn capabilities_from_token(
         return Ok(vec![AGENT_AUTH_CAPABILITY.clone()]);
     }
 
-    check_token_revoked(&token)?;
-
-    let claims = decode_jwt(token).map_err(|e| e.to_string())?;
+    let claims = match decode_jwt(token.clone(), ACCESS_TOKEN_AUDIENCE) {
+        Ok(claims) => {
+            check_token_revoked(&claims)?;
+            claims
+        }
+        Err(e) => {
+            // Tokens issued before access tokens had an audience and a subject
+            // stay valid until they expire.
+            let claims = decode_legacy_jwt(token.clone()).map_err(|_| e.to_string())?;
+            if claims.subject().is_some()
+                || claims.audience() == ACCESS_TOKEN_AUDIENCE
+                || claims.audience() == REFRESH_TOKEN_AUDIENCE
+            {
+                return Err(e.to_string());
+            }
+            apps_map::check_legacy_app_token(&token)?;
+            claims
+        }
+    };
 
     if claims.capabilities.capabilities.is_none() {
         Ok(vec![AGENT_AUTH_CAPABILITY.clone()])
@@ -137,27 +173,73 @@ pub fn permit_capability(auth_info_extended: AuthInfoExtended) -> Result<String,
     Ok(rand)
 }
 
-pub async fn generate_capability_token(request_id: String, rand: String) -> Result<String, String> {
-    let auth_key = gen_request_key(&request_id, &rand);
-
-    let auth = get_request(&auth_key)?.ok_or("Can't find permitted request")?;
+/// Issues a new pair of access and refresh tokens to the app with the given request id
+fn issue_tokens(request_id: &str, auth: AuthInfo) -> Result<AuthTokens, String> {
+    let (access_token, access_claims) = token::generate_jwt(
+        ACCESS_TOKEN_AUDIENCE.to_string(),
+        Some(request_id.to_string()),
+        ACCESS_TOKEN_VALID_PERIOD,
+        auth.clone(),
+    )
+    .map_err(|e| e.to_string())?;
 
-    let cap_token = token::generate_jwt(
-        auth.app_name.clone(),
+    let (refresh_token, refresh_claims) = token::generate_jwt(
+        REFRESH_TOKEN_AUDIENCE.to_string(),
+        Some(request_id.to_string()),
         DEFAULT_TOKEN_VALID_PERIOD,
-        auth.clone(),
+        auth,
     )
     .map_err(|e| e.to_string())?;
 
+    apps_map::add_app_tokens(
+        request_id,
+        access_token.clone(),
+        vec![
+            apps_map::AppToken::new(apps_map::TokenKind::Access, &access_claims),
+            apps_map::AppToken::new(apps_map::TokenKind::Refresh, &refresh_claims),
+        ],
+    )?;
+
+    Ok(AuthTokens::new(access_token, refresh_token, access_claims.expires_at()))
+}
+
+/// Turns a permitted request into an app and returns the capabilities it was granted
+fn register_app(request_id: &str, rand: &str) -> Result<AuthInfo, String> {
+    let auth_key = gen_request_key(request_id, rand);
+
+    let auth = get_request(&auth_key)?.ok_or("Can't find permitted request")?;
+
     remove_request(&auth_key)?;
 
     apps_map::insert_app(
-        request_id.clone(),
+        request_id.to_string(),
         AuthInfoExtended {
-            request_id: request_id.clone(),
-            auth,
+            request_id: request_id.to_string(),
+            auth: auth.clone(),
         },
+        String::new(),
+    )?;
+
+    Ok(auth)
+}
+
+/// Issues a single long-lived access token for clients that don't refresh their tokens.
+/// New clients should use [`generate_auth_tokens`] instead.
+pub async fn generate_capability_token(request_id: String, rand: String) -> Result<String, String> {
+    let auth = register_app(&request_id, &rand)?;
+
+    let (cap_token, claims) = token::generate_jwt(
+        ACCESS_TOKEN_AUDIENCE.to_string(),
+        Some(request_id.clone()),
+        DEFAULT_TOKEN_VALID_PERIOD,
+        auth,
+    )
+    .map_err(|e| e.to_string())?;
+
+    apps_map::add_app_tokens(
+        &request_id,
         cap_token.clone(),
+        vec![apps_map::AppToken::new(apps_map::TokenKind::Access, &claims)],
     )?;
 
     get_global_pubsub()
@@ -168,6 +250,51 @@ pub async fn generate_capability_token(request_id: String, rand: String) -> Resu
     Ok(cap_token)
 }
 
+/// Issues a short-lived access token together with a refresh token
+pub async fn generate_auth_tokens(request_id: String, rand: String) -> Result<AuthTokens, String> {
+    let auth = register_app(&request_id, &rand)?;
+
+    let tokens = issue_tokens(&request_id, auth)?;
+
+    get_global_pubsub()
+        .await
+        .publish(&APPS_CHANGED, &String::from(""))
+        .await;
+
+    Ok(tokens)
+}
+
+/// Exchanges a refresh token for a new pair of tokens.
+/// Every refresh token can only be used once. Presenting one that was already used
+/// means it leaked, so all tokens of the app get revoked in that case.
+pub fn refresh_capability_token(refresh_token: String) -> Result<AuthTokens, String> {
+    let claims = decode_jwt(refresh_token, REFRESH_TOKEN_AUDIENCE).map_err(|e| e.to_string())?;
+    let request_id = claims
+        .subject()
+        .ok_or("Unauthorized access".to_string())?
+        .clone();
+
+    apps_map::use_refresh_token(&request_id, claims.nonce())?;
+
+    issue_tokens(&request_id, claims.capabilities)
+}
+
+/// Revokes all tokens of the app and issues a new pair
+pub async fn rotate_capability_tokens(request_id: String) -> Result<AuthTokens, String> {
+    let app = apps_map::get_app(&request_id)?
+        .ok_or(format!("App with request_key '{}' not found.", request_id))?;
+
+    apps_map::revoke_app_tokens(&request_id)?;
+    let tokens = issue_tokens(&request_id, app.auth_info_extended().auth.clone())?;
+
+    get_global_pubsub()
+        .await
+        .publish(&APPS_CHANGED, &String::from(""))
+        .await;
+
+    Ok(tokens)
+}
+
 pub fn gen_random_digits() -> String {
     use rand::Rng;
     let mut rng = rand::thread_rng();
@@ -286,6 +413,114 @@ mod tests {
         assert!(check_capability(&Ok(vec![query_capability]), &expected_capability).is_ok());
     }
 
+    #[test]
+    fn agent_with_scoped_perspective_capability_cannot_access_other_perspectives() {
+        let granted = Ok(vec![
+            perspective_query_capability(vec!["123".to_string()]),
+            perspective_subscribe_capability(vec!["123".to_string()]),
+        ]);
+        assert!(check_capability(&granted, &perspective_query_capability(vec!["123".to_string()])).is_ok());
+        assert!(check_capability(&granted, &perspective_query_capability(vec!["456".to_string()])).is_err());
+        assert!(check_capability(&granted, &perspective_update_capability(vec!["123".to_string()])).is_err());
+        assert!(check_capability(&granted, &perspective_subscribe_capability(vec!["456".to_string()])).is_err());
+    }
+
+    #[test]
+    fn any_pointer_check_requires_a_matching_perspective_capability() {
+        let scoped = Ok(vec![perspective_query_capability(vec!["123".to_string()])]);
+        assert!(check_capability_for_any_pointer(&scoped, &perspective_query_capability(vec![])).is_ok());
+        assert!(check_capability_for_any_pointer(&scoped, &PERSPECTIVE_SUBSCRIBE_CAPABILITY).is_err());
+
+        let agent_only = Ok(vec![AGENT_READ_CAPABILITY.clone()]);
+        assert!(check_capability_for_any_pointer(&agent_only, &perspective_query_capability(vec![])).is_err());
+    }
+
+    fn setup_apps() {
+        crate::test_utils::setup_wallet();
+        apps_map::set_data_file_path(
+            std::env::temp_dir()
+                .join("ad4m_test_apps_data.json")
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+
+    #[test]
+    fn refresh_tokens_can_only_be_used_once() {
+        setup_apps();
+
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let auth = AuthInfo {
+            app_name: "test-app".to_string(),
+            capabilities: Some(vec![AGENT_READ_CAPABILITY.clone()]),
+            ..Default::default()
+        };
+        apps_map::insert_app(
+            request_id.clone(),
+            AuthInfoExtended { request_id: request_id.clone(), auth: auth.clone() },
+            String::new(),
+        )
+        .unwrap();
+        let tokens = issue_tokens(&request_id, auth).unwrap();
+
+        let capabilities = capabilities_from_token(tokens.access_token.clone(), None).unwrap();
+        assert_eq!(capabilities.len(), 1);
+        assert!(capabilities_from_token(tokens.refresh_token.clone(), None).is_err());
+
+        let refreshed = refresh_capability_token(tokens.refresh_token.clone()).unwrap();
+        assert!(capabilities_from_token(refreshed.access_token.clone(), None).is_ok());
+
+        // Reusing a refresh token revokes everything the app got so far
+        assert!(refresh_capability_token(tokens.refresh_token).is_err());
+        assert!(capabilities_from_token(refreshed.access_token, None).is_err());
+        assert!(refresh_capability_token(refreshed.refresh_token).is_err());
+        assert!(apps_map::get_app_tokens(&request_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn revoked_apps_lose_their_tokens() {
+        setup_apps();
+        let request_id = uuid::Uuid::new_v4().to_string();
+        apps_map::insert_app(
+            request_id.clone(),
+            AuthInfoExtended { request_id: request_id.clone(), auth: AuthInfo::default() },
+            String::new(),
+        )
+        .unwrap();
+        let tokens = issue_tokens(&request_id, AuthInfo::default()).unwrap();
+        assert!(capabilities_from_token(tokens.access_token.clone(), None).is_ok());
+
+        apps_map::revoke_app(&request_id).unwrap();
+        assert!(capabilities_from_token(tokens.access_token, None).is_err());
+        assert!(refresh_capability_token(tokens.refresh_token).is_err());
+    }
+
+    #[test]
+    fn legacy_tokens_are_valid_while_their_app_is() {
+        setup_apps();
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let auth = AuthInfo {
+            app_name: "legacy-app".to_string(),
+            capabilities: Some(vec![AGENT_READ_CAPABILITY.clone()]),
+            ..Default::default()
+        };
+        let (legacy_token, _) =
+            token::generate_jwt(auth.app_name.clone(), None, 60, auth.clone()).unwrap();
+        apps_map::insert_app(
+            request_id.clone(),
+            AuthInfoExtended { request_id: request_id.clone(), auth },
+            legacy_token.clone(),
+        )
+        .unwrap();
+
+        let capabilities = capabilities_from_token(legacy_token.clone(), None).unwrap();
+        assert_eq!(capabilities.len(), 1);
+        assert_eq!(capabilities[0].can, AGENT_READ_CAPABILITY.can);
+
+        apps_map::revoke_app(&request_id).unwrap();
+        assert!(capabilities_from_token(legacy_token, None).is_err());
+    }
+
     #[test]
     fn gen_random_digits_returns_a_6_digit_string() {
         let rand = gen_random_digits();
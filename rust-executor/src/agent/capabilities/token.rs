@@ -3,11 +3,19 @@ use crate::wallet::Wallet;
 use deno_core::{anyhow::anyhow, error::AnyError};
 use jsonwebtoken::{encode, Algorithm, DecodingKey, EncodingKey, Header};
 
+/// Audience of tokens that grant capabilities on requests
+pub const ACCESS_TOKEN_AUDIENCE: &str = "ad4m:access";
+/// Audience of tokens that can only be exchanged for new tokens through agentRefreshJwt
+pub const REFRESH_TOKEN_AUDIENCE: &str = "ad4m:refresh";
+
+/// Signs a new token and returns it together with its claims.
+/// `subject` is the request id of the app the token gets issued to.
 pub fn generate_jwt(
     audience: String,
+    subject: Option<String>,
     expiration_time: u64,
     capabilities: AuthInfo,
-) -> Result<String, AnyError> {
+) -> Result<(String, Claims), AnyError> {
     // Get the private key
     let wallet = Wallet::instance();
     let wallet_lock = wallet.lock().expect("wallet lock");
@@ -22,7 +30,13 @@ pub fn generate_jwt(
         .get_did_document(&name)
         .ok_or(anyhow!("main did not found. call createMainKey() first"))?;
 
-    let payload = Claims::new(did_document.id, audience, expiration_time, capabilities);
+    let payload = Claims::new(
+        did_document.id,
+        audience,
+        subject,
+        expiration_time,
+        capabilities,
+    );
 
     let token = encode(
         &Header::default(),
@@ -30,10 +44,11 @@ pub fn generate_jwt(
         &EncodingKey::from_secret(secret_key.as_slice()),
     )?;
 
-    Ok(token)
+    Ok((token, payload))
 }
 
-pub fn decode_jwt(token: String) -> Result<Claims, AnyError> {
+/// Verifies signature, expiry and audience of the given token.
+pub fn decode_jwt(token: String, audience: &str) -> Result<Claims, AnyError> {
     //Get the private key
     let wallet = Wallet::instance();
     let wallet_lock = wallet.lock().expect("wallet lock");
@@ -44,11 +59,93 @@ pub fn decode_jwt(token: String) -> Result<Claims, AnyError> {
         .get_secret_key(&name)
         .ok_or(anyhow!("main key not found. call createMainKey() first"))?;
 
+    let mut validation = jsonwebtoken::Validation::new(Algorithm::HS256);
+    // Tokens are only ever checked by the executor that issued them, so there is no clock skew to allow for
+    validation.leeway = 0;
+    validation.set_audience(&[audience]);
+    validation.set_required_spec_claims(&["exp", "aud"]);
+
+    let result = jsonwebtoken::decode::<Claims>(
+        &token,
+        &DecodingKey::from_secret(secret_key.as_slice()),
+        &validation,
+    )?;
+
+    Ok(result.claims)
+}
+
+/// Verifies signature and expiry of a token issued before tokens had a fixed audience.
+/// Those carry the app name as audience, so it can't be checked here.
+pub fn decode_legacy_jwt(token: String) -> Result<Claims, AnyError> {
+    let wallet = Wallet::instance();
+    let wallet_lock = wallet.lock().expect("wallet lock");
+    let wallet_ref = wallet_lock.as_ref().expect("wallet instance");
+    let name = "main".to_string();
+
+    let secret_key = wallet_ref
+        .get_secret_key(&name)
+        .ok_or(anyhow!("main key not found. call createMainKey() first"))?;
+
+    let mut validation = jsonwebtoken::Validation::new(Algorithm::HS256);
+    validation.leeway = 0;
+    validation.validate_aud = false;
+    validation.set_required_spec_claims(&["exp"]);
+
     let result = jsonwebtoken::decode::<Claims>(
         &token,
         &DecodingKey::from_secret(secret_key.as_slice()),
-        &jsonwebtoken::Validation::new(Algorithm::HS256),
+        &validation,
     )?;
 
     Ok(result.claims)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::setup_wallet;
+
+    #[test]
+    fn decode_jwt_checks_audience() {
+        setup_wallet();
+        let (token, claims) = generate_jwt(
+            ACCESS_TOKEN_AUDIENCE.to_string(),
+            Some("request-id".to_string()),
+            60,
+            AuthInfo::default(),
+        )
+        .unwrap();
+
+        let decoded = decode_jwt(token.clone(), ACCESS_TOKEN_AUDIENCE).unwrap();
+        assert_eq!(decoded.nonce(), claims.nonce());
+        assert_eq!(decoded.subject(), Some(&"request-id".to_string()));
+        assert!(decode_jwt(token, REFRESH_TOKEN_AUDIENCE).is_err());
+    }
+
+    #[test]
+    fn decode_jwt_rejects_expired_tokens() {
+        setup_wallet();
+        let (token, _) = generate_jwt(
+            ACCESS_TOKEN_AUDIENCE.to_string(),
+            None,
+            0,
+            AuthInfo::default(),
+        )
+        .unwrap();
+
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        assert!(decode_jwt(token, ACCESS_TOKEN_AUDIENCE).is_err());
+    }
+
+    #[test]
+    fn decode_legacy_jwt_ignores_the_audience() {
+        setup_wallet();
+        let (token, _) =
+            generate_jwt("legacy-app".to_string(), None, 60, AuthInfo::default()).unwrap();
+
+        assert!(decode_jwt(token.clone(), ACCESS_TOKEN_AUDIENCE).is_err());
+        let claims = decode_legacy_jwt(token).unwrap();
+        assert_eq!(claims.audience(), "legacy-app");
+        assert_eq!(claims.subject(), None);
+    }
+}
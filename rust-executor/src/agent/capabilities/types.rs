@@ -67,10 +67,13 @@ impl From<crate::graphql::graphql_types::ResourceInput> for Resource {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     iss: String,
     aud: String,
+    /// Request id of the app this token was issued to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
     exp: u64,
     iat: u64,
     nonce: String,
@@ -81,6 +84,7 @@ impl Claims {
     pub fn new(
         issuer: String,
         audience: String,
+        subject: Option<String>,
         expiration_time: u64,
         capabilities: AuthInfo,
     ) -> Self {
@@ -95,10 +99,31 @@ impl Claims {
         Claims {
             iss: issuer,
             aud: audience,
+            sub: subject,
             exp: unix_timestamp + expiration_time,
             iat: unix_timestamp,
             nonce,
             capabilities,
         }
     }
+
+    pub fn audience(&self) -> &str {
+        &self.aud
+    }
+
+    pub fn subject(&self) -> Option<&String> {
+        self.sub.as_ref()
+    }
+
+    pub fn nonce(&self) -> &String {
+        &self.nonce
+    }
+
+    pub fn issued_at(&self) -> u64 {
+        self.iat
+    }
+
+    pub fn expires_at(&self) -> u64 {
+        self.exp
+    }
 }
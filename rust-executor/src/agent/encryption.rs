@@ -0,0 +1,128 @@
+use std::convert::TryInto;
+use base64::Engine;
+use crypto_box::aead::Aead;
+use crypto_box::{Nonce, PublicKey, SalsaBox, SecretKey};
+use deno_core::anyhow::anyhow;
+use deno_core::error::AnyError;
+use did_key::{Ed25519KeyPair, Generate, KeyMaterial, PatchedKeyPair};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::wallet::Wallet;
+
+/// Data encrypted to the X25519 key derived from an agent's did:key.
+///
+/// Every message uses a fresh ephemeral key pair, so only the recipient
+/// can decrypt it and senders don't need to know their own keys.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedData {
+    /// Hex encoded X25519 public key of the ephemeral sender key
+    pub ephemeral_public_key: String,
+    /// Hex encoded 24 byte nonce
+    pub nonce: String,
+    /// Base64 encoded ciphertext
+    pub ciphertext: String,
+}
+
+fn key_from_bytes(bytes: &[u8]) -> Result<[u8; 32], AnyError> {
+    bytes
+        .try_into()
+        .map_err(|_| anyhow!("Expected a 32 byte key, got {} bytes", bytes.len()))
+}
+
+fn x25519_public_key_for_did(did: &str) -> Result<PublicKey, AnyError> {
+    let key_pair = PatchedKeyPair::try_from(did)
+        .map_err(|e| anyhow!("Failed to parse DID as key method: {} ({:?})", did, e))?;
+    let ed25519 = Ed25519KeyPair::from_public_key(&key_pair.public_key_bytes());
+    Ok(PublicKey::from(key_from_bytes(&ed25519.get_x25519().public_key_bytes())?))
+}
+
+fn x25519_secret_key(ed25519_secret: &[u8]) -> Result<SecretKey, AnyError> {
+    let ed25519 = Ed25519KeyPair::from_secret_key(ed25519_secret);
+    Ok(SecretKey::from(key_from_bytes(&ed25519.get_x25519().private_key_bytes())?))
+}
+
+pub fn encrypt_for_did(did: &str, message: &str) -> Result<EncryptedData, AnyError> {
+    let recipient_key = x25519_public_key_for_did(did)?;
+
+    let mut ephemeral_secret = [0u8; 32];
+    let mut nonce = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut ephemeral_secret);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ephemeral_secret = SecretKey::from(ephemeral_secret);
+
+    let ciphertext = SalsaBox::new(&recipient_key, &ephemeral_secret)
+        .encrypt(&Nonce::from(nonce), message.as_bytes())
+        .map_err(|e| anyhow!("Failed to encrypt message for {}: {}", did, e))?;
+
+    Ok(EncryptedData {
+        ephemeral_public_key: hex::encode(ephemeral_secret.public_key().as_bytes()),
+        nonce: hex::encode(nonce),
+        ciphertext: base64::engine::general_purpose::STANDARD.encode(ciphertext),
+    })
+}
+
+fn decrypt_with_key(ed25519_secret: &[u8], encrypted: &EncryptedData) -> Result<String, AnyError> {
+    let ephemeral_key = PublicKey::from(key_from_bytes(&hex::decode(&encrypted.ephemeral_public_key)?)?);
+    let nonce: [u8; 24] = hex::decode(&encrypted.nonce)?
+        .as_slice()
+        .try_into()
+        .map_err(|_| anyhow!("Expected a 24 byte nonce"))?;
+    let ciphertext = base64::engine::general_purpose::STANDARD.decode(&encrypted.ciphertext)?;
+
+    let plaintext = SalsaBox::new(&ephemeral_key, &x25519_secret_key(ed25519_secret)?)
+        .decrypt(&Nonce::from(nonce), ciphertext.as_slice())
+        .map_err(|_| anyhow!("Failed to decrypt message: not encrypted for this agent or tampered with"))?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+/// Decrypts data that was encrypted to the DID of this agent's main key.
+pub fn decrypt(encrypted: &EncryptedData) -> Result<String, AnyError> {
+    let secret = {
+        let wallet_instance = Wallet::instance();
+        let wallet = wallet_instance.lock().expect("wallet lock");
+        let wallet_ref = wallet.as_ref().expect("wallet instance");
+        wallet_ref
+            .get_secret_key(&"main".to_string())
+            .ok_or(anyhow!("main key not found. call createMainKey() first"))?
+    };
+    decrypt_with_key(&secret, encrypted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::did;
+    use crate::test_utils::setup_wallet;
+    use did_key::DIDCore;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        setup_wallet();
+        let message = r#"{"links":[]}"#;
+        let encrypted = encrypt_for_did(&did(), message).expect("Failed to encrypt");
+
+        assert!(!encrypted.ciphertext.contains("links"));
+        assert_eq!(decrypt(&encrypted).expect("Failed to decrypt"), message);
+
+        // Fresh ephemeral key and nonce for every message
+        assert_ne!(encrypt_for_did(&did(), message).unwrap(), encrypted);
+    }
+
+    #[test]
+    fn test_only_recipient_can_decrypt() {
+        setup_wallet();
+        let other = did_key::generate::<Ed25519KeyPair>(None);
+        let other_did = other.get_did_document(did_key::Config::default()).id;
+        let encrypted = encrypt_for_did(&other_did, "secret").expect("Failed to encrypt");
+
+        assert!(decrypt(&encrypted).is_err());
+        assert_eq!(decrypt_with_key(&other.private_key_bytes(), &encrypted).unwrap(), "secret");
+
+        let mut tampered = encrypted.clone();
+        tampered.ciphertext = base64::engine::general_purpose::STANDARD.encode(b"not the ciphertext at all");
+        assert!(decrypt_with_key(&other.private_key_bytes(), &tampered).is_err());
+    }
+}
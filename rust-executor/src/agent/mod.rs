@@ -7,6 +7,7 @@ use crate::types::{Expression, ExpressionProof};
 use crate::wallet::Wallet;
 
 pub mod capabilities;
+pub mod encryption;
 pub mod signatures;
 
 pub fn did_document() -> did_key::Document {
@@ -1,3 +1,5 @@
+use crate::expression_cache::{DEFAULT_MAX_SIZE, DEFAULT_TTL_SECONDS};
+use crate::prolog_service::limits::{DEFAULT_INFERENCE_LIMIT, DEFAULT_QUERY_TIMEOUT_SECONDS};
 use crate::utils;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -23,6 +25,10 @@ pub struct Ad4mConfig {
     pub connect_holochain: Option<bool>,
     pub admin_credential: Option<String>,
     pub auto_permit_cap_requests: Option<bool>,
+    pub expression_cache_ttl_seconds: Option<u64>,
+    pub expression_cache_max_size: Option<u64>,
+    pub prolog_query_timeout_seconds: Option<u64>,
+    pub prolog_inference_limit: Option<u64>,
 }
 
 impl Ad4mConfig {
@@ -72,6 +78,18 @@ impl Ad4mConfig {
         if self.hc_use_proxy.is_none() {
             self.hc_use_proxy = Some(true)
         }
+        if self.expression_cache_ttl_seconds.is_none() {
+            self.expression_cache_ttl_seconds = Some(DEFAULT_TTL_SECONDS);
+        }
+        if self.expression_cache_max_size.is_none() {
+            self.expression_cache_max_size = Some(DEFAULT_MAX_SIZE);
+        }
+        if self.prolog_query_timeout_seconds.is_none() {
+            self.prolog_query_timeout_seconds = Some(DEFAULT_QUERY_TIMEOUT_SECONDS);
+        }
+        if self.prolog_inference_limit.is_none() {
+            self.prolog_inference_limit = Some(DEFAULT_INFERENCE_LIMIT);
+        }
     }
 
     pub fn get_json(&self) -> String {
@@ -98,6 +116,10 @@ impl Default for Ad4mConfig {
             connect_holochain: None,
             admin_credential: None,
             auto_permit_cap_requests: None,
+            expression_cache_ttl_seconds: None,
+            expression_cache_max_size: None,
+            prolog_query_timeout_seconds: None,
+            prolog_inference_limit: None,
         };
         config.prepare();
         config
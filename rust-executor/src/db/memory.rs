@@ -0,0 +1,905 @@
+use super::{expression_language, searchable_text, Ad4mDbResult, Ad4mStorage};
+use crate::graphql::graphql_types::{
+    DecoratedPerspectiveDiff, LinkQuery, LinkStatus, PerspectiveHandle,
+};
+use crate::types::{
+    CachedExpression, DiffOrigin, Expression, LinkCursor, LinkExpression, PerspectiveDiff,
+    PerspectiveHistoryEntry,
+};
+use deno_core::anyhow::anyhow;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+/// Ad4mStorage that keeps everything in memory, meant for tests.
+///
+/// Behaves like the SQLite storage, except that the full-text search
+/// is a plain case-insensitive word match.
+#[derive(Default)]
+pub struct MemoryStorage {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    perspectives: Vec<PerspectiveHandle>,
+    links: HashMap<String, Vec<StoredLink>>,
+    next_link_id: i64,
+    pending_diffs: HashMap<String, Vec<PerspectiveDiff>>,
+    history: Vec<StoredHistoryEntry>,
+    next_history_id: i64,
+    history_truncated_until: HashMap<String, chrono::DateTime<chrono::Utc>>,
+    expressions: HashMap<String, StoredExpression>,
+    next_access: u64,
+}
+
+struct StoredLink {
+    /// Like the SQLite row id, only used to order links with the same timestamp.
+    id: i64,
+    link: LinkExpression,
+    status: LinkStatus,
+}
+
+struct StoredExpression {
+    entry: CachedExpression,
+    /// Increases with every access, so eviction order doesn't depend on clock resolution.
+    last_access: u64,
+}
+
+struct StoredHistoryEntry {
+    perspective: String,
+    entry: PerspectiveHistoryEntry,
+    discarded: bool,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<MemoryState> {
+        self.state
+            .lock()
+            .expect("Couldn't get lock on MemoryStorage")
+    }
+
+    fn add_links<'a>(
+        &self,
+        perspective_uuid: &str,
+        links: impl Iterator<Item = &'a LinkExpression>,
+        status: &LinkStatus,
+    ) {
+        let mut state = self.state();
+        let mut next_id = state.next_link_id;
+        let stored = state.links.entry(perspective_uuid.to_string()).or_default();
+        for link in links {
+            next_id += 1;
+            let (link, status) = with_status(link, status);
+            stored.push(StoredLink {
+                id: next_id,
+                link,
+                status,
+            });
+        }
+        state.next_link_id = next_id;
+    }
+
+    fn stored_links(&self, perspective_uuid: &str) -> Vec<(i64, LinkExpression, LinkStatus)> {
+        self.state()
+            .links
+            .get(perspective_uuid)
+            .map(|links| {
+                links
+                    .iter()
+                    .map(|l| (l.id, l.link.clone(), l.status.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Same identity as the SQLite storage uses: a missing predicate equals an empty one.
+fn same_link(a: &LinkExpression, b: &LinkExpression) -> bool {
+    a.author == b.author && a.timestamp == b.timestamp && a.data.normalize() == b.data.normalize()
+}
+
+fn with_status(link: &LinkExpression, status: &LinkStatus) -> (LinkExpression, LinkStatus) {
+    let mut link = link.clone();
+    link.data = link.data.normalize();
+    link.status = Some(status.clone());
+    (link, status.clone())
+}
+
+fn timestamp(link: &LinkExpression) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(&link.timestamp)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&chrono::Utc))
+}
+
+/// Applies a LinkQuery to links with their ids, the way the SQLite storage does in SQL:
+/// exact matches on source, predicate and target, an inclusive date range, order by timestamp
+/// and id (descending if from_date is later than until_date), the `after` cursor and the limit.
+pub(crate) fn apply_link_query(
+    links: Vec<(i64, LinkExpression, LinkStatus)>,
+    query: &LinkQuery,
+) -> Ad4mDbResult<Vec<(LinkCursor, LinkExpression, LinkStatus)>> {
+    let from_date: Option<chrono::DateTime<chrono::Utc>> =
+        query.from_date.clone().map(|d| d.into());
+    let until_date: Option<chrono::DateTime<chrono::Utc>> =
+        query.until_date.clone().map(|d| d.into());
+
+    let (from_date, until_date, descending) = match (from_date, until_date) {
+        (Some(from), Some(until)) if from > until => (Some(until), Some(from), true),
+        (from, until) => (from, until, false),
+    };
+
+    let after = query.after.as_deref().map(LinkCursor::decode).transpose()?;
+    let after = after.map(|cursor| {
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&cursor.timestamp)
+            .ok()
+            .map(|timestamp| timestamp.with_timezone(&chrono::Utc));
+        (timestamp, cursor.id)
+    });
+
+    let mut links: Vec<(i64, LinkExpression, LinkStatus)> = links
+        .into_iter()
+        .filter(|(id, link, _)| {
+            query
+                .source
+                .as_ref()
+                .map_or(true, |source| &link.data.source == source)
+                && query.predicate.as_ref().map_or(true, |predicate| {
+                    link.data.predicate.as_deref().unwrap_or("") == predicate
+                })
+                && query
+                    .target
+                    .as_ref()
+                    .map_or(true, |target| &link.data.target == target)
+                && from_date.map_or(true, |from| timestamp(link).map_or(false, |t| t >= from))
+                && until_date.map_or(true, |until| timestamp(link).map_or(false, |t| t <= until))
+                && after.map_or(true, |after| {
+                    let position = (timestamp(link), *id);
+                    if descending {
+                        position < after
+                    } else {
+                        position > after
+                    }
+                })
+        })
+        .collect();
+
+    links.sort_by_key(|(id, link, _)| (timestamp(link), *id));
+    if descending {
+        links.reverse();
+    }
+    if let Some(limit) = query.limit {
+        links.truncate(limit.max(0) as usize);
+    }
+    Ok(links
+        .into_iter()
+        .map(|(id, link, status)| {
+            (
+                LinkCursor {
+                    timestamp: link.timestamp.clone(),
+                    id,
+                },
+                link,
+                status,
+            )
+        })
+        .collect())
+}
+
+impl Ad4mStorage for MemoryStorage {
+    fn add_perspective(&self, perspective: &PerspectiveHandle) -> Ad4mDbResult<()> {
+        let mut state = self.state();
+        if state
+            .perspectives
+            .iter()
+            .any(|p| p.uuid == perspective.uuid)
+        {
+            return Err(anyhow!(
+                "Perspective with uuid {} already exists",
+                perspective.uuid
+            ));
+        }
+        state.perspectives.push(perspective.clone());
+        Ok(())
+    }
+
+    fn get_perspective(&self, uuid: &str) -> Ad4mDbResult<Option<PerspectiveHandle>> {
+        Ok(self
+            .state()
+            .perspectives
+            .iter()
+            .find(|p| p.uuid == uuid)
+            .cloned())
+    }
+
+    fn get_all_perspectives(&self) -> Ad4mDbResult<Vec<PerspectiveHandle>> {
+        Ok(self.state().perspectives.clone())
+    }
+
+    fn update_perspective(&self, perspective: &PerspectiveHandle) -> Ad4mDbResult<()> {
+        if let Some(stored) = self
+            .state()
+            .perspectives
+            .iter_mut()
+            .find(|p| p.uuid == perspective.uuid)
+        {
+            *stored = perspective.clone();
+        }
+        Ok(())
+    }
+
+    fn remove_perspective(&self, uuid: &str) -> Ad4mDbResult<()> {
+        let mut state = self.state();
+        state.perspectives.retain(|p| p.uuid != uuid);
+        state.links.remove(uuid);
+        state.pending_diffs.remove(uuid);
+        state.history.retain(|h| h.perspective != uuid);
+        state.history_truncated_until.remove(uuid);
+        Ok(())
+    }
+
+    fn add_link(
+        &self,
+        perspective_uuid: &str,
+        link: &LinkExpression,
+        status: &LinkStatus,
+    ) -> Ad4mDbResult<()> {
+        self.add_links(perspective_uuid, std::iter::once(link), status);
+        Ok(())
+    }
+
+    fn add_many_links(
+        &self,
+        perspective_uuid: &str,
+        links: Vec<LinkExpression>,
+        status: &LinkStatus,
+    ) -> Ad4mDbResult<()> {
+        self.add_links(perspective_uuid, links.iter(), status);
+        Ok(())
+    }
+
+    fn update_link(
+        &self,
+        perspective_uuid: &str,
+        old_link: &LinkExpression,
+        new_link: &LinkExpression,
+    ) -> Ad4mDbResult<()> {
+        if let Some(links) = self.state().links.get_mut(perspective_uuid) {
+            for stored in links
+                .iter_mut()
+                .filter(|stored| same_link(&stored.link, old_link))
+            {
+                stored.link = with_status(new_link, &stored.status).0;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_link(&self, perspective_uuid: &str, link: &LinkExpression) -> Ad4mDbResult<()> {
+        if let Some(links) = self.state().links.get_mut(perspective_uuid) {
+            links.retain(|stored| !same_link(&stored.link, link));
+        }
+        Ok(())
+    }
+
+    fn get_link(
+        &self,
+        perspective_uuid: &str,
+        link: &LinkExpression,
+    ) -> Ad4mDbResult<Option<(LinkExpression, LinkStatus)>> {
+        Ok(self
+            .get_all_links(perspective_uuid)?
+            .into_iter()
+            .find(|(stored, _)| same_link(stored, link)))
+    }
+
+    fn get_all_links(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        Ok(self
+            .stored_links(perspective_uuid)
+            .into_iter()
+            .map(|(_, link, status)| (link, status))
+            .collect())
+    }
+
+    fn get_links_by_source(
+        &self,
+        perspective_uuid: &str,
+        source: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        let mut links = self.get_all_links(perspective_uuid)?;
+        links.retain(|(link, _)| link.data.source == source);
+        Ok(links)
+    }
+
+    fn get_links_by_target(
+        &self,
+        perspective_uuid: &str,
+        target: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        let mut links = self.get_all_links(perspective_uuid)?;
+        links.retain(|(link, _)| link.data.target == target);
+        Ok(links)
+    }
+
+    fn query_links(
+        &self,
+        perspective_uuid: &str,
+        query: &LinkQuery,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        Ok(self
+            .query_links_page(perspective_uuid, query)?
+            .into_iter()
+            .map(|(_, link, status)| (link, status))
+            .collect())
+    }
+
+    fn query_links_page(
+        &self,
+        perspective_uuid: &str,
+        query: &LinkQuery,
+    ) -> Ad4mDbResult<Vec<(LinkCursor, LinkExpression, LinkStatus)>> {
+        apply_link_query(self.stored_links(perspective_uuid), query)
+    }
+
+    fn search_links(
+        &self,
+        perspective_uuid: &str,
+        text: &str,
+        predicate: Option<&str>,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut matches: Vec<(usize, (LinkExpression, LinkStatus))> = self
+            .get_all_links(perspective_uuid)?
+            .into_iter()
+            .filter(|(link, _)| {
+                predicate.map_or(true, |predicate| {
+                    link.data.predicate.as_deref().unwrap_or("") == predicate
+                })
+            })
+            .filter_map(|(link, status)| {
+                let text = searchable_text(&link.data.target)?.to_lowercase();
+                let tokens: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).collect();
+                let occurrences = words
+                    .iter()
+                    .map(|word| {
+                        tokens
+                            .iter()
+                            .filter(|token| **token == word.as_str())
+                            .count()
+                    })
+                    .collect::<Vec<usize>>();
+                if occurrences.iter().all(|count| *count > 0) {
+                    Some((occurrences.iter().sum(), (link, status)))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        matches.sort_by(|(a, _), (b, _)| b.cmp(a));
+        Ok(matches.into_iter().map(|(_, link)| link).collect())
+    }
+
+    fn add_pending_diff(&self, perspective_uuid: &str, diff: &PerspectiveDiff) -> Ad4mDbResult<()> {
+        self.state()
+            .pending_diffs
+            .entry(perspective_uuid.to_string())
+            .or_default()
+            .push(diff.clone());
+        Ok(())
+    }
+
+    fn get_pending_diffs(&self, perspective_uuid: &str) -> Ad4mDbResult<PerspectiveDiff> {
+        let mut merged = PerspectiveDiff {
+            additions: vec![],
+            removals: vec![],
+        };
+        for diff in self
+            .state()
+            .pending_diffs
+            .get(perspective_uuid)
+            .into_iter()
+            .flatten()
+        {
+            merged.additions.extend(diff.additions.iter().cloned());
+            merged.removals.extend(diff.removals.iter().cloned());
+        }
+        Ok(merged)
+    }
+
+    fn clear_pending_diffs(&self, perspective_uuid: &str) -> Ad4mDbResult<()> {
+        self.state().pending_diffs.remove(perspective_uuid);
+        Ok(())
+    }
+
+    fn add_history_entry(
+        &self,
+        perspective_uuid: &str,
+        diff: &DecoratedPerspectiveDiff,
+        origin: &DiffOrigin,
+        max_entries: u32,
+    ) -> Ad4mDbResult<i64> {
+        let mut state = self.state();
+        state.next_history_id += 1;
+        let id = state.next_history_id;
+        state.history.push(StoredHistoryEntry {
+            perspective: perspective_uuid.to_string(),
+            entry: PerspectiveHistoryEntry {
+                id,
+                diff: diff.clone(),
+                origin: origin.clone(),
+                timestamp: chrono::Utc::now().to_rfc3339(),
+                undone: false,
+            },
+            discarded: false,
+        });
+
+        let count = state
+            .history
+            .iter()
+            .filter(|h| h.perspective == perspective_uuid)
+            .count();
+        let mut to_drop = count.saturating_sub(max_entries as usize);
+        let mut truncated_until = None;
+        state.history.retain(|h| {
+            if to_drop > 0 && h.perspective == perspective_uuid {
+                to_drop -= 1;
+                truncated_until = Some(h.entry.timestamp.clone());
+                false
+            } else {
+                true
+            }
+        });
+        if let Some(timestamp) = truncated_until {
+            let timestamp =
+                chrono::DateTime::parse_from_rfc3339(&timestamp)?.with_timezone(&chrono::Utc);
+            state
+                .history_truncated_until
+                .insert(perspective_uuid.to_string(), timestamp);
+        }
+        Ok(id)
+    }
+
+    fn get_last_undoable_history_entry(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<PerspectiveHistoryEntry>> {
+        Ok(self
+            .state()
+            .history
+            .iter()
+            .rev()
+            .find(|h| {
+                h.perspective == perspective_uuid
+                    && h.entry.origin == DiffOrigin::Local
+                    && !h.entry.undone
+            })
+            .map(|h| h.entry.clone()))
+    }
+
+    fn get_next_redoable_history_entry(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<PerspectiveHistoryEntry>> {
+        Ok(self
+            .state()
+            .history
+            .iter()
+            .find(|h| h.perspective == perspective_uuid && h.entry.undone && !h.discarded)
+            .map(|h| h.entry.clone()))
+    }
+
+    fn set_history_entry_undone(&self, id: i64, undone: bool) -> Ad4mDbResult<()> {
+        if let Some(h) = self.state().history.iter_mut().find(|h| h.entry.id == id) {
+            h.entry.undone = undone;
+        }
+        Ok(())
+    }
+
+    fn clear_redo_history(&self, perspective_uuid: &str) -> Ad4mDbResult<()> {
+        for h in self
+            .state()
+            .history
+            .iter_mut()
+            .filter(|h| h.perspective == perspective_uuid && h.entry.undone)
+        {
+            h.discarded = true;
+        }
+        Ok(())
+    }
+
+    fn history_truncated_until(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<chrono::DateTime<chrono::Utc>>> {
+        Ok(self
+            .state()
+            .history_truncated_until
+            .get(perspective_uuid)
+            .cloned())
+    }
+
+    fn get_history_entries_since(
+        &self,
+        perspective_uuid: &str,
+        since: &chrono::DateTime<chrono::Utc>,
+    ) -> Ad4mDbResult<Vec<PerspectiveHistoryEntry>> {
+        Ok(self
+            .state()
+            .history
+            .iter()
+            .rev()
+            .filter(|h| h.perspective == perspective_uuid)
+            .filter(|h| {
+                chrono::DateTime::parse_from_rfc3339(&h.entry.timestamp)
+                    .map_or(false, |t| t > *since)
+            })
+            .map(|h| h.entry.clone())
+            .collect())
+    }
+
+    fn add_expression_fetched_at(
+        &self,
+        url: &str,
+        expression: &Expression<JsonValue>,
+        fetched_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Ad4mDbResult<()> {
+        let size = serde_json::to_string(expression)?.len() as u64;
+        let now = chrono::Utc::now();
+        let mut state = self.state();
+        state.next_access += 1;
+        let last_access = state.next_access;
+        state.expressions.insert(
+            url.to_string(),
+            StoredExpression {
+                entry: CachedExpression {
+                    url: url.to_string(),
+                    expression: expression.clone(),
+                    language: expression_language(url),
+                    size,
+                    fetched_at: *fetched_at,
+                    last_accessed: now,
+                },
+                last_access,
+            },
+        );
+        Ok(())
+    }
+
+    fn get_expression(&self, url: &str) -> Ad4mDbResult<Option<Expression<JsonValue>>> {
+        Ok(self
+            .state()
+            .expressions
+            .get(url)
+            .map(|stored| stored.entry.expression.clone()))
+    }
+
+    fn get_cached_expression(&self, url: &str) -> Ad4mDbResult<Option<CachedExpression>> {
+        let mut state = self.state();
+        state.next_access += 1;
+        let last_access = state.next_access;
+        Ok(state.expressions.get_mut(url).map(|stored| {
+            stored.last_access = last_access;
+            stored.entry.last_accessed = chrono::Utc::now();
+            stored.entry.clone()
+        }))
+    }
+
+    fn remove_expression(&self, url: &str) -> Ad4mDbResult<()> {
+        self.state().expressions.remove(url);
+        Ok(())
+    }
+
+    fn expression_cache_usage(&self) -> Ad4mDbResult<(u64, u64)> {
+        let state = self.state();
+        let size = state
+            .expressions
+            .values()
+            .map(|stored| stored.entry.size)
+            .sum();
+        Ok((state.expressions.len() as u64, size))
+    }
+
+    fn evict_expressions(&self, max_size: u64) -> Ad4mDbResult<u64> {
+        let mut state = self.state();
+        let mut total_size: u64 = state
+            .expressions
+            .values()
+            .map(|stored| stored.entry.size)
+            .sum();
+        let mut by_access: Vec<(u64, String, u64)> = state
+            .expressions
+            .values()
+            .map(|stored| {
+                (
+                    stored.last_access,
+                    stored.entry.url.clone(),
+                    stored.entry.size,
+                )
+            })
+            .collect();
+        by_access.sort();
+
+        let mut evicted = 0;
+        for (_, url, size) in by_access {
+            if total_size <= max_size {
+                break;
+            }
+            state.expressions.remove(&url);
+            total_size = total_size.saturating_sub(size);
+            evicted += 1;
+        }
+        Ok(evicted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DecoratedLinkExpression, ExpressionProof, Link};
+    use ad4m_client::literal::Literal;
+    use chrono::Utc;
+
+    fn link(
+        source: &str,
+        predicate: Option<&str>,
+        target: &str,
+        timestamp: &str,
+    ) -> LinkExpression {
+        LinkExpression {
+            data: Link {
+                source: source.to_string(),
+                predicate: predicate.map(str::to_string),
+                target: target.to_string(),
+            },
+            proof: ExpressionProof {
+                signature: "signature".to_string(),
+                key: "key".to_string(),
+            },
+            author: "did:test:key".to_string(),
+            timestamp: timestamp.to_string(),
+            status: None,
+        }
+    }
+
+    #[test]
+    fn stores_links_per_perspective() {
+        let storage = MemoryStorage::new();
+        let first = link("ad4m://self", None, "ad4m://a", "2023-01-01T00:00:00Z");
+        let second = link(
+            "ad4m://self",
+            Some("p://p"),
+            "ad4m://b",
+            "2023-01-02T00:00:00Z",
+        );
+        storage.add_link("p1", &first, &LinkStatus::Shared).unwrap();
+        storage.add_link("p1", &second, &LinkStatus::Local).unwrap();
+        storage.add_link("p2", &first, &LinkStatus::Shared).unwrap();
+
+        assert_eq!(storage.get_all_links("p1").unwrap().len(), 2);
+        assert_eq!(
+            storage.get_link("p1", &second).unwrap().unwrap().1,
+            LinkStatus::Local
+        );
+
+        let mut renamed = second.clone();
+        renamed.data.target = "ad4m://c".to_string();
+        storage.update_link("p1", &second, &renamed).unwrap();
+        assert!(storage.get_link("p1", &second).unwrap().is_none());
+        assert_eq!(
+            storage.get_links_by_target("p1", "ad4m://c").unwrap().len(),
+            1
+        );
+
+        storage.remove_link("p1", &first).unwrap();
+        assert_eq!(storage.get_all_links("p1").unwrap().len(), 1);
+        assert_eq!(storage.get_all_links("p2").unwrap().len(), 1);
+
+        storage.remove_perspective("p1").unwrap();
+        assert!(storage.get_all_links("p1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn queries_links_like_sqlite() {
+        let storage = MemoryStorage::new();
+        let now = Utc::now();
+        let mut links = Vec::new();
+        for i in 0..5 {
+            let predicate = if i % 2 == 0 { Some("p://even") } else { None };
+            let l = link(
+                "ad4m://self",
+                predicate,
+                &format!("ad4m://{}", i),
+                &(now - chrono::Duration::minutes(5 - i)).to_rfc3339(),
+            );
+            storage.add_link("p", &l, &LinkStatus::Shared).unwrap();
+            links.push(l);
+        }
+
+        let by_predicate = storage
+            .query_links(
+                "p",
+                &LinkQuery {
+                    predicate: Some("p://even".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(by_predicate.len(), 3);
+
+        let descending = storage
+            .query_links(
+                "p",
+                &LinkQuery {
+                    from_date: Some(now.into()),
+                    until_date: Some((now - chrono::Duration::minutes(10)).into()),
+                    limit: Some(2),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            descending
+                .into_iter()
+                .map(|(l, _)| l.data.target)
+                .collect::<Vec<_>>(),
+            vec![links[4].data.target.clone(), links[3].data.target.clone(),]
+        );
+    }
+
+    #[test]
+    fn pages_links_with_cursors() {
+        let storage = MemoryStorage::new();
+        let links: Vec<LinkExpression> = (0..3)
+            .map(|i| {
+                link(
+                    "ad4m://self",
+                    None,
+                    &format!("ad4m://{}", i),
+                    "2023-01-01T00:00:00Z",
+                )
+            })
+            .collect();
+        storage
+            .add_many_links("p", links.clone(), &LinkStatus::Shared)
+            .unwrap();
+
+        let first = storage
+            .query_links_page(
+                "p",
+                &LinkQuery {
+                    limit: Some(2),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(first.len(), 2);
+
+        let rest = storage
+            .query_links(
+                "p",
+                &LinkQuery {
+                    after: Some(first[1].0.encode()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            rest.into_iter()
+                .map(|(l, _)| l.data.target)
+                .collect::<Vec<_>>(),
+            vec![links[2].data.target.clone()]
+        );
+    }
+
+    #[test]
+    fn searches_literal_targets() {
+        let storage = MemoryStorage::new();
+        let title = link(
+            "ad4m://self",
+            Some("flux://title"),
+            &Literal::from_string("Meeting notes".to_string())
+                .to_url()
+                .unwrap(),
+            "2023-01-01T00:00:00Z",
+        );
+        let body = link(
+            "ad4m://self",
+            Some("flux://body"),
+            &Literal::from_string("notes, notes and the meeting".to_string())
+                .to_url()
+                .unwrap(),
+            "2023-01-01T00:00:00Z",
+        );
+        storage.add_link("p", &title, &LinkStatus::Shared).unwrap();
+        storage.add_link("p", &body, &LinkStatus::Shared).unwrap();
+
+        let found = storage.search_links("p", "notes", None).unwrap();
+        assert_eq!(
+            found
+                .into_iter()
+                .map(|(l, _)| l.data.target)
+                .collect::<Vec<_>>(),
+            vec![body.data.target.clone(), title.data.target.clone(),]
+        );
+        assert_eq!(
+            storage
+                .search_links("p", "MEETING", Some("flux://title"))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(storage
+            .search_links("p", "meeting agenda", None)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn keeps_bounded_history_with_undo_and_redo() {
+        let storage = MemoryStorage::new();
+        let diff = DecoratedPerspectiveDiff {
+            additions: vec![DecoratedLinkExpression::from((
+                link("a", None, "b", "2023-01-01T00:00:00Z"),
+                LinkStatus::Shared,
+            ))],
+            removals: vec![],
+        };
+
+        let mut ids = Vec::new();
+        for _ in 0..4 {
+            ids.push(
+                storage
+                    .add_history_entry("p", &diff, &DiffOrigin::Local, 3)
+                    .unwrap(),
+            );
+        }
+        let since = Utc::now() - chrono::Duration::minutes(1);
+        assert_eq!(
+            storage
+                .get_history_entries_since("p", &since)
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(storage.history_truncated_until("p").unwrap().unwrap() > since);
+        assert!(storage
+            .query_links_at("p", &LinkQuery::default(), &since)
+            .is_err());
+
+        storage.set_history_entry_undone(ids[3], true).unwrap();
+        assert_eq!(
+            storage
+                .get_last_undoable_history_entry("p")
+                .unwrap()
+                .unwrap()
+                .id,
+            ids[2]
+        );
+        assert_eq!(
+            storage
+                .get_next_redoable_history_entry("p")
+                .unwrap()
+                .unwrap()
+                .id,
+            ids[3]
+        );
+
+        storage.clear_redo_history("p").unwrap();
+        assert!(storage
+            .get_next_redoable_history_entry("p")
+            .unwrap()
+            .is_none());
+    }
+}
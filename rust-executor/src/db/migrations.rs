@@ -0,0 +1,321 @@
+use deno_core::anyhow::anyhow;
+use rusqlite::{params, Connection, OptionalExtension};
+use super::Ad4mDbResult;
+
+/// A single forward step of the Ad4mDb schema.
+/// Migrations are applied in order of their version, each one inside its own transaction.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+/// All known schema migrations, ordered by version.
+/// Never change a migration that has been released, append a new one instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial layout",
+        // Databases created before versioning was introduced already have these tables,
+        // so this step has to stay idempotent.
+        sql: "
+            CREATE TABLE IF NOT EXISTS perspective_handle (
+                uuid TEXT PRIMARY KEY,
+                name TEXT,
+                neighbourhood TEXT,
+                shared_url TEXT,
+                state TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS link (
+                id INTEGER PRIMARY KEY,
+                perspective TEXT NOT NULL,
+                source TEXT NOT NULL,
+                predicate TEXT NOT NULL,
+                target TEXT NOT NULL,
+                author TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                key TEXT NOT NULL,
+                status TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS expression (
+                id INTEGER PRIMARY KEY,
+                url TEXT NOT NULL UNIQUE,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS perspective_diff (
+                id INTEGER PRIMARY KEY,
+                perspective TEXT NOT NULL,
+                additions TEXT NOT NULL,
+                removals TEXT NOT NULL,
+                is_pending BOOLEAN NOT NULL
+            );
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "link query indexes",
+        // Timestamps are stored as RFC3339 strings with varying precision and offsets,
+        // so we index and order by their julian day value instead of the raw text.
+        sql: "
+            CREATE INDEX IF NOT EXISTS link_perspective_source ON link (perspective, source);
+            CREATE INDEX IF NOT EXISTS link_perspective_target ON link (perspective, target);
+            CREATE INDEX IF NOT EXISTS link_perspective_predicate ON link (perspective, predicate);
+            CREATE INDEX IF NOT EXISTS link_perspective_timestamp ON link (perspective, julianday(timestamp));
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "perspective diff history",
+        sql: "
+            CREATE TABLE perspective_history (
+                id INTEGER PRIMARY KEY,
+                perspective TEXT NOT NULL,
+                additions TEXT NOT NULL,
+                removals TEXT NOT NULL,
+                origin TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                undone BOOLEAN NOT NULL DEFAULT 0
+            );
+            CREATE INDEX perspective_history_perspective ON perspective_history (perspective, id);
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "keep discarded redo history for time travel",
+        sql: "
+            ALTER TABLE perspective_history ADD COLUMN discarded BOOLEAN NOT NULL DEFAULT 0;
+            CREATE INDEX perspective_history_timestamp ON perspective_history (perspective, julianday(timestamp));
+        ",
+    },
+    Migration {
+        version: LINK_SEARCH_VERSION,
+        description: "full-text index over literal link targets",
+        // The rowid of each entry is the id of the link it was decoded from.
+        // Decoding literals needs Rust, so existing links get indexed by Ad4mDb after migrating.
+        sql: "
+            CREATE VIRTUAL TABLE link_search USING fts5(text, tokenize = 'unicode61 remove_diacritics 2');
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "expression cache metadata",
+        // Expressions cached before this version were keyed by their bare address
+        // instead of their URL, so they would never be hit again and get dropped.
+        // The remaining ones count as fetched right now, so they get a full TTL
+        // instead of all expiring at once.
+        sql: "
+            DELETE FROM expression WHERE url NOT LIKE '%://%';
+            ALTER TABLE expression ADD COLUMN language TEXT;
+            ALTER TABLE expression ADD COLUMN size INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE expression ADD COLUMN fetched_at TEXT;
+            ALTER TABLE expression ADD COLUMN last_accessed TEXT;
+            UPDATE expression SET
+                size = length(CAST(data AS BLOB)),
+                fetched_at = strftime('%Y-%m-%dT%H:%M:%fZ', 'now'),
+                last_accessed = strftime('%Y-%m-%dT%H:%M:%fZ', 'now');
+            CREATE INDEX expression_last_accessed ON expression (julianday(last_accessed));
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "track how far the bounded perspective history goes back",
+        sql: "
+            CREATE TABLE perspective_history_truncation (
+                perspective TEXT PRIMARY KEY,
+                truncated_until TEXT NOT NULL
+            );
+        ",
+    },
+];
+
+/// Schema version that introduced the `link_search` table.
+pub const LINK_SEARCH_VERSION: u32 = 5;
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn current_version(conn: &Connection) -> Ad4mDbResult<u32> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+         )",
+        [],
+    )?;
+
+    let version: Option<u32> = conn
+        .query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0))
+        .optional()?
+        .flatten();
+
+    Ok(version.unwrap_or(0))
+}
+
+/// Brings the database up to the latest schema version.
+/// Refuses to touch databases that were written by a newer version of the executor.
+pub fn migrate(conn: &mut Connection) -> Ad4mDbResult<()> {
+    migrate_to(conn, MIGRATIONS)
+}
+
+fn migrate_to(conn: &mut Connection, migrations: &[Migration]) -> Ad4mDbResult<()> {
+    let current = current_version(conn)?;
+    let latest = migrations.last().map(|m| m.version).unwrap_or(0);
+
+    if current > latest {
+        return Err(anyhow!(
+            "Ad4mDb schema version {} is newer than the latest version {} known to this executor. Refusing to open database.",
+            current,
+            latest
+        ));
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        log::info!("Migrating Ad4mDb to schema version {} ({})", migration.version, migration.description);
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.sql)
+            .map_err(|e| anyhow!("Ad4mDb migration {} failed: {}", migration.version, e))?;
+        tx.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?1, ?2, ?3)",
+            params![migration.version, migration.description, chrono::Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Layout written by executors before schema versioning existed.
+    const UNVERSIONED_LAYOUT: &str = "
+        CREATE TABLE perspective_handle (
+            uuid TEXT PRIMARY KEY,
+            name TEXT,
+            neighbourhood TEXT,
+            shared_url TEXT,
+            state TEXT NOT NULL
+        );
+        CREATE TABLE link (
+            id INTEGER PRIMARY KEY,
+            perspective TEXT NOT NULL,
+            source TEXT NOT NULL,
+            predicate TEXT NOT NULL,
+            target TEXT NOT NULL,
+            author TEXT NOT NULL,
+            timestamp TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            key TEXT NOT NULL,
+            status TEXT NOT NULL
+        );
+        CREATE TABLE expression (
+            id INTEGER PRIMARY KEY,
+            url TEXT NOT NULL UNIQUE,
+            data TEXT NOT NULL
+        );
+        CREATE TABLE perspective_diff (
+            id INTEGER PRIMARY KEY,
+            perspective TEXT NOT NULL,
+            additions TEXT NOT NULL,
+            removals TEXT NOT NULL,
+            is_pending BOOLEAN NOT NULL
+        );
+        INSERT INTO perspective_handle (uuid, name, state) VALUES ('p1', 'Legacy', '\"Private\"');
+        INSERT INTO link (perspective, source, predicate, target, author, timestamp, signature, key, status)
+            VALUES ('p1', 'ad4m://self', 'p://has', 'literal://string:legacy', 'did:test', '2023-01-01T00:00:00Z', 'sig', 'key', '\"shared\"');
+    ";
+
+    fn index_names(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'link'")
+            .unwrap();
+        stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn migrates_empty_database_to_latest() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        assert!(index_names(&conn).contains(&"link_perspective_timestamp".to_string()));
+    }
+
+    #[test]
+    fn migrates_unversioned_database_and_keeps_data() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(UNVERSIONED_LAYOUT).unwrap();
+
+        migrate(&mut conn).unwrap();
+
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+        let link_count: i64 = conn.query_row("SELECT COUNT(*) FROM link", [], |row| row.get(0)).unwrap();
+        assert_eq!(link_count, 1);
+        let name: String = conn.query_row("SELECT name FROM perspective_handle WHERE uuid = 'p1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(name, "Legacy");
+        assert!(index_names(&conn).contains(&"link_perspective_source".to_string()));
+    }
+
+    #[test]
+    fn drops_expressions_cached_by_bare_address() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(UNVERSIONED_LAYOUT).unwrap();
+        conn.execute_batch(
+            "INSERT INTO expression (url, data) VALUES ('QmLegacy', '{}');
+             INSERT INTO expression (url, data) VALUES ('lang://QmCurrent', '{}');",
+        ).unwrap();
+
+        migrate(&mut conn).unwrap();
+
+        let mut stmt = conn.prepare("SELECT url FROM expression").unwrap();
+        let urls = stmt.query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<Vec<String>, _>>()
+            .unwrap();
+        assert_eq!(urls, vec!["lang://QmCurrent".to_string()]);
+    }
+
+    #[test]
+    fn migrating_twice_is_a_no_op() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        migrate(&mut conn).unwrap();
+        let applied: i64 = conn.query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+    }
+
+    #[test]
+    fn refuses_database_from_newer_version() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, description, applied_at) VALUES (?1, 'future', 'now')",
+            params![latest_version() + 1],
+        ).unwrap();
+
+        assert!(migrate(&mut conn).is_err());
+    }
+
+    #[test]
+    fn failed_migration_is_rolled_back() {
+        const BROKEN: &[Migration] = &[
+            Migration { version: 1, description: "ok", sql: "CREATE TABLE a (id INTEGER);" },
+            Migration { version: 2, description: "broken", sql: "CREATE TABLE b (id INTEGER); NOT VALID SQL;" },
+        ];
+        let mut conn = Connection::open_in_memory().unwrap();
+
+        assert!(migrate_to(&mut conn, BROKEN).is_err());
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        let b_exists: Option<String> = conn
+            .query_row("SELECT name FROM sqlite_master WHERE name = 'b'", [], |row| row.get(0))
+            .optional()
+            .unwrap();
+        assert!(b_exists.is_none());
+    }
+}
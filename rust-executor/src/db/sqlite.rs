@@ -0,0 +1,1765 @@
+use super::{expression_language, migrations, searchable_text, Ad4mDbResult, Ad4mStorage};
+use crate::graphql::graphql_types::{
+    DecoratedPerspectiveDiff, LinkQuery, LinkStatus, PerspectiveHandle,
+};
+use crate::types::{
+    CachedExpression, DecoratedLinkExpression, DiffOrigin, Expression, ExpressionProof, Link,
+    LinkCursor, LinkExpression, PerspectiveDiff, PerspectiveHistoryEntry,
+};
+use deno_core::anyhow::anyhow;
+use r2d2::{Pool, PooledConnection};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value as JsonValue;
+use std::time::Duration;
+
+/// How long a connection waits for another one's write lock before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Opens connections to one SQLite database file for the pool.
+struct SqliteConnectionManager {
+    path: String,
+}
+
+impl r2d2::ManageConnection for SqliteConnectionManager {
+    type Connection = Connection;
+    type Error = rusqlite::Error;
+
+    fn connect(&self) -> Result<Connection, rusqlite::Error> {
+        let conn = Connection::open(&self.path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(conn)
+    }
+
+    fn is_valid(&self, conn: &mut Connection) -> Result<(), rusqlite::Error> {
+        conn.execute_batch("")
+    }
+
+    fn has_broken(&self, _conn: &mut Connection) -> bool {
+        false
+    }
+}
+
+/// Ad4mStorage on top of SQLite.
+///
+/// `open()` uses a single connection, like the executor always did, and also works
+/// with `:memory:` databases. `open_pooled()` switches the database to WAL mode and
+/// hands out connections from a pool, so readers don't wait for writers and perspectives
+/// that sync concurrently don't block each other.
+pub struct SqliteStorage {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl SqliteStorage {
+    pub fn open(db_path: &str) -> Ad4mDbResult<Self> {
+        Self::with_pool_size(db_path, 1, false)
+    }
+
+    pub fn open_pooled(db_path: &str, pool_size: u32) -> Ad4mDbResult<Self> {
+        Self::with_pool_size(db_path, pool_size, true)
+    }
+
+    fn with_pool_size(db_path: &str, pool_size: u32, wal: bool) -> Ad4mDbResult<Self> {
+        let pool = Pool::builder()
+            .max_size(pool_size)
+            // In-memory databases are gone once their connection is closed
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .build(SqliteConnectionManager {
+                path: db_path.to_string(),
+            })?;
+        let storage = Self { pool };
+
+        let version_before = {
+            let mut conn = storage.conn()?;
+            if wal {
+                conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get::<_, String>(0))?;
+            }
+            let version_before = migrations::current_version(&conn)?;
+            migrations::migrate(&mut conn)?;
+            version_before
+        };
+        if version_before < migrations::LINK_SEARCH_VERSION {
+            storage.rebuild_search_index()?;
+        }
+        Ok(storage)
+    }
+
+    fn conn(&self) -> Ad4mDbResult<PooledConnection<SqliteConnectionManager>> {
+        Ok(self.pool.get()?)
+    }
+
+    /// Re-creates the search index from all stored links.
+    fn rebuild_search_index(&self) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM link_search", [])?;
+        let mut stmt =
+            conn.prepare("SELECT id, target FROM link WHERE target LIKE 'literal://%'")?;
+        let links = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for link in links {
+            let (id, target) = link?;
+            if let Some(text) = searchable_text(&target) {
+                conn.execute(
+                    "INSERT INTO link_search (rowid, text) VALUES (?1, ?2)",
+                    params![id, text],
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Ad4mStorage for SqliteStorage {
+    fn add_perspective(&self, perspective: &PerspectiveHandle) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO perspective_handle (name, uuid, neighbourhood, shared_url, state)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                perspective.name,
+                perspective.uuid,
+                perspective
+                    .neighbourhood
+                    .as_ref()
+                    .map(|n| serde_json::to_string(n).ok())
+                    .flatten(),
+                perspective.shared_url,
+                serde_json::to_string(&perspective.state)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get_perspective(&self, uuid: &str) -> Ad4mDbResult<Option<PerspectiveHandle>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT name, uuid, neighbourhood, shared_url, state FROM perspective_handle WHERE uuid = ?1",
+        )?;
+
+        let found_perspective = stmt
+            .query_map([uuid], |row| {
+                Ok(PerspectiveHandle {
+                    name: row.get(0)?,
+                    uuid: row.get(1)?,
+                    neighbourhood: row
+                        .get::<usize, Option<String>>(3)?
+                        .map(|n| serde_json::from_str(&n).ok())
+                        .flatten(),
+                    shared_url: row.get(4)?,
+                    state: serde_json::from_str(row.get::<usize, String>(5)?.as_str())
+                        .expect("Could not deserialize perspective state from DB"),
+                })
+            })?
+            .map(|p| p.ok())
+            .next()
+            .ok_or(anyhow!("No perspective found with given uuid"))?
+            .clone();
+
+        Ok(found_perspective)
+    }
+
+    fn get_all_perspectives(&self) -> Ad4mDbResult<Vec<PerspectiveHandle>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT name, uuid, neighbourhood, shared_url, state FROM perspective_handle",
+        )?;
+        let perspective_iter = stmt.query_map([], |row| {
+            Ok(PerspectiveHandle {
+                name: row.get(0)?,
+                uuid: row.get(1)?,
+                neighbourhood: row
+                    .get::<usize, Option<String>>(2)?
+                    .map(|n| serde_json::from_str(&n).ok())
+                    .flatten(),
+                shared_url: row.get(3)?,
+                state: serde_json::from_str(row.get::<usize, String>(4)?.as_str())
+                    .expect("Could not deserialize perspective state from DB"),
+            })
+        })?;
+
+        let mut perspectives = Vec::new();
+        for perspective in perspective_iter {
+            perspectives.push(perspective?);
+        }
+
+        Ok(perspectives)
+    }
+
+    fn update_perspective(&self, perspective: &PerspectiveHandle) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE perspective_handle SET name = ?1, neighbourhood = ?2, shared_url = ?3, state = ?4 WHERE uuid = ?5",
+            params![
+                perspective.name,
+                perspective.neighbourhood.as_ref().map(|n| serde_json::to_string(n).ok()).flatten(),
+                perspective.shared_url,
+                serde_json::to_string(&perspective.state)?,
+                perspective.uuid,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove_perspective(&self, uuid: &str) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM perspective_handle WHERE uuid = ?1", [uuid])?;
+        // Leftovers would resurface if a perspective with the same uuid gets imported again
+        conn.execute(
+            "DELETE FROM link_search WHERE rowid IN (SELECT id FROM link WHERE perspective = ?1)",
+            [uuid],
+        )?;
+        conn.execute("DELETE FROM link WHERE perspective = ?1", [uuid])?;
+        conn.execute(
+            "DELETE FROM perspective_diff WHERE perspective = ?1",
+            [uuid],
+        )?;
+        conn.execute(
+            "DELETE FROM perspective_history WHERE perspective = ?1",
+            [uuid],
+        )?;
+        conn.execute(
+            "DELETE FROM perspective_history_truncation WHERE perspective = ?1",
+            [uuid],
+        )?;
+        Ok(())
+    }
+
+    fn add_link(
+        &self,
+        perspective_uuid: &str,
+        link: &LinkExpression,
+        status: &LinkStatus,
+    ) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO link (perspective, source, predicate, target, author, timestamp, signature, key, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                perspective_uuid,
+                link.data.source,
+                link.data.predicate.as_ref().unwrap_or(&"".to_string()),
+                link.data.target,
+                link.author,
+                link.timestamp,
+                link.proof.signature,
+                link.proof.key,
+                serde_json::to_string(status)?,
+            ],
+        )?;
+        index_link(&conn, conn.last_insert_rowid(), link)?;
+        Ok(())
+    }
+
+    fn add_many_links(
+        &self,
+        perspective_uuid: &str,
+        links: Vec<LinkExpression>,
+        status: &LinkStatus,
+    ) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        for link in links.iter() {
+            conn.execute(
+                "INSERT INTO link (perspective, source, predicate, target, author, timestamp, signature, key, status)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    perspective_uuid,
+                    link.data.source,
+                    link.data.predicate.as_ref().unwrap_or(&"".to_string()),
+                    link.data.target,
+                    link.author,
+                    link.timestamp,
+                    link.proof.signature,
+                    link.proof.key,
+                    serde_json::to_string(&status)?,
+                ],
+            )?;
+            index_link(&conn, conn.last_insert_rowid(), link)?;
+        }
+        Ok(())
+    }
+
+    fn update_link(
+        &self,
+        perspective_uuid: &str,
+        old_link: &LinkExpression,
+        new_link: &LinkExpression,
+    ) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        let ids = link_ids(&conn, perspective_uuid, old_link)?;
+        conn.execute(
+            "UPDATE link SET source = ?1, predicate = ?2, target = ?3, author = ?4, timestamp = ?5, signature = ?6, key = ?7
+             WHERE perspective = ?8 AND source = ?9 AND predicate = ?10 AND target = ?11 AND author = ?12 AND timestamp = ?13",
+            params![
+                new_link.data.source,
+                new_link.data.predicate.as_ref().unwrap_or(&"".to_string()),
+                new_link.data.target,
+                new_link.author,
+                new_link.timestamp,
+                new_link.proof.signature,
+                new_link.proof.key,
+                perspective_uuid,
+                old_link.data.source,
+                old_link.data.predicate.as_ref().unwrap_or(&"".to_string()),
+                old_link.data.target,
+                old_link.author,
+                old_link.timestamp,
+            ],
+        )?;
+        for id in ids {
+            unindex_link(&conn, id)?;
+            index_link(&conn, id, new_link)?;
+        }
+        Ok(())
+    }
+
+    fn remove_link(&self, perspective_uuid: &str, link: &LinkExpression) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        for id in link_ids(&conn, perspective_uuid, link)? {
+            unindex_link(&conn, id)?;
+        }
+        conn.execute(
+            "DELETE FROM link WHERE perspective = ?1 AND source = ?2 AND predicate = ?3 AND target = ?4 AND author = ?5 AND timestamp = ?6",
+            params![
+                perspective_uuid,
+                link.data.source,
+                link.data.predicate.as_ref().unwrap_or(&"".to_string()),
+                link.data.target,
+                link.author,
+                link.timestamp,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get_link(
+        &self,
+        perspective_uuid: &str,
+        link: &LinkExpression,
+    ) -> Ad4mDbResult<Option<(LinkExpression, LinkStatus)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT perspective, source, predicate, target, author, timestamp, signature, key, status FROM link WHERE perspective = ?1 AND source = ?2 AND predicate = ?3 AND target = ?4 AND author = ?5 AND timestamp = ?6",
+        )?;
+        let link_expression: Option<(LinkExpression, LinkStatus)> = stmt
+            .query_row(
+                params![
+                    perspective_uuid,
+                    link.data.source,
+                    link.data.predicate.as_ref().unwrap_or(&"".to_string()),
+                    link.data.target,
+                    link.author,
+                    link.timestamp
+                ],
+                |row| {
+                    let status: LinkStatus = serde_json::from_str(&row.get::<_, String>(8)?)
+                        .map_err(|e| {
+                            rusqlite::Error::FromSqlConversionFailure(
+                                8,
+                                rusqlite::types::Type::Text,
+                                Box::new(e),
+                            )
+                        })?;
+
+                    let link = LinkExpression {
+                        data: Link {
+                            source: row.get(1)?,
+                            predicate: row.get(2).map(|p: Option<String>| {
+                                match p.as_ref().map(|p| p.as_str()) {
+                                    Some("") => None,
+                                    _ => p,
+                                }
+                            })?,
+                            target: row.get(3)?,
+                        },
+                        proof: ExpressionProof {
+                            signature: row.get(6)?,
+                            key: row.get(7)?,
+                        },
+                        author: row.get(4)?,
+                        timestamp: row.get(5)?,
+                        status: Some(status.clone()),
+                    };
+
+                    Ok((link, status))
+                },
+            )
+            .optional()?;
+        Ok(link_expression)
+    }
+
+    fn get_all_links(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT perspective, source, predicate, target, author, timestamp, signature, key, status FROM link WHERE perspective = ?1",
+        )?;
+        let link_iter = stmt.query_map(params![perspective_uuid], |row| {
+            let status: LinkStatus =
+                serde_json::from_str(&row.get::<_, String>(8)?).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        8,
+                        rusqlite::types::Type::Text,
+                        Box::new(e),
+                    )
+                })?;
+            let link_expression = LinkExpression {
+                data: Link {
+                    source: row.get(1)?,
+                    predicate: row.get(2)?,
+                    target: row.get(3)?,
+                },
+                proof: ExpressionProof {
+                    signature: row.get(6)?,
+                    key: row.get(7)?,
+                },
+                author: row.get(4)?,
+                timestamp: row.get(5)?,
+                status: Some(status.clone()),
+            };
+            Ok((link_expression, status))
+        })?;
+        let links: Result<Vec<_>, _> = link_iter.collect();
+        Ok(links?)
+    }
+
+    fn get_links_by_source(
+        &self,
+        perspective_uuid: &str,
+        source: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT perspective, source, predicate, target, author, timestamp, signature, key, status FROM link WHERE perspective = ?1 AND source = ?2",
+        )?;
+        let link_iter = stmt.query_map(params![perspective_uuid, source], |row| {
+            let status: LinkStatus =
+                serde_json::from_str(&row.get::<_, String>(8)?).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        8,
+                        rusqlite::types::Type::Text,
+                        Box::new(e),
+                    )
+                })?;
+            let link_expression = LinkExpression {
+                data: Link {
+                    source: row.get(1)?,
+                    predicate: row.get(2)?,
+                    target: row.get(3)?,
+                },
+                proof: ExpressionProof {
+                    signature: row.get(6)?,
+                    key: row.get(7)?,
+                },
+                author: row.get(4)?,
+                timestamp: row.get(5)?,
+                status: Some(status.clone()),
+            };
+            Ok((link_expression, status))
+        })?;
+        let links: Result<Vec<_>, _> = link_iter.collect();
+        Ok(links?)
+    }
+
+    fn get_links_by_target(
+        &self,
+        perspective_uuid: &str,
+        target: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT perspective, source, predicate, target, author, timestamp, signature, key, status FROM link WHERE perspective = ?1 AND target = ?2",
+        )?;
+        let link_iter = stmt.query_map(params![perspective_uuid, target], |row| {
+            let status: LinkStatus =
+                serde_json::from_str(&row.get::<_, String>(8)?).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        8,
+                        rusqlite::types::Type::Text,
+                        Box::new(e),
+                    )
+                })?;
+            let link_expression = LinkExpression {
+                data: Link {
+                    source: row.get(1)?,
+                    predicate: row.get(2)?,
+                    target: row.get(3)?,
+                },
+                proof: ExpressionProof {
+                    signature: row.get(6)?,
+                    key: row.get(7)?,
+                },
+                author: row.get(4)?,
+                timestamp: row.get(5)?,
+                status: Some(status.clone()),
+            };
+            Ok((link_expression, status))
+        })?;
+        let links: Result<Vec<_>, _> = link_iter.collect();
+        Ok(links?)
+    }
+
+    /// Runs the whole LinkQuery as a single SQL statement.
+    fn query_links(
+        &self,
+        perspective_uuid: &str,
+        query: &LinkQuery,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        Ok(self
+            .query_links_page(perspective_uuid, query)?
+            .into_iter()
+            .map(|(_, link, status)| (link, status))
+            .collect())
+    }
+
+    fn query_links_page(
+        &self,
+        perspective_uuid: &str,
+        query: &LinkQuery,
+    ) -> Ad4mDbResult<Vec<(LinkCursor, LinkExpression, LinkStatus)>> {
+        let conn = self.conn()?;
+        let from_date: Option<chrono::DateTime<chrono::Utc>> =
+            query.from_date.clone().map(|d| d.into());
+        let until_date: Option<chrono::DateTime<chrono::Utc>> =
+            query.until_date.clone().map(|d| d.into());
+
+        let (from_date, until_date, descending) = match (from_date, until_date) {
+            (Some(from), Some(until)) if from > until => (Some(until), Some(from), true),
+            (from, until) => (from, until, false),
+        };
+        let from_date = from_date.map(|d| d.to_rfc3339());
+        let until_date = until_date.map(|d| d.to_rfc3339());
+        let limit = query.limit.map(|l| l as i64);
+        let after = query.after.as_deref().map(LinkCursor::decode).transpose()?;
+
+        let mut sql = String::from(
+            "SELECT perspective, source, predicate, target, author, timestamp, signature, key, status, id FROM link WHERE perspective = ?",
+        );
+        let mut values: Vec<&dyn rusqlite::ToSql> = vec![&perspective_uuid];
+
+        if let Some(source) = query.source.as_ref() {
+            sql.push_str(" AND source = ?");
+            values.push(source);
+        }
+        if let Some(predicate) = query.predicate.as_ref() {
+            sql.push_str(" AND predicate = ?");
+            values.push(predicate);
+        }
+        if let Some(target) = query.target.as_ref() {
+            sql.push_str(" AND target = ?");
+            values.push(target);
+        }
+        if let Some(from_date) = from_date.as_ref() {
+            sql.push_str(" AND julianday(timestamp) >= julianday(?)");
+            values.push(from_date);
+        }
+        if let Some(until_date) = until_date.as_ref() {
+            sql.push_str(" AND julianday(timestamp) <= julianday(?)");
+            values.push(until_date);
+        }
+        if let Some(after) = after.as_ref() {
+            // Same order as below, so the page starts right behind the cursor
+            if descending {
+                sql.push_str(" AND (julianday(timestamp) < julianday(?) OR (julianday(timestamp) = julianday(?) AND id < ?))");
+            } else {
+                sql.push_str(" AND (julianday(timestamp) > julianday(?) OR (julianday(timestamp) = julianday(?) AND id > ?))");
+            }
+            values.push(&after.timestamp);
+            values.push(&after.timestamp);
+            values.push(&after.id);
+        }
+
+        if descending {
+            sql.push_str(" ORDER BY julianday(timestamp) DESC, id DESC");
+        } else {
+            sql.push_str(" ORDER BY julianday(timestamp) ASC, id ASC");
+        }
+
+        if let Some(limit) = limit.as_ref() {
+            sql.push_str(" LIMIT ?");
+            values.push(limit);
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let link_iter = stmt.query_map(values.as_slice(), |row| {
+            let (link, status) = link_from_row(row)?;
+            let cursor = LinkCursor {
+                timestamp: link.timestamp.clone(),
+                id: row.get(9)?,
+            };
+            Ok((cursor, link, status))
+        })?;
+        let links: Result<Vec<_>, _> = link_iter.collect();
+        Ok(links?)
+    }
+
+    fn search_links(
+        &self,
+        perspective_uuid: &str,
+        text: &str,
+        predicate: Option<&str>,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        let conn = self.conn()?;
+        // Quoting every word keeps user input from being interpreted as FTS5 query syntax
+        let match_expression = text
+            .split_whitespace()
+            .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+            .collect::<Vec<String>>()
+            .join(" ");
+        if match_expression.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut sql = String::from(
+            "SELECT link.perspective, link.source, link.predicate, link.target, link.author, link.timestamp, link.signature, link.key, link.status
+             FROM link_search JOIN link ON link.id = link_search.rowid
+             WHERE link_search MATCH ? AND link.perspective = ?",
+        );
+        let mut values: Vec<&dyn rusqlite::ToSql> = vec![&match_expression, &perspective_uuid];
+        if let Some(predicate) = predicate.as_ref() {
+            sql.push_str(" AND link.predicate = ?");
+            values.push(predicate);
+        }
+        sql.push_str(" ORDER BY bm25(link_search), link.id");
+
+        let mut stmt = conn.prepare(&sql)?;
+        let link_iter = stmt.query_map(values.as_slice(), link_from_row)?;
+        let links: Result<Vec<_>, _> = link_iter.collect();
+        Ok(links?)
+    }
+
+    fn add_pending_diff(&self, perspective_uuid: &str, diff: &PerspectiveDiff) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO perspective_diff (perspective, additions, removals, is_pending)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                perspective_uuid,
+                serde_json::to_string(&diff.additions)?,
+                serde_json::to_string(&diff.removals)?,
+                true,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get_pending_diffs(&self, perspective_uuid: &str) -> Ad4mDbResult<PerspectiveDiff> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT additions, removals FROM perspective_diff WHERE perspective = ?1 AND is_pending = ?2",
+        )?;
+        let diffs_iter = stmt.query_map(params![perspective_uuid, true], |row| {
+            let additions: Vec<LinkExpression> =
+                serde_json::from_str(&row.get::<_, String>(0).unwrap()).unwrap();
+            let removals: Vec<LinkExpression> =
+                serde_json::from_str(&row.get::<_, String>(1).unwrap()).unwrap();
+            Ok(PerspectiveDiff {
+                additions,
+                removals,
+            })
+        })?;
+        let mut diffs = Vec::new();
+        for diff in diffs_iter {
+            diffs.push(diff?);
+        }
+        // Assuming we want to concatenate all additions and removals from different diffs
+        let mut all_additions = Vec::new();
+        let mut all_removals = Vec::new();
+        for diff in diffs {
+            all_additions.extend(diff.additions);
+            all_removals.extend(diff.removals);
+        }
+        Ok(PerspectiveDiff {
+            additions: all_additions,
+            removals: all_removals,
+        })
+    }
+
+    fn clear_pending_diffs(&self, perspective_uuid: &str) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "DELETE FROM perspective_diff WHERE perspective = ?1 AND is_pending = ?2",
+            params![perspective_uuid, true],
+        )?;
+        Ok(())
+    }
+
+    fn add_history_entry(
+        &self,
+        perspective_uuid: &str,
+        diff: &DecoratedPerspectiveDiff,
+        origin: &DiffOrigin,
+        max_entries: u32,
+    ) -> Ad4mDbResult<i64> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO perspective_history (perspective, additions, removals, origin, timestamp, undone)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                perspective_uuid,
+                serde_json::to_string(&diff.additions)?,
+                serde_json::to_string(&diff.removals)?,
+                serde_json::to_string(origin)?,
+                chrono::Utc::now().to_rfc3339(),
+                false,
+            ],
+        )?;
+        let id = conn.last_insert_rowid();
+
+        // Remember the newest entry we drop, so we know how far back the history goes
+        conn.execute(
+            "INSERT INTO perspective_history_truncation (perspective, truncated_until)
+             SELECT perspective, timestamp FROM perspective_history WHERE perspective = ?1 AND id NOT IN (
+                SELECT id FROM perspective_history WHERE perspective = ?1 ORDER BY id DESC LIMIT ?2
+             ) ORDER BY id DESC LIMIT 1
+             ON CONFLICT (perspective) DO UPDATE SET truncated_until = excluded.truncated_until",
+            params![perspective_uuid, max_entries],
+        )?;
+        conn.execute(
+            "DELETE FROM perspective_history WHERE perspective = ?1 AND id NOT IN (
+                SELECT id FROM perspective_history WHERE perspective = ?1 ORDER BY id DESC LIMIT ?2
+             )",
+            params![perspective_uuid, max_entries],
+        )?;
+        Ok(id)
+    }
+
+    fn get_last_undoable_history_entry(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<PerspectiveHistoryEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, additions, removals, origin, timestamp, undone FROM perspective_history
+             WHERE perspective = ?1 AND origin = ?2 AND undone = ?3 ORDER BY id DESC LIMIT 1",
+        )?;
+        let entry = stmt
+            .query_row(
+                params![
+                    perspective_uuid,
+                    serde_json::to_string(&DiffOrigin::Local)?,
+                    false
+                ],
+                history_entry_from_row,
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    fn get_next_redoable_history_entry(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<PerspectiveHistoryEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, additions, removals, origin, timestamp, undone FROM perspective_history
+             WHERE perspective = ?1 AND undone = ?2 AND discarded = ?3 ORDER BY id ASC LIMIT 1",
+        )?;
+        let entry = stmt
+            .query_row(
+                params![perspective_uuid, true, false],
+                history_entry_from_row,
+            )
+            .optional()?;
+        Ok(entry)
+    }
+
+    fn set_history_entry_undone(&self, id: i64, undone: bool) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE perspective_history SET undone = ?1 WHERE id = ?2",
+            params![undone, id],
+        )?;
+        Ok(())
+    }
+
+    fn clear_redo_history(&self, perspective_uuid: &str) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE perspective_history SET discarded = ?3 WHERE perspective = ?1 AND undone = ?2",
+            params![perspective_uuid, true, true],
+        )?;
+        Ok(())
+    }
+
+    fn history_truncated_until(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<chrono::DateTime<chrono::Utc>>> {
+        let conn = self.conn()?;
+        let truncated_until: Option<String> = conn
+            .query_row(
+                "SELECT truncated_until FROM perspective_history_truncation WHERE perspective = ?1",
+                params![perspective_uuid],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(truncated_until
+            .map(|t| chrono::DateTime::parse_from_rfc3339(&t))
+            .transpose()?
+            .map(|t| t.with_timezone(&chrono::Utc)))
+    }
+
+    fn get_history_entries_since(
+        &self,
+        perspective_uuid: &str,
+        since: &chrono::DateTime<chrono::Utc>,
+    ) -> Ad4mDbResult<Vec<PerspectiveHistoryEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, additions, removals, origin, timestamp, undone FROM perspective_history
+             WHERE perspective = ?1 AND julianday(timestamp) > julianday(?2) ORDER BY id DESC",
+        )?;
+        let entries = stmt
+            .query_map(
+                params![perspective_uuid, since.to_rfc3339()],
+                history_entry_from_row,
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+
+    fn add_expression_fetched_at(
+        &self,
+        url: &str,
+        expression: &Expression<JsonValue>,
+        fetched_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        let data = serde_json::to_string(expression)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO expression (url, data, language, size, fetched_at, last_accessed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (url) DO UPDATE SET
+                data = excluded.data,
+                language = excluded.language,
+                size = excluded.size,
+                fetched_at = excluded.fetched_at,
+                last_accessed = excluded.last_accessed",
+            params![url, data, expression_language(url), data.len() as i64, fetched_at.to_rfc3339(), now],
+        )?;
+        Ok(())
+    }
+
+    fn get_expression(&self, url: &str) -> Ad4mDbResult<Option<Expression<JsonValue>>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT data FROM expression WHERE url = ?1")?;
+        let expression: Option<String> =
+            stmt.query_row(params![url], |row| row.get(0)).optional()?;
+        Ok(expression.map(|e| serde_json::from_str(&e).unwrap()))
+    }
+
+    fn get_cached_expression(&self, url: &str) -> Ad4mDbResult<Option<CachedExpression>> {
+        let conn = self.conn()?;
+        let now = chrono::Utc::now();
+        let row = conn
+            .query_row(
+                "SELECT data, language, size, fetched_at FROM expression WHERE url = ?1",
+                params![url],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let (data, language, size, fetched_at) = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        conn.execute(
+            "UPDATE expression SET last_accessed = ?1 WHERE url = ?2",
+            params![now.to_rfc3339(), url],
+        )?;
+
+        Ok(Some(CachedExpression {
+            url: url.to_string(),
+            expression: serde_json::from_str(&data)?,
+            language,
+            size: size as u64,
+            // Unparsable fetch times make the entry count as expired
+            fetched_at: fetched_at
+                .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+                .map(|t| t.with_timezone(&chrono::Utc))
+                .unwrap_or(chrono::DateTime::<chrono::Utc>::MIN_UTC),
+            last_accessed: now,
+        }))
+    }
+
+    fn remove_expression(&self, url: &str) -> Ad4mDbResult<()> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM expression WHERE url = ?1", params![url])?;
+        Ok(())
+    }
+
+    fn expression_cache_usage(&self) -> Ad4mDbResult<(u64, u64)> {
+        let conn = self.conn()?;
+        let (count, size) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM expression",
+            [],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        Ok((count as u64, size as u64))
+    }
+
+    fn evict_expressions(&self, max_size: u64) -> Ad4mDbResult<u64> {
+        let conn = self.conn()?;
+        let mut total_size =
+            conn.query_row("SELECT COALESCE(SUM(size), 0) FROM expression", [], |row| {
+                row.get::<_, i64>(0)
+            })? as u64;
+        if total_size <= max_size {
+            return Ok(0);
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT id, size FROM expression ORDER BY julianday(last_accessed) ASC, id ASC",
+        )?;
+        let mut evict = Vec::new();
+        for row in stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)))? {
+            if total_size <= max_size {
+                break;
+            }
+            let (id, size) = row?;
+            total_size = total_size.saturating_sub(size as u64);
+            evict.push(id);
+        }
+
+        for id in evict.iter() {
+            conn.execute("DELETE FROM expression WHERE id = ?1", [id])?;
+        }
+        Ok(evict.len() as u64)
+    }
+}
+
+fn index_link(conn: &Connection, id: i64, link: &LinkExpression) -> Ad4mDbResult<()> {
+    if let Some(text) = searchable_text(&link.data.target) {
+        conn.execute(
+            "INSERT INTO link_search (rowid, text) VALUES (?1, ?2)",
+            params![id, text],
+        )?;
+    }
+    Ok(())
+}
+
+fn unindex_link(conn: &Connection, id: i64) -> Ad4mDbResult<()> {
+    conn.execute("DELETE FROM link_search WHERE rowid = ?1", [id])?;
+    Ok(())
+}
+
+fn link_ids(
+    conn: &Connection,
+    perspective_uuid: &str,
+    link: &LinkExpression,
+) -> Ad4mDbResult<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        "SELECT id FROM link WHERE perspective = ?1 AND source = ?2 AND predicate = ?3 AND target = ?4 AND author = ?5 AND timestamp = ?6",
+    )?;
+    let ids = stmt.query_map(
+        params![
+            perspective_uuid,
+            link.data.source,
+            link.data.predicate.as_ref().unwrap_or(&"".to_string()),
+            link.data.target,
+            link.author,
+            link.timestamp,
+        ],
+        |row| row.get(0),
+    )?;
+    let ids: Result<Vec<i64>, _> = ids.collect();
+    Ok(ids?)
+}
+
+fn link_from_row(row: &rusqlite::Row) -> rusqlite::Result<(LinkExpression, LinkStatus)> {
+    let status: LinkStatus = serde_json::from_str(&row.get::<_, String>(8)?).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(8, rusqlite::types::Type::Text, Box::new(e))
+    })?;
+    let link_expression = LinkExpression {
+        data: Link {
+            source: row.get(1)?,
+            predicate: row.get(2).map(|p: Option<String>| {
+                match p.as_ref().map(|p| p.as_str()) {
+                    Some("") => None,
+                    _ => p,
+                }
+            })?,
+            target: row.get(3)?,
+        },
+        proof: ExpressionProof {
+            signature: row.get(6)?,
+            key: row.get(7)?,
+        },
+        author: row.get(4)?,
+        timestamp: row.get(5)?,
+        status: Some(status.clone()),
+    };
+    Ok((link_expression, status))
+}
+
+fn history_entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<PerspectiveHistoryEntry> {
+    fn json_column<T: serde::de::DeserializeOwned>(
+        row: &rusqlite::Row,
+        index: usize,
+    ) -> rusqlite::Result<T> {
+        serde_json::from_str(&row.get::<_, String>(index)?).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(
+                index,
+                rusqlite::types::Type::Text,
+                Box::new(e),
+            )
+        })
+    }
+
+    Ok(PerspectiveHistoryEntry {
+        id: row.get(0)?,
+        diff: DecoratedPerspectiveDiff {
+            additions: json_column::<Vec<DecoratedLinkExpression>>(row, 1)?,
+            removals: json_column::<Vec<DecoratedLinkExpression>>(row, 2)?,
+        },
+        origin: json_column(row, 3)?,
+        timestamp: row.get(4)?,
+        undone: row.get(5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ExpressionProof, Link, LinkExpression};
+    use ad4m_client::literal::Literal;
+    use chrono::Utc;
+    use fake::{Fake, Faker};
+    use uuid::Uuid;
+
+    fn construct_dummy_link_expression(status: LinkStatus) -> LinkExpression {
+        LinkExpression {
+            data: Link {
+                source: Faker.fake::<String>(),
+                target: Faker.fake::<String>(),
+                predicate: Some(Faker.fake::<String>()),
+            },
+            proof: ExpressionProof {
+                signature: "signature".to_string(),
+                key: "key".to_string(),
+            },
+            author: "did:test:key".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            status: Some(status),
+        }
+    }
+
+    #[test]
+    fn can_store_and_retrieve_links() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let link = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link, &LinkStatus::Shared).unwrap();
+
+        let result: Option<(LinkExpression, LinkStatus)> = db.get_link(&p_uuid, &link).unwrap();
+        assert!(result.is_some());
+        assert_eq!(result.unwrap(), (link, LinkStatus::Shared));
+    }
+
+    #[test]
+    fn can_store_and_get_link_with_missing_predicate() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let mut link = construct_dummy_link_expression(LinkStatus::Shared);
+        link.data.predicate = None;
+        db.add_link(&p_uuid, &link, &LinkStatus::Shared).unwrap();
+
+        let result = db.get_link(&p_uuid, &link).unwrap();
+        assert_eq!(result, Some((link, LinkStatus::Shared)));
+    }
+
+    #[test]
+    fn can_call_get_link_multiple_times() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let link = construct_dummy_link_expression(LinkStatus::Local);
+        db.add_link(&p_uuid, &link, &LinkStatus::Local).unwrap();
+
+        for _ in 0..3 {
+            let result = db.get_link(&p_uuid, &link).unwrap();
+            assert_eq!(result, Some((link.clone(), LinkStatus::Local)));
+        }
+    }
+
+    #[test]
+    fn can_get_all_links() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let link1 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link1, &LinkStatus::Shared).unwrap();
+        let link2 = construct_dummy_link_expression(LinkStatus::Local);
+        db.add_link(&p_uuid, &link2, &LinkStatus::Local).unwrap();
+
+        let all_links = db.get_all_links(&p_uuid).unwrap();
+        assert_eq!(
+            all_links,
+            vec![(link1, LinkStatus::Shared), (link2, LinkStatus::Local)]
+        );
+    }
+
+    #[test]
+    fn can_call_get_all_links_multiple_times() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let link1 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link1, &LinkStatus::Shared).unwrap();
+
+        for _ in 0..3 {
+            let all_links = db.get_all_links(&p_uuid).unwrap();
+            assert_eq!(all_links, vec![(link1.clone(), LinkStatus::Shared)]);
+        }
+    }
+
+    #[test]
+    fn can_get_links_by_source() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let link1 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link1, &LinkStatus::Shared).unwrap();
+        let link2 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link2, &LinkStatus::Shared).unwrap();
+
+        let result = db.get_links_by_source(&p_uuid, &link1.data.source).unwrap();
+        assert_eq!(result, vec![(link1, LinkStatus::Shared)]);
+    }
+
+    #[test]
+    fn can_get_links_by_target() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let link1 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link1, &LinkStatus::Shared).unwrap();
+        let link2 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link2, &LinkStatus::Shared).unwrap();
+
+        let result = db.get_links_by_target(&p_uuid, &link1.data.target).unwrap();
+        assert_eq!(result, vec![(link1, LinkStatus::Shared)]);
+    }
+
+    #[test]
+    fn can_query_links_with_filters_order_and_limit() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let mut links = Vec::new();
+        for i in 0..5 {
+            let mut link = construct_dummy_link_expression(LinkStatus::Shared);
+            link.data.source = "ad4m://self".to_string();
+            link.data.predicate = if i % 2 == 0 {
+                Some("p://even".to_string())
+            } else {
+                None
+            };
+            link.timestamp = (now - chrono::Duration::minutes(5 - i)).to_rfc3339();
+            db.add_link(&p_uuid, &link, &LinkStatus::Shared).unwrap();
+            links.push(link);
+        }
+        let other = construct_dummy_link_expression(LinkStatus::Local);
+        db.add_link(&p_uuid, &other, &LinkStatus::Local).unwrap();
+
+        let by_source = db
+            .query_links(
+                &p_uuid,
+                &LinkQuery {
+                    source: Some("ad4m://self".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            by_source.into_iter().map(|(l, _)| l).collect::<Vec<_>>(),
+            links
+        );
+
+        let by_predicate = db
+            .query_links(
+                &p_uuid,
+                &LinkQuery {
+                    predicate: Some("p://even".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(by_predicate.len(), 3);
+
+        let descending = db
+            .query_links(
+                &p_uuid,
+                &LinkQuery {
+                    source: Some("ad4m://self".to_string()),
+                    from_date: Some(now.into()),
+                    until_date: Some((now - chrono::Duration::minutes(10)).into()),
+                    limit: Some(2),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            descending.into_iter().map(|(l, _)| l).collect::<Vec<_>>(),
+            vec![links[4].clone(), links[3].clone()]
+        );
+
+        let range = db
+            .query_links(
+                &p_uuid,
+                &LinkQuery {
+                    from_date: Some((now - chrono::Duration::minutes(4)).into()),
+                    until_date: Some((now - chrono::Duration::minutes(2)).into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            range.into_iter().map(|(l, _)| l).collect::<Vec<_>>(),
+            links[1..4].to_vec()
+        );
+    }
+
+    #[test]
+    fn can_page_through_links_with_cursors() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let mut links = Vec::new();
+        for i in 0..5 {
+            let mut link = construct_dummy_link_expression(LinkStatus::Shared);
+            // Pairs of links with the same timestamp
+            link.timestamp = (now - chrono::Duration::minutes(5 - i / 2)).to_rfc3339();
+            db.add_link(&p_uuid, &link, &LinkStatus::Shared).unwrap();
+            links.push(link);
+        }
+
+        let mut pages = Vec::new();
+        let mut after = None;
+        loop {
+            let page = db
+                .query_links_page(
+                    &p_uuid,
+                    &LinkQuery {
+                        limit: Some(2),
+                        after: after.clone(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            if page.is_empty() {
+                break;
+            }
+            after = page.last().map(|(cursor, _, _)| cursor.encode());
+            pages.push(page.into_iter().map(|(_, l, _)| l).collect::<Vec<_>>());
+        }
+        assert_eq!(
+            pages,
+            vec![
+                links[0..2].to_vec(),
+                links[2..4].to_vec(),
+                links[4..5].to_vec()
+            ]
+        );
+
+        let first = db
+            .query_links_page(
+                &p_uuid,
+                &LinkQuery {
+                    from_date: Some(now.into()),
+                    until_date: Some((now - chrono::Duration::minutes(10)).into()),
+                    limit: Some(3),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let rest = db
+            .query_links(
+                &p_uuid,
+                &LinkQuery {
+                    from_date: Some(now.into()),
+                    until_date: Some((now - chrono::Duration::minutes(10)).into()),
+                    after: Some(first[2].0.encode()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            rest.into_iter().map(|(l, _)| l).collect::<Vec<_>>(),
+            vec![links[1].clone(), links[0].clone()]
+        );
+
+        assert!(db
+            .query_links(
+                &p_uuid,
+                &LinkQuery {
+                    after: Some("not a cursor".to_string()),
+                    ..Default::default()
+                }
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn can_update_link() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let link1 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link1, &LinkStatus::Shared).unwrap();
+        let link2 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.update_link(&p_uuid, &link1, &link2).unwrap();
+
+        assert!(db.get_link(&p_uuid, &link1).unwrap().is_none());
+        let result = db.get_link(&p_uuid, &link2).unwrap();
+        assert_eq!(result, Some((link2, LinkStatus::Shared)));
+    }
+
+    #[test]
+    fn can_remove_link() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let link1 = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_link(&p_uuid, &link1, &LinkStatus::Shared).unwrap();
+
+        let result = db.get_link(&p_uuid, &link1).unwrap();
+        assert_eq!(result, Some((link1.clone(), LinkStatus::Shared)));
+        db.remove_link(&p_uuid, &link1).unwrap();
+        assert!(db.get_link(&p_uuid, &link1).unwrap().is_none());
+    }
+
+    #[test]
+    fn can_search_literal_link_targets() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let literal_link = |target: String, predicate: &str| {
+            let mut link = construct_dummy_link_expression(LinkStatus::Shared);
+            link.data.target = target;
+            link.data.predicate = Some(predicate.to_string());
+            link
+        };
+
+        let title = literal_link(
+            Literal::from_string("Café meeting notes".to_string())
+                .to_url()
+                .unwrap(),
+            "flux://title",
+        );
+        let body = literal_link(
+            Literal::from_string("notes notes and more notes about the meeting".to_string())
+                .to_url()
+                .unwrap(),
+            "flux://body",
+        );
+        let json = literal_link(
+            Literal::from_json(serde_json::json!({"title": "Meeting agenda", "count": 3}))
+                .to_url()
+                .unwrap(),
+            "flux://body",
+        );
+        let other = literal_link("ad4m://meeting".to_string(), "flux://title");
+        for link in [&title, &body, &json, &other] {
+            db.add_link(&p_uuid, link, &LinkStatus::Shared).unwrap();
+        }
+        db.add_link(&Uuid::new_v4().to_string(), &title, &LinkStatus::Shared)
+            .unwrap();
+
+        let found: Vec<LinkExpression> = db
+            .search_links(&p_uuid, "meeting", None)
+            .unwrap()
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect();
+        assert_eq!(found.len(), 3);
+        assert!(!found.contains(&other));
+
+        // More occurrences of the term rank higher
+        let found = db.search_links(&p_uuid, "notes", None).unwrap();
+        assert_eq!(
+            found.iter().map(|(l, _)| l.clone()).collect::<Vec<_>>(),
+            vec![body.clone(), title.clone()]
+        );
+
+        // All words have to match, diacritics and FTS syntax in the input are ignored
+        assert_eq!(
+            db.search_links(&p_uuid, "cafe MEETING", None).unwrap(),
+            vec![(title.clone(), LinkStatus::Shared)]
+        );
+        assert!(db
+            .search_links(&p_uuid, "\"meeting OR", None)
+            .unwrap()
+            .is_empty());
+        assert!(db.search_links(&p_uuid, "  ", None).unwrap().is_empty());
+
+        let found = db
+            .search_links(&p_uuid, "meeting", Some("flux://title"))
+            .unwrap();
+        assert_eq!(found, vec![(title.clone(), LinkStatus::Shared)]);
+
+        let mut renamed = title.clone();
+        renamed.data.target = Literal::from_string("Weekly sync".to_string())
+            .to_url()
+            .unwrap();
+        db.update_link(&p_uuid, &title, &renamed).unwrap();
+        db.remove_link(&p_uuid, &json).unwrap();
+        assert_eq!(
+            db.search_links(&p_uuid, "meeting", None).unwrap(),
+            vec![(body.clone(), LinkStatus::Shared)]
+        );
+        assert_eq!(
+            db.search_links(&p_uuid, "sync", None).unwrap(),
+            vec![(renamed.clone(), LinkStatus::Shared)]
+        );
+
+        db.rebuild_search_index().unwrap();
+        assert_eq!(
+            db.search_links(&p_uuid, "meeting", None).unwrap(),
+            vec![(body, LinkStatus::Shared)]
+        );
+    }
+
+    #[test]
+    fn can_undo_and_redo_history_entries() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let diff = |link: LinkExpression| DecoratedPerspectiveDiff {
+            additions: vec![DecoratedLinkExpression::from((link, LinkStatus::Shared))],
+            removals: vec![],
+        };
+
+        let first = db
+            .add_history_entry(
+                &p_uuid,
+                &diff(construct_dummy_link_expression(LinkStatus::Shared)),
+                &DiffOrigin::Local,
+                10,
+            )
+            .unwrap();
+        let second = db
+            .add_history_entry(
+                &p_uuid,
+                &diff(construct_dummy_link_expression(LinkStatus::Shared)),
+                &DiffOrigin::Local,
+                10,
+            )
+            .unwrap();
+        db.add_history_entry(
+            &p_uuid,
+            &diff(construct_dummy_link_expression(LinkStatus::Shared)),
+            &DiffOrigin::LinkLanguage,
+            10,
+        )
+        .unwrap();
+
+        assert_eq!(
+            db.get_last_undoable_history_entry(&p_uuid)
+                .unwrap()
+                .unwrap()
+                .id,
+            second
+        );
+        assert!(db
+            .get_next_redoable_history_entry(&p_uuid)
+            .unwrap()
+            .is_none());
+
+        db.set_history_entry_undone(second, true).unwrap();
+        db.set_history_entry_undone(first, true).unwrap();
+        assert!(db
+            .get_last_undoable_history_entry(&p_uuid)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            db.get_next_redoable_history_entry(&p_uuid)
+                .unwrap()
+                .unwrap()
+                .id,
+            first
+        );
+
+        db.clear_redo_history(&p_uuid).unwrap();
+        assert!(db
+            .get_next_redoable_history_entry(&p_uuid)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn can_query_links_at_a_point_in_time() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let set_timestamp = |id: i64, timestamp: &str| {
+            db.conn()
+                .unwrap()
+                .execute(
+                    "UPDATE perspective_history SET timestamp = ?2 WHERE id = ?1",
+                    params![id, timestamp],
+                )
+                .unwrap();
+        };
+
+        let mut kept = construct_dummy_link_expression(LinkStatus::Shared);
+        kept.timestamp = "2023-01-01T00:00:00Z".to_string();
+        let mut removed = construct_dummy_link_expression(LinkStatus::Local);
+        removed.timestamp = "2023-01-02T00:00:00Z".to_string();
+        let mut added = construct_dummy_link_expression(LinkStatus::Shared);
+        added.timestamp = "2023-01-01T12:00:00Z".to_string();
+        let mut created_later = construct_dummy_link_expression(LinkStatus::Shared);
+        created_later.timestamp = "2023-03-01T00:00:00Z".to_string();
+
+        // Current state: `removed` got removed and `added` got added in February.
+        // `created_later` has no history entry, e.g. because it predates the log.
+        db.add_link(&p_uuid, &kept, &LinkStatus::Shared).unwrap();
+        db.add_link(&p_uuid, &added, &LinkStatus::Shared).unwrap();
+        db.add_link(&p_uuid, &created_later, &LinkStatus::Shared)
+            .unwrap();
+        let removal = db
+            .add_history_entry(
+                &p_uuid,
+                &DecoratedPerspectiveDiff {
+                    additions: vec![],
+                    removals: vec![DecoratedLinkExpression::from((
+                        removed.clone(),
+                        LinkStatus::Local,
+                    ))],
+                },
+                &DiffOrigin::Local,
+                10,
+            )
+            .unwrap();
+        set_timestamp(removal, "2023-02-01T00:00:00Z");
+        let addition = db
+            .add_history_entry(
+                &p_uuid,
+                &DecoratedPerspectiveDiff {
+                    additions: vec![DecoratedLinkExpression::from((
+                        added.clone(),
+                        LinkStatus::Shared,
+                    ))],
+                    removals: vec![],
+                },
+                &DiffOrigin::LinkLanguage,
+                10,
+            )
+            .unwrap();
+        set_timestamp(addition, "2023-02-02T00:00:00Z");
+
+        let at = chrono::DateTime::parse_from_rfc3339("2023-01-15T00:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let links = db
+            .query_links_at(&p_uuid, &LinkQuery::default(), &at)
+            .unwrap();
+        assert_eq!(
+            links
+                .iter()
+                .map(|(l, _)| l.data.target.clone())
+                .collect::<Vec<_>>(),
+            vec![kept.data.target.clone(), removed.data.target.clone(),]
+        );
+        assert_eq!(links[1].1, LinkStatus::Local);
+
+        let query = LinkQuery {
+            source: Some(kept.data.source.clone()),
+            ..Default::default()
+        };
+        assert_eq!(db.query_links_at(&p_uuid, &query, &at).unwrap().len(), 1);
+
+        let between = chrono::DateTime::parse_from_rfc3339("2023-02-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let links = db
+            .query_links_at(&p_uuid, &LinkQuery::default(), &between)
+            .unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].0.data.target, kept.data.target);
+    }
+
+    #[test]
+    fn refuses_to_query_links_before_the_retained_history() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let diff = DecoratedPerspectiveDiff {
+            additions: vec![DecoratedLinkExpression::from((
+                construct_dummy_link_expression(LinkStatus::Shared),
+                LinkStatus::Shared,
+            ))],
+            removals: vec![],
+        };
+
+        let before = chrono::Utc::now() - chrono::Duration::minutes(1);
+        for _ in 0..2 {
+            db.add_history_entry(&p_uuid, &diff, &DiffOrigin::Local, 2)
+                .unwrap();
+        }
+        assert!(db.history_truncated_until(&p_uuid).unwrap().is_none());
+        assert!(db
+            .query_links_at(&p_uuid, &LinkQuery::default(), &before)
+            .is_ok());
+
+        db.add_history_entry(&p_uuid, &diff, &DiffOrigin::Local, 2)
+            .unwrap();
+        let truncated_until = db.history_truncated_until(&p_uuid).unwrap().unwrap();
+        assert!(truncated_until > before);
+        assert!(db
+            .query_links_at(&p_uuid, &LinkQuery::default(), &before)
+            .is_err());
+        assert!(db
+            .query_links_at(&p_uuid, &LinkQuery::default(), &chrono::Utc::now())
+            .is_ok());
+
+        db.remove_perspective(&p_uuid).unwrap();
+        assert!(db.history_truncated_until(&p_uuid).unwrap().is_none());
+    }
+
+    #[test]
+    fn can_get_history_entries_since() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let diff = DecoratedPerspectiveDiff {
+            additions: vec![DecoratedLinkExpression::from((
+                construct_dummy_link_expression(LinkStatus::Shared),
+                LinkStatus::Shared,
+            ))],
+            removals: vec![],
+        };
+
+        let first = db
+            .add_history_entry(&p_uuid, &diff, &DiffOrigin::Local, 10)
+            .unwrap();
+        let second = db
+            .add_history_entry(&p_uuid, &diff, &DiffOrigin::LinkLanguage, 10)
+            .unwrap();
+        db.conn()
+            .unwrap()
+            .execute(
+                "UPDATE perspective_history SET timestamp = ?2 WHERE id = ?1",
+                params![first, "2023-01-01T00:00:00Z"],
+            )
+            .unwrap();
+
+        let since = chrono::DateTime::parse_from_rfc3339("2023-06-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let entries = db.get_history_entries_since(&p_uuid, &since).unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![second]
+        );
+
+        // Discarded redo history stays part of the log
+        db.set_history_entry_undone(second, true).unwrap();
+        db.clear_redo_history(&p_uuid).unwrap();
+        assert_eq!(
+            db.get_history_entries_since(&p_uuid, &since).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let mut last = 0;
+        for _ in 0..5 {
+            let diff = DecoratedPerspectiveDiff {
+                additions: vec![DecoratedLinkExpression::from((
+                    construct_dummy_link_expression(LinkStatus::Local),
+                    LinkStatus::Local,
+                ))],
+                removals: vec![],
+            };
+            last = db
+                .add_history_entry(&p_uuid, &diff, &DiffOrigin::Local, 3)
+                .unwrap();
+        }
+
+        let count: i64 = db
+            .conn()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM perspective_history WHERE perspective = ?1",
+                [&p_uuid],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            db.get_last_undoable_history_entry(&p_uuid)
+                .unwrap()
+                .unwrap()
+                .id,
+            last
+        );
+    }
+
+    #[test]
+    fn can_get_and_remove_pending_diffs() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let p_uuid = Uuid::new_v4().to_string();
+        let addition = construct_dummy_link_expression(LinkStatus::Shared);
+        let removal = construct_dummy_link_expression(LinkStatus::Shared);
+        db.add_pending_diff(
+            &p_uuid,
+            &PerspectiveDiff {
+                additions: vec![addition.clone()],
+                removals: vec![removal.clone()],
+            },
+        )
+        .unwrap();
+
+        let get = db.get_pending_diffs(&p_uuid).unwrap();
+        assert_eq!(get.additions.len(), 1);
+        assert_eq!(get.removals.len(), 1);
+        assert_eq!(
+            get,
+            PerspectiveDiff {
+                additions: vec![addition],
+                removals: vec![removal],
+            }
+        );
+
+        db.clear_pending_diffs(&p_uuid).unwrap();
+        let get2 = db.get_pending_diffs(&p_uuid).unwrap();
+        assert_eq!(get2.additions.len(), 0);
+    }
+
+    #[test]
+    fn can_cache_and_evict_expressions() {
+        let db = SqliteStorage::open(":memory:").unwrap();
+        let expression = |data: &str| Expression {
+            author: "did:test:key".to_string(),
+            timestamp: "2023-01-01T00:00:00Z".to_string(),
+            data: serde_json::json!(data),
+            proof: ExpressionProof {
+                key: "key".to_string(),
+                signature: "signature".to_string(),
+            },
+        };
+
+        db.add_expression("lang://a", &expression("a")).unwrap();
+        db.add_expression("lang://b", &expression("b")).unwrap();
+        // Replacing keeps a single entry
+        db.add_expression("lang://b", &expression("c")).unwrap();
+        let (count, size) = db.expression_cache_usage().unwrap();
+        assert_eq!(count, 2);
+
+        let cached = db.get_cached_expression("lang://b").unwrap().unwrap();
+        assert_eq!(cached.expression.data, serde_json::json!("c"));
+        assert_eq!(cached.language, Some("lang".to_string()));
+        assert_eq!(cached.size * 2, size);
+
+        // `a` has not been used since it got added, so it goes first
+        assert_eq!(db.evict_expressions(size - 1).unwrap(), 1);
+        assert!(db.get_expression("lang://a").unwrap().is_none());
+        assert!(db.get_expression("lang://b").unwrap().is_some());
+
+        db.remove_expression("lang://b").unwrap();
+        assert_eq!(db.expression_cache_usage().unwrap(), (0, 0));
+    }
+}
@@ -0,0 +1,242 @@
+use super::{memory, Ad4mDbResult};
+use crate::graphql::graphql_types::{
+    DecoratedPerspectiveDiff, LinkQuery, LinkStatus, PerspectiveHandle,
+};
+use crate::types::{
+    CachedExpression, DiffOrigin, Expression, LinkCursor, LinkExpression, PerspectiveDiff,
+    PerspectiveHistoryEntry,
+};
+use deno_core::anyhow::anyhow;
+use serde_json::Value as JsonValue;
+
+/// Everything the executor persists: perspective handles, their links,
+/// pending diffs, history log and cached expressions.
+///
+/// Implementations have to be usable from several threads at once
+/// and do their own locking, so all methods take `&self`.
+pub trait Ad4mStorage: Send + Sync {
+    // Perspectives
+
+    fn add_perspective(&self, perspective: &PerspectiveHandle) -> Ad4mDbResult<()>;
+    fn get_perspective(&self, uuid: &str) -> Ad4mDbResult<Option<PerspectiveHandle>>;
+    fn get_all_perspectives(&self) -> Ad4mDbResult<Vec<PerspectiveHandle>>;
+    fn update_perspective(&self, perspective: &PerspectiveHandle) -> Ad4mDbResult<()>;
+    /// Removes the perspective together with its links, pending diffs and history.
+    fn remove_perspective(&self, uuid: &str) -> Ad4mDbResult<()>;
+
+    // Links
+
+    fn add_link(
+        &self,
+        perspective_uuid: &str,
+        link: &LinkExpression,
+        status: &LinkStatus,
+    ) -> Ad4mDbResult<()>;
+    fn add_many_links(
+        &self,
+        perspective_uuid: &str,
+        links: Vec<LinkExpression>,
+        status: &LinkStatus,
+    ) -> Ad4mDbResult<()>;
+    fn update_link(
+        &self,
+        perspective_uuid: &str,
+        old_link: &LinkExpression,
+        new_link: &LinkExpression,
+    ) -> Ad4mDbResult<()>;
+    fn remove_link(&self, perspective_uuid: &str, link: &LinkExpression) -> Ad4mDbResult<()>;
+    fn get_link(
+        &self,
+        perspective_uuid: &str,
+        link: &LinkExpression,
+    ) -> Ad4mDbResult<Option<(LinkExpression, LinkStatus)>>;
+    fn get_all_links(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>>;
+    fn get_links_by_source(
+        &self,
+        perspective_uuid: &str,
+        source: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>>;
+    fn get_links_by_target(
+        &self,
+        perspective_uuid: &str,
+        target: &str,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>>;
+
+    /// Runs a full LinkQuery.
+    /// If from_date is later than until_date, the range gets swapped and
+    /// links are returned in descending timestamp order.
+    fn query_links(
+        &self,
+        perspective_uuid: &str,
+        query: &LinkQuery,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>>;
+
+    /// Like query_links() but with the cursor of each link, for paging through results.
+    /// Links are ordered by timestamp and then by insertion, so cursors are stable
+    /// even if several links share a timestamp. `query.after` starts the page behind that cursor.
+    fn query_links_page(
+        &self,
+        perspective_uuid: &str,
+        query: &LinkQuery,
+    ) -> Ad4mDbResult<Vec<(LinkCursor, LinkExpression, LinkStatus)>>;
+
+    /// Full-text search over the decoded literal targets of the perspective's links,
+    /// best matches first. All words of `text` have to occur in the literal.
+    fn search_links(
+        &self,
+        perspective_uuid: &str,
+        text: &str,
+        predicate: Option<&str>,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>>;
+
+    /// Runs the given query against the links of the perspective as they were at `at`.
+    ///
+    /// The current links get rewound by reverting every history entry applied after `at`,
+    /// newest first. Links created after `at` are dropped as well, so this also covers
+    /// links that are older than the history log. Changes that got trimmed from the log
+    /// can't be reverted though, so the result is only exact within the retained history.
+    fn query_links_at(
+        &self,
+        perspective_uuid: &str,
+        query: &LinkQuery,
+        at: &chrono::DateTime<chrono::Utc>,
+    ) -> Ad4mDbResult<Vec<(LinkExpression, LinkStatus)>> {
+        Ok(self
+            .query_links_page_at(perspective_uuid, query, at)?
+            .into_iter()
+            .map(|(_, link, status)| (link, status))
+            .collect())
+    }
+
+    /// query_links_at() with cursors, see query_links_page().
+    /// Links that got restored from the history get ids behind all current links.
+    /// Fails for points in time before the oldest retained history entry,
+    /// since the links can't be rewound that far anymore.
+    fn query_links_page_at(
+        &self,
+        perspective_uuid: &str,
+        query: &LinkQuery,
+        at: &chrono::DateTime<chrono::Utc>,
+    ) -> Ad4mDbResult<Vec<(LinkCursor, LinkExpression, LinkStatus)>> {
+        fn same_link(a: &LinkExpression, b: &LinkExpression) -> bool {
+            a.author == b.author
+                && a.timestamp == b.timestamp
+                && a.data.normalize() == b.data.normalize()
+        }
+
+        if let Some(truncated_until) = self.history_truncated_until(perspective_uuid)? {
+            if *at < truncated_until {
+                return Err(anyhow!(
+                    "History of perspective {} only goes back to {}, can't rebuild its links at {}",
+                    perspective_uuid,
+                    truncated_until.to_rfc3339(),
+                    at.to_rfc3339()
+                ));
+            }
+        }
+
+        let mut links: Vec<(i64, LinkExpression, LinkStatus)> = self
+            .query_links_page(perspective_uuid, &LinkQuery::default())?
+            .into_iter()
+            .map(|(cursor, link, status)| (cursor.id, link, status))
+            .collect();
+        let mut next_id = links.iter().map(|(id, _, _)| *id).max().unwrap_or(0);
+
+        for entry in self.get_history_entries_since(perspective_uuid, at)? {
+            for addition in entry.diff.additions {
+                let addition = LinkExpression::from(addition);
+                links.retain(|(_, link, _)| !same_link(link, &addition));
+            }
+            for removal in entry.diff.removals {
+                let status = removal.status.clone().unwrap_or(LinkStatus::Shared);
+                let removal = LinkExpression::from(removal);
+                if !links.iter().any(|(_, link, _)| same_link(link, &removal)) {
+                    next_id += 1;
+                    links.push((next_id, removal, status));
+                }
+            }
+        }
+
+        links.retain(|(_, link, _)| {
+            chrono::DateTime::parse_from_rfc3339(&link.timestamp)
+                .map(|timestamp| timestamp <= *at)
+                .unwrap_or(true)
+        });
+
+        // Filtering, ordering and limits are the same as for the live links
+        memory::apply_link_query(links, query)
+    }
+
+    // Pending diffs
+
+    fn add_pending_diff(&self, perspective_uuid: &str, diff: &PerspectiveDiff) -> Ad4mDbResult<()>;
+    /// All pending diffs of the perspective merged into one.
+    fn get_pending_diffs(&self, perspective_uuid: &str) -> Ad4mDbResult<PerspectiveDiff>;
+    fn clear_pending_diffs(&self, perspective_uuid: &str) -> Ad4mDbResult<()>;
+
+    // Perspective history
+
+    /// Appends a diff to the perspective's history and drops the oldest entries beyond `max_entries`.
+    fn add_history_entry(
+        &self,
+        perspective_uuid: &str,
+        diff: &DecoratedPerspectiveDiff,
+        origin: &DiffOrigin,
+        max_entries: u32,
+    ) -> Ad4mDbResult<i64>;
+    /// Returns the most recent local change that has not been undone yet.
+    fn get_last_undoable_history_entry(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<PerspectiveHistoryEntry>>;
+    /// Returns the undone change that should be redone next, i.e. the one that got undone last.
+    fn get_next_redoable_history_entry(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<PerspectiveHistoryEntry>>;
+    fn set_history_entry_undone(&self, id: i64, undone: bool) -> Ad4mDbResult<()>;
+    /// Makes all undone entries non-redoable, which is what happens to the redo stack
+    /// once a new local change comes in. They are kept (instead of deleted)
+    /// since rewinding the perspective to a point in time needs the complete log.
+    fn clear_redo_history(&self, perspective_uuid: &str) -> Ad4mDbResult<()>;
+    /// Timestamp of the newest history entry that got dropped to stay within `max_entries`,
+    /// None if the perspective's history is complete.
+    fn history_truncated_until(
+        &self,
+        perspective_uuid: &str,
+    ) -> Ad4mDbResult<Option<chrono::DateTime<chrono::Utc>>>;
+    /// Returns all history entries applied after the given point in time, newest first.
+    fn get_history_entries_since(
+        &self,
+        perspective_uuid: &str,
+        since: &chrono::DateTime<chrono::Utc>,
+    ) -> Ad4mDbResult<Vec<PerspectiveHistoryEntry>>;
+
+    // Expressions
+
+    /// Stores a fetched expression, replacing what was stored under that URL before.
+    /// Fetch time, size and source language are recorded for the expression cache.
+    fn add_expression(&self, url: &str, expression: &Expression<JsonValue>) -> Ad4mDbResult<()> {
+        self.add_expression_fetched_at(url, expression, &chrono::Utc::now())
+    }
+    /// Like add_expression() for an expression that got fetched earlier, e.g. one restored from an archive.
+    fn add_expression_fetched_at(
+        &self,
+        url: &str,
+        expression: &Expression<JsonValue>,
+        fetched_at: &chrono::DateTime<chrono::Utc>,
+    ) -> Ad4mDbResult<()>;
+    fn get_expression(&self, url: &str) -> Ad4mDbResult<Option<Expression<JsonValue>>>;
+    /// Like get_expression() but with the cache metadata.
+    /// Also marks the expression as used, which keeps it from being evicted.
+    fn get_cached_expression(&self, url: &str) -> Ad4mDbResult<Option<CachedExpression>>;
+    fn remove_expression(&self, url: &str) -> Ad4mDbResult<()>;
+    /// Number of stored expressions and their total size in bytes.
+    fn expression_cache_usage(&self) -> Ad4mDbResult<(u64, u64)>;
+    /// Removes the least recently used expressions until the total size is at most `max_size`.
+    /// Returns how many expressions got removed.
+    fn evict_expressions(&self, max_size: u64) -> Ad4mDbResult<u64>;
+}
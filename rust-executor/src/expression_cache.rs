@@ -0,0 +1,181 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use serde_json::Value as JsonValue;
+use crate::db::{Ad4mDbResult, Ad4mStorage};
+use crate::graphql::graphql_types::ExpressionCacheStats;
+use crate::types::Expression;
+
+pub const DEFAULT_TTL_SECONDS: u64 = 60 * 60;
+pub const DEFAULT_MAX_SIZE: u64 = 100 * 1024 * 1024;
+/// Upper bound for how long mutable expressions are served from the cache.
+pub const MUTABLE_TTL_SECONDS: i64 = 60;
+
+lazy_static! {
+    static ref EXPRESSION_CACHE: RwLock<Arc<ExpressionCache>> =
+        RwLock::new(Arc::new(ExpressionCache::new(DEFAULT_TTL_SECONDS, DEFAULT_MAX_SIZE)));
+}
+
+/// Keeps expressions fetched from languages in Ad4mDb.
+///
+/// Entries older than the TTL count as missing, so they get fetched again.
+/// Expressions that their language doesn't declare immutable only stay fresh
+/// for `MUTABLE_TTL_SECONDS`, so changes to them show up quickly.
+/// Once the cache grows beyond its max size the least recently used
+/// expressions are evicted.
+pub struct ExpressionCache {
+    ttl: chrono::Duration,
+    mutable_ttl: chrono::Duration,
+    max_size: u64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl ExpressionCache {
+    pub fn new(ttl_seconds: u64, max_size: u64) -> Self {
+        let ttl = chrono::Duration::seconds(ttl_seconds.min(i64::MAX as u64 / 1000) as i64);
+        ExpressionCache {
+            ttl,
+            mutable_ttl: ttl.min(chrono::Duration::seconds(MUTABLE_TTL_SECONDS)),
+            max_size,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub fn init_global_instance(ttl_seconds: u64, max_size: u64) {
+        let mut cache = EXPRESSION_CACHE.write().expect("Couldn't get write lock on ExpressionCache");
+        *cache = Arc::new(ExpressionCache::new(ttl_seconds, max_size));
+    }
+
+    pub fn global_instance() -> Arc<ExpressionCache> {
+        EXPRESSION_CACHE.read().expect("Couldn't get lock on ExpressionCache").clone()
+    }
+
+    /// Returns the cached expression, unless there is none or it has expired.
+    pub fn get(&self, db: &dyn Ad4mStorage, url: &str, immutable: bool) -> Ad4mDbResult<Option<Expression<JsonValue>>> {
+        let ttl = if immutable { self.ttl } else { self.mutable_ttl };
+        let entry = db
+            .get_cached_expression(url)?
+            .filter(|entry| chrono::Utc::now() - entry.fetched_at <= ttl);
+
+        match entry {
+            Some(entry) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Ok(Some(entry.expression))
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Stores a freshly fetched expression and evicts what doesn't fit anymore.
+    pub fn put(&self, db: &dyn Ad4mStorage, url: &str, expression: &Expression<JsonValue>) -> Ad4mDbResult<()> {
+        db.add_expression(url, expression)?;
+        let evicted = db.evict_expressions(self.max_size)?;
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn invalidate(&self, db: &dyn Ad4mStorage, url: &str) -> Ad4mDbResult<()> {
+        db.remove_expression(url)
+    }
+
+    pub fn stats(&self, db: &dyn Ad4mStorage) -> Ad4mDbResult<ExpressionCacheStats> {
+        fn saturating_i32(value: u64) -> i32 {
+            i32::try_from(value).unwrap_or(i32::MAX)
+        }
+
+        let (entries, total_size) = db.expression_cache_usage()?;
+        Ok(ExpressionCacheStats {
+            entries: saturating_i32(entries),
+            total_size: saturating_i32(total_size),
+            max_size: saturating_i32(self.max_size),
+            ttl_seconds: saturating_i32(self.ttl.num_seconds() as u64),
+            hits: saturating_i32(self.hits.load(Ordering::Relaxed)),
+            misses: saturating_i32(self.misses.load(Ordering::Relaxed)),
+            evictions: saturating_i32(self.evictions.load(Ordering::Relaxed)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MemoryStorage;
+    use crate::types::ExpressionProof;
+
+    fn expression(data: &str) -> Expression<JsonValue> {
+        Expression {
+            author: "did:test:key".to_string(),
+            timestamp: "2023-01-01T00:00:00Z".to_string(),
+            data: JsonValue::String(data.to_string()),
+            proof: ExpressionProof {
+                key: "key".to_string(),
+                signature: "signature".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let db = MemoryStorage::new();
+        let cache = ExpressionCache::new(60, 1024);
+
+        assert!(cache.get(&db, "lang://a", true).unwrap().is_none());
+        cache.put(&db, "lang://a", &expression("a")).unwrap();
+        assert_eq!(cache.get(&db, "lang://a", true).unwrap().unwrap().data, JsonValue::String("a".to_string()));
+
+        let stats = cache.stats(&db).unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+        assert!(stats.total_size > 0);
+
+        cache.invalidate(&db, "lang://a").unwrap();
+        assert!(cache.get(&db, "lang://a", true).unwrap().is_none());
+    }
+
+    #[test]
+    fn expired_entries_are_misses() {
+        let db = MemoryStorage::new();
+        let cache = ExpressionCache::new(0, 1024);
+        cache.put(&db, "lang://a", &expression("a")).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        assert!(cache.get(&db, "lang://a", true).unwrap().is_none());
+        // Expired entries stay around until they get refetched or evicted
+        assert_eq!(db.get_cached_expression("lang://a").unwrap().unwrap().language, Some("lang".to_string()));
+    }
+
+    #[test]
+    fn mutable_expressions_expire_sooner() {
+        let db = MemoryStorage::new();
+        let mut cache = ExpressionCache::new(60, 1024);
+        cache.mutable_ttl = chrono::Duration::zero();
+        cache.put(&db, "lang://a", &expression("a")).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+
+        assert!(cache.get(&db, "lang://a", true).unwrap().is_some());
+        assert!(cache.get(&db, "lang://a", false).unwrap().is_none());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let db = MemoryStorage::new();
+        let size = serde_json::to_string(&expression("a")).unwrap().len() as u64;
+        let cache = ExpressionCache::new(60, size * 2);
+
+        cache.put(&db, "lang://a", &expression("a")).unwrap();
+        cache.put(&db, "lang://b", &expression("b")).unwrap();
+        // Using `a` makes `b` the least recently used one
+        cache.get(&db, "lang://a", true).unwrap();
+        cache.put(&db, "lang://c", &expression("c")).unwrap();
+
+        assert!(db.get_expression("lang://a").unwrap().is_some());
+        assert!(db.get_expression("lang://b").unwrap().is_none());
+        assert!(db.get_expression("lang://c").unwrap().is_some());
+        assert_eq!(cache.stats(&db).unwrap().evictions, 1);
+    }
+}
@@ -51,6 +51,55 @@ pub struct Apps {
     pub token: String,
 }
 
+#[derive(GraphQLObject, Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AppTokenInfo {
+    pub id: String,
+    pub kind: String,
+    pub issued_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+impl From<crate::agent::capabilities::apps_map::AppToken> for AppTokenInfo {
+    fn from(token: crate::agent::capabilities::apps_map::AppToken) -> Self {
+        let kind = match token.kind {
+            crate::agent::capabilities::apps_map::TokenKind::Access => "access",
+            crate::agent::capabilities::apps_map::TokenKind::Refresh => "refresh",
+        };
+        AppTokenInfo {
+            id: token.nonce,
+            kind: kind.to_string(),
+            issued_at: unix_to_datetime(token.issued_at),
+            expires_at: unix_to_datetime(token.expires_at),
+        }
+    }
+}
+
+#[derive(GraphQLObject, Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthTokens {
+    pub access_token: String,
+    pub refresh_token: String,
+    /// When the access token expires and has to be refreshed
+    pub expires_at: DateTime,
+}
+
+impl AuthTokens {
+    pub fn new(access_token: String, refresh_token: String, expires_at: u64) -> Self {
+        AuthTokens {
+            access_token,
+            refresh_token,
+            expires_at: unix_to_datetime(expires_at),
+        }
+    }
+}
+
+fn unix_to_datetime(timestamp: u64) -> DateTime {
+    chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .into()
+}
+
 #[derive(GraphQLInputObject, Default, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthInfoInput {
@@ -287,6 +336,8 @@ pub struct LinkInput {
 #[derive(GraphQLInputObject, Default, Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LinkQuery {
+    /// Cursor of a LinkConnection edge, the query continues right after that link.
+    pub after: Option<String>,
     pub from_date: Option<DateTime>,
     pub limit: Option<i32>,
     pub predicate: Option<String>,
@@ -295,6 +346,83 @@ pub struct LinkQuery {
     pub until_date: Option<DateTime>,
 }
 
+/// One page of a link query, in the shape of a Relay connection.
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkConnection {
+    pub edges: Vec<LinkEdge>,
+    pub page_info: PageInfo,
+}
+
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkEdge {
+    pub cursor: String,
+    pub node: DecoratedLinkExpression,
+}
+
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    /// Pass as `after` in the next query to get the following page.
+    pub end_cursor: Option<String>,
+    pub has_next_page: bool,
+}
+
+/// Result of a Prolog query with its solutions as typed terms.
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrologQueryResult {
+    /// False if the query has no solution.
+    pub success: bool,
+    /// One entry per solution. A query without variables that succeeds
+    /// has a single solution without bindings.
+    pub solutions: Vec<PrologSolution>,
+}
+
+/// The variable bindings of one solution.
+///
+/// Since GraphQL can't select trees of arbitrary depth, the terms of a solution
+/// are returned as a flat list and refer to their arguments by index.
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrologSolution {
+    pub bindings: Vec<PrologBinding>,
+    pub terms: Vec<PrologTerm>,
+}
+
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrologBinding {
+    pub variable: String,
+    /// Index of the bound term in the solution's terms.
+    pub term: i32,
+}
+
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PrologTerm {
+    pub kind: PrologTermKind,
+    /// Number as text, the atom's name, the string or the functor of a structure.
+    /// Not set for lists and variables.
+    pub value: Option<String>,
+    /// Indices of list elements or structure arguments in the solution's terms.
+    pub args: Vec<i32>,
+}
+
+#[derive(GraphQLEnum, Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub enum PrologTermKind {
+    Integer,
+    Float,
+    Rational,
+    #[default]
+    Atom,
+    String,
+    List,
+    Structure,
+    Variable,
+}
+
 #[derive(GraphQLInputObject, Default, Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LinkMutations {
@@ -440,6 +568,8 @@ pub enum PerspectiveState {
     LinkLanguageFailedToInstall,
     LinkLanguageInstalledButNotSynced,
     Synced,
+    NotPermitted,
+    Archived,
 }
 
 #[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone)]
@@ -515,11 +645,41 @@ pub struct RuntimeInfo {
     pub is_unlocked: bool,
 }
 
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpressionCacheStats {
+    pub entries: i32,
+    /// Bytes
+    pub total_size: i32,
+    /// Bytes
+    pub max_size: i32,
+    pub ttl_seconds: i32,
+    pub hits: i32,
+    pub misses: i32,
+    pub evictions: i32,
+}
+
 #[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SentMessage {
+    /// Signature of the message, also how the recipient refers to it
+    pub id: String,
     pub message: PerspectiveExpression,
     pub recipient: String,
+    pub reply_to: Option<String>,
+    /// SENT, DELIVERED_P2P, QUEUED_INBOX, FETCHED or READ
+    pub status: String,
+    pub status_updated_at: String,
+}
+
+#[derive(GraphQLObject, Default, Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceivedMessage {
+    pub id: String,
+    pub message: PerspectiveExpression,
+    pub reply_to: Option<String>,
+    /// DELIVERED_P2P, FETCHED or READ
+    pub status: String,
 }
 
 #[derive(Default, Debug, Deserialize, Serialize)]
@@ -763,3 +923,19 @@ impl GetFilter for PerspectiveExpression {
         None
     }
 }
+
+//Implement the trait for `ReceivedMessage`
+impl GetValue for ReceivedMessage {
+    type Value = ReceivedMessage;
+
+    fn get_value(&self) -> Self::Value {
+        self.clone()
+    }
+}
+
+//Implement the trait for `ReceivedMessage`
+impl GetFilter for ReceivedMessage {
+    fn get_filter(&self) -> Option<String> {
+        None
+    }
+}
@@ -134,3 +134,104 @@ pub async fn start_server(js_core_handle: JsCoreHandle, config: Ad4mConfig) -> R
     warp::serve(routes).run(([127, 0, 0, 1], port)).await;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::capabilities::{Capability, Resource};
+
+    const LINK: &str = r#"{source: "ad4m://self", target: "ad4m://target"}"#;
+    const LINK_EXPRESSION: &str = r#"{author: "did:test", timestamp: "2024-01-01T00:00:00.000Z", data: {source: "ad4m://self", target: "ad4m://target"}, proof: {signature: "", key: ""}}"#;
+
+    /// A token that may do anything with neighbourhoods and with one perspective, but not with others
+    fn scoped_context() -> RequestContext {
+        let capability = |domain: &str, pointers: Vec<String>| Capability {
+            with: Resource {
+                domain: domain.to_string(),
+                pointers,
+            },
+            can: vec!["*".to_string()],
+        };
+        RequestContext {
+            capabilities: Ok(vec![
+                capability("perspective", vec!["granted-perspective".to_string()]),
+                capability("neighbourhood", vec!["*".to_string()]),
+            ]),
+            js_handle: JsCoreHandle::disconnected(),
+            auto_permit_cap_requests: false,
+        }
+    }
+
+    async fn assert_denied(operation: String) {
+        let (_, errors) = coasys_juniper::execute(
+            &operation,
+            None,
+            &schema(),
+            &coasys_juniper::Variables::new(),
+            &scoped_context(),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("{} is not valid: {}", operation, e));
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.error().message().contains("Capability is not matched")),
+            "{} was not denied: {:?}",
+            operation,
+            errors
+        );
+    }
+
+    #[tokio::test]
+    async fn perspective_queries_require_a_capability_for_the_perspective() {
+        let uuid = r#""other-perspective""#;
+        for field in [
+            format!("neighbourhoodHasTelepresenceAdapter(perspectiveUUID: {uuid})"),
+            format!("neighbourhoodOnlineAgents(perspectiveUUID: {uuid}) {{ did }}"),
+            format!("neighbourhoodOtherAgents(perspectiveUUID: {uuid})"),
+            format!("perspective(uuid: {uuid}) {{ uuid }}"),
+            format!(r#"perspectiveExportRdf(uuid: {uuid}, format: "turtle")"#),
+            format!("perspectiveQueryLinks(uuid: {uuid}, query: {{}}) {{ author }}"),
+            format!("perspectiveQueryLinksConnection(uuid: {uuid}, query: {{}}) {{ edges {{ cursor }} }}"),
+            format!(r#"perspectiveSearch(uuid: {uuid}, text: "text") {{ author }}"#),
+            format!(r#"perspectiveQueryProlog(uuid: {uuid}, query: "true.")"#),
+            format!(r#"perspectiveQueryPrologResult(uuid: {uuid}, query: "true.") {{ success }}"#),
+            format!("perspectiveSnapshot(uuid: {uuid}) {{ links {{ author }} }}"),
+        ] {
+            assert_denied(format!("query {{ {} }}", field)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn perspective_mutations_require_a_capability_for_the_perspective() {
+        let uuid = r#""other-perspective""#;
+        for field in [
+            format!("neighbourhoodLeave(uuid: {uuid}, keepLocal: true)"),
+            format!(r#"neighbourhoodPublishFromPerspective(perspectiveUUID: {uuid}, linkLanguage: "lang", meta: {{links: []}})"#),
+            format!("neighbourhoodSendBroadcast(perspectiveUUID: {uuid}, payload: {{links: []}})"),
+            format!("neighbourhoodSendBroadcastU(perspectiveUUID: {uuid}, payload: {{links: []}})"),
+            format!(r#"neighbourhoodSendSignal(perspectiveUUID: {uuid}, remoteAgentDid: "did:test", payload: {{links: []}})"#),
+            format!(r#"neighbourhoodSendSignalU(perspectiveUUID: {uuid}, remoteAgentDid: "did:test", payload: {{links: []}})"#),
+            format!("neighbourhoodSetOnlineStatus(perspectiveUUID: {uuid}, status: {{links: []}})"),
+            format!("neighbourhoodSetOnlineStatusU(perspectiveUUID: {uuid}, status: {{links: []}})"),
+            format!("perspectiveAddLink(uuid: {uuid}, link: {LINK}) {{ author }}"),
+            format!("perspectiveAddLinkExpression(uuid: {uuid}, link: {LINK_EXPRESSION}) {{ author }}"),
+            format!("perspectiveAddLinks(uuid: {uuid}, links: [{LINK}]) {{ author }}"),
+            format!(r#"perspectiveAddSdna(uuid: {uuid}, name: "Todo", sdnaCode: "", sdnaType: "subject_class")"#),
+            format!("perspectiveCancelPrologQuery(uuid: {uuid})"),
+            format!("perspectiveExport(uuid: {uuid})"),
+            format!(r#"perspectiveImportRdf(uuid: {uuid}, data: "", format: "turtle") {{ additions {{ author }} }}"#),
+            format!("perspectiveLinkMutations(uuid: {uuid}, mutations: {{additions: [{LINK}], removals: []}}) {{ additions {{ author }} }}"),
+            format!("perspectivePublishSnapshot(uuid: {uuid})"),
+            format!("perspectiveRedo(uuid: {uuid}) {{ additions {{ author }} }}"),
+            format!("perspectiveRemove(uuid: {uuid})"),
+            format!("perspectiveRemoveLink(uuid: {uuid}, link: {LINK_EXPRESSION})"),
+            format!("perspectiveRemoveLinks(uuid: {uuid}, links: [{LINK_EXPRESSION}]) {{ author }}"),
+            format!("perspectiveUndo(uuid: {uuid}) {{ additions {{ author }} }}"),
+            format!(r#"perspectiveUpdate(uuid: {uuid}, name: "renamed") {{ uuid }}"#),
+            format!("perspectiveUpdateLink(uuid: {uuid}, oldLink: {LINK_EXPRESSION}, newLink: {LINK}) {{ author }}"),
+        ] {
+            assert_denied(format!("mutation {{ {} }}", field)).await;
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{agent::create_signed_expression, neighbourhoods::{self, install_neighbourhood}, perspectives::{add_perspective, get_perspective, perspective_instance::{PerspectiveInstance, SdnaType}, remove_perspective, update_perspective}, types::{DecoratedLinkExpression, Link, LinkExpression}};
+use crate::{agent::create_signed_expression, neighbourhoods::{self, install_neighbourhood}, perspectives::{add_perspective, archive::{export_perspective, import_perspective, PerspectiveArchive}, get_perspective, perspective_instance::{PerspectiveInstance, SdnaType}, rdf::RdfFormat, remove_perspective, update_perspective}, types::{DecoratedLinkExpression, Link, LinkExpression}};
 use coasys_juniper::{graphql_object, graphql_value, FieldResult, FieldError};
 
 use super::graphql_types::*;
@@ -183,6 +183,17 @@ impl Mutation {
         Ok(random_number_challenge)
     }
 
+    async fn agent_generate_auth_tokens(
+        &self,
+        context: &RequestContext,
+        rand: String,
+        request_id: String,
+    ) -> FieldResult<AuthTokens> {
+        check_capability(&context.capabilities, &AGENT_AUTH_CAPABILITY)?;
+        let tokens = agent::capabilities::generate_auth_tokens(request_id, rand).await?;
+        Ok(tokens)
+    }
+
     async fn agent_generate_jwt(
         &self,
         context: &RequestContext,
@@ -194,6 +205,27 @@ impl Mutation {
         Ok(cap_token)
     }
 
+    async fn agent_refresh_jwt(
+        &self,
+        _context: &RequestContext,
+        refresh_token: String,
+    ) -> FieldResult<AuthTokens> {
+        // No capability check: the access token of the caller has most likely expired already,
+        // the refresh token itself is the credential here.
+        let tokens = agent::capabilities::refresh_capability_token(refresh_token)?;
+        Ok(tokens)
+    }
+
+    async fn agent_rotate_app_tokens(
+        &self,
+        context: &RequestContext,
+        request_id: String,
+    ) -> FieldResult<AuthTokens> {
+        check_capability(&context.capabilities, &AGENT_UPDATE_CAPABILITY)?;
+        let tokens = agent::capabilities::rotate_capability_tokens(request_id).await?;
+        Ok(tokens)
+    }
+
     async fn agent_revoke_token(
         &self,
         context: &RequestContext,
@@ -430,6 +462,27 @@ impl Mutation {
         Ok(install_neighbourhood(url).await?)
     }
 
+    async fn neighbourhood_leave(
+        &self,
+        context: &RequestContext,
+        uuid: String,
+        keep_local: bool,
+    ) -> FieldResult<bool> {
+        if keep_local {
+            check_capability(
+                &context.capabilities,
+                &perspective_update_capability(vec![uuid.clone()]),
+            )?;
+        } else {
+            check_capability(
+                &context.capabilities,
+                &perspective_delete_capability(vec![uuid.clone()]),
+            )?;
+        }
+        neighbourhoods::neighbourhood_leave(&uuid, keep_local).await?;
+        Ok(true)
+    }
+
     async fn neighbourhood_publish_from_perspective(
         &self,
         context: &RequestContext,
@@ -439,6 +492,10 @@ impl Mutation {
         perspectiveUUID: String,
     ) -> FieldResult<String> {
         check_capability(&context.capabilities, &NEIGHBOURHOOD_CREATE_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![perspectiveUUID.clone()]),
+        )?;
         let url = neighbourhoods::neighbourhood_publish_from_perspective(
             &perspectiveUUID,
             link_language,
@@ -457,6 +514,10 @@ impl Mutation {
     ) -> FieldResult<bool> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_UPDATE_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
         let perspective = Perspective::from(payload);
         let perspective = create_signed_expression(perspective)?;
         get_perspective(&uuid)
@@ -476,6 +537,10 @@ impl Mutation {
     ) -> FieldResult<bool> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_UPDATE_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
         let perspective = Perspective {
             links: payload.links
             .into_iter()
@@ -505,6 +570,10 @@ impl Mutation {
     ) -> FieldResult<bool> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_UPDATE_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
         let perspective = Perspective::from(payload);
         let perspective = create_signed_expression(perspective)?;
         get_perspective(&uuid)
@@ -525,6 +594,10 @@ impl Mutation {
     ) -> FieldResult<bool> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_UPDATE_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
         let perspective = Perspective {
             links: payload.links
             .into_iter()
@@ -553,6 +626,10 @@ impl Mutation {
     ) -> FieldResult<bool> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_UPDATE_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
         let perspective = Perspective::from(status);
         let perspective = create_signed_expression(perspective)?;
         get_perspective(&uuid)
@@ -572,6 +649,10 @@ impl Mutation {
     ) -> FieldResult<bool> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_UPDATE_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
         let perspective = Perspective {
             links: status.links
             .into_iter()
@@ -655,6 +736,59 @@ impl Mutation {
         ).await?)
     }
 
+    async fn perspective_cancel_prolog_query(
+        &self,
+        context: &RequestContext,
+        uuid: String,
+    ) -> FieldResult<bool> {
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
+        let perspective = get_perspective_with_uuid_field_error(&uuid)?;
+        Ok(perspective.cancel_prolog_query())
+    }
+
+    async fn perspective_export(
+        &self,
+        context: &RequestContext,
+        uuid: String,
+    ) -> FieldResult<String> {
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
+        let archive = export_perspective(&uuid).await?;
+        Ok(serde_json::to_string(&archive)?)
+    }
+
+    async fn perspective_import(
+        &self,
+        context: &RequestContext,
+        archive: String,
+    ) -> FieldResult<PerspectiveHandle> {
+        check_capability(&context.capabilities, &PERSPECTIVE_CREATE_CAPABILITY)?;
+        let archive: PerspectiveArchive = serde_json::from_str(&archive)?;
+        Ok(import_perspective(archive).await?)
+    }
+
+    async fn perspective_import_rdf(
+        &self,
+        context: &RequestContext,
+        uuid: String,
+        data: String,
+        format: String,
+        status: Option<String>,
+    ) -> FieldResult<DecoratedPerspectiveDiff> {
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
+        let mut perspective = get_perspective_with_uuid_field_error(&uuid)?;
+        let format = RdfFormat::try_from(format.as_str())?;
+        Ok(perspective.import_rdf(&data, format, link_status_from_input(status)?).await?)
+    }
+
     async fn perspective_link_mutations(
         &self,
         context: &RequestContext,
@@ -694,6 +828,19 @@ impl Mutation {
         Ok(remove_perspective(&uuid).await.is_some())
     }
 
+    async fn perspective_redo(
+        &self,
+        context: &RequestContext,
+        uuid: String,
+    ) -> FieldResult<Option<DecoratedPerspectiveDiff>> {
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
+        let mut perspective = get_perspective_with_uuid_field_error(&uuid)?;
+        Ok(perspective.redo().await?)
+    }
+
     async fn perspective_remove_link(
         &self,
         context: &RequestContext,
@@ -730,6 +877,19 @@ impl Mutation {
         Ok(removed_links)
     }
 
+    async fn perspective_undo(
+        &self,
+        context: &RequestContext,
+        uuid: String,
+    ) -> FieldResult<Option<DecoratedPerspectiveDiff>> {
+        check_capability(
+            &context.capabilities,
+            &perspective_update_capability(vec![uuid.clone()]),
+        )?;
+        let mut perspective = get_perspective_with_uuid_field_error(&uuid)?;
+        Ok(perspective.undo().await?)
+    }
+
     async fn perspective_update(
         &self,
         context: &RequestContext,
@@ -836,18 +996,20 @@ impl Mutation {
         context: &RequestContext,
         did: String,
         message: PerspectiveInput,
+        reply_to: Option<String>,
     ) -> FieldResult<bool> {
         check_capability(&context.capabilities, &RUNTIME_MESSAGES_CREATE_CAPABILITY)?;
         let mut js = context.js_handle.clone();
         let message_json = serde_json::to_string(&message)?;
+        let reply_to_json = serde_json::to_string(&reply_to)?;
         let script = format!(
             r#"JSON.stringify(
             await core.callResolver(
                 "Mutation",
                 "runtimeFriendSendMessage",
-                {{ did: "{}", message: {} }},
+                {{ did: "{}", message: {}, replyTo: {} }},
             ))"#,
-            did, message_json,
+            did, message_json, reply_to_json,
         );
         let result = js.execute(script).await?;
         let result: JsResultType<bool> = serde_json::from_str(&result)?;
@@ -879,6 +1041,29 @@ impl Mutation {
         Ok(true)
     }
 
+    async fn runtime_mark_messages_read(
+        &self,
+        context: &RequestContext,
+        ids: Vec<String>,
+    ) -> FieldResult<bool> {
+        // Sends read receipts, so it needs more than reading messages
+        check_capability(&context.capabilities, &RUNTIME_MESSAGES_CREATE_CAPABILITY)?;
+        let mut js = context.js_handle.clone();
+        let ids_json = serde_json::to_string(&ids)?;
+        let script = format!(
+            r#"JSON.stringify(
+            await core.callResolver(
+                "Mutation",
+                "runtimeMarkMessagesRead",
+                {{ ids: {} }},
+            ))"#,
+            ids_json,
+        );
+        let result = js.execute(script).await?;
+        let result: JsResultType<bool> = serde_json::from_str(&result)?;
+        result.get_graphql_result()
+    }
+
     async fn runtime_open_link(&self, context: &RequestContext, url: String) -> FieldResult<bool> {
         let mut js = context.js_handle.clone();
         let script = format!(
@@ -1,14 +1,46 @@
 #![allow(non_snake_case)]
-use coasys_juniper::{graphql_object, FieldError, FieldResult};
+use coasys_juniper::{graphql_object, graphql_value, FieldError, FieldResult};
+use deno_core::error::AnyError;
 
 
-use crate::{holochain_service::get_holochain_service, perspectives::{all_perspectives, get_perspective}, types::{DecoratedLinkExpression }};
+use crate::{db::Ad4mDb, expression_cache::ExpressionCache, holochain_service::get_holochain_service, perspectives::{all_perspectives, get_perspective, rdf::RdfFormat}, prolog_service::limits::{PrologQueryError, PrologQueryLimits}, types::{DecoratedLinkExpression }};
 
 use super::graphql_types::*;
 use crate::agent::{capabilities::*, signatures};
 
 pub struct Query;
 
+/// The executor's default limits with what the query set itself.
+fn prolog_query_limits(timeout_ms: Option<i32>, inference_limit: Option<i32>) -> PrologQueryLimits {
+    PrologQueryLimits::defaults().with_overrides(
+        timeout_ms.map(|ms| std::time::Duration::from_millis(ms.max(0) as u64)),
+        inference_limit.map(|limit| limit.max(0) as u64),
+    )
+}
+
+/// Prolog errors carry their code and limits as extensions,
+/// so clients can tell a timeout from a broken query.
+fn prolog_query_field_error(error: AnyError) -> FieldError {
+    match error.downcast_ref::<PrologQueryError>() {
+        Some(prolog_error) => {
+            let code = prolog_error.code();
+            let extensions = match prolog_error {
+                PrologQueryError::Timeout(timeout) => {
+                    let timeout_ms = timeout.as_millis() as i32;
+                    graphql_value!({ "code": code, "timeoutMs": timeout_ms })
+                }
+                PrologQueryError::InferenceLimitExceeded(limit) => {
+                    let inference_limit = *limit as i32;
+                    graphql_value!({ "code": code, "inferenceLimit": inference_limit })
+                }
+                _ => graphql_value!({ "code": code }),
+            };
+            FieldError::new(prolog_error.to_string(), extensions)
+        }
+        None => FieldError::from(error),
+    }
+}
+
 #[graphql_object(context = RequestContext)]
 impl Query {
     async fn agent(&self, context: &RequestContext) -> FieldResult<Agent> {
@@ -53,6 +85,15 @@ impl Query {
         Ok(apps_map::get_apps())
     }
 
+    async fn agent_get_app_tokens(
+        &self,
+        context: &RequestContext,
+        request_id: String,
+    ) -> FieldResult<Vec<AppTokenInfo>> {
+        check_capability(&context.capabilities, &AGENT_READ_CAPABILITY)?;
+        Ok(apps_map::get_app_tokens(&request_id)?)
+    }
+
     async fn agent_get_entanglement_proofs(
         &self,
         context: &RequestContext,
@@ -94,13 +135,15 @@ impl Query {
         &self,
         context: &RequestContext,
         url: String,
+        refresh: Option<bool>,
     ) -> FieldResult<Option<ExpressionRendered>> {
         check_capability(&context.capabilities, &EXPRESSION_READ_CAPABILITY)?;
         let mut js = context.js_handle.clone();
         let result = js
             .execute(format!(
-                r#"JSON.stringify(await core.callResolver("Query", "expression", {{ url: "{}" }}))"#,
-                url
+                r#"JSON.stringify(await core.callResolver("Query", "expression", {{ url: "{}", refresh: {} }}))"#,
+                url,
+                refresh.unwrap_or(false)
             ))
             .await?;
         let result: JsResultType<Option<ExpressionRendered>> = serde_json::from_str(&result)?;
@@ -255,6 +298,10 @@ impl Query {
     ) -> FieldResult<bool> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_READ_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
         Ok(get_perspective(&uuid)
             .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?
             .has_telepresence_adapter() 
@@ -269,6 +316,10 @@ impl Query {
     ) -> FieldResult<Vec<OnlineAgent>> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_READ_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
         get_perspective(&uuid)
             .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?
             .online_agents() 
@@ -284,6 +335,10 @@ impl Query {
     ) -> FieldResult<Vec<String>> {
         let uuid = perspectiveUUID;
         check_capability(&context.capabilities, &NEIGHBOURHOOD_READ_CAPABILITY)?;
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
         get_perspective(&uuid)
             .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?
             .others() 
@@ -308,11 +363,66 @@ impl Query {
         }
     }
 
+    async fn perspective_export_rdf(
+        &self,
+        context: &RequestContext,
+        uuid: String,
+        format: String,
+    ) -> FieldResult<String> {
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
+        let perspective = get_perspective(&uuid)
+            .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?;
+        Ok(perspective.export_rdf(RdfFormat::try_from(format.as_str())?).await?)
+    }
+
     async fn perspective_query_links(
         &self,
         context: &RequestContext,
         query: LinkQuery,
         uuid: String,
+        at: Option<DateTime>,
+    ) -> FieldResult<Vec<DecoratedLinkExpression>> {
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
+
+        let perspective = get_perspective(&uuid)
+            .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?;
+
+        Ok(match at {
+            Some(at) => perspective.get_links_at(&query, at.into()).await?,
+            None => perspective.get_links(&query).await?,
+        })
+    }
+
+    async fn perspective_query_links_connection(
+        &self,
+        context: &RequestContext,
+        query: LinkQuery,
+        uuid: String,
+        at: Option<DateTime>,
+    ) -> FieldResult<LinkConnection> {
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
+
+        let perspective = get_perspective(&uuid)
+            .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?;
+
+        Ok(perspective.get_links_page(&query, at.map(|at| at.into())).await?)
+    }
+
+    async fn perspective_search(
+        &self,
+        context: &RequestContext,
+        uuid: String,
+        text: String,
+        predicate: Option<String>,
     ) -> FieldResult<Vec<DecoratedLinkExpression>> {
         check_capability(
             &context.capabilities,
@@ -321,7 +431,7 @@ impl Query {
 
         Ok(get_perspective(&uuid)
             .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?
-            .get_links(&query)
+            .search_links(&text, predicate)
             .await?)
     }
 
@@ -330,32 +440,62 @@ impl Query {
         context: &RequestContext,
         query: String,
         uuid: String,
+        timeout_ms: Option<i32>,
+        inference_limit: Option<i32>,
     ) -> FieldResult<String> {
         check_capability(
             &context.capabilities,
             &perspective_query_capability(vec![uuid.clone()]),
         )?;
 
-        Ok(get_perspective(&uuid)
+        let limits = prolog_query_limits(timeout_ms, inference_limit);
+
+        get_perspective(&uuid)
             .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?
-            .prolog_query(query)
-            .await?)
+            .prolog_query_with_limits(query, limits)
+            .await
+            .map_err(prolog_query_field_error)
+    }
+
+    async fn perspective_query_prolog_result(
+        &self,
+        context: &RequestContext,
+        query: String,
+        uuid: String,
+        timeout_ms: Option<i32>,
+        inference_limit: Option<i32>,
+    ) -> FieldResult<PrologQueryResult> {
+        check_capability(
+            &context.capabilities,
+            &perspective_query_capability(vec![uuid.clone()]),
+        )?;
+
+        let limits = prolog_query_limits(timeout_ms, inference_limit);
+
+        get_perspective(&uuid)
+            .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?
+            .prolog_query_result(query, limits)
+            .await
+            .map_err(prolog_query_field_error)
     }
 
     async fn perspective_snapshot(
         &self,
         context: &RequestContext,
         uuid: String,
+        at: Option<DateTime>,
     ) -> FieldResult<Perspective> {
         check_capability(
             &context.capabilities,
             &perspective_query_capability(vec![uuid.clone()]),
         )?;
 
-        let all_links = get_perspective(&uuid)
-            .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?
-            .get_links(&LinkQuery::default())
-            .await?;
+        let perspective = get_perspective(&uuid)
+            .ok_or(FieldError::from(format!("No perspective found with uuid {}", uuid)))?;
+        let all_links = match at {
+            Some(at) => perspective.get_links_at(&LinkQuery::default(), at.into()).await?,
+            None => perspective.get_links(&LinkQuery::default()).await?,
+        };
 
         Ok(Perspective {
             links: all_links,
@@ -363,15 +503,23 @@ impl Query {
     }
 
     async fn perspectives(&self, context: &RequestContext) -> FieldResult<Vec<PerspectiveHandle>> {
-        check_capability(
+        check_capability_for_any_pointer(
             &context.capabilities,
-            &perspective_query_capability(vec!["*".into()]),
+            &perspective_query_capability(vec![]),
         )?;
 
+        // Only list the perspectives the app got access to
         let mut result = Vec::new();
         for p in all_perspectives().iter() {
             let handle = p.persisted.lock().await.clone();
-            result.push(handle);
+            if check_capability(
+                &context.capabilities,
+                &perspective_query_capability(vec![handle.uuid.clone()]),
+            )
+            .is_ok()
+            {
+                result.push(handle);
+            }
         }
         Ok(result)
     }
@@ -425,6 +573,11 @@ impl Query {
         Ok(serde_json::to_string(&encoded_infos)?)
     }
 
+    async fn runtime_expression_cache_stats(&self, context: &RequestContext) -> FieldResult<ExpressionCacheStats> {
+        check_capability(&context.capabilities, &ALL_CAPABILITY)?;
+        Ok(ExpressionCache::global_instance().stats(&Ad4mDb::global_instance())?)
+    }
+
     async fn runtime_info(&self, context: &RequestContext) -> FieldResult<RuntimeInfo> {
         let mut js = context.js_handle.clone();
         let result = js
@@ -471,6 +624,25 @@ impl Query {
         result.get_graphql_result()
     }
 
+    async fn runtime_received_messages(
+        &self,
+        context: &RequestContext,
+        filter: Option<String>,
+    ) -> FieldResult<Vec<ReceivedMessage>> {
+        check_capability(&context.capabilities, &RUNTIME_MESSAGES_READ_CAPABILITY)?;
+        let filter_str = filter
+            .map(|val| format!(r#"{{ filter: "{}" }}"#, val))
+            .unwrap_or_else(|| String::from("{ filter: null }"));
+        let script = format!(
+            r#"JSON.stringify(await core.callResolver("Query", "runtimeReceivedMessages", {}))"#,
+            filter_str,
+        );
+        let mut js = context.js_handle.clone();
+        let result = js.execute(script).await?;
+        let result: JsResultType<Vec<ReceivedMessage>> = serde_json::from_str(&result)?;
+        result.get_graphql_result()
+    }
+
     async fn runtime_message_outbox(
         &self,
         context: &RequestContext,
@@ -5,11 +5,11 @@ use coasys_juniper::FieldResult;
 use std::pin::Pin;
 
 use crate::{pubsub::{
-    get_global_pubsub, subscribe_and_process, AGENT_STATUS_CHANGED_TOPIC, AGENT_UPDATED_TOPIC,
+    get_global_pubsub, subscribe_and_process, subscribe_and_process_with, AGENT_STATUS_CHANGED_TOPIC, AGENT_UPDATED_TOPIC,
     APPS_CHANGED, EXCEPTION_OCCURRED_TOPIC, NEIGHBOURHOOD_SIGNAL_TOPIC, PERSPECTIVE_ADDED_TOPIC,
     PERSPECTIVE_LINK_ADDED_TOPIC, PERSPECTIVE_LINK_REMOVED_TOPIC, PERSPECTIVE_LINK_UPDATED_TOPIC,
     PERSPECTIVE_REMOVED_TOPIC, PERSPECTIVE_SYNC_STATE_CHANGE_TOPIC, PERSPECTIVE_UPDATED_TOPIC,
-    RUNTIME_MESSAGED_RECEIVED_TOPIC,
+    RUNTIME_MESSAGED_RECEIVED_TOPIC, RUNTIME_RECEIVED_MESSAGE_TOPIC,
 }, types::DecoratedLinkExpression};
 
 use super::graphql_types::*;
@@ -83,7 +83,12 @@ impl Subscription {
         context: &RequestContext,
         perspectiveUUID: String,
     ) -> Pin<Box<dyn Stream<Item = FieldResult<PerspectiveExpression>> + Send>> {
-        match check_capability(&context.capabilities, &NEIGHBOURHOOD_READ_CAPABILITY) {
+        match check_capability(&context.capabilities, &NEIGHBOURHOOD_READ_CAPABILITY).and_then(|_| {
+            check_capability(
+                &context.capabilities,
+                &perspective_subscribe_capability(vec![perspectiveUUID.clone()]),
+            )
+        }) {
             Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
             Ok(_) => {
                 let pubsub = get_global_pubsub().await;
@@ -102,12 +107,17 @@ impl Subscription {
         &self,
         context: &RequestContext,
     ) -> Pin<Box<dyn Stream<Item = FieldResult<PerspectiveHandle>> + Send>> {
-        match check_capability(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
+        match check_capability_for_any_pointer(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
             Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
             Ok(_) => {
                 let pubsub = get_global_pubsub().await;
                 let topic = &PERSPECTIVE_ADDED_TOPIC;
-                subscribe_and_process::<PerspectiveHandle>(pubsub, topic.to_string(), None).await
+                // Only pass on perspectives the app got access to
+                let capabilities = context.capabilities.clone();
+                subscribe_and_process_with::<PerspectiveHandle, _>(pubsub, topic.to_string(), move |handle: &PerspectiveHandle| {
+                    check_capability(&capabilities, &perspective_subscribe_capability(vec![handle.uuid.clone()])).is_ok()
+                })
+                .await
             }
         }
     }
@@ -117,7 +127,10 @@ impl Subscription {
         context: &RequestContext,
         uuid: String,
     ) -> Pin<Box<dyn Stream<Item = FieldResult<DecoratedLinkExpression>> + Send>> {
-        match check_capability(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
+        match check_capability(
+            &context.capabilities,
+            &perspective_subscribe_capability(vec![uuid.clone()]),
+        ) {
             Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
             Ok(_) => {
                 let pubsub = get_global_pubsub().await;
@@ -137,7 +150,10 @@ impl Subscription {
         context: &RequestContext,
         uuid: String,
     ) -> Pin<Box<dyn Stream<Item = FieldResult<DecoratedLinkExpression>> + Send>> {
-        match check_capability(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
+        match check_capability(
+            &context.capabilities,
+            &perspective_subscribe_capability(vec![uuid.clone()]),
+        ) {
             Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
             Ok(_) => {
                 let pubsub = get_global_pubsub().await;
@@ -157,7 +173,10 @@ impl Subscription {
         context: &RequestContext,
         uuid: String,
     ) -> Pin<Box<dyn Stream<Item = FieldResult<LinkUpdated>> + Send>> {
-        match check_capability(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
+        match check_capability(
+            &context.capabilities,
+            &perspective_subscribe_capability(vec![uuid.clone()]),
+        ) {
             Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
             Ok(_) => {
                 let pubsub = get_global_pubsub().await;
@@ -176,12 +195,17 @@ impl Subscription {
         &self,
         context: &RequestContext,
     ) -> Pin<Box<dyn Stream<Item = FieldResult<String>> + Send>> {
-        match check_capability(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
+        match check_capability_for_any_pointer(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
             Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
             Ok(_) => {
                 let pubsub = get_global_pubsub().await;
                 let topic = &PERSPECTIVE_REMOVED_TOPIC;
-                subscribe_and_process::<String>(pubsub, topic.to_string(), None).await
+                // Only pass on perspectives the app got access to
+                let capabilities = context.capabilities.clone();
+                subscribe_and_process_with::<String, _>(pubsub, topic.to_string(), move |uuid: &String| {
+                    check_capability(&capabilities, &perspective_subscribe_capability(vec![uuid.clone()])).is_ok()
+                })
+                .await
             }
         }
     }
@@ -191,7 +215,10 @@ impl Subscription {
         context: &RequestContext,
         uuid: String,
     ) -> Pin<Box<dyn Stream<Item = FieldResult<String>> + Send>> {
-        match check_capability(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
+        match check_capability(
+            &context.capabilities,
+            &perspective_subscribe_capability(vec![uuid.clone()]),
+        ) {
             Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
             Ok(_) => {
                 let pubsub = get_global_pubsub().await;
@@ -210,12 +237,17 @@ impl Subscription {
         &self,
         context: &RequestContext,
     ) -> Pin<Box<dyn Stream<Item = FieldResult<PerspectiveHandle>> + Send>> {
-        match check_capability(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
+        match check_capability_for_any_pointer(&context.capabilities, &PERSPECTIVE_SUBSCRIBE_CAPABILITY) {
             Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
             Ok(_) => {
                 let pubsub = get_global_pubsub().await;
                 let topic = &PERSPECTIVE_UPDATED_TOPIC;
-                subscribe_and_process::<PerspectiveHandle>(pubsub, topic.to_string(), None).await
+                // Only pass on perspectives the app got access to
+                let capabilities = context.capabilities.clone();
+                subscribe_and_process_with::<PerspectiveHandle, _>(pubsub, topic.to_string(), move |handle: &PerspectiveHandle| {
+                    check_capability(&capabilities, &perspective_subscribe_capability(vec![handle.uuid.clone()])).is_ok()
+                })
+                .await
             }
         }
     }
@@ -234,4 +266,20 @@ impl Subscription {
             }
         }
     }
+
+    /// Like runtimeMessageReceived, with the message's id, delivery status and the message it replies to
+    async fn runtime_received_message(
+        &self,
+        context: &RequestContext,
+    ) -> Pin<Box<dyn Stream<Item = FieldResult<ReceivedMessage>> + Send>> {
+        match check_capability(&context.capabilities, &RUNTIME_MESSAGES_READ_CAPABILITY) {
+            Err(e) => return Box::pin(stream::once(async move { Err(e.into()) })),
+            Ok(_) => {
+                let pubsub = get_global_pubsub().await;
+                let topic = &RUNTIME_RECEIVED_MESSAGE_TOPIC;
+                subscribe_and_process::<ReceivedMessage>(pubsub, topic.to_string(), None)
+                    .await
+            }
+        }
+    }
 }
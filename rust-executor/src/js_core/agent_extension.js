@@ -35,5 +35,11 @@
         signStringHex: (payload) => {
             return core.ops.agent_sign_string_hex(payload);
         },
+        encryptFor: (did, message) => {
+            return core.ops.agent_encrypt_for(did, message);
+        },
+        decrypt: (encrypted) => {
+            return core.ops.agent_decrypt(encrypted);
+        },
     };
 })(globalThis);
\ No newline at end of file
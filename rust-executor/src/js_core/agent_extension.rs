@@ -1,5 +1,6 @@
 use deno_core::{error::AnyError, include_js_files, op2, Extension, Op};
 use std::borrow::Cow;
+use crate::agent::encryption::{decrypt, encrypt_for_did, EncryptedData};
 use crate::agent::{create_signed_expression, did, did_document, sign, sign_string_hex, signing_key_id};
 
 use super::utils::sort_json_value;
@@ -53,6 +54,18 @@ fn agent_sign_string_hex(#[string] payload: String) -> Result<String, AnyError>
     sign_string_hex(payload)
 }
 
+#[op2]
+#[serde]
+fn agent_encrypt_for(#[string] did: String, #[string] message: String) -> Result<EncryptedData, AnyError> {
+    encrypt_for_did(&did, &message)
+}
+
+#[op2]
+#[string]
+fn agent_decrypt(#[serde] encrypted: EncryptedData) -> Result<String, AnyError> {
+    decrypt(&encrypted)
+}
+
 
 pub fn build() -> Extension {
     Extension {
@@ -66,6 +79,8 @@ pub fn build() -> Extension {
             agent_create_signed_expression_stringified::DECL,
             agent_sign::DECL,
             agent_sign_string_hex::DECL,
+            agent_encrypt_for::DECL,
+            agent_decrypt::DECL,
         ]),
         ..Default::default()
     }
@@ -11,6 +11,15 @@
         telepresenceSignalReceived: (signal, language_address) => {
             return core.ops.telepresence_signal_received(signal, language_address);
         },
+        expressionCacheGet: (url, immutable) => {
+            return core.ops.expression_cache_get(url, immutable);
+        },
+        expressionCachePut: (url, expression) => {
+            return core.ops.expression_cache_put(url, expression);
+        },
+        expressionCacheInvalidate: (url) => {
+            return core.ops.expression_cache_invalidate(url);
+        },
     };
   })(globalThis);
   
\ No newline at end of file
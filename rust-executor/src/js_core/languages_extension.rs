@@ -1,7 +1,7 @@
 use std::borrow::Cow;
-use deno_core::{include_js_files, op2, Extension, Op};
+use deno_core::{error::AnyError, include_js_files, op2, Extension, Op};
 
-use crate::{graphql::graphql_types::{PerspectiveExpression, PerspectiveState}, types::PerspectiveDiff};
+use crate::{db::Ad4mDb, expression_cache::ExpressionCache, graphql::graphql_types::{PerspectiveExpression, PerspectiveState}, types::{Expression, PerspectiveDiff}};
 
 
 #[op2]
@@ -32,6 +32,24 @@ fn telepresence_signal_received(
     crate::perspectives::handle_telepresence_signal_from_link_language(signal, language_address);
 }
 
+#[op2]
+#[serde]
+fn expression_cache_get(#[string] url: String, immutable: bool) -> Result<Option<Expression<serde_json::Value>>, AnyError> {
+    ExpressionCache::global_instance().get(&Ad4mDb::global_instance(), &url, immutable)
+}
+
+#[op2]
+fn expression_cache_put(
+    #[string] url: String,
+    #[serde] expression: Expression<serde_json::Value>,
+) -> Result<(), AnyError> {
+    ExpressionCache::global_instance().put(&Ad4mDb::global_instance(), &url, &expression)
+}
+
+#[op2]
+fn expression_cache_invalidate(#[string] url: String) -> Result<(), AnyError> {
+    ExpressionCache::global_instance().invalidate(&Ad4mDb::global_instance(), &url)
+}
 
 pub fn build() -> Extension {
     Extension {
@@ -41,6 +59,9 @@ pub fn build() -> Extension {
             perspective_diff_received::DECL,
             sync_state_changed::DECL,
             telepresence_signal_received::DECL,
+            expression_cache_get::DECL,
+            expression_cache_put::DECL,
+            expression_cache_invalidate::DECL,
         ]),
         ..Default::default()
     }
@@ -56,6 +56,21 @@ impl Clone for JsCoreHandle {
 }
 
 impl JsCoreHandle {
+    /// A handle without a running JS core behind it, for tests of code that
+    /// only needs to pass one around.
+    #[cfg(test)]
+    pub fn disconnected() -> JsCoreHandle {
+        let (broadcast_tx, rx) = broadcast::channel::<JsCoreResponse>(1);
+        let (tx, _) = mpsc::unbounded_channel::<JsCoreRequest>();
+        let (tx_module_load, _) = mpsc::unbounded_channel::<JsCoreRequest>();
+        JsCoreHandle {
+            rx,
+            tx,
+            tx_module_load,
+            broadcast_tx
+        }
+    }
+
     pub async fn initialized(&mut self) {
         self.rx.recv().await.expect("couldn't receive on channel");
     }
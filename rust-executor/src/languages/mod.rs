@@ -4,7 +4,7 @@ mod byte_array;
 use std::sync::{Arc, Mutex};
 use deno_core::error::AnyError;
 
-use crate::{graphql::graphql_types::{DecoratedNeighbourhoodExpression, Neighbourhood}, js_core::JsCoreHandle};
+use crate::{graphql::graphql_types::{DecoratedNeighbourhoodExpression, LanguageRef, Neighbourhood}, js_core::JsCoreHandle};
 use crate::types::Address;
 use language::Language;
 
@@ -46,6 +46,46 @@ impl LanguageController {
         Ok(())
     }
 
+    /// Tears down the language and removes it from memory while keeping its files.
+    pub async fn unload_language(language: Address) -> Result<(), AnyError> {
+        Self::global_instance().js_core.execute("await core.waitForLanguages()".into()).await?;
+
+        let script = format!(
+            r#"JSON.stringify(
+                core.languageController.languageUnload("{}")
+            )"#,
+            language,
+        );
+        let _result = Self::global_instance().js_core.execute(script).await?;
+        Ok(())
+    }
+
+    /// Templates `language` with `template_data` and publishes the result.
+    /// Languages that are templated already get templated again from their source,
+    /// with `template_data` added to the parameters they were templated with.
+    pub async fn apply_template_and_publish(language: Address, template_data: serde_json::Value) -> Result<Address, AnyError> {
+        Self::global_instance().js_core.execute("await core.waitForLanguages()".into()).await?;
+
+        let script = format!(
+            r#"await (async () => {{
+                const language = "{}"
+                const expression = await core.languageController.getLanguageExpression(language)
+                if(!expression) throw new Error(`Language not found: ${{language}}`)
+                let source = language
+                let templateData = {}
+                if(expression.data.templateSourceLanguageAddress && expression.data.templateAppliedParams) {{
+                    source = expression.data.templateSourceLanguageAddress
+                    templateData = {{ ...JSON.parse(expression.data.templateAppliedParams), ...templateData }}
+                }}
+                return JSON.stringify(await core.languageApplyTemplateAndPublish(source, templateData))
+            }})()"#,
+            language, template_data,
+        );
+        let result: String = Self::global_instance().js_core.execute(script).await?;
+        let language_ref: LanguageRef = serde_json::from_str(&result)?;
+        Ok(language_ref.address)
+    }
+
     pub async fn create_neighbourhood(neighbourhood: Neighbourhood) -> Result<Address, AnyError> {
         Self::global_instance().js_core.execute("await core.waitForLanguages()".into()).await?;
 
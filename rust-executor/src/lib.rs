@@ -16,6 +16,7 @@ mod pubsub;
 mod dapp_server;
 pub mod agent;
 mod db;
+mod expression_cache;
 pub mod types;
 pub mod perspectives;
 pub mod languages;
@@ -33,7 +34,10 @@ use js_core::JsCore;
 pub use config::Ad4mConfig;
 pub use holochain_service::run_local_hc_services;
 
-use crate::{dapp_server::serve_dapp, db::Ad4mDb, prolog_service::init_prolog_service, languages::LanguageController};
+use crate::{dapp_server::serve_dapp, db::Ad4mDb, expression_cache::ExpressionCache, prolog_service::{init_prolog_service, limits::PrologQueryLimits}, languages::LanguageController};
+
+/// Number of SQLite connections perspectives can use concurrently
+const DB_POOL_SIZE: u32 = 8;
 
 /// Runs the GraphQL server and the deno core runtime
 pub async fn run(mut config: Ad4mConfig) -> JoinHandle<()> {
@@ -43,13 +47,20 @@ pub async fn run(mut config: Ad4mConfig) -> JoinHandle<()> {
 
     info!("Initializing Ad4mDb...");
 
-    Ad4mDb::init_global_instance(
-        config.app_data_path
-            .as_ref()
-            .map(|path| std::path::Path::new(path).join("ad4m_db.sqlite").to_string_lossy().into_owned())
-            .expect("App data path not set in Ad4mConfig")
-            .as_str()
-    ).expect("Failed to initialize Ad4mDb");
+    Ad4mDb::set_global_instance(
+        Ad4mDb::new_pooled(
+            config.app_data_path
+                .as_ref()
+                .map(|path| std::path::Path::new(path).join("ad4m_db.sqlite").to_string_lossy().into_owned())
+                .expect("App data path not set in Ad4mConfig")
+                .as_str(),
+            DB_POOL_SIZE,
+        ).expect("Failed to initialize Ad4mDb")
+    );
+    ExpressionCache::init_global_instance(
+        config.expression_cache_ttl_seconds.expect("Expression cache TTL not set in Ad4mConfig"),
+        config.expression_cache_max_size.expect("Expression cache max size not set in Ad4mConfig"),
+    );
 
     agent::capabilities::apps_map::set_data_file_path(
         config.app_data_path
@@ -68,6 +79,10 @@ pub async fn run(mut config: Ad4mConfig) -> JoinHandle<()> {
 
     info!("Initializing Prolog service...");
     init_prolog_service().await;
+    PrologQueryLimits::set_defaults(
+        config.prolog_query_timeout_seconds.expect("Prolog query timeout not set in Ad4mConfig"),
+        config.prolog_inference_limit.expect("Prolog inference limit not set in Ad4mConfig"),
+    );
 
     info!("Starting js_core...");
     let mut js_core_handle = JsCore::start(config.clone()).await;
@@ -1,12 +1,23 @@
 use deno_core::anyhow::anyhow;
 use deno_core::error::AnyError;
+use neighbourhood_membership::MembershipPolicy;
+use serde_json::json;
 use uuid::Uuid;
 
-use crate::graphql::graphql_types::{PerspectiveHandle, PerspectiveState, Neighbourhood, Perspective};
+use crate::agent;
+use crate::graphql::graphql_types::{DecoratedNeighbourhoodExpression, PerspectiveHandle, PerspectiveState, Neighbourhood, Perspective};
 use crate::languages::LanguageController;
-use crate::perspectives::{add_perspective, all_perspectives, get_perspective, update_perspective};
+use crate::perspectives::{add_perspective, all_perspectives, get_perspective, remove_perspective, update_perspective};
 use crate::types::*;
 
+/// Membership policy a neighbourhood by `author` declares in its `meta` perspective.
+pub fn membership_policy(author: &str, meta: &Perspective) -> MembershipPolicy {
+    MembershipPolicy::from_meta(
+        author,
+        meta.links.iter().map(|link| (link.data.predicate.as_deref(), link.data.target.as_str())),
+    )
+}
+
 pub async fn neighbourhood_publish_from_perspective(
     uuid: &str,
     link_language: String,
@@ -14,6 +25,14 @@ pub async fn neighbourhood_publish_from_perspective(
 ) -> Result<String, AnyError> {
     let perspective = get_perspective(uuid).ok_or(anyhow!("Perspective not found"))?;
 
+    // Let the link language enforce the membership policy by templating it into its DNA
+    let policy = membership_policy(&agent::did(), &meta);
+    let link_language = if policy.is_open() {
+        link_language
+    } else {
+        LanguageController::apply_template_and_publish(link_language, json!({ "membership": policy })).await?
+    };
+
     LanguageController::install_language(link_language.clone()).await?;
 
     let neighbourhood = Neighbourhood {
@@ -44,8 +63,11 @@ pub async fn install_neighbourhood(
     let perspectives = all_perspectives();
 
     for p in perspectives.iter() {
-        let handle = p.persisted.lock().await;
+        let handle = p.persisted.lock().await.clone();
         if handle.shared_url == Some(url.clone()) {
+            if handle.state == PerspectiveState::Archived {
+                return rejoin_neighbourhood(handle).await;
+            }
             return Err(anyhow!("Neighbourhood with URL {} already installed", url));
         }
     }
@@ -58,11 +80,7 @@ pub async fn install_neighbourhood(
     log::info!("Core.install_neighbourhood(): Got neighbourhood {:?}", neighbourhood_exp);
     let neighbourhood = neighbourhood_exp.unwrap();
 
-    let state = if LanguageController::language_by_address(neighbourhood.data.link_language.clone()).await?.is_some() {
-        PerspectiveState::LinkLanguageInstalledButNotSynced
-    } else {
-        PerspectiveState::LinkLanguageFailedToInstall
-    };
+    let state = join_state(&url, &neighbourhood).await?;
 
     log::info!("Core.install_neighbourhood(): Creating perspective {}, {:?}, {:?}", url, neighbourhood, state);
 
@@ -77,3 +95,98 @@ pub async fn install_neighbourhood(
 
     Ok(handle)
 }
+
+async fn join_state(url: &str, neighbourhood: &DecoratedNeighbourhoodExpression) -> Result<PerspectiveState, AnyError> {
+    if LanguageController::language_by_address(neighbourhood.data.link_language.clone()).await?.is_none() {
+        return Ok(PerspectiveState::LinkLanguageFailedToInstall);
+    }
+
+    let policy = membership_policy(&neighbourhood.author, &neighbourhood.data.meta);
+    Ok(if policy.can_write(&agent::did()) {
+        PerspectiveState::LinkLanguageInstalledButNotSynced
+    } else {
+        log::warn!("Core.install_neighbourhood(): {} is not permitted to write to {}, joining read-only", agent::did(), url);
+        PerspectiveState::NotPermitted
+    })
+}
+
+/// Picks up syncing an archived neighbourhood again, using the neighbourhood expression
+/// stored with the perspective. The perspective's local links are kept and get merged
+/// with the neighbourhood's state once the link language is synced.
+async fn rejoin_neighbourhood(mut handle: PerspectiveHandle) -> Result<PerspectiveHandle, AnyError> {
+    let url = handle.shared_url.clone().ok_or(anyhow!("Perspective {} has no shared URL", handle.uuid))?;
+    let neighbourhood = handle.neighbourhood.clone().ok_or(anyhow!("Perspective {} has no neighbourhood", handle.uuid))?;
+
+    log::info!("Core.install_neighbourhood(): Rejoining archived neighbourhood {}", url);
+    handle.state = join_state(&url, &neighbourhood).await?;
+    update_perspective(&handle).await.map_err(|e| anyhow!(e))?;
+    Ok(handle)
+}
+
+/// Stops syncing a neighbourhood: detaches and unloads its link language
+/// and uninstalls the link language's Holochain app.
+/// With `keep_local` the perspective stays as a read-only archive
+/// that can be rejoined through its `shared_url`, otherwise it gets removed.
+pub async fn neighbourhood_leave(uuid: &str, keep_local: bool) -> Result<(), AnyError> {
+    let perspective = get_perspective(uuid).ok_or(anyhow!("Perspective not found"))?;
+    let mut handle = perspective.persisted.lock().await.clone();
+    if handle.neighbourhood.is_none() {
+        return Err(anyhow!("Perspective {} is not a neighbourhood", uuid));
+    }
+    if handle.state == PerspectiveState::Archived {
+        return Err(anyhow!("Neighbourhood {} was already left", handle.shared_url.unwrap_or_default()));
+    }
+
+    if keep_local {
+        // Change the state first so the perspective doesn't re-attach the link language
+        // while we are tearing it down
+        handle.state = PerspectiveState::Archived;
+        update_perspective(&handle).await.map_err(|e| anyhow!(e))?;
+        perspective.teardown_link_language().await?;
+    } else {
+        // Tears down the link language as well
+        remove_perspective(uuid).await;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DecoratedLinkExpression, Link};
+    use neighbourhood_membership::{HAS_MEMBER_PREDICATE, HAS_READ_ONLY_MEMBER_PREDICATE, MEMBERSHIP_PREDICATE, MEMBERS_ONLY};
+
+    fn meta_link(predicate: &str, target: &str) -> DecoratedLinkExpression {
+        DecoratedLinkExpression {
+            data: Link {
+                source: "ad4m://self".to_string(),
+                predicate: Some(predicate.to_string()),
+                target: target.to_string(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn neighbourhoods_without_policy_are_open() {
+        let policy = membership_policy("did:key:author", &Perspective { links: vec![] });
+        assert!(policy.is_open());
+        assert!(policy.can_write("did:key:anyone"));
+    }
+
+    #[test]
+    fn members_only_policy_is_read_from_meta() {
+        let policy = membership_policy("did:key:author", &Perspective { links: vec![
+            meta_link(MEMBERSHIP_PREDICATE, MEMBERS_ONLY),
+            meta_link(HAS_MEMBER_PREDICATE, "did:key:member"),
+            meta_link(HAS_READ_ONLY_MEMBER_PREDICATE, "did:key:reader"),
+        ]});
+
+        assert!(policy.members_only);
+        assert_eq!(policy.members, vec!["did:key:member".to_string()]);
+        assert_eq!(policy.read_only, vec!["did:key:reader".to_string()]);
+        assert!(policy.can_write("did:key:author"));
+        assert!(!policy.can_write("did:key:stranger"));
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::BTreeSet;
+use deno_core::anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use deno_core::error::AnyError;
+use serde::{Deserialize, Serialize};
+use crate::agent::signatures::verify;
+use crate::db::Ad4mDb;
+use crate::graphql::graphql_types::{LinkQuery, LinkStatus, PerspectiveHandle, PerspectiveState};
+use crate::types::{Expression, LinkExpression};
+use super::{add_perspective, get_perspective};
+
+/// Bumped whenever the archive layout changes in a way older executors can't read.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Portable, self-contained copy of a perspective.
+/// Links are stored exactly as they were signed, so their proofs stay verifiable
+/// on whatever executor the archive gets imported into.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerspectiveArchive {
+    pub version: u32,
+    pub handle: PerspectiveHandle,
+    pub links: Vec<ArchivedLink>,
+    pub expressions: Vec<ArchivedExpression>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedLink {
+    pub link: LinkExpression,
+    pub status: LinkStatus,
+}
+
+/// Cached expression from the `expression` table that one of the links points to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedExpression {
+    pub url: String,
+    pub expression: Expression<serde_json::Value>,
+    /// When the expression got fetched from its language.
+    /// Missing in older archives, those expressions get imported as expired.
+    #[serde(default)]
+    pub fetched_at: Option<DateTime<Utc>>,
+}
+
+pub async fn export_perspective(uuid: &str) -> Result<PerspectiveArchive, AnyError> {
+    let perspective = get_perspective(uuid)
+        .ok_or(anyhow!("Perspective with uuid {} not found", uuid))?;
+    let handle = perspective.persisted.lock().await.clone();
+
+    Ad4mDb::with_global_instance(|db| {
+        // This includes SDNA links, they are regular links of the perspective
+        let links = db.query_links(uuid, &LinkQuery::default())?;
+
+        let urls = links
+            .iter()
+            .flat_map(|(link, _)| vec![link.data.source.clone(), link.data.target.clone()])
+            .collect::<BTreeSet<String>>();
+        let mut expressions = Vec::new();
+        for url in urls {
+            if let Some(cached) = db.get_cached_expression(&url)? {
+                expressions.push(ArchivedExpression {
+                    url,
+                    expression: cached.expression,
+                    fetched_at: Some(cached.fetched_at),
+                });
+            }
+        }
+
+        Ok(PerspectiveArchive {
+            version: ARCHIVE_VERSION,
+            handle,
+            links: links
+                .into_iter()
+                .map(|(link, status)| ArchivedLink { link, status })
+                .collect(),
+            expressions,
+        })
+    })
+}
+
+/// Creates a new perspective from the given archive and returns its handle.
+/// The perspective keeps its uuid unless that is already taken on this executor.
+/// Neighbourhood perspectives get re-joined through their link language.
+/// Links and cached expressions whose proofs don't verify are left out, since they would
+/// otherwise show up as if they had been signed by their claimed author.
+/// Cached expressions keep the time they were fetched at, so they expire as they would have
+/// on the exporting executor.
+pub async fn import_perspective(archive: PerspectiveArchive) -> Result<PerspectiveHandle, AnyError> {
+    if archive.version > ARCHIVE_VERSION {
+        return Err(anyhow!(
+            "Perspective archive has version {}, but this executor only supports up to version {}",
+            archive.version,
+            ARCHIVE_VERSION
+        ));
+    }
+
+    let mut handle = archive.handle;
+    if get_perspective(&handle.uuid).is_some() {
+        handle.uuid = uuid::Uuid::new_v4().to_string();
+    }
+    handle.state = match handle.neighbourhood {
+        Some(_) => PerspectiveState::NeighbourhoodJoinInitiated,
+        None => PerspectiveState::Private,
+    };
+
+    let (links, rejected): (Vec<ArchivedLink>, Vec<ArchivedLink>) = archive
+        .links
+        .into_iter()
+        .partition(|archived| archived.link.has_valid_signature());
+    if !rejected.is_empty() {
+        log::warn!(
+            "Skipping {} links with invalid proofs while importing perspective {}",
+            rejected.len(),
+            handle.uuid
+        );
+    }
+
+    let (expressions, rejected): (Vec<ArchivedExpression>, Vec<ArchivedExpression>) = archive
+        .expressions
+        .into_iter()
+        .partition(|archived| verify(&archived.expression).unwrap_or(false));
+    if !rejected.is_empty() {
+        log::warn!(
+            "Skipping {} expressions with invalid proofs while importing perspective {}",
+            rejected.len(),
+            handle.uuid
+        );
+    }
+
+    Ad4mDb::with_global_instance(|db| {
+        for archived in &links {
+            db.add_link(&handle.uuid, &archived.link, &archived.status)?;
+        }
+        for archived in &expressions {
+            if db.get_expression(&archived.url)?.is_none() {
+                let fetched_at = archived.fetched_at.unwrap_or(DateTime::<Utc>::MIN_UTC);
+                db.add_expression_fetched_at(&archived.url, &archived.expression, &fetched_at)?;
+            }
+        }
+        Ok::<(), AnyError>(())
+    })?;
+
+    add_perspective(handle.clone(), None)
+        .await
+        .map_err(|e| anyhow!(e))?;
+
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::create_signed_expression;
+    use crate::perspectives::remove_perspective;
+    use crate::test_utils::setup_wallet;
+    use crate::types::{ExpressionProof, Link};
+
+    fn link_expression(source: &str, target: &str) -> LinkExpression {
+        setup_wallet();
+        LinkExpression::from(create_signed_expression(Link {
+            source: source.to_string(),
+            predicate: Some("test://predicate".to_string()),
+            target: target.to_string(),
+        }).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        Ad4mDb::init_global_instance(":memory:").unwrap();
+
+        let handle = PerspectiveHandle::new_from_name("Archived".to_string());
+        add_perspective(handle.clone(), None).await.unwrap();
+
+        let shared = link_expression("test://source", "test://expression");
+        let local = link_expression("test://source", "literal://string:local");
+        let expression = create_signed_expression(serde_json::json!({"title": "cached"})).unwrap();
+        Ad4mDb::with_global_instance(|db| {
+            db.add_link(&handle.uuid, &shared, &LinkStatus::Shared).unwrap();
+            db.add_link(&handle.uuid, &local, &LinkStatus::Local).unwrap();
+            db.add_expression("test://expression", &expression).unwrap();
+        });
+
+        let archive = export_perspective(&handle.uuid).await.unwrap();
+        assert_eq!(archive.links.len(), 2);
+        assert_eq!(archive.expressions.len(), 1);
+        assert_eq!(archive.expressions[0].url, "test://expression");
+        assert_eq!(archive.expressions[0].expression, expression);
+        assert!(archive.expressions[0].fetched_at.is_some());
+
+        let json = serde_json::to_string(&archive).unwrap();
+
+        // Importing next to the original needs a new uuid
+        let copy = import_perspective(serde_json::from_str(&json).unwrap()).await.unwrap();
+        assert_ne!(copy.uuid, handle.uuid);
+        assert_eq!(copy.name, handle.name);
+
+        remove_perspective(&handle.uuid).await;
+        let restored = import_perspective(serde_json::from_str(&json).unwrap()).await.unwrap();
+        assert_eq!(restored.uuid, handle.uuid);
+
+        let links = Ad4mDb::with_global_instance(|db| db.query_links(&restored.uuid, &LinkQuery::default())).unwrap();
+        let mut links: Vec<ArchivedLink> = links
+            .into_iter()
+            .map(|(link, status)| ArchivedLink { link, status })
+            .collect();
+        let mut expected = archive.links.clone();
+        links.sort_by(|a, b| a.link.data.target.cmp(&b.link.data.target));
+        expected.sort_by(|a, b| a.link.data.target.cmp(&b.link.data.target));
+        assert_eq!(links, expected);
+
+        remove_perspective(&copy.uuid).await;
+        remove_perspective(&restored.uuid).await;
+    }
+
+    #[tokio::test]
+    async fn test_import_skips_links_with_invalid_proofs() {
+        Ad4mDb::init_global_instance(":memory:").unwrap();
+
+        let valid = link_expression("test://source", "test://valid");
+        let mut forged = link_expression("test://source", "test://forged");
+        forged.data.target = "test://tampered".to_string();
+        let archive = PerspectiveArchive {
+            version: ARCHIVE_VERSION,
+            handle: PerspectiveHandle::new_from_name("Tampered".to_string()),
+            links: vec![
+                ArchivedLink { link: valid.clone(), status: LinkStatus::Shared },
+                ArchivedLink { link: forged, status: LinkStatus::Shared },
+            ],
+            expressions: vec![],
+        };
+
+        let imported = import_perspective(archive).await.unwrap();
+        let links = Ad4mDb::with_global_instance(|db| db.query_links(&imported.uuid, &LinkQuery::default())).unwrap();
+        assert_eq!(links.into_iter().map(|(link, _)| link.data.target).collect::<Vec<_>>(), vec![valid.data.target]);
+
+        remove_perspective(&imported.uuid).await;
+    }
+
+    #[tokio::test]
+    async fn test_import_keeps_only_verified_expressions_with_their_fetch_time() {
+        Ad4mDb::init_global_instance(":memory:").unwrap();
+        setup_wallet();
+
+        let fetched_at = Utc::now() - chrono::Duration::days(2);
+        let valid = create_signed_expression(serde_json::json!({"title": "valid"})).unwrap();
+        let mut forged = create_signed_expression(serde_json::json!({"title": "forged"})).unwrap();
+        forged.data = serde_json::json!({"title": "tampered"});
+        let unsigned = Expression {
+            author: "did:test:key".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            data: serde_json::json!({"title": "unsigned"}),
+            proof: ExpressionProof::default(),
+        };
+        let from_older_archive = create_signed_expression(serde_json::json!({"title": "older"})).unwrap();
+
+        let archived = |url: &str, expression: &Expression<serde_json::Value>, fetched_at: Option<DateTime<Utc>>| ArchivedExpression {
+            url: url.to_string(),
+            expression: expression.clone(),
+            fetched_at,
+        };
+        let archive = PerspectiveArchive {
+            version: ARCHIVE_VERSION,
+            handle: PerspectiveHandle::new_from_name("Cached".to_string()),
+            links: vec![],
+            expressions: vec![
+                archived("test://valid", &valid, Some(fetched_at)),
+                archived("test://forged", &forged, Some(fetched_at)),
+                archived("test://unsigned", &unsigned, Some(fetched_at)),
+                archived("test://older", &from_older_archive, None),
+            ],
+        };
+
+        let imported = import_perspective(archive).await.unwrap();
+        Ad4mDb::with_global_instance(|db| {
+            assert!(db.get_expression("test://forged").unwrap().is_none());
+            assert!(db.get_expression("test://unsigned").unwrap().is_none());
+
+            let cached = db.get_cached_expression("test://valid").unwrap().unwrap();
+            assert_eq!(cached.expression, valid);
+            assert_eq!(cached.fetched_at, fetched_at);
+
+            let cached = db.get_cached_expression("test://older").unwrap().unwrap();
+            assert_eq!(cached.fetched_at, DateTime::<Utc>::MIN_UTC);
+        });
+
+        remove_perspective(&imported.uuid).await;
+    }
+
+    #[tokio::test]
+    async fn test_import_refuses_newer_archives() {
+        Ad4mDb::init_global_instance(":memory:").unwrap();
+
+        let archive = PerspectiveArchive {
+            version: ARCHIVE_VERSION + 1,
+            handle: PerspectiveHandle::new_from_name("From the future".to_string()),
+            links: vec![],
+            expressions: vec![],
+        };
+        assert!(import_perspective(archive).await.is_err());
+    }
+}
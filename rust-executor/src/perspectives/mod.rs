@@ -1,4 +1,6 @@
+pub mod archive;
 pub mod perspective_instance;
+pub mod rdf;
 pub mod sdna;
 pub mod utils;
 use std::sync::RwLock;
@@ -17,10 +19,6 @@ lazy_static! {
 
 pub fn initialize_from_db() {
     let handles = Ad4mDb::global_instance()
-        .lock()
-        .expect("Couldn't get write lock on Ad4mDb")
-        .as_ref()
-        .expect("Ad4mDb not initialized")
         .get_all_perspectives()
         .expect("Couldn't get perspectives from db");
     let mut perspectives = PERSPECTIVES.write().unwrap();
@@ -40,10 +38,6 @@ pub async fn add_perspective(handle: PerspectiveHandle, created_from_join: Optio
     }
 
     Ad4mDb::global_instance()
-        .lock()
-        .expect("Couldn't get write lock on Ad4mDb")
-        .as_ref()
-        .expect("Ad4mDb not initialized")
         .add_perspective(&handle)
         .map_err(|e| e.to_string())?;
 
@@ -121,10 +115,6 @@ pub async fn update_perspective(handle: &PerspectiveHandle) -> Result<(), String
 
 pub async fn remove_perspective(uuid: &str) -> Option<PerspectiveInstance> {
     if let Err(e) = Ad4mDb::global_instance()
-        .lock()
-        .expect("Couldn't get write lock on Ad4mDb")
-        .as_ref()
-        .expect("Ad4mDb not initialized")
         .remove_perspective(uuid) {
             log::error!("Error removing perspective from db: {}", e);
         }
@@ -136,6 +126,12 @@ pub async fn remove_perspective(uuid: &str) -> Option<PerspectiveInstance> {
 
     if let Some(ref instance) = removed_instance {
         instance.teardown_background_tasks().await;
+        // Archived neighbourhoods had their link language torn down when they were left
+        if instance.persisted.lock().await.state != PerspectiveState::Archived {
+            if let Err(e) = instance.teardown_link_language().await {
+                log::error!("Error tearing down link language of perspective {}: {}", uuid, e);
+            }
+        }
     }
 
     get_global_pubsub()
@@ -0,0 +1,604 @@
+//! RDF import and export for perspectives.
+//!
+//! Every link becomes one triple. Literal URLs (`literal://...`) become typed RDF literals,
+//! other URLs that aren't valid IRIs become blank nodes.
+//! Author, timestamp and proof of each link are attached through standard RDF reification,
+//! so an exported perspective can be imported again with all signatures intact.
+//! Triples without such a statement, or with a proof that doesn't verify,
+//! get imported as new links signed by this agent.
+//! Parsing and serialization is done by oxrdfio.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use ad4m_client::literal::{Literal, LiteralValue};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use deno_core::anyhow::anyhow;
+use deno_core::error::AnyError;
+use oxrdf::{BlankNode, NamedNode};
+use oxrdfio::{JsonLdProfileSet, RdfParser, RdfSerializer};
+use serde_json::Value;
+use crate::graphql::graphql_types::LinkStatus;
+use crate::types::{ExpressionProof, Link, LinkExpression};
+
+pub const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+pub const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+pub const AD4M: &str = "ad4m://";
+
+/// RDF has no triples without predicate, so links without one use this IRI instead.
+pub const NO_PREDICATE: &str = "ad4m://no_predicate";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RdfFormat {
+    NTriples,
+    Turtle,
+    JsonLd,
+}
+
+impl TryFrom<&str> for RdfFormat {
+    type Error = AnyError;
+
+    fn try_from(format: &str) -> Result<Self, Self::Error> {
+        match format.to_lowercase().as_str() {
+            "ntriples" | "n-triples" | "nt" => Ok(RdfFormat::NTriples),
+            "turtle" | "ttl" => Ok(RdfFormat::Turtle),
+            "jsonld" | "json-ld" => Ok(RdfFormat::JsonLd),
+            _ => Err(anyhow!("Unknown RDF format '{}', must be one of 'ntriples', 'turtle' or 'jsonld'", format)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Term {
+    Iri(String),
+    Blank(String),
+    Literal {
+        value: String,
+        datatype: Option<String>,
+        language: Option<String>,
+    },
+}
+
+impl Term {
+    fn literal(value: &str, datatype: Option<String>) -> Term {
+        Term::Literal { value: value.to_string(), datatype, language: None }
+    }
+
+    fn value(&self) -> &str {
+        match self {
+            Term::Iri(iri) => iri,
+            Term::Blank(label) => label,
+            Term::Literal { value, .. } => value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Triple {
+    pub subject: Term,
+    pub predicate: Term,
+    pub object: Term,
+}
+
+/// A link read from RDF, together with its original proof if the data contained one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedLink {
+    pub link: Link,
+    pub signed: Option<LinkExpression>,
+    pub status: Option<LinkStatus>,
+}
+
+fn rdf(name: &str) -> String {
+    format!("{}{}", RDF, name)
+}
+
+fn xsd(name: &str) -> String {
+    format!("{}{}", XSD, name)
+}
+
+fn ad4m(name: &str) -> String {
+    format!("{}{}", AD4M, name)
+}
+
+fn status_name(status: &LinkStatus) -> &'static str {
+    match status {
+        LinkStatus::Shared => "shared",
+        LinkStatus::Local => "local",
+    }
+}
+
+// Literal mapping
+
+fn literal_url_to_term(url: &str) -> Option<Term> {
+    let value = Literal::from_url(url.to_string()).ok()?.get().ok()?;
+    Some(match value {
+        LiteralValue::String(string) => Term::literal(&string, None),
+        LiteralValue::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+            Term::literal(&format!("{}", number as i64), Some(xsd("integer")))
+        }
+        LiteralValue::Number(number) => Term::literal(&number.to_string(), Some(xsd("double"))),
+        LiteralValue::Integer(integer) => Term::literal(&integer.to_string(), Some(xsd("integer"))),
+        LiteralValue::Boolean(boolean) => Term::literal(&boolean.to_string(), Some(xsd("boolean"))),
+        LiteralValue::DateTime(datetime) => Term::literal(&datetime.to_rfc3339(), Some(xsd("dateTime"))),
+        LiteralValue::Bytes(bytes) => Term::literal(&STANDARD.encode(bytes), Some(xsd("base64Binary"))),
+        LiteralValue::Json(json) => Term::literal(&json.to_string(), Some(rdf("JSON"))),
+        _ => return None,
+    })
+}
+
+fn literal_term_to_url(value: &str, datatype: &Option<String>) -> Result<String, AnyError> {
+    const INTEGER: &[&str] = &[
+        "integer", "int", "long", "short", "byte",
+        "nonNegativeInteger", "positiveInteger", "negativeInteger", "nonPositiveInteger",
+        "unsignedLong", "unsignedInt", "unsignedShort", "unsignedByte",
+    ];
+    const FLOAT: &[&str] = &["decimal", "double", "float"];
+
+    let local = datatype.as_ref().and_then(|d| d.strip_prefix(XSD));
+    let literal = match (datatype.as_deref(), local) {
+        (_, Some(name)) if INTEGER.contains(&name) => match value.trim().parse::<i64>() {
+            Ok(integer) => Literal::from_integer(integer),
+            // Too big for i64, keep at least the magnitude
+            Err(_) => match value.trim().parse::<f64>() {
+                Ok(number) => Literal::from_number(number),
+                Err(_) => Literal::from_string(value.to_string()),
+            },
+        },
+        (_, Some(name)) if FLOAT.contains(&name) => match value.trim().parse::<f64>() {
+            Ok(number) => Literal::from_number(number),
+            Err(_) => Literal::from_string(value.to_string()),
+        },
+        (_, Some("boolean")) => match value.trim() {
+            "true" | "1" => Literal::from_bool(true),
+            "false" | "0" => Literal::from_bool(false),
+            _ => Literal::from_string(value.to_string()),
+        },
+        (_, Some("dateTime")) => match chrono::DateTime::parse_from_rfc3339(value.trim()) {
+            Ok(datetime) => Literal::from_datetime(datetime),
+            Err(_) => Literal::from_string(value.to_string()),
+        },
+        (_, Some("base64Binary")) => match STANDARD.decode(value.trim()) {
+            Ok(bytes) => Literal::from_bytes(bytes),
+            Err(_) => Literal::from_string(value.to_string()),
+        },
+        (Some(d), _) if d == rdf("JSON") => match serde_json::from_str::<Value>(value) {
+            Ok(json) => Literal::from_json(json),
+            Err(_) => Literal::from_string(value.to_string()),
+        },
+        _ => Literal::from_string(value.to_string()),
+    };
+    Ok(literal.to_url()?)
+}
+
+// Links <-> triples
+
+/// URLs that aren't valid IRIs, like the `literal://` sources of SDNA links,
+/// become one blank node per URL. The URL itself is kept on the link's statement.
+fn url_to_term(url: &str, stand_ins: &mut HashMap<String, Term>) -> Term {
+    if let Some(label) = url.strip_prefix("_:") {
+        return Term::Blank(label.to_string());
+    }
+    if NamedNode::new(url).is_ok() {
+        return Term::Iri(url.to_string());
+    }
+    let count = stand_ins.len();
+    stand_ins
+        .entry(url.to_string())
+        .or_insert_with(|| Term::Blank(format!("url{}", count)))
+        .clone()
+}
+
+fn stands_in_for_url(term: &Term, url: &str) -> bool {
+    matches!(term, Term::Blank(_)) && !url.starts_with("_:")
+}
+
+/// Turns links into triples, plus one reified statement per link carrying its author and proof.
+pub fn links_to_triples(links: &[(LinkExpression, LinkStatus)]) -> Vec<Triple> {
+    let mut triples = Vec::new();
+    let mut stand_ins = HashMap::new();
+    for (index, (link, status)) in links.iter().enumerate() {
+        let subject = url_to_term(&link.data.source, &mut stand_ins);
+        let predicate = Term::Iri(link.data.predicate.clone().unwrap_or(NO_PREDICATE.to_string()));
+        let literal_target = literal_url_to_term(&link.data.target);
+        let object = literal_target.clone().unwrap_or_else(|| url_to_term(&link.data.target, &mut stand_ins));
+        let author = match NamedNode::new(&link.author) {
+            Ok(_) => Term::Iri(link.author.clone()),
+            Err(_) => Term::literal(&link.author, None),
+        };
+        let source_stand_in = stands_in_for_url(&subject, &link.data.source);
+        let target_stand_in = literal_target.is_some() || stands_in_for_url(&object, &link.data.target);
+
+        triples.push(Triple { subject: subject.clone(), predicate: predicate.clone(), object: object.clone() });
+
+        let statement = Term::Blank(format!("link{}", index));
+        let mut describe = |name: String, value: Term| triples.push(Triple {
+            subject: statement.clone(),
+            predicate: Term::Iri(name),
+            object: value,
+        });
+        describe(rdf("type"), Term::Iri(rdf("Statement")));
+        describe(rdf("subject"), subject);
+        describe(rdf("predicate"), predicate);
+        describe(rdf("object"), object);
+        describe(ad4m("author"), author);
+        describe(ad4m("timestamp"), Term::literal(&link.timestamp, Some(xsd("dateTime"))));
+        describe(ad4m("signature"), Term::literal(&link.proof.signature, None));
+        describe(ad4m("key"), Term::literal(&link.proof.key, None));
+        describe(ad4m("status"), Term::literal(status_name(status), None));
+        if source_stand_in {
+            describe(ad4m("sourceUrl"), Term::literal(&link.data.source, None));
+        }
+        // Literal URLs can be encoded in more than one way, but the signature covers the exact one
+        if target_stand_in {
+            describe(ad4m("targetUrl"), Term::literal(&link.data.target, None));
+        }
+    }
+    triples
+}
+
+/// Turns triples into links. Reified statements that carry a complete proof
+/// restore the original link expression, all other triples become plain links.
+pub fn triples_to_links(triples: &[Triple]) -> Result<Vec<ImportedLink>, AnyError> {
+    // Blank node labels are only unique within one document
+    let import_id = uuid::Uuid::new_v4().simple().to_string()[..8].to_string();
+    let term_to_url = |term: &Term| -> Result<String, AnyError> {
+        match term {
+            Term::Iri(iri) => Ok(iri.clone()),
+            Term::Blank(label) => Ok(format!("_:{}-{}", import_id, label)),
+            Term::Literal { value, datatype, .. } => literal_term_to_url(value, datatype),
+        }
+    };
+    let predicate_from_term = |term: &Term| -> Result<Option<String>, AnyError> {
+        match term {
+            Term::Iri(iri) if iri == NO_PREDICATE => Ok(None),
+            Term::Literal { .. } => Err(anyhow!("Literals can't be used as predicate: {:?}", term)),
+            _ => Ok(Some(term_to_url(term)?)),
+        }
+    };
+
+    let statement_nodes: HashSet<&Term> = triples
+        .iter()
+        .filter(|t| t.predicate == Term::Iri(rdf("type")) && t.object == Term::Iri(rdf("Statement")))
+        .map(|t| &t.subject)
+        .collect();
+
+    let mut statements: BTreeMap<&Term, HashMap<String, &Term>> = BTreeMap::new();
+    for triple in triples.iter().filter(|t| statement_nodes.contains(&t.subject)) {
+        if let Term::Iri(predicate) = &triple.predicate {
+            statements.entry(&triple.subject).or_default().insert(predicate.clone(), &triple.object);
+        }
+    }
+
+    let mut links = Vec::new();
+    let mut covered: HashSet<(&Term, &Term, &Term)> = HashSet::new();
+    for properties in statements.values() {
+        let get = |name: String| properties.get(&name).copied();
+        let (subject, predicate, object) = match (get(rdf("subject")), get(rdf("predicate")), get(rdf("object"))) {
+            (Some(s), Some(p), Some(o)) => (s, p, o),
+            _ => continue,
+        };
+        let (author, timestamp, signature, key) = match (get(ad4m("author")), get(ad4m("timestamp")), get(ad4m("signature")), get(ad4m("key"))) {
+            (Some(a), Some(t), Some(s), Some(k)) => (a, t, s, k),
+            _ => continue,
+        };
+
+        let source = match get(ad4m("sourceUrl")) {
+            Some(url) => url.value().to_string(),
+            None => term_to_url(subject)?,
+        };
+        let target = match get(ad4m("targetUrl")) {
+            Some(url) => url.value().to_string(),
+            None => term_to_url(object)?,
+        };
+        let link = Link {
+            source,
+            predicate: predicate_from_term(predicate)?,
+            target,
+        };
+        let status = match get(ad4m("status")).map(|s| s.value()) {
+            Some("shared") => Some(LinkStatus::Shared),
+            Some("local") => Some(LinkStatus::Local),
+            _ => None,
+        };
+
+        links.push(ImportedLink {
+            link: link.clone(),
+            signed: Some(LinkExpression {
+                author: author.value().to_string(),
+                timestamp: timestamp.value().to_string(),
+                data: link,
+                proof: ExpressionProof {
+                    key: key.value().to_string(),
+                    signature: signature.value().to_string(),
+                },
+                status: status.clone(),
+            }),
+            status,
+        });
+        covered.insert((subject, predicate, object));
+    }
+
+    for triple in triples.iter().filter(|t| !statement_nodes.contains(&t.subject)) {
+        if covered.contains(&(&triple.subject, &triple.predicate, &triple.object)) {
+            continue;
+        }
+        links.push(ImportedLink {
+            link: Link {
+                source: term_to_url(&triple.subject)?,
+                predicate: predicate_from_term(&triple.predicate)?,
+                target: term_to_url(&triple.object)?,
+            },
+            signed: None,
+            status: None,
+        });
+    }
+
+    Ok(links)
+}
+
+pub fn serialize(triples: &[Triple], format: RdfFormat) -> Result<String, AnyError> {
+    let mut serializer = RdfSerializer::from_format(format.into());
+    for (prefix, namespace) in PREFIXES {
+        serializer = serializer.with_prefix(*prefix, *namespace)?;
+    }
+    let mut writer = serializer.for_writer(Vec::new());
+    for triple in triples {
+        writer.serialize_triple(&triple.to_rdf()?)?;
+    }
+    Ok(String::from_utf8(writer.finish()?)?)
+}
+
+pub fn parse(data: &str, format: RdfFormat) -> Result<Vec<Triple>, AnyError> {
+    RdfParser::from_format(format.into())
+        .for_slice(data.as_bytes())
+        .map(|quad| Ok(Triple::from_rdf(quad?.into())))
+        .collect()
+}
+
+// Conversion from and to the oxrdf model used by the parsers and serializers
+
+const PREFIXES: &[(&str, &str)] = &[("rdf", RDF), ("xsd", XSD), ("ad4m", AD4M)];
+
+impl From<RdfFormat> for oxrdfio::RdfFormat {
+    fn from(format: RdfFormat) -> Self {
+        match format {
+            RdfFormat::NTriples => oxrdfio::RdfFormat::NTriples,
+            RdfFormat::Turtle => oxrdfio::RdfFormat::Turtle,
+            RdfFormat::JsonLd => oxrdfio::RdfFormat::JsonLd { profile: JsonLdProfileSet::empty() },
+        }
+    }
+}
+
+impl Term {
+    fn to_rdf(&self) -> Result<oxrdf::Term, AnyError> {
+        Ok(match self {
+            Term::Iri(iri) => NamedNode::new(iri)
+                .map_err(|e| anyhow!("'{}' is not a valid IRI: {}", iri, e))?
+                .into(),
+            Term::Blank(label) => BlankNode::new(label)
+                .map_err(|e| anyhow!("'{}' is not a valid blank node label: {}", label, e))?
+                .into(),
+            Term::Literal { value, language: Some(language), .. } => {
+                oxrdf::Literal::new_language_tagged_literal(value, language)?.into()
+            }
+            Term::Literal { value, datatype: Some(datatype), .. } => {
+                oxrdf::Literal::new_typed_literal(value, NamedNode::new(datatype)?).into()
+            }
+            Term::Literal { value, .. } => oxrdf::Literal::new_simple_literal(value).into(),
+        })
+    }
+
+    fn from_rdf(term: oxrdf::Term) -> Term {
+        match term {
+            oxrdf::Term::NamedNode(node) => Term::Iri(node.into_string()),
+            oxrdf::Term::BlankNode(node) => Term::Blank(node.into_string()),
+            oxrdf::Term::Literal(literal) => {
+                let language = literal.language().map(String::from);
+                let datatype = match literal.datatype() {
+                    _ if language.is_some() => None,
+                    datatype if datatype == oxrdf::vocab::xsd::STRING => None,
+                    datatype => Some(datatype.as_str().to_string()),
+                };
+                Term::Literal { value: literal.value().to_string(), datatype, language }
+            }
+        }
+    }
+}
+
+impl Triple {
+    fn to_rdf(&self) -> Result<oxrdf::Triple, AnyError> {
+        let predicate = match self.predicate.to_rdf()? {
+            oxrdf::Term::NamedNode(node) => node,
+            _ => return Err(anyhow!("Predicates have to be IRIs: {:?}", self.predicate)),
+        };
+        let object = self.object.to_rdf()?;
+        Ok(match self.subject.to_rdf()? {
+            oxrdf::Term::NamedNode(node) => oxrdf::Triple::new(node, predicate, object),
+            oxrdf::Term::BlankNode(node) => oxrdf::Triple::new(node, predicate, object),
+            _ => return Err(anyhow!("Literals can't be used as subject: {:?}", self.subject)),
+        })
+    }
+
+    fn from_rdf(triple: oxrdf::Triple) -> Triple {
+        Triple {
+            subject: Term::from_rdf(triple.subject.into()),
+            predicate: Term::Iri(triple.predicate.into_string()),
+            object: Term::from_rdf(triple.object),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iri(iri: &str) -> Term {
+        Term::Iri(iri.to_string())
+    }
+
+    fn link(source: &str, predicate: Option<&str>, target: &str) -> (LinkExpression, LinkStatus) {
+        (LinkExpression {
+            author: "did:key:z6Mktest".to_string(),
+            timestamp: "2023-06-01T12:00:00.000Z".to_string(),
+            data: Link {
+                source: source.to_string(),
+                predicate: predicate.map(String::from),
+                target: target.to_string(),
+            },
+            proof: ExpressionProof {
+                key: "did:key:z6Mktest#z6Mktest".to_string(),
+                signature: "abcdef0123".to_string(),
+            },
+            status: None,
+        }, LinkStatus::Shared)
+    }
+
+    fn test_links() -> Vec<(LinkExpression, LinkStatus)> {
+        vec![
+            link("ad4m://self", Some("ad4m://has_child"), "neighbourhood://Qm123"),
+            link("neighbourhood://Qm123", Some("sioc://content"), "literal://string:Hello%20%22world%22%0A"),
+            link("neighbourhood://Qm123", Some("ad4m://count"), "literal://number:42"),
+            link("neighbourhood://Qm123", Some("ad4m://ratio"), "literal://number:0.5"),
+            link("neighbourhood://Qm123", Some("ad4m://meta"), "literal://json:%7B%22a%22%3A1%7D"),
+            link("ad4m://self", None, "did:key:z6Mkother"),
+        ]
+    }
+
+    #[test]
+    fn literals_become_typed_rdf_literals() {
+        let triples = links_to_triples(&test_links());
+        let object_of = |predicate: &str| triples
+            .iter()
+            .find(|t| t.predicate == iri(predicate) && t.subject == iri("neighbourhood://Qm123"))
+            .unwrap()
+            .object
+            .clone();
+
+        assert_eq!(object_of("sioc://content"), Term::literal("Hello \"world\"\n", None));
+        assert_eq!(object_of("ad4m://count"), Term::literal("42", Some(xsd("integer"))));
+        assert_eq!(object_of("ad4m://ratio"), Term::literal("0.5", Some(xsd("double"))));
+        assert_eq!(object_of("ad4m://meta"), Term::literal("{\"a\":1}", Some(rdf("JSON"))));
+    }
+
+    #[test]
+    fn roundtrip_keeps_signed_links_in_all_formats() {
+        let links = test_links();
+        for format in [RdfFormat::NTriples, RdfFormat::Turtle, RdfFormat::JsonLd] {
+            let serialized = serialize(&links_to_triples(&links), format).unwrap();
+            let imported = triples_to_links(&parse(&serialized, format).unwrap()).unwrap();
+
+            assert_eq!(imported.len(), links.len(), "{:?}:\n{}", format, serialized);
+            for (expected, _) in &links {
+                let found = imported
+                    .iter()
+                    .find(|i| i.link == expected.data)
+                    .unwrap_or_else(|| panic!("{:?}: missing link {:?}", format, expected.data));
+                let signed = found.signed.clone().expect("Expected signed link");
+                assert_eq!(signed.author, expected.author);
+                assert_eq!(signed.timestamp, expected.timestamp);
+                assert_eq!(signed.proof, expected.proof);
+                assert_eq!(found.status, Some(LinkStatus::Shared));
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_keeps_links_from_literal_sources() {
+        let links = vec![
+            link("ad4m://self", Some("ad4m://has_sdna"), "literal://string:Todo"),
+            link("literal://string:Todo", Some("ad4m://sdna"), "literal://string:subject_class(%22Todo%22%2C%20c)."),
+            link("literal://string:Todo", Some("ad4m://sdna_version"), "literal://number:1"),
+        ];
+        let triples = links_to_triples(&links);
+        let sources: HashSet<&Term> = triples
+            .iter()
+            .filter(|t| t.predicate == iri("ad4m://sdna") || t.predicate == iri("ad4m://sdna_version"))
+            .map(|t| &t.subject)
+            .collect();
+        assert_eq!(sources.len(), 1);
+        assert!(matches!(sources.iter().next(), Some(Term::Blank(_))));
+
+        for format in [RdfFormat::NTriples, RdfFormat::Turtle, RdfFormat::JsonLd] {
+            let serialized = serialize(&triples, format).unwrap();
+            let imported = triples_to_links(&parse(&serialized, format).unwrap()).unwrap();
+
+            assert_eq!(imported.len(), links.len(), "{:?}:\n{}", format, serialized);
+            for (expected, _) in &links {
+                let found = imported
+                    .iter()
+                    .find(|i| i.link == expected.data)
+                    .unwrap_or_else(|| panic!("{:?}: missing link {:?}", format, expected.data));
+                assert_eq!(found.signed.as_ref().map(|s| &s.proof), Some(&expected.proof));
+            }
+        }
+    }
+
+    #[test]
+    fn can_import_plain_turtle() {
+        let turtle = r#"
+            @prefix foaf: <http://xmlns.com/foaf/0.1/> .
+            PREFIX ex: <http://example.org/>
+            # A comment
+            ex:alice a foaf:Person ;
+                foaf:name "Alice"@en, 'Ally' ;
+                foaf:age 42 ;
+                ex:active true ;
+                foaf:knows [ foaf:name """Bob
+the builder""" ] .
+            <http://example.org/bob> ex:score 1.5e2 .
+        "#;
+
+        let triples = parse(turtle, RdfFormat::Turtle).unwrap();
+        assert_eq!(triples.len(), 8);
+        assert_eq!(triples[0], Triple {
+            subject: iri("http://example.org/alice"),
+            predicate: Term::Iri(rdf("type")),
+            object: iri("http://xmlns.com/foaf/0.1/Person"),
+        });
+
+        let links = triples_to_links(&triples).unwrap();
+        assert!(links.iter().all(|l| l.signed.is_none()));
+        let targets: Vec<String> = links.iter().map(|l| l.link.target.clone()).collect();
+        assert!(targets.contains(&"literal://string:Alice".to_string()));
+        assert!(targets.contains(&"literal://integer:42".to_string()));
+        assert!(targets.contains(&"literal://boolean:true".to_string()));
+        assert!(targets.contains(&"literal://number:150".to_string()));
+        assert!(links.iter().any(|l| l.link.source.starts_with("_:") && l.link.target == "literal://string:Bob%0Athe%20builder"));
+    }
+
+    #[test]
+    fn can_import_compacted_jsonld() {
+        let document = serde_json::json!({
+            "@context": {
+                "schema": "http://schema.org/",
+                "name": "schema:name",
+                "author": { "@id": "schema:author", "@type": "@id" }
+            },
+            "@id": "http://example.org/post/1",
+            "@type": "schema:BlogPosting",
+            "name": "First post",
+            "author": { "@id": "did:key:z6Mkauthor" },
+            "schema:wordCount": 300,
+            "ignored": "not in context"
+        });
+
+        let triples = parse(&document.to_string(), RdfFormat::JsonLd).unwrap();
+        assert_eq!(triples.len(), 4);
+        assert!(triples.contains(&Triple {
+            subject: iri("http://example.org/post/1"),
+            predicate: iri("http://schema.org/name"),
+            object: Term::literal("First post", None),
+        }));
+        assert!(triples.contains(&Triple {
+            subject: iri("http://example.org/post/1"),
+            predicate: iri("http://schema.org/author"),
+            object: iri("did:key:z6Mkauthor"),
+        }));
+    }
+
+    #[test]
+    fn rejects_unknown_formats_and_broken_input() {
+        assert!(RdfFormat::try_from("rdfxml").is_err());
+        assert_eq!(RdfFormat::try_from("N-Triples").unwrap(), RdfFormat::NTriples);
+        assert!(parse("<a> <b> \"unterminated .", RdfFormat::NTriples).is_err());
+        assert!(parse("undefined:x <b> <c> .", RdfFormat::Turtle).is_err());
+    }
+}
@@ -3,17 +3,17 @@ use crate::types::{DecoratedLinkExpression, ExpressionRef, LanguageRef, Link};
 use ad4m_client::literal::Literal;
 use chrono::DateTime;
 use deno_core::error::AnyError;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use log;
 
-fn triple_fact(l: &DecoratedLinkExpression) -> String {
-    format!("triple(\"{}\", \"{}\", \"{}\").", l.data.source, l.data.predicate.as_ref().unwrap_or(&"".to_string()), l.data.target)
+pub(crate) fn triple_term(l: &DecoratedLinkExpression) -> String {
+    format!("triple(\"{}\", \"{}\", \"{}\")", l.data.source, l.data.predicate.as_ref().unwrap_or(&"".to_string()), l.data.target)
 }
 
-fn link_fact(l: &DecoratedLinkExpression) -> String {
+pub(crate) fn link_term(l: &DecoratedLinkExpression) -> String {
     format!(
-        "link(\"{}\", \"{}\", \"{}\", {}, \"{}\").",
+        "link(\"{}\", \"{}\", \"{}\", {}, \"{}\")",
         l.data.source,
         l.data.predicate.as_ref().unwrap_or(&"".to_string()),
         l.data.target,
@@ -22,65 +22,127 @@ fn link_fact(l: &DecoratedLinkExpression) -> String {
     )
 }
 
-async fn node_facts(all_links: &Vec<&DecoratedLinkExpression>) -> Result<Vec<String>, AnyError> {
-    let mut lang_addrs = Vec::new();
-    let mut lang_names = Vec::new();
-    let mut expr_addrs = Vec::new();
+/// Nodes of a link, i.e. its non-empty source, predicate and target.
+fn link_nodes(l: &DecoratedLinkExpression) -> Vec<&String> {
+    let mut nodes = vec![&l.data.source];
+    if let Some(predicate) = l.data.predicate.as_ref() {
+        nodes.push(predicate);
+    }
+    nodes.push(&l.data.target);
+    nodes.into_iter().filter(|node| !node.is_empty()).collect()
+}
 
-    let mut nodes = HashSet::new();
-    for link in all_links.iter() {
-        if !link.data.source.is_empty() {
-            nodes.insert(link.data.source.clone());
-        }
-        if let Some(predicate) = link.data.predicate.as_ref() {
-            if !predicate.is_empty() {
-                nodes.insert(predicate.clone());
+/// languageAddress/2, languageName/2 and expressionAddress/2 terms of a node
+/// that is an expression URL, none for other nodes.
+fn node_terms(node: &str) -> Vec<String> {
+    if node == "false" || node == "true" {
+        return vec![];
+    }
+    match ExpressionRef::try_from(node.to_string()) {
+        Ok(expression_ref) => {
+            let lang = if expression_ref.language.name.is_empty() {
+                //TODO wire up LanguageController
+                //language_controller.language_by_ref(&ref.language).await?
+                LanguageRef {
+                    name: "unknown".to_string(),
+                    address: "unknown".to_string(),
+                }
+            } else {
+                expression_ref.language.clone()
+            };
+
+            vec![
+                format!("languageAddress(\"{}\", \"{}\")", node, expression_ref.language.address),
+                format!("languageName(\"{}\", \"{}\")", node, lang.name),
+                format!("expressionAddress(\"{}\", \"{}\")", node, expression_ref.expression),
+            ]
+        },
+        Err(e) => {
+            if !e.to_string().contains("Language not found by reference") {
+                log::debug!("While creating expressionLanguageFacts: {:?}", e);
             }
-        }
-        if !link.data.target.is_empty() {
-            nodes.insert(link.data.target.clone());
+            vec![]
         }
     }
+}
 
-    lang_addrs.push(":- discontiguous(languageAddress/2).".to_string());
-    lang_names.push(":- discontiguous(languageName/2).".to_string());
-    expr_addrs.push(":- discontiguous(expressionAddress/2).".to_string());
 
-    for node in nodes.iter() {
-        if node == "false" || node == "true" {
-            continue;
-        }
-        match ExpressionRef::try_from(node.clone()) {
-            Ok(expression_ref) => {
-                let lang = if expression_ref.language.name.is_empty() {
-                    //TODO wire up LanguageController
-                    //language_controller.language_by_ref(&ref.language).await?
-                    LanguageRef {
-                        name: "unknown".to_string(),
-                        address: "unknown".to_string(),
-                    }
-                } else {
-                    expression_ref.language.clone()
-                };
-
-                lang_addrs.push(format!("languageAddress(\"{}\", \"{}\").", node, expression_ref.language.address));
-                lang_names.push(format!("languageName(\"{}\", \"{}\").", node, lang.name));
-                expr_addrs.push(format!("expressionAddress(\"{}\", \"{}\").", node, expression_ref.expression));
-            },
-            Err(e) => {
-                if !e.to_string().contains("Language not found by reference") {
-                    log::debug!("While creating expressionLanguageFacts: {:?}", e);
-                }
+fn is_sdna_link(link: &Link) -> bool {
+    link.source == "ad4m://self" && ["ad4m://has_subject_class", "ad4m://has_flow", "ad4m://has_custom_sdna"].contains(&link.predicate.as_deref().unwrap_or(""))
+}
+
+/// True for links that either declare an SDNA entry or carry its code.
+/// Changes to those require regenerating all engine facts.
+pub(crate) fn is_sdna_related_link(link: &Link) -> bool {
+    is_sdna_link(link) || link.predicate.as_deref() == Some("ad4m://sdna")
+}
+
+/// Link facts are asserted at runtime, both when the engine gets (re)built and
+/// for incremental updates, so they always end up in the module queries run in.
+const LINK_FACTS_MODULE: &str = "user";
+
+/// How many links get asserted per query when (re)building the engine's facts
+const LINK_FACTS_BATCH_SIZE: usize = 500;
+
+/// Builds a single Prolog goal that retracts the facts of the removed links
+/// and asserts the facts of the added ones: triple/3, link/5 and the node facts
+/// (languageAddress/2, languageName/2, expressionAddress/2) of their nodes.
+pub(crate) fn fact_updates_query(additions: &Vec<DecoratedLinkExpression>, removals: &Vec<DecoratedLinkExpression>) -> Option<String> {
+    let m = LINK_FACTS_MODULE;
+    // link/5 facts are unique per link expression, so we use them to keep
+    // these updates idempotent even if the engine got rebuilt in between.
+    let mut goals = Vec::new();
+    for link in removals.iter().filter(|l| !is_sdna_link(&l.data)) {
+        goals.push(format!(
+            "(retract({m}:{}) -> (retract({m}:{}) -> true ; true) ; true)",
+            link_term(link),
+            triple_term(link)
+        ));
+        // Node facts stay as long as any remaining link uses the node
+        for node in link_nodes(link) {
+            if node_terms(node).is_empty() {
+                continue;
             }
+            goals.push(format!(
+                "(({m}:triple(\"{node}\", _, _) ; {m}:triple(_, \"{node}\", _) ; {m}:triple(_, _, \"{node}\")) -> true ; \
+                 retractall({m}:languageAddress(\"{node}\", _)), retractall({m}:languageName(\"{node}\", _)), retractall({m}:expressionAddress(\"{node}\", _)))"
+            ));
+        }
+    }
+    for link in additions.iter().filter(|l| !is_sdna_link(&l.data)) {
+        goals.push(format!(
+            "({m}:{} -> true ; assertz({m}:{}), assertz({m}:{}))",
+            link_term(link),
+            triple_term(link),
+            link_term(link)
+        ));
+        for term in link_nodes(link).into_iter().flat_map(|node| node_terms(node)) {
+            goals.push(format!("({m}:{term} -> true ; assertz({m}:{term}))"));
         }
     }
 
-    Ok(lang_addrs.into_iter().chain(lang_names).chain(expr_addrs).collect())
+    if goals.is_empty() {
+        None
+    } else {
+        Some(format!("{}.", goals.join(", ")))
+    }
 }
 
-
-fn is_sdna_link(link: &Link) -> bool {
-    link.source == "ad4m://self" && ["ad4m://has_subject_class", "ad4m://has_flow", "ad4m://has_custom_sdna"].contains(&link.predicate.as_deref().unwrap_or(""))
+/// Queries that replace all link facts in the engine with the facts of the given links.
+/// Uses fact_updates_query(), so a rebuilt engine has exactly the facts
+/// incremental updates would have produced.
+pub(crate) fn link_facts_queries(all_links: &Vec<DecoratedLinkExpression>) -> Vec<String> {
+    let m = LINK_FACTS_MODULE;
+    let mut queries = vec![format!(
+        "retractall({m}:triple(_, _, _)), retractall({m}:link(_, _, _, _, _)), \
+         retractall({m}:languageAddress(_, _)), retractall({m}:languageName(_, _)), retractall({m}:expressionAddress(_, _))."
+    )];
+    for batch in all_links.chunks(LINK_FACTS_BATCH_SIZE) {
+        if let Some(query) = fact_updates_query(&batch.to_vec(), &vec![]) {
+            queries.push(query);
+        }
+    }
+    queries
 }
 
 
@@ -90,17 +152,14 @@ pub async fn init_engine_facts(all_links: Vec<DecoratedLinkExpression>, neighbou
 
     // triple/3
     // link/5
-    lines.push(":- discontiguous(triple/3).".to_string());
-    lines.push(":- discontiguous(link/5).".to_string());
-
-    let links_without_sdna: Vec<_> = all_links.iter().filter(|l| !is_sdna_link(&l.data)).collect();
-
-    for link in &links_without_sdna {
-        lines.push(triple_fact(link));
-    }
-    for link in &links_without_sdna {
-        lines.push(link_fact(link));
-    }
+    // languageAddress/2, languageName/2, expressionAddress/2
+    // The facts themselves get asserted with link_facts_queries() after loading these lines,
+    // the same way incremental updates apply link changes.
+    lines.push(":- dynamic(triple/3).".to_string());
+    lines.push(":- dynamic(link/5).".to_string());
+    lines.push(":- dynamic(languageAddress/2).".to_string());
+    lines.push(":- dynamic(languageName/2).".to_string());
+    lines.push(":- dynamic(expressionAddress/2).".to_string());
 
     // reachable/2
     lines.push(":- discontiguous(reachable/2).".to_string());
@@ -110,8 +169,6 @@ pub async fn init_engine_facts(all_links: Vec<DecoratedLinkExpression>, neighbou
     // hiddenExpression/1
     lines.push(":- discontiguous(hiddenExpression/1).".to_string());
 
-    // lines.extend(node_facts(&links_without_sdna).await?);
-
     // Social DNA zomes
     lines.push(":- discontiguous(register_sdna_flow/2).".to_string());
     lines.push(":- discontiguous(flowable/2).".to_string());
@@ -142,6 +199,8 @@ pub async fn init_engine_facts(all_links: Vec<DecoratedLinkExpression>, neighbou
     lines.push(":- discontiguous(p3_instance_color/3).".to_string());
 
     lines.push(":- use_module(library(lists)).".to_string());
+    // For call_with_inference_limit/3, which all queries get wrapped in
+    lines.push(":- use_module(library(iso_ext)).".to_string());
 
     let lib = r#"
 :- discontiguous(paginate/4).
@@ -182,18 +241,15 @@ takeN(Rest, NextN, PageRest).
 
         if link_expression.proof.valid.unwrap_or(false) && author_agents.contains(&link_expression.author) {
             if is_sdna_link(link) {
-                let name = Literal::from_url(link.target.clone())?
-                    .get()
-                    .expect("must work")
-                    .to_string();
+                let name = Literal::from_url(link.target.clone())?.get()?.to_string();
 
                 let entry = seen_subject_classes.entry(name.clone()).or_insert_with(|| HashMap::new());
                 entry.insert("type".to_string(), link.predicate.as_ref().expect("sdna link must have predicate").clone());
             }
 
             if link.predicate == Some("ad4m://sdna".to_string()) {
-                let name = Literal::from_url(link.source.clone())?.get().expect("must work").to_string();
-                let code = Literal::from_url(link.target.clone())?.get().expect("must work").to_string();
+                let name = Literal::from_url(link.source.clone())?.get()?.to_string();
+                let code = Literal::from_url(link.target.clone())?.get()?.to_string();
 
                 let subject_class = seen_subject_classes.entry(name.clone()).or_insert_with(|| HashMap::new());
                 let existing_timestamp = subject_class.get("timestamp").and_then(|t| t.parse::<i64>().ok());
@@ -1,4 +1,5 @@
 use scryer_prolog::machine::parsed_results::{Value, QueryMatch, QueryResolution};
+use crate::graphql::graphql_types::{PrologBinding, PrologQueryResult, PrologSolution, PrologTerm, PrologTermKind};
 
 pub fn prolog_value_to_json_tring(value: Value) -> String {
     match value {
@@ -69,4 +70,89 @@ pub fn prolog_resolution_to_string(resultion: QueryResolution) -> String {
             format!("[{}]", matches_json.join(", "))
         }
     }
-}
\ No newline at end of file
+}
+
+/// Appends the term and all its arguments to `terms` and returns its index.
+fn push_prolog_term(value: Value, terms: &mut Vec<PrologTerm>) -> i32 {
+    let (kind, text, args) = match value {
+        Value::Integer(i) => (PrologTermKind::Integer, Some(format!("{}", i)), vec![]),
+        Value::Float(f) => (PrologTermKind::Float, Some(format!("{}", f)), vec![]),
+        Value::Rational(r) => (PrologTermKind::Rational, Some(format!("{}", r)), vec![]),
+        Value::Atom(a) => (PrologTermKind::Atom, Some(format!("{}", a.as_str())), vec![]),
+        Value::String(s) => (PrologTermKind::String, Some(s), vec![]),
+        Value::List(l) => (PrologTermKind::List, None, l),
+        Value::Structure(s, l) => (PrologTermKind::Structure, Some(format!("{}", s.as_str())), l),
+        _ => (PrologTermKind::Variable, None, vec![]),
+    };
+
+    // Reserve the slot first, so parents come before their arguments
+    let index = terms.len();
+    terms.push(PrologTerm { kind, value: text, args: vec![] });
+    let args = args.into_iter().map(|arg| push_prolog_term(arg, terms)).collect();
+    terms[index].args = args;
+    index as i32
+}
+
+fn prolog_match_to_solution(query_match: QueryMatch) -> PrologSolution {
+    let mut terms = Vec::new();
+    let bindings = query_match
+        .bindings
+        .into_iter()
+        .map(|(variable, value)| PrologBinding {
+            variable,
+            term: push_prolog_term(value, &mut terms),
+        })
+        .collect();
+    PrologSolution { bindings, terms }
+}
+
+/// Typed counterpart of prolog_resolution_to_string().
+pub fn prolog_resolution_to_result(resolution: QueryResolution) -> PrologQueryResult {
+    match resolution {
+        QueryResolution::True => PrologQueryResult {
+            success: true,
+            solutions: vec![PrologSolution::default()],
+        },
+        QueryResolution::False => PrologQueryResult {
+            success: false,
+            solutions: vec![],
+        },
+        QueryResolution::Matches(matches) => PrologQueryResult {
+            success: !matches.is_empty(),
+            solutions: matches.into_iter().map(prolog_match_to_solution).collect(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::btreemap;
+
+    #[test]
+    fn converts_matches_to_flat_term_lists() {
+        let resolution = QueryResolution::Matches(vec![QueryMatch::from(btreemap! {
+            "X" => Value::List(vec![Value::from("a"), Value::List(vec![Value::from("b")])]),
+            "Y" => Value::from("c"),
+        })]);
+
+        let result = prolog_resolution_to_result(resolution);
+        assert!(result.success);
+        let solution = &result.solutions[0];
+        assert_eq!(solution.bindings, vec![
+            PrologBinding { variable: "X".to_string(), term: 0 },
+            PrologBinding { variable: "Y".to_string(), term: 4 },
+        ]);
+        assert_eq!(solution.terms[0].kind, PrologTermKind::List);
+        assert_eq!(solution.terms[0].args, vec![1, 2]);
+        assert_eq!(solution.terms[2].args, vec![3]);
+        assert_eq!(solution.terms[3].value, Some("b".to_string()));
+    }
+
+    #[test]
+    fn converts_true_and_false() {
+        assert_eq!(prolog_resolution_to_result(QueryResolution::True).solutions, vec![PrologSolution::default()]);
+        let result = prolog_resolution_to_result(QueryResolution::False);
+        assert!(!result.success && result.solutions.is_empty());
+    }
+}
@@ -1,7 +1,11 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
 use deno_core::anyhow::Error;
-use scryer_prolog::machine::{parsed_results::QueryResult, Machine};
+use scryer_prolog::machine::{parsed_results::{QueryResolution, QueryResult}, Machine};
 use tokio::sync::{mpsc, oneshot};
 
+use super::limits::{check_inference_limit, limit_inferences, PrologQueryError, PrologQueryLimits};
+
 #[derive(Debug)]
 pub enum PrologServiceRequest {
     RunQuery(String, oneshot::Sender<PrologServiceResponse>),
@@ -18,6 +22,8 @@ pub enum PrologServiceResponse {
 pub struct PrologEngine {
     request_sender: mpsc::UnboundedSender<PrologServiceRequest>,
     request_receiver: Option<mpsc::UnboundedReceiver<PrologServiceRequest>>,
+    killed: Arc<AtomicBool>,
+    pending_requests: Arc<AtomicUsize>,
 }
 
 impl PrologEngine {
@@ -27,6 +33,8 @@ impl PrologEngine {
         PrologEngine {
             request_sender,
             request_receiver: Some(request_receiver),
+            killed: Arc::new(AtomicBool::new(false)),
+            pending_requests: Arc::new(AtomicUsize::new(0)),
         }
     }
 
@@ -36,6 +44,8 @@ impl PrologEngine {
             .take()
             .ok_or_else(|| Error::msg("PrologEngine::spawn called twice"))?;
         let (response_sender, response_receiver) = oneshot::channel();
+        let killed = self.killed.clone();
+        let pending_requests = self.pending_requests.clone();
 
         std::thread::spawn(move || {
             let rt = tokio::runtime::Builder::new_current_thread()
@@ -54,9 +64,14 @@ impl PrologEngine {
                         .unwrap();
 
                     while let Some(message) = receiver.recv().await {
+                        if killed.load(Ordering::SeqCst) {
+                            break;
+                        }
                         match message {
                             PrologServiceRequest::RunQuery(query, response) => {
                                 let result = machine.run_query(query);
+                                // Done before answering, so the caller never sees its own request as pending
+                                pending_requests.fetch_sub(1, Ordering::SeqCst);
                                 let _ = response.send(PrologServiceResponse::QueryResult(result));
                             }
                             PrologServiceRequest::LoadModuleString(
@@ -71,6 +86,7 @@ impl PrologEngine {
                                     .join("\n");
                                 let _result =
                                     machine.consult_module_string(module_name.as_str(), program);
+                                pending_requests.fetch_sub(1, Ordering::SeqCst);
                                 let _ = response.send(PrologServiceResponse::LoadModuleResult(Ok(())));
                             }
                         }
@@ -87,14 +103,46 @@ impl PrologEngine {
         Ok(())
     }
 
+    /// Stops the engine's thread. A query it is busy with can't be interrupted,
+    /// but every query runs with an inference limit, so the thread exits once that one
+    /// is cut off at the latest, dropping all queued requests and the machine with its facts.
+    pub fn kill(&self) {
+        self.killed.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the engine is still working on earlier requests,
+    /// so a new one would have to wait for them.
+    pub fn is_busy(&self) -> bool {
+        self.pending_requests.load(Ordering::SeqCst) > 0
+    }
+
+    /// Runs the query with the inference limit configured for the executor,
+    /// so no query can keep the engine's thread busy forever.
     pub async fn run_query(&self, query: String) -> Result<QueryResult, Error> {
+        let inference_limit = PrologQueryLimits::defaults().inference_limit;
+        Ok(self
+            .run_query_with_inference_limit(query, inference_limit)
+            .await?
+            .map_err(|error| match error {
+                PrologQueryError::Prolog(error) => error,
+                error => error.to_string(),
+            }))
+    }
+
+    pub async fn run_query_with_inference_limit(
+        &self,
+        query: String,
+        inference_limit: u64,
+    ) -> Result<Result<QueryResolution, PrologQueryError>, Error> {
         let (response_sender, response_receiver) = oneshot::channel();
-        self.request_sender
-            .send(PrologServiceRequest::RunQuery(query, response_sender))?;
+        self.send(PrologServiceRequest::RunQuery(
+            limit_inferences(&query, inference_limit),
+            response_sender,
+        ))?;
         let response = response_receiver
             .await?;
         match response {
-            PrologServiceResponse::QueryResult(query_result) => Ok(query_result),
+            PrologServiceResponse::QueryResult(query_result) => Ok(check_inference_limit(query_result, inference_limit)),
             _ => unreachable!(),
         }
     }
@@ -105,12 +153,11 @@ impl PrologEngine {
         program_lines: Vec<String>,
     ) -> Result<(), Error> {
         let (response_sender, response_receiver) = oneshot::channel();
-        self.request_sender
-            .send(PrologServiceRequest::LoadModuleString(
-                module_name,
-                program_lines,
-                response_sender,
-            ))?;
+        self.send(PrologServiceRequest::LoadModuleString(
+            module_name,
+            program_lines,
+            response_sender,
+        ))?;
         let response = response_receiver
             .await?;
         match response {
@@ -118,6 +165,14 @@ impl PrologEngine {
             _ => unreachable!(),
         }
     }
+
+    fn send(&self, request: PrologServiceRequest) -> Result<(), Error> {
+        self.pending_requests.fetch_add(1, Ordering::SeqCst);
+        self.request_sender.send(request).map_err(|error| {
+            self.pending_requests.fetch_sub(1, Ordering::SeqCst);
+            Error::from(error)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -156,4 +211,32 @@ mod prolog_test {
         println!("Output: {:?}", output);
         assert!(output.is_ok());
     }
+
+    #[tokio::test]
+    async fn killed_engine_stops_answering() {
+        let mut engine = PrologEngine::new();
+        engine.spawn().await.unwrap();
+        assert!(engine.run_query("true.".to_string()).await.is_ok());
+
+        engine.kill();
+        assert!(engine.run_query("true.".to_string()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn non_terminating_queries_get_cut_off() {
+        let mut engine = PrologEngine::new();
+        engine.spawn().await.unwrap();
+
+        // Enumerates ever longer lists, never succeeding
+        let result = engine
+            .run_query_with_inference_limit("length(L, N), N < 0.".to_string(), 10_000)
+            .await
+            .unwrap();
+        assert_eq!(result, Err(PrologQueryError::InferenceLimitExceeded(10_000)));
+
+        assert_eq!(
+            engine.run_query("true.".to_string()).await.unwrap(),
+            Ok(QueryResolution::True)
+        );
+    }
 }
@@ -0,0 +1,173 @@
+use std::sync::RwLock;
+use std::time::Duration;
+use lazy_static::lazy_static;
+use scryer_prolog::machine::parsed_results::{QueryResolution, QueryResult};
+
+use crate::perspectives::utils::prolog_value_to_json_tring;
+
+pub const DEFAULT_QUERY_TIMEOUT_SECONDS: u64 = 30;
+pub const DEFAULT_INFERENCE_LIMIT: u64 = 10_000_000;
+
+/// Variable the outcome of call_with_inference_limit/3 gets bound to.
+/// It is removed from the results before they are returned.
+const INFERENCE_RESULT_VARIABLE: &str = "Ad4mInferenceLimitResult";
+
+lazy_static! {
+    static ref DEFAULT_QUERY_LIMITS: RwLock<PrologQueryLimits> = RwLock::new(PrologQueryLimits {
+        timeout: Duration::from_secs(DEFAULT_QUERY_TIMEOUT_SECONDS),
+        inference_limit: DEFAULT_INFERENCE_LIMIT,
+    });
+}
+
+/// How long a single Prolog query may run and how many inferences it may take.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrologQueryLimits {
+    pub timeout: Duration,
+    pub inference_limit: u64,
+}
+
+impl PrologQueryLimits {
+    pub fn set_defaults(timeout_seconds: u64, inference_limit: u64) {
+        let mut limits = DEFAULT_QUERY_LIMITS.write().expect("Couldn't get write lock on Prolog query limits");
+        *limits = PrologQueryLimits {
+            timeout: Duration::from_secs(timeout_seconds),
+            inference_limit,
+        };
+    }
+
+    /// The limits configured for the executor, used for all queries that don't set their own.
+    pub fn defaults() -> Self {
+        *DEFAULT_QUERY_LIMITS.read().expect("Couldn't get lock on Prolog query limits")
+    }
+
+    pub fn with_overrides(self, timeout: Option<Duration>, inference_limit: Option<u64>) -> Self {
+        PrologQueryLimits {
+            timeout: timeout.unwrap_or(self.timeout),
+            inference_limit: inference_limit.unwrap_or(self.inference_limit),
+        }
+    }
+}
+
+/// Why a Prolog query didn't produce a result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrologQueryError {
+    /// The query ran longer than the timeout. The engine got restarted.
+    Timeout(Duration),
+    /// The query needed more inferences than allowed.
+    InferenceLimitExceeded(u64),
+    /// The query got cancelled while running. The engine got restarted.
+    Cancelled,
+    /// Prolog raised an error, e.g. for a syntax error or an unknown predicate.
+    Prolog(String),
+    /// The engine itself failed, e.g. because its thread died.
+    Engine(String),
+}
+
+impl PrologQueryError {
+    /// Machine readable error code, returned to GraphQL clients as error extension.
+    pub fn code(&self) -> &'static str {
+        match self {
+            PrologQueryError::Timeout(_) => "PROLOG_TIMEOUT",
+            PrologQueryError::InferenceLimitExceeded(_) => "PROLOG_INFERENCE_LIMIT_EXCEEDED",
+            PrologQueryError::Cancelled => "PROLOG_CANCELLED",
+            PrologQueryError::Prolog(_) => "PROLOG_ERROR",
+            PrologQueryError::Engine(_) => "PROLOG_ENGINE_ERROR",
+        }
+    }
+}
+
+impl std::fmt::Display for PrologQueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrologQueryError::Timeout(timeout) => write!(f, "Prolog query timed out after {} ms", timeout.as_millis()),
+            PrologQueryError::InferenceLimitExceeded(limit) => write!(f, "Prolog query exceeded the limit of {} inferences", limit),
+            PrologQueryError::Cancelled => write!(f, "Prolog query was cancelled"),
+            PrologQueryError::Prolog(error) => write!(f, "Prolog error: {}", error),
+            PrologQueryError::Engine(error) => write!(f, "Prolog engine failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for PrologQueryError {}
+
+/// Wraps the query in call_with_inference_limit/3 (from library(iso_ext)),
+/// so that runaway queries stop on their own even if nobody waits for them anymore.
+pub fn limit_inferences(query: &str, inference_limit: u64) -> String {
+    let goal = query.trim().trim_end_matches('.');
+    format!("call_with_inference_limit(({}), {}, {}).", goal, inference_limit, INFERENCE_RESULT_VARIABLE)
+}
+
+/// Turns the result of a query built with limit_inferences() back into
+/// the resolution of the original query.
+pub fn check_inference_limit(result: QueryResult, inference_limit: u64) -> Result<QueryResolution, PrologQueryError> {
+    let matches = match result.map_err(PrologQueryError::Prolog)? {
+        QueryResolution::Matches(matches) => matches,
+        resolution => return Ok(resolution),
+    };
+
+    let mut original_matches = Vec::new();
+    for mut query_match in matches {
+        if let Some(outcome) = query_match.bindings.remove(INFERENCE_RESULT_VARIABLE) {
+            if prolog_value_to_json_tring(outcome) == "inference_limit_exceeded" {
+                return Err(PrologQueryError::InferenceLimitExceeded(inference_limit));
+            }
+        }
+        original_matches.push(query_match);
+    }
+
+    // The original query didn't have any variables
+    if original_matches.iter().all(|query_match| query_match.bindings.is_empty()) {
+        return Ok(if original_matches.is_empty() {
+            QueryResolution::False
+        } else {
+            QueryResolution::True
+        });
+    }
+
+    Ok(QueryResolution::Matches(original_matches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::btreemap;
+    use scryer_prolog::machine::parsed_results::{QueryMatch, Value};
+
+    #[test]
+    fn wraps_query_in_inference_limit() {
+        assert_eq!(
+            limit_inferences("triple(\"a\", P, \"b\"). ", 1000),
+            "call_with_inference_limit((triple(\"a\", P, \"b\")), 1000, Ad4mInferenceLimitResult)."
+        );
+    }
+
+    #[test]
+    fn removes_inference_result_from_matches() {
+        let result = Ok(QueryResolution::Matches(vec![
+            QueryMatch::from(btreemap! {
+                "P" => Value::from("p1"),
+                INFERENCE_RESULT_VARIABLE => Value::from("true"),
+            }),
+        ]));
+        assert_eq!(
+            check_inference_limit(result, 1000),
+            Ok(QueryResolution::Matches(vec![QueryMatch::from(btreemap! {
+                "P" => Value::from("p1"),
+            })]))
+        );
+
+        let result = Ok(QueryResolution::Matches(vec![
+            QueryMatch::from(btreemap! {
+                INFERENCE_RESULT_VARIABLE => Value::from("!"),
+            }),
+        ]));
+        assert_eq!(check_inference_limit(result, 1000), Ok(QueryResolution::True));
+    }
+
+    #[test]
+    fn prolog_errors_are_structured() {
+        let error = check_inference_limit(Err("error existence_error".to_string()), 1000).unwrap_err();
+        assert_eq!(error.code(), "PROLOG_ERROR");
+        assert_eq!(PrologQueryError::Timeout(Duration::from_millis(500)).to_string(), "Prolog query timed out after 500 ms");
+    }
+}
@@ -6,6 +6,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub(crate) mod engine;
+pub(crate) mod limits;
 pub(crate) mod prolog_service_extension;
 
 use self::engine::PrologEngine;
@@ -76,6 +76,27 @@ pub(crate) async fn subscribe_and_process<
     pubsub: Arc<PubSub>,
     topic: Topic,
     filter: Option<String>,
+) -> Pin<Box<dyn Stream<Item = FieldResult<T::Value>> + Send>> {
+    subscribe_and_process_with::<T, _>(pubsub, topic, move |data: &T| match &filter {
+        Some(filter) => {
+            &data
+                .get_filter()
+                .expect("Could not get filter on T where we expected to filter")
+                == filter
+        }
+        None => true,
+    })
+    .await
+}
+
+/// Like subscribe_and_process(), but only passes on messages for which `keep` returns true
+pub(crate) async fn subscribe_and_process_with<
+    T: DeserializeOwned + Send + 'static + std::fmt::Debug + GetValue + GetFilter,
+    F: Fn(&T) -> bool + Send + Sync + 'static,
+>(
+    pubsub: Arc<PubSub>,
+    topic: Topic,
+    keep: F,
 ) -> Pin<Box<dyn Stream<Item = FieldResult<T::Value>> + Send>> {
     debug!("Subscribing to topic: {}", topic);
     pubsub.remove_dead_subscribers().await;
@@ -85,14 +106,8 @@ pub(crate) async fn subscribe_and_process<
     let mapped_stream = receiver_stream.filter_map(move |msg| {
         match serde_json::from_str::<T>(&msg) {
             Ok(data) => {
-                if let Some(filter) = &filter {
-                    if &data
-                        .get_filter()
-                        .expect("Could not get filter on T where we expected to filter")
-                        != filter
-                    {
-                        return futures::future::ready(None);
-                    }
+                if !keep(&data) {
+                    return futures::future::ready(None);
                 }
                 let value = data.get_value(); // Get the underlying value using the GetValue trait
                 futures::future::ready(Some(Ok(value)))
@@ -131,6 +146,7 @@ lazy_static::lazy_static! {
     pub static ref PERSPECTIVE_UPDATED_TOPIC: String = "perspective-updated-topic".to_owned();
     pub static ref PERSPECTIVE_SYNC_STATE_CHANGE_TOPIC: String = "perspective-sync-state-change-topic".to_owned();
     pub static ref RUNTIME_MESSAGED_RECEIVED_TOPIC: String = "runtime-messaged-received-topic".to_owned();
+    pub static ref RUNTIME_RECEIVED_MESSAGE_TOPIC: String = "runtime-received-message-topic".to_owned();
 }
 
 pub async fn get_global_pubsub() -> Arc<PubSub> {
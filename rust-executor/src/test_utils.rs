@@ -4,5 +4,8 @@ pub fn setup_wallet() {
     let wallet_instance = Wallet::instance();
     let mut wallet = wallet_instance.lock().expect("wallet lock");
     let wallet_ref = wallet.as_mut().expect("wallet instance");
-    wallet_ref.generate_keypair("main".to_string());
-}
\ No newline at end of file
+    // Tests run in parallel, regenerating the key would invalidate signatures other tests are about to check
+    if wallet_ref.get_secret_key(&"main".to_string()).is_none() {
+        wallet_ref.generate_keypair("main".to_string());
+    }
+}
@@ -4,8 +4,9 @@ use coasys_juniper::{
     GraphQLObject, GraphQLValue,
 };
 
-use crate::{agent::signatures::verify, graphql::graphql_types::{LinkExpressionInput, LinkInput, LinkStatus, PerspectiveInput}};
+use crate::{agent::signatures::verify, graphql::graphql_types::{DecoratedPerspectiveDiff, LinkExpressionInput, LinkInput, LinkStatus, PerspectiveInput}};
 use regex::Regex;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 
 #[derive(Default, Debug, Deserialize, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -140,6 +141,17 @@ impl LinkExpression {
             status: input.status.into()
         }
     }
+
+    /// Whether the proof is a valid signature of this link by its author
+    pub fn has_valid_signature(&self) -> bool {
+        let link_expr = Expression::<Link> {
+            author: self.author.clone(),
+            timestamp: self.timestamp.clone(),
+            data: self.data.normalize(),
+            proof: self.proof.clone(),
+        };
+        verify(&link_expr).unwrap_or(false)
+    }
 }
 
 impl From<LinkExpression> for Expression<Link> {
@@ -338,4 +350,63 @@ impl ToString for ExpressionRef {
 pub struct PerspectiveDiff {
     pub additions: Vec<LinkExpression>,
     pub removals: Vec<LinkExpression>,
-}
\ No newline at end of file
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffOrigin {
+    Local,
+    LinkLanguage,
+    Undo,
+    Redo,
+}
+
+/// A diff that got applied to a perspective, as kept in its history log.
+#[derive(Debug, Clone)]
+pub struct PerspectiveHistoryEntry {
+    pub id: i64,
+    pub diff: DecoratedPerspectiveDiff,
+    pub origin: DiffOrigin,
+    pub timestamp: String,
+    pub undone: bool,
+}
+
+/// Position of a link in the timestamp order of link queries.
+/// Clients get it as an opaque string, so they can continue a query right after
+/// a given link, even if other links share its timestamp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkCursor {
+    pub timestamp: String,
+    /// Tie-breaker for links with the same timestamp, in the order they were stored.
+    pub id: i64,
+}
+
+impl LinkCursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}|{}", self.id, self.timestamp))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, AnyError> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor)?)?;
+        let (id, timestamp) = decoded
+            .split_once('|')
+            .ok_or(anyhow!("Invalid link cursor: {}", cursor))?;
+        Ok(LinkCursor {
+            timestamp: timestamp.to_string(),
+            id: id.parse()?,
+        })
+    }
+}
+
+/// An expression kept in the expression cache, with the metadata needed to expire and evict it.
+#[derive(Debug, Clone)]
+pub struct CachedExpression {
+    pub url: String,
+    pub expression: Expression<serde_json::Value>,
+    /// Address of the language the expression was fetched from.
+    pub language: Option<String>,
+    /// Size of the serialized expression in bytes.
+    pub size: u64,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+    pub last_accessed: chrono::DateTime<chrono::Utc>,
+}
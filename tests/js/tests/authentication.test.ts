@@ -295,6 +295,45 @@ describe("Authentication integration tests", () => {
             await expect(call()).to.be.rejectedWith("Unauthorized access");
         })
 
+        it("user can refresh tokens once and lose them on rotation", async () => {
+            let requestId = await unAuthenticatedAppAd4mClient!.agent.requestCapability({
+                appName: "demo-app",
+                appDesc: "demo-desc",
+                appDomain: "test.ad4m.org",
+                appUrl: "https://demo-link",
+                capabilities: [
+                    {
+                        with: {
+                            domain:"agent",
+                            pointers:["*"]
+                        },
+                        can: ["READ"]
+                    }
+                ] as CapabilityInput[]
+            } as AuthInfoInput)
+            let rand = await adminAd4mClient!.agent.permitCapability(`{"requestId":"${requestId}","auth":{"appName":"demo-app","appDesc":"demo-desc","appUrl":"demo-url","capabilities":[{"with":{"domain":"agent","pointers":["*"]},"can":["READ"]}]}}`)
+            let tokens = await adminAd4mClient!.agent.generateAuthTokens(requestId, rand)
+
+            let refreshed = await unAuthenticatedAppAd4mClient!.agent.refreshJwt(tokens.refreshToken)
+            // @ts-ignore
+            let authenticatedAppAd4mClient = new Ad4mClient(apolloClient(gqlPort, refreshed.accessToken), false)
+            expect((await authenticatedAppAd4mClient!.agent.status()).isUnlocked).to.be.true;
+
+            const reuse = async () => {
+                return await unAuthenticatedAppAd4mClient!.agent.refreshJwt(tokens.refreshToken)
+            }
+            await expect(reuse()).to.be.rejectedWith("Unauthorized access");
+
+            let appTokens = await adminAd4mClient!.agent.getAppTokens(requestId)
+            expect(appTokens.length).to.be.equal(0)
+
+            let rotated = await adminAd4mClient!.agent.rotateAppTokens(requestId)
+            // @ts-ignore
+            let rotatedAppAd4mClient = new Ad4mClient(apolloClient(gqlPort, rotated.accessToken), false)
+            expect((await rotatedAppAd4mClient!.agent.status()).isUnlocked).to.be.true;
+            await expect(authenticatedAppAd4mClient!.agent.status()).to.be.rejectedWith("Unauthorized access");
+        })
+
         it("requesting a capability toke should trigger a CapabilityRequested exception", async () => {
             let excpetions: ExceptionInfo[] = [];
             adminAd4mClient!.runtime.addExceptionCallback((e) => { excpetions.push(e); return null; })
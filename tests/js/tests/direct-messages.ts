@@ -73,6 +73,9 @@ export default function directMessageTests(testContext: TestContext) {
                 const bobMessageCallback = sinon.fake()
                 //@ts-ignore
                 await bob.runtime.addMessageCallback(bobMessageCallback)
+                const bobReceivedMessageCallback = sinon.fake()
+                //@ts-ignore
+                await bob.runtime.addReceivedMessageCallback(bobReceivedMessageCallback)
                 //@ts-ignore
                 await alice.runtime.friendSendMessage(didBob, message)
                 await sleep(1000)
@@ -83,6 +86,12 @@ export default function directMessageTests(testContext: TestContext) {
                 expect(bobMessageCallback.calledOnce).to.be.true;
                 expect(bobMessageCallback.getCall(0).args[0]).to.be.eql(bobsInbox[0])
 
+                expect(bobReceivedMessageCallback.calledOnce).to.be.true;
+                const received = bobReceivedMessageCallback.getCall(0).args[0]
+                expect(received.message).to.be.eql(bobsInbox[0])
+                expect(received.id).to.be.equal(bobsInbox[0].proof.signature)
+                expect(received.replyTo).to.be.null
+
                 delete bobsInbox[0].data.links[0].proof.invalid
                 delete bobsInbox[0].data.links[0].proof.valid
                 expect(bobsInbox[0].data).to.be.eql(message)
@@ -103,11 +112,45 @@ export default function directMessageTests(testContext: TestContext) {
                 delete outbox[0].message.data.links[0].proof.invalid
                 delete outbox[0].message.data.links[0].proof.valid
                 expect(outbox[0].message.data).to.be.eql(message)
+                expect(outbox[0].id).to.be.equal(outbox[0].message.proof.signature)
+                expect(outbox[0].status).to.be.equal("SENT")
 
                 //@ts-ignore
                 const filteredOutbox = await alice.runtime.messageOutbox("did:test:other")
                 expect(filteredOutbox.length).to.be.equal(0)
             })
+
+            it("Bob's read receipt reaches Alice's outbox", async () => {
+                //@ts-ignore
+                const [received] = await bob.runtime.receivedMessages(didAlice)
+                expect(received.status).to.be.equal("DELIVERED_P2P")
+
+                //@ts-ignore
+                await bob.runtime.markMessagesRead([received.id])
+                await sleep(1000)
+
+                //@ts-ignore
+                expect((await bob.runtime.receivedMessages(didAlice))[0].status).to.be.equal("READ")
+                //@ts-ignore
+                const [sent] = await alice.runtime.messageOutbox(didBob)
+                expect(sent.id).to.be.equal(received.id)
+                expect(sent.status).to.be.equal("READ")
+            })
+
+            it("Bob can reply to Alice's message", async () => {
+                //@ts-ignore
+                const [received] = await bob.runtime.receivedMessages(didAlice)
+                //@ts-ignore
+                await bob.runtime.friendSendMessage(didAlice, message, received.id)
+                await sleep(1000)
+
+                //@ts-ignore
+                const [reply] = await alice.runtime.receivedMessages(didBob)
+                expect(reply.replyTo).to.be.equal(received.id)
+                //@ts-ignore
+                const [sentReply] = await bob.runtime.messageOutbox(didAlice)
+                expect(sentReply.replyTo).to.be.equal(received.id)
+            })
         })
     }
 }